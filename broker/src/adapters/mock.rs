@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use chrono::{Duration, Utc};
 use market::BrokerBar;
 use struxis::Timeframe;
@@ -10,6 +12,8 @@ pub struct MockAdapter {
     next_datetime: chrono::DateTime<Utc>,
     price: f64,
     connected: bool,
+    /// 预置的 bar（如 `struxis::synthetic` 生成的行情），取完后继续按固定步长生成。
+    queued: VecDeque<BrokerBar>,
 }
 
 impl MockAdapter {
@@ -20,7 +24,23 @@ impl MockAdapter {
             next_datetime: Utc::now(),
             price: start_price,
             connected: false,
+            queued: VecDeque::new(),
+        }
+    }
+
+    /// 依次回放 `bars`，symbol/exchange 取第一根 bar 的值。
+    pub fn with_bars(bars: Vec<BrokerBar>) -> Self {
+        let first = bars.first();
+        let mut adapter = Self::new(
+            first.map(|x| x.symbol.clone()).unwrap_or_default(),
+            first.map(|x| x.exchange.clone()).unwrap_or_default(),
+            bars.last().map_or(0.0, |x| x.close_price),
+        );
+        if let Some(last) = bars.last() {
+            adapter.next_datetime = last.datetime + Duration::minutes(1);
         }
+        adapter.queued = bars.into();
+        adapter
     }
 }
 
@@ -38,6 +58,9 @@ impl ExchangeAdapter for MockAdapter {
         if !self.connected {
             return Err(BrokerError::NotConnected);
         }
+        if let Some(bar) = self.queued.pop_front() {
+            return Ok(Some(bar));
+        }
 
         let open = self.price;
         let close = open + 0.2;
@@ -101,4 +124,24 @@ mod tests {
         assert_eq!(bar.exchange, "MOCK");
 		assert!(bar.close_price > bar.open_price);
     }
+
+    #[test]
+    fn mock_adapter_replays_synthetic_bars_first() {
+        let series = struxis::RandomWalk {
+            bars: 3,
+            ..struxis::RandomWalk::default()
+        }
+        .generate(&struxis::SeriesSpec::default());
+        let mut adapter = MockAdapter::with_bars(series.sbars.clone());
+        adapter.connect().expect("mock adapter should connect");
+
+        for expected in &series.sbars {
+            let bar = adapter.poll_bar().expect("poll").expect("bar");
+            assert_eq!(bar.close_price, expected.close_price);
+            assert_eq!(bar.datetime, expected.datetime);
+        }
+        let next = adapter.poll_bar().expect("poll").expect("bar");
+        assert_eq!(next.open_price, series.sbars[2].close_price);
+        assert!(next.datetime > series.sbars[2].datetime);
+    }
 }
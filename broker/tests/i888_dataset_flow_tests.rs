@@ -84,10 +84,7 @@ fn broker_feed_receiver_pipeline_validates_i888_dataset() {
     let mut sent: HashMap<Timeframe, usize> = HashMap::new();
     let mut received: HashMap<Timeframe, usize> = HashMap::new();
 
-    loop {
-        let Some(bar) = adapter.poll_bar().expect("poll should not fail") else {
-            break;
-        };
+    while let Some(bar) = adapter.poll_bar().expect("poll should not fail") {
 
         let tf = bar.timeframe;
         *sent.entry(tf).or_insert(0) += 1;
@@ -270,21 +267,9 @@ fn parse_f64(raw: &str, file_path: &Path, line_no: usize, column: &str) -> Resul
 }
 
 fn timeframe_secs(tf: Timeframe) -> u64 {
-    match tf {
-        Timeframe::M1 => 60,
-        Timeframe::M5 => 5 * 60,
-        Timeframe::M15 => 15 * 60,
-        Timeframe::H1 => 60 * 60,
-        Timeframe::D1 => 24 * 60 * 60,
-    }
+    tf.seconds() as u64
 }
 
 fn timeframe_minutes(tf: Timeframe) -> u64 {
-    match tf {
-        Timeframe::M1 => 1,
-        Timeframe::M5 => 5,
-        Timeframe::M15 => 15,
-        Timeframe::H1 => 60,
-        Timeframe::D1 => 1440,
-    }
+    tf.minutes() as u64
 }
@@ -9,20 +9,15 @@ use crossbeam::queue::ArrayQueue;
 use crate::SharedBar;
 
 /// ingress 满载时的处理策略。
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum OverloadPolicy {
 	/// 丢弃当前新入队数据，保留既有队列内容。
 	DropNewest,
 	/// 丢弃队列最旧数据，再尝试写入当前新数据。
+	#[default]
 	DropOldest,
 }
 
-impl Default for OverloadPolicy {
-	fn default() -> Self {
-		Self::DropOldest
-	}
-}
-
 /// 单次 `push` 的结果，用于上层统计背压与丢弃行为。
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IngressPushResult {
@@ -86,6 +81,11 @@ impl RingBuffer {
 		self.queue.len()
 	}
 
+	/// 队列是否为空。
+	pub fn is_empty(&self) -> bool {
+		self.queue.is_empty()
+	}
+
 	/// 队列容量上限。
 	pub fn capacity(&self) -> usize {
 		self.capacity
@@ -26,10 +26,10 @@ impl BarStore {
 	/// 以 append 模式打开（不存在则创建）存储文件。
 	pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
 		let path = path.as_ref().to_path_buf();
-		if let Some(parent) = path.parent() {
-			if !parent.as_os_str().is_empty() {
-				fs::create_dir_all(parent)?;
-			}
+		if let Some(parent) = path.parent()
+			&& !parent.as_os_str().is_empty()
+		{
+			fs::create_dir_all(parent)?;
 		}
 
 		let file = OpenOptions::new().create(true).append(true).open(&path)?;
@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+
+use struxis::calibration::calibrate;
+use struxis::{
+    CalibrationConfig, CalibrationStats, SupplyDemandConfig, SupplyDemandProfileConfig, Timeframe,
+    load_market_bar_inputs,
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 5 {
+        eprintln!(
+            "usage: cargo run -q -p replay --bin calibrate_sd -- <csv_path> <symbol> <exchange> <timeframe> [output_yaml] [base_profile_yaml] [iterations]"
+        );
+        std::process::exit(2);
+    }
+
+    let csv_path = PathBuf::from(&args[1]);
+    let symbol = args[2].clone();
+    let exchange = args[3].clone();
+    let timeframe = Timeframe::parse(&args[4])?;
+    let output = PathBuf::from(
+        args.get(5)
+            .map(String::as_str)
+            .unwrap_or("config/sd.calibrated.yaml"),
+    );
+    // 校准结果写入单独文件，不覆盖手工维护的基准 profile
+    if args
+        .get(6)
+        .is_some_and(|x| same_file(&output, &PathBuf::from(x)))
+    {
+        eprintln!("output_yaml must differ from base_profile_yaml");
+        std::process::exit(2);
+    }
+    let base = match args.get(6) {
+        Some(path) => {
+            SupplyDemandProfileConfig::from_yaml_file(path)?.resolve_for(&symbol, timeframe)
+        }
+        None => SupplyDemandConfig::default(),
+    };
+    let mut config = CalibrationConfig::default();
+    if let Some(iterations) = args.get(7) {
+        config.iterations = iterations.parse()?;
+    }
+
+    let sbars = load_market_bar_inputs(csv_path, &symbol, &exchange, timeframe)?
+        .into_iter()
+        .map(|x| x.into_sbar())
+        .collect::<Vec<_>>();
+    let report = calibrate(
+        &format!("{symbol}.{exchange}"),
+        timeframe,
+        &sbars,
+        &base,
+        &config,
+    )?;
+    report.write_profile(&output)?;
+
+    println!(
+        "CALIBRATE {} bars={} horizon={} -> {}",
+        report.profile_key(),
+        sbars.len(),
+        report.horizon,
+        output.display()
+    );
+    for (name, stats) in [
+        ("baseline in-sample", &report.baseline_in_sample),
+        ("baseline out-of-sample", &report.baseline_out_of_sample),
+        ("calibrated in-sample", &report.in_sample),
+        ("calibrated out-of-sample", &report.out_of_sample),
+    ] {
+        print_stats(name, stats);
+    }
+    Ok(())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn print_stats(name: &str, stats: &CalibrationStats) {
+    println!(
+        "- {name}: samples={} ic={:.4} hit={:.3} swing_hit={:.3} objective={:.4}",
+        stats.samples, stats.ic, stats.hit_rate, stats.swing_hit_rate, stats.objective
+    );
+}
@@ -5,8 +5,8 @@ use std::path::PathBuf;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use struxis::{
-    DataReceiver, Direction, FractalType, MarketBarInput, MultiTimeframeContext, SwingState,
-    Timeframe,
+    DataReceiver, Direction, FractalType, IdStrategy, MarketBarInput, MultiTimeframeContext,
+    SwingState, Timeframe,
 };
 
 #[derive(Debug, Serialize)]
@@ -91,7 +91,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     } else {
         PathBuf::from(format!(
             "replay/web/kline-structures-data-{}.json",
-            normalize_tf_label(&args[4])
+            timeframe.as_str()
         ))
     };
     let max_rows = if args.len() >= 7 {
@@ -100,7 +100,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         None
     };
 
-    let mut receiver = DataReceiver::new(MultiTimeframeContext::new(format!("{}.{}", symbol, exchange)));
+    let mut receiver = DataReceiver::new(MultiTimeframeContext::with_id_strategy(
+        format!("{}.{}", symbol, exchange),
+        IdStrategy::Sequential,
+    ));
     receiver.register_timeframe(timeframe);
     let rows = load_market_bar_inputs(&csv_path, &symbol, &exchange, timeframe)?;
     let mut ingested = 0usize;
@@ -150,7 +153,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    let mut prev_cbar_close: Option<f64> = None;
     for (sbar_id, sbar_ts) in &sbar_rows {
         let Some(cbar) = cbar_by_sbar_id.get(sbar_id).copied() else {
             continue;
@@ -314,33 +316,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         payload.trend_segments_completed.len(),
         payload.keyzones.len(),
     );
+    for quality in receiver.quality_reports() {
+        println!("quality: {quality}");
+    }
 
     Ok(())
 }
 
 fn parse_timeframe(raw: &str) -> Result<Timeframe, Box<dyn std::error::Error>> {
-    let tf = match raw.to_ascii_lowercase().as_str() {
-        "1m" => Timeframe::M1,
-        "5m" => Timeframe::M5,
-        "15m" => Timeframe::M15,
-        "1h" | "60m" => Timeframe::H1,
-        "1d" => Timeframe::D1,
-        _ => {
-            return Err(format!("unsupported timeframe: {}", raw).into());
-        }
-    };
-    Ok(tf)
-}
-
-fn normalize_tf_label(raw: &str) -> &'static str {
-    match raw.to_ascii_lowercase().as_str() {
-        "1m" => "1m",
-        "5m" => "5m",
-        "15m" => "15m",
-        "1h" | "60m" => "1h",
-        "1d" => "1d",
-        _ => "15m",
-    }
+    Ok(Timeframe::parse(raw)?)
 }
 
 fn direction_label(direction: Direction) -> &'static str {
@@ -440,10 +424,10 @@ fn parse_datetime(value: &str) -> Result<DateTime<Utc>, Box<dyn std::error::Erro
         }
     }
 
-    if let Ok(d) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
-        if let Some(dt) = d.and_hms_opt(0, 0, 0) {
-            return Ok(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc));
-        }
+    if let Ok(d) = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        && let Some(dt) = d.and_hms_opt(0, 0, 0)
+    {
+        return Ok(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc));
     }
 
     Err(format!("invalid datetime: {}", value).into())
@@ -2,9 +2,9 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 use serde::Serialize;
-use struxis::{DataReceiver, MarketBarInput, MultiTimeframeContext, Timeframe};
+use struxis::{DataReceiver, IdStrategy, MarketBarInput, MultiTimeframeContext, Timeframe};
 
 #[derive(Debug, Serialize)]
 struct Payload {
@@ -45,14 +45,7 @@ struct FractalMarker {
 }
 
 fn parse_timeframe(s: &str) -> Result<Timeframe, String> {
-    match s.trim().to_ascii_lowercase().as_str() {
-        "15m" => Ok(Timeframe::M15),
-        "1h" => Ok(Timeframe::H1),
-        "1d" => Ok(Timeframe::D1),
-        "5m" => Ok(Timeframe::M5),
-        "1m" => Ok(Timeframe::M1),
-        _ => Err(format!("unsupported timeframe: {}", s)),
-    }
+    Timeframe::parse(s).map_err(|e| e.to_string())
 }
 
 fn fractal_label(ft: struxis::FractalType) -> i8 {
@@ -77,12 +70,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let output = if args.len() >= 6 {
         PathBuf::from(&args[5])
     } else {
-        let name = format!("{}_{}_{}_structures.json", symbol, exchange, format!("{:?}", timeframe).to_lowercase());
+        let name = format!("{}_{}_{}_structures.json", symbol, exchange, timeframe.as_str());
         PathBuf::from("dataset").join(name)
     };
     let max_rows = if args.len() >= 7 { Some(args[6].parse::<usize>()?) } else { None };
 
-    let mut receiver = DataReceiver::new(MultiTimeframeContext::new(format!("{}.{}", symbol, exchange)));
+    let mut receiver = DataReceiver::new(MultiTimeframeContext::with_id_strategy(
+        format!("{}.{}", symbol, exchange),
+        IdStrategy::Sequential,
+    ));
     receiver.register_timeframe(timeframe);
 
     // load csv
@@ -94,7 +90,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             // minimal parse, rely on export_kline_structures for full fidelity
             // here we just construct MarketBarInput by reading required keys
             let datetime = map.get("datetime").or_else(|| map.get("time")).ok_or("missing datetime")?.to_string();
-            let dt = if let Ok(t) = chrono::DateTime::parse_from_rfc3339(&datetime) { t.with_timezone(&Utc) } else if let Ok(naive) = chrono::NaiveDateTime::parse_from_str(&datetime, "%Y-%m-%d %H:%M:%S%.f") { chrono::DateTime::<Utc>::from_utc(naive, Utc) } else { return Err("invalid datetime in csv".into()); };
+            let dt = if let Ok(t) = chrono::DateTime::parse_from_rfc3339(&datetime) { t.with_timezone(&Utc) } else if let Ok(naive) = chrono::NaiveDateTime::parse_from_str(&datetime, "%Y-%m-%d %H:%M:%S%.f") { chrono::DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc) } else { return Err("invalid datetime in csv".into()); };
             let open = map.get("open").or_else(|| map.get("open_price")).ok_or("missing open")?.parse::<f64>()?;
             let high = map.get("high").or_else(|| map.get("high_price")).ok_or("missing high")?.parse::<f64>()?;
             let low = map.get("low").or_else(|| map.get("low_price")).ok_or("missing low")?.parse::<f64>()?;
@@ -105,6 +101,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     for row in rows.into_iter().take(max_rows.unwrap_or(usize::MAX)) { receiver.ingest_bar(row); }
+    for quality in receiver.quality_reports() {
+        println!("quality: {quality}");
+    }
 
     let mtc = receiver.mtc();
     let count = mtc.count(timeframe);
@@ -146,13 +145,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut cbar_fractals = Vec::new();
     for c in &cbars {
-        if let Some(t1) = sbar_time.get(&c.sbar_end_id).copied() {
-            if let Some(mid_id) = c.id {
-                match c.fractal_type {
-                    struxis::FractalType::Top => cbar_fractals.push(FractalMarker { id: mid_id.to_string(), time: t1, price: c.high_price, kind: "Top".to_string() }),
-                    struxis::FractalType::Bottom => cbar_fractals.push(FractalMarker { id: mid_id.to_string(), time: t1, price: c.low_price, kind: "Bottom".to_string() }),
-                    _ => {}
-                }
+        if let Some(t1) = sbar_time.get(&c.sbar_end_id).copied()
+            && let Some(mid_id) = c.id
+        {
+            match c.fractal_type {
+                struxis::FractalType::Top => cbar_fractals.push(FractalMarker { id: mid_id.to_string(), time: t1, price: c.high_price, kind: "Top".to_string() }),
+                struxis::FractalType::Bottom => cbar_fractals.push(FractalMarker { id: mid_id.to_string(), time: t1, price: c.low_price, kind: "Bottom".to_string() }),
+                _ => {}
             }
         }
     }
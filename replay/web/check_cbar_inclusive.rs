@@ -1,20 +1,20 @@
+use chrono::DateTime;
 use serde::Deserialize;
 use std::fs::File;
 use std::io::BufReader;
+use struxis::validation::validate_cbars;
+use struxis::{CBar, FractalType, InvariantRule};
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct CBarCandle {
     id: u64,
-    time: u64,
+    time: i64,
     open: f64,
     high: f64,
     low: f64,
     close: f64,
-    fractal: String,
-}
-
-fn is_inclusive(a: &CBarCandle, b: &CBarCandle) -> bool {
-    (a.high >= b.high && a.low <= b.low) || (a.high <= b.high && a.low >= b.low)
+    fractal: FractalType,
 }
 
 fn main() {
@@ -23,19 +23,28 @@ fn main() {
     let data: serde_json::Value = serde_json::from_reader(reader).expect("parse json");
     let cbar_candles: Vec<CBarCandle> = serde_json::from_value(data["cbar_candles"].clone()).expect("parse cbar_candles");
 
-    let mut found = false;
-    for pair in cbar_candles.windows(2) {
-        let a = &pair[0];
-        let b = &pair[1];
-        if is_inclusive(a, b) {
-            found = true;
-            println!(
-                "包含关系: #{} ({}-{}) [{}] 和 #{} ({}-{}) [{}]",
-                a.id, a.high, a.low, a.time, b.id, b.high, b.low, b.time
-            );
+    // 导出数据按 SBar 展开，同一 CBar 连续出现多次；SBar 序号以蜡烛下标代替
+    let mut cbars: Vec<CBar> = Vec::new();
+    for (i, candle) in cbar_candles.iter().enumerate() {
+        match cbars.last_mut() {
+            Some(last) if last.id == Some(candle.id) => last.sbar_end_id = i as u64,
+            _ => cbars.push(CBar {
+                id: Some(candle.id),
+                sbar_start_id: i as u64,
+                sbar_end_id: i as u64,
+                high_price: candle.high,
+                low_price: candle.low,
+                fractal_type: candle.fractal,
+                created_at: DateTime::from_timestamp(candle.time, 0).unwrap_or_default(),
+            }),
         }
     }
-    if !found {
+
+    let violations = validate_cbars(&cbars);
+    for violation in &violations {
+        println!("{violation}");
+    }
+    if !violations.iter().any(|x| x.rule == InvariantRule::CBarInclusive) {
         println!("未发现包含关系，归约正确");
     }
-}
\ No newline at end of file
+}
@@ -40,8 +40,7 @@ pub fn run_live_with_mode(mode: RuntimeMode) {
 	let mut monitor_subs = symbols
 		.iter()
 		.filter(|symbol| *symbol != &primary_symbol)
-		.cloned()
-		.map(|symbol| (symbol.clone(), feed.subscribe(&symbol, 300)))
+		.map(|symbol| (symbol.clone(), feed.subscribe(symbol, 300)))
 		.collect::<Vec<_>>();
 
 	let published = match mode {
@@ -212,7 +212,10 @@ impl DecisionEngine {
 #[cfg(test)]
 mod tests {
     use struxis::constant::Direction;
-    use struxis::{KeyZoneBehavior, SupplyDemandFactors, SupplyDemandResult, SupplyDemandStage};
+    use struxis::{
+        KeyZoneBehavior, SupplyDemandAnchor, SupplyDemandFactors, SupplyDemandResult,
+        SupplyDemandStage,
+    };
 
     use super::{DecisionAction, DecisionContext, DecisionEngine, PositionSide};
 
@@ -226,6 +229,7 @@ mod tests {
             },
             factors: SupplyDemandFactors::default(),
             explanation: "test".to_string(),
+            anchor: SupplyDemandAnchor::default(),
         }
     }
 
@@ -2,8 +2,8 @@ mod decision;
 
 use market::{Bar, SharedBar};
 use struxis::{
-	AnalysisEngine, Direction, KeyZoneBehavior, SupplyDemandFactors, SupplyDemandResult,
-	SupplyDemandStage, Timeframe,
+	AnalysisEngine, Direction, KeyZoneBehavior, SupplyDemandAnchor, SupplyDemandFactors,
+	SupplyDemandResult, SupplyDemandStage, Timeframe,
 };
 
 pub use decision::{DecisionAction, DecisionContext, DecisionEngine, DecisionResult, PositionSide};
@@ -101,5 +101,6 @@ fn default_sd() -> SupplyDemandResult {
 		stage: SupplyDemandStage::Failed,
 		factors: SupplyDemandFactors::default(),
 		explanation: "no sd".to_string(),
+		anchor: SupplyDemandAnchor::default(),
 	}
 }
@@ -1,8 +1,9 @@
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
 use crate::constant::{FractalType, Timeframe};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SBar {
     pub id: Option<u64>,
     pub symbol: String,
@@ -36,7 +37,7 @@ impl SBar {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CBar {
     pub id: Option<u64>,
     pub sbar_start_id: u64,
@@ -54,7 +55,7 @@ impl CBar {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fractal {
     pub left: CBar,
     pub middle: CBar,
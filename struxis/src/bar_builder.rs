@@ -0,0 +1,317 @@
+//! 信息驱动 bar 构造器。
+//!
+//! 按价格波动（range / renko）、成交量或 tick 笔数切分 bar，输出的 `SBar` 以伪周期
+//! `Timeframe::Bars` 标记，可像时间周期一样注册到 `MultiTimeframeContext`。
+//! 时间早于上一笔的 tick 直接忽略；单笔 tick 的成交量不拆分到多根 bar。
+
+use chrono::{DateTime, Utc};
+
+use crate::bar::SBar;
+use crate::constant::{BarKind, Timeframe};
+use crate::tick::{CumulativeVolume, TickInput};
+
+pub trait BarBuilder: Send {
+    fn timeframe(&self) -> Timeframe;
+
+    /// 返回本笔 tick 完成的 bar，按先后排列。
+    fn update(&mut self, tick: &TickInput) -> Vec<SBar>;
+
+    /// 输出未完成的 bar；renko 没有未完成的砖，返回 `None`。
+    fn flush(&mut self) -> Option<SBar>;
+}
+
+/// 按伪周期创建构造器；`tick_size` 为最小变动价位，仅 range / renko 使用。
+/// 非伪周期或 `tick_size` 非正时返回 `None`。
+pub fn bar_builder(timeframe: Timeframe, tick_size: f64) -> Option<Box<dyn BarBuilder>> {
+    let Timeframe::Bars(kind, n) = timeframe else {
+        return None;
+    };
+    if n == 0 || tick_size <= 0.0 {
+        return None;
+    }
+    Some(match kind {
+        BarKind::Range => Box::new(RangeBarBuilder::new(n, tick_size)),
+        BarKind::Renko => Box::new(RenkoBarBuilder::new(n, tick_size)),
+        BarKind::Volume => Box::new(VolumeBarBuilder::new(n)),
+        BarKind::TickCount => Box::new(TickCountBarBuilder::new(n)),
+    })
+}
+
+/// 各构造器共用的 tick 过滤与 OHLCV 累计。
+struct Forming {
+    timeframe: Timeframe,
+    cumulative: CumulativeVolume,
+    last_tick_at: Option<DateTime<Utc>>,
+    current: Option<SBar>,
+    ticks: u32,
+}
+
+impl Forming {
+    fn new(timeframe: Timeframe) -> Self {
+        Self {
+            timeframe,
+            cumulative: CumulativeVolume::default(),
+            last_tick_at: None,
+            current: None,
+            ticks: 0,
+        }
+    }
+
+    /// 按序且价格有效的 tick 返回其成交量、成交额增量，否则返回 `None`。
+    fn accept(&mut self, tick: &TickInput) -> Option<(f64, f64)> {
+        if !tick.last_price.is_finite() || tick.last_price <= 0.0 {
+            return None;
+        }
+        if self.last_tick_at.is_some_and(|x| tick.datetime < x) {
+            return None;
+        }
+        self.last_tick_at = Some(tick.datetime);
+        let (volume, turnover, _) = self.cumulative.delta(tick);
+        Some((volume, turnover))
+    }
+
+    fn add(&mut self, tick: &TickInput, volume: f64, turnover: f64) {
+        self.ticks += 1;
+        match self.current.as_mut() {
+            Some(bar) => {
+                bar.high_price = bar.high_price.max(tick.last_price);
+                bar.low_price = bar.low_price.min(tick.last_price);
+                bar.close_price = tick.last_price;
+                bar.volume += volume;
+                bar.turnover += turnover;
+                bar.open_interest = tick.open_interest;
+            }
+            None => {
+                self.current = Some(SBar {
+                    id: None,
+                    symbol: tick.symbol.clone(),
+                    exchange: tick.exchange.clone(),
+                    timeframe: self.timeframe,
+                    datetime: tick.datetime,
+                    open_price: tick.last_price,
+                    high_price: tick.last_price,
+                    low_price: tick.last_price,
+                    close_price: tick.last_price,
+                    volume,
+                    open_interest: tick.open_interest,
+                    turnover,
+                });
+            }
+        }
+    }
+
+    fn close(&mut self) -> Option<SBar> {
+        self.ticks = 0;
+        self.current.take()
+    }
+}
+
+/// 高低价差达到 `size` 即收盘；会使价差超过 `size` 的 tick 先收掉当前 bar 再开新 bar，
+/// 因此每根 bar 的价差不超过 `size`（单笔跳空除外）。
+pub struct RangeBarBuilder {
+    forming: Forming,
+    size: f64,
+}
+
+impl RangeBarBuilder {
+    /// 价差阈值为 `ticks` 个 `tick_size`。
+    pub fn new(ticks: u32, tick_size: f64) -> Self {
+        Self {
+            forming: Forming::new(Timeframe::Bars(BarKind::Range, ticks)),
+            size: ticks as f64 * tick_size,
+        }
+    }
+}
+
+impl BarBuilder for RangeBarBuilder {
+    fn timeframe(&self) -> Timeframe {
+        self.forming.timeframe
+    }
+
+    fn update(&mut self, tick: &TickInput) -> Vec<SBar> {
+        let Some((volume, turnover)) = self.forming.accept(tick) else {
+            return Vec::new();
+        };
+        let eps = self.size * 1e-9;
+        let price = tick.last_price;
+        let mut closed = Vec::new();
+        if let Some(bar) = self.forming.current.as_ref() {
+            let range = bar.high_price.max(price) - bar.low_price.min(price);
+            if range > self.size + eps {
+                closed.extend(self.forming.close());
+            }
+        }
+        self.forming.add(tick, volume, turnover);
+        if let Some(bar) = self.forming.current.as_ref()
+            && bar.high_price - bar.low_price >= self.size - eps
+        {
+            closed.extend(self.forming.close());
+        }
+        closed
+    }
+
+    fn flush(&mut self) -> Option<SBar> {
+        self.forming.close()
+    }
+}
+
+/// 经典 renko：同向走出一块砖、反向走出两块砖才生成新砖，砖的 OHLC 只含砖体。
+/// 一笔 tick 可生成多块砖，自上一块砖以来的成交量计入其中第一块。
+pub struct RenkoBarBuilder {
+    forming: Forming,
+    brick: f64,
+    anchor: Option<f64>,
+    direction: i8,
+    volume: f64,
+    turnover: f64,
+}
+
+impl RenkoBarBuilder {
+    /// 砖高为 `ticks` 个 `tick_size`。
+    pub fn new(ticks: u32, tick_size: f64) -> Self {
+        Self {
+            forming: Forming::new(Timeframe::Bars(BarKind::Renko, ticks)),
+            brick: ticks as f64 * tick_size,
+            anchor: None,
+            direction: 0,
+            volume: 0.0,
+            turnover: 0.0,
+        }
+    }
+
+    fn brick(&mut self, tick: &TickInput, open: f64, close: f64) -> SBar {
+        self.anchor = Some(close);
+        self.direction = if close > open { 1 } else { -1 };
+        SBar {
+            id: None,
+            symbol: tick.symbol.clone(),
+            exchange: tick.exchange.clone(),
+            timeframe: self.forming.timeframe,
+            datetime: tick.datetime,
+            open_price: open,
+            high_price: open.max(close),
+            low_price: open.min(close),
+            close_price: close,
+            volume: std::mem::take(&mut self.volume),
+            open_interest: tick.open_interest,
+            turnover: std::mem::take(&mut self.turnover),
+        }
+    }
+}
+
+impl BarBuilder for RenkoBarBuilder {
+    fn timeframe(&self) -> Timeframe {
+        self.forming.timeframe
+    }
+
+    fn update(&mut self, tick: &TickInput) -> Vec<SBar> {
+        let Some((volume, turnover)) = self.forming.accept(tick) else {
+            return Vec::new();
+        };
+        self.volume += volume;
+        self.turnover += turnover;
+        let price = tick.last_price;
+        let Some(mut anchor) = self.anchor else {
+            self.anchor = Some(price);
+            return Vec::new();
+        };
+
+        let (brick, eps) = (self.brick, self.brick * 1e-9);
+        let mut bricks = Vec::new();
+        loop {
+            let up = self.direction >= 0 && price >= anchor + brick - eps;
+            let down = self.direction <= 0 && price <= anchor - brick + eps;
+            let bar = if up {
+                self.brick(tick, anchor, anchor + brick)
+            } else if down {
+                self.brick(tick, anchor, anchor - brick)
+            } else if self.direction > 0 && price <= anchor - 2.0 * brick + eps {
+                self.brick(tick, anchor - brick, anchor - 2.0 * brick)
+            } else if self.direction < 0 && price >= anchor + 2.0 * brick - eps {
+                self.brick(tick, anchor + brick, anchor + 2.0 * brick)
+            } else {
+                break;
+            };
+            anchor = bar.close_price;
+            bricks.push(bar);
+        }
+        bricks
+    }
+
+    fn flush(&mut self) -> Option<SBar> {
+        None
+    }
+}
+
+/// 累计成交量达到阈值即收盘。
+pub struct VolumeBarBuilder {
+    forming: Forming,
+    threshold: f64,
+}
+
+impl VolumeBarBuilder {
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            forming: Forming::new(Timeframe::Bars(BarKind::Volume, threshold)),
+            threshold: threshold as f64,
+        }
+    }
+}
+
+impl BarBuilder for VolumeBarBuilder {
+    fn timeframe(&self) -> Timeframe {
+        self.forming.timeframe
+    }
+
+    fn update(&mut self, tick: &TickInput) -> Vec<SBar> {
+        let Some((volume, turnover)) = self.forming.accept(tick) else {
+            return Vec::new();
+        };
+        self.forming.add(tick, volume, turnover);
+        match self.forming.current.as_ref() {
+            Some(bar) if bar.volume >= self.threshold => self.forming.close().into_iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn flush(&mut self) -> Option<SBar> {
+        self.forming.close()
+    }
+}
+
+/// 每 N 笔 tick 收盘。
+pub struct TickCountBarBuilder {
+    forming: Forming,
+    count: u32,
+}
+
+impl TickCountBarBuilder {
+    pub fn new(count: u32) -> Self {
+        Self {
+            forming: Forming::new(Timeframe::Bars(BarKind::TickCount, count)),
+            count,
+        }
+    }
+}
+
+impl BarBuilder for TickCountBarBuilder {
+    fn timeframe(&self) -> Timeframe {
+        self.forming.timeframe
+    }
+
+    fn update(&mut self, tick: &TickInput) -> Vec<SBar> {
+        let Some((volume, turnover)) = self.forming.accept(tick) else {
+            return Vec::new();
+        };
+        self.forming.add(tick, volume, turnover);
+        if self.forming.ticks >= self.count {
+            self.forming.close().into_iter().collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn flush(&mut self) -> Option<SBar> {
+        self.forming.close()
+    }
+}
@@ -0,0 +1,484 @@
+//! SD 权重校准。
+//!
+//! 在历史 SBar 上运行完整管线并逐根记录 SD 因子，以前瞻收益与后验 swing 方向为标签：
+//! - 按时间先后切分样本内与样本外，分界处丢弃 `horizon` 个样本，避免前瞻收益跨越分界；
+//! - 在样本内搜索层权重与因子权重，目标为 score 与前瞻收益的相关系数及 swing 方向命中率；
+//! - 阶段阈值取样本内 `|score|` 的分位数；
+//! - 输出可由 `SupplyDemandProfileConfig` 直接加载的 profile YAML，附样本内外统计。
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+
+use crate::bar::SBar;
+use crate::constant::{DataError, Direction, IdStrategy, Timeframe};
+use crate::mtc::MultiTimeframeContext;
+use crate::sd::{SupplyDemand, SupplyDemandConfig, SupplyDemandFactors};
+use crate::utils::Rng;
+
+#[derive(Debug, Clone)]
+pub struct CalibrationConfig {
+    /// 前瞻收益的 SBar 数。
+    pub horizon: usize,
+    /// 管线预热的 SBar 数，期间不取样本。
+    pub warmup: usize,
+    /// 样本内占比，其余按时间顺序作为样本外（分界后的 `horizon` 个样本不使用）。
+    pub train_ratio: f64,
+    /// 搜索的候选组数：前一半随机抽样，后一半在当前最优附近扰动。
+    pub iterations: usize,
+    pub seed: u64,
+    /// 目标函数中 swing 方向命中率的权重，其余为相关系数。
+    pub swing_weight: f64,
+    /// Stable/Weakening/Critical 阈值取样本内 `|score|` 的这些分位数。
+    pub stage_quantiles: [f64; 3],
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        Self {
+            horizon: 8,
+            warmup: 30,
+            train_ratio: 0.7,
+            iterations: 2000,
+            seed: 7,
+            swing_weight: 0.5,
+            stage_quantiles: [0.8, 0.5, 0.2],
+        }
+    }
+}
+
+/// 一根 SBar 收盘时的 SD 因子及其事后标签。
+#[derive(Debug, Clone)]
+pub struct CalibrationSample {
+    pub sbar_id: Option<u64>,
+    pub factors: SupplyDemandFactors,
+    /// `horizon` 根之后的收盘相对当前收盘的收益率。
+    pub forward_return: f64,
+    /// 全部数据处理完后，当前 SBar 之后价格所处 swing 的方向；尚无 swing 覆盖时为 `None`。
+    pub swing_direction: Direction,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StageStats {
+    pub samples: usize,
+    /// 该阶段 score 方向与前瞻收益方向一致的比例。
+    pub hit_rate: f64,
+    /// 按 score 方向计的平均前瞻收益。
+    pub mean_return: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CalibrationStats {
+    pub samples: usize,
+    /// score 与前瞻收益的 Pearson 相关系数。
+    pub ic: f64,
+    /// score 非零的样本中方向与前瞻收益一致的比例。
+    pub hit_rate: f64,
+    /// score 非零且有 swing 标签的样本中方向与 swing 一致的比例。
+    pub swing_hit_rate: f64,
+    pub objective: f64,
+    /// 键为阶段名（stable/weakening/critical/failed）。
+    pub stages: BTreeMap<String, StageStats>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CalibrationReport {
+    pub symbol: String,
+    pub timeframe: Timeframe,
+    pub horizon: usize,
+    /// 校准后的完整配置，非权重与阈值的字段沿用输入配置。
+    pub config: SupplyDemandConfig,
+    pub baseline_in_sample: CalibrationStats,
+    pub baseline_out_of_sample: CalibrationStats,
+    pub in_sample: CalibrationStats,
+    pub out_of_sample: CalibrationStats,
+}
+
+/// 写入 profile 的校准结果：只覆盖权重与阈值。
+#[derive(Serialize)]
+struct CalibratedPatch {
+    layer1_weight: f64,
+    layer2_weight: f64,
+    layer3_weight: f64,
+    f1_weight: f64,
+    f2_weight: f64,
+    f3_weight: f64,
+    f4_weight: f64,
+    f5_weight: f64,
+    f6_weight: f64,
+    f7_weight: f64,
+    f8_weight: f64,
+    f9_weight: f64,
+    stable_threshold: f64,
+    weakening_threshold: f64,
+    critical_threshold: f64,
+}
+
+#[derive(Serialize)]
+struct CalibrationSection<'a> {
+    horizon: usize,
+    baseline_in_sample: &'a CalibrationStats,
+    baseline_out_of_sample: &'a CalibrationStats,
+    in_sample: &'a CalibrationStats,
+    out_of_sample: &'a CalibrationStats,
+}
+
+/// 以 `base` 配置逐根运行管线并打标签，`sbars` 需为同一 timeframe、时间升序。
+pub fn collect_samples(
+    symbol: &str,
+    timeframe: Timeframe,
+    sbars: &[SBar],
+    base: &SupplyDemandConfig,
+    config: &CalibrationConfig,
+) -> Vec<CalibrationSample> {
+    let mut mtc = MultiTimeframeContext::with_id_strategy(symbol, IdStrategy::Sequential);
+    mtc.register(timeframe);
+    mtc.set_sd_config(timeframe, base.clone());
+    let mut recorded = Vec::with_capacity(sbars.len());
+    for sbar in sbars {
+        mtc.append(timeframe, sbar.clone());
+        if let (Some(last), Some(sd)) = (
+            mtc.get_sbar_window(timeframe, 1).pop(),
+            mtc.get_sd(timeframe),
+        ) {
+            recorded.push((last.id, sd.factors.clone()));
+        }
+    }
+
+    let rows = mtc.get_sbar_window(timeframe, usize::MAX);
+    let index_of = rows
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, x)| x.id.map(|id| (id, idx)))
+        .collect::<HashMap<_, _>>();
+    // 每根 SBar 之后价格所处的 swing：起点 <= idx < 终点
+    let mut swing_directions = vec![Direction::None; rows.len()];
+    for swing in mtc.get_swing_window(timeframe, usize::MAX) {
+        let (Some(start), Some(end)) = (
+            index_of.get(&swing.sbar_start_id),
+            index_of.get(&swing.sbar_end_id),
+        ) else {
+            continue;
+        };
+        for direction in &mut swing_directions[*start..*end] {
+            *direction = swing.direction;
+        }
+    }
+
+    let horizon = config.horizon.max(1);
+    recorded
+        .into_iter()
+        .filter_map(|(sbar_id, factors)| {
+            let idx = *index_of.get(&sbar_id?)?;
+            let close = rows[idx].close_price;
+            let future = rows.get(idx + horizon)?;
+            (idx >= config.warmup && close.abs() > f64::EPSILON).then(|| CalibrationSample {
+                sbar_id,
+                factors,
+                forward_return: (future.close_price - close) / close,
+                swing_direction: swing_directions[idx],
+            })
+        })
+        .collect()
+}
+
+/// 在样本内搜索权重与阈值，返回校准配置及其与 `base` 的样本内外对比。
+///
+/// 样本外跳过分界后的 `horizon` 个样本，其标签与样本内最后的前瞻收益重叠。
+/// 样本内或样本外不足 10 个样本时返回 `DataError::InsufficientSamples`。
+pub fn calibrate(
+    symbol: &str,
+    timeframe: Timeframe,
+    sbars: &[SBar],
+    base: &SupplyDemandConfig,
+    config: &CalibrationConfig,
+) -> Result<CalibrationReport, DataError> {
+    let samples = collect_samples(symbol, timeframe, sbars, base, config);
+    let split = (samples.len() as f64 * config.train_ratio.clamp(0.0, 1.0)) as usize;
+    let (train, test) = samples.split_at(split);
+    let test = &test[config.horizon.max(1).min(test.len())..];
+    if train.len() < 10 || test.len() < 10 {
+        return Err(DataError::InsufficientSamples(samples.len()));
+    }
+
+    let objective = |weights: &[f64; 12]| {
+        let model = SupplyDemand::with_config(with_weights(base, weights));
+        evaluate(&model, train, config.swing_weight).objective
+    };
+    let mut rng = Rng::new(config.seed);
+    let mut best = normalize(weights_of(base));
+    let mut best_objective = objective(&best);
+    let explore = config.iterations / 2;
+    for iteration in 0..config.iterations {
+        let candidate = if iteration < explore {
+            normalize(std::array::from_fn(|_| rng.next_f64()))
+        } else {
+            normalize(std::array::from_fn(|i| {
+                (best[i] + (rng.next_f64() - 0.5) * 0.2).max(0.0)
+            }))
+        };
+        let candidate_objective = objective(&candidate);
+        if candidate_objective > best_objective {
+            best = candidate;
+            best_objective = candidate_objective;
+        }
+    }
+
+    let mut calibrated = with_weights(base, &best);
+    let model = SupplyDemand::with_config(calibrated.clone());
+    let mut magnitudes = train
+        .iter()
+        .map(|x| model.score_factors(&x.factors).abs())
+        .collect::<Vec<_>>();
+    magnitudes.sort_by(f64::total_cmp);
+    let [stable, weakening, critical] = config.stage_quantiles.map(|q| quantile(&magnitudes, q));
+    calibrated.stable_threshold = stable;
+    calibrated.weakening_threshold = weakening.min(stable);
+    calibrated.critical_threshold = critical.min(calibrated.weakening_threshold);
+
+    let baseline = SupplyDemand::with_config(base.clone());
+    let model = SupplyDemand::with_config(calibrated.clone());
+    Ok(CalibrationReport {
+        symbol: symbol.to_string(),
+        timeframe,
+        horizon: config.horizon,
+        baseline_in_sample: evaluate(&baseline, train, config.swing_weight),
+        baseline_out_of_sample: evaluate(&baseline, test, config.swing_weight),
+        in_sample: evaluate(&model, train, config.swing_weight),
+        out_of_sample: evaluate(&model, test, config.swing_weight),
+        config: calibrated,
+    })
+}
+
+impl CalibrationReport {
+    /// profile 中的键，如 `I8888.15m`；symbol 取交易所之前的部分。
+    pub fn profile_key(&self) -> String {
+        let code = self.symbol.split('.').next().unwrap_or_default();
+        format!("{code}.{}", self.timeframe.as_str())
+    }
+
+    /// 把校准结果写入 `path` 的 `symbol_timeframe` 节，统计写入 `calibration` 节。
+    ///
+    /// 文件已存在时只替换同一键，其余内容保留。重写无法保留注释，
+    /// 因此含注释的文件（通常是手工维护的 profile）返回 `DataError::ProfileHasComments`，不做修改。
+    pub fn write_profile(&self, path: impl AsRef<Path>) -> Result<(), DataError> {
+        let path = path.as_ref();
+        let mut root = if path.exists() {
+            let raw = fs::read_to_string(path)?;
+            if raw
+                .lines()
+                .any(|x| x.trim_start().starts_with('#') || x.contains(" #"))
+            {
+                return Err(DataError::ProfileHasComments(path.display().to_string()));
+            }
+            match serde_yaml::from_str::<Value>(&raw)? {
+                Value::Mapping(x) => x,
+                _ => Mapping::new(),
+            }
+        } else {
+            Mapping::new()
+        };
+
+        let key = Value::from(self.profile_key());
+        let config = &self.config;
+        let patch = CalibratedPatch {
+            layer1_weight: config.layer1_weight,
+            layer2_weight: config.layer2_weight,
+            layer3_weight: config.layer3_weight,
+            f1_weight: config.f1_weight,
+            f2_weight: config.f2_weight,
+            f3_weight: config.f3_weight,
+            f4_weight: config.f4_weight,
+            f5_weight: config.f5_weight,
+            f6_weight: config.f6_weight,
+            f7_weight: config.f7_weight,
+            f8_weight: config.f8_weight,
+            f9_weight: config.f9_weight,
+            stable_threshold: config.stable_threshold,
+            weakening_threshold: config.weakening_threshold,
+            critical_threshold: config.critical_threshold,
+        };
+        let section = CalibrationSection {
+            horizon: self.horizon,
+            baseline_in_sample: &self.baseline_in_sample,
+            baseline_out_of_sample: &self.baseline_out_of_sample,
+            in_sample: &self.in_sample,
+            out_of_sample: &self.out_of_sample,
+        };
+        for (name, value) in [
+            ("symbol_timeframe", serde_yaml::to_value(patch)?),
+            ("calibration", serde_yaml::to_value(section)?),
+        ] {
+            let entry = root
+                .entry(Value::from(name))
+                .or_insert_with(|| Value::Mapping(Mapping::new()));
+            if !entry.is_mapping() {
+                *entry = Value::Mapping(Mapping::new());
+            }
+            if let Value::Mapping(map) = entry {
+                map.insert(key.clone(), value);
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_yaml::to_string(&Value::Mapping(root))?)?;
+        Ok(())
+    }
+}
+
+fn evaluate(
+    model: &SupplyDemand,
+    samples: &[CalibrationSample],
+    swing_weight: f64,
+) -> CalibrationStats {
+    let scored = samples
+        .iter()
+        .map(|x| (model.score_factors(&x.factors), x))
+        .collect::<Vec<_>>();
+    let ic = correlation(scored.iter().map(|(score, x)| (*score, x.forward_return)));
+    let hit_rate = ratio(
+        scored
+            .iter()
+            .filter(|(score, _)| *score != 0.0)
+            .map(|(score, x)| score.signum() * x.forward_return > 0.0),
+    );
+    let swing_hit_rate = ratio(
+        scored
+            .iter()
+            .filter(|(score, x)| *score != 0.0 && x.swing_direction != Direction::None)
+            .map(|(score, x)| (*score > 0.0) == (x.swing_direction == Direction::Up)),
+    );
+
+    let mut stages = BTreeMap::<String, Vec<(f64, f64)>>::new();
+    for (score, x) in &scored {
+        let stage = format!("{:?}", model.stage(*score)).to_ascii_lowercase();
+        stages
+            .entry(stage)
+            .or_default()
+            .push((*score, x.forward_return));
+    }
+    let stages = stages
+        .into_iter()
+        .map(|(stage, rows)| {
+            let signed = rows
+                .iter()
+                .map(|(score, ret)| score.signum() * ret)
+                .collect::<Vec<_>>();
+            let stats = StageStats {
+                samples: rows.len(),
+                hit_rate: ratio(signed.iter().map(|x| *x > 0.0)),
+                mean_return: signed.iter().sum::<f64>() / signed.len() as f64,
+            };
+            (stage, stats)
+        })
+        .collect();
+
+    let swing_weight = swing_weight.clamp(0.0, 1.0);
+    CalibrationStats {
+        samples: samples.len(),
+        ic,
+        hit_rate,
+        swing_hit_rate,
+        objective: (1.0 - swing_weight) * ic + swing_weight * (2.0 * swing_hit_rate - 1.0),
+        stages,
+    }
+}
+
+/// 顺序：layer1-3，f1-f9。
+fn weights_of(config: &SupplyDemandConfig) -> [f64; 12] {
+    [
+        config.layer1_weight,
+        config.layer2_weight,
+        config.layer3_weight,
+        config.f1_weight,
+        config.f2_weight,
+        config.f3_weight,
+        config.f4_weight,
+        config.f5_weight,
+        config.f6_weight,
+        config.f7_weight,
+        config.f8_weight,
+        config.f9_weight,
+    ]
+}
+
+fn with_weights(base: &SupplyDemandConfig, weights: &[f64; 12]) -> SupplyDemandConfig {
+    SupplyDemandConfig {
+        layer1_weight: weights[0],
+        layer2_weight: weights[1],
+        layer3_weight: weights[2],
+        f1_weight: weights[3],
+        f2_weight: weights[4],
+        f3_weight: weights[5],
+        f4_weight: weights[6],
+        f5_weight: weights[7],
+        f6_weight: weights[8],
+        f7_weight: weights[9],
+        f8_weight: weights[10],
+        f9_weight: weights[11],
+        ..base.clone()
+    }
+}
+
+/// 层权重之和、每层内因子权重之和各归一为 1，与默认配置的约定一致。
+fn normalize(mut weights: [f64; 12]) -> [f64; 12] {
+    for group in weights.chunks_mut(3) {
+        let sum = group.iter().map(|x| x.max(0.0)).sum::<f64>();
+        for weight in group {
+            *weight = if sum > f64::EPSILON {
+                weight.max(0.0) / sum
+            } else {
+                1.0 / 3.0
+            };
+        }
+    }
+    weights
+}
+
+fn correlation(pairs: impl Iterator<Item = (f64, f64)>) -> f64 {
+    let pairs = pairs.collect::<Vec<_>>();
+    let n = pairs.len() as f64;
+    if pairs.len() < 2 {
+        return 0.0;
+    }
+    let mean_x = pairs.iter().map(|x| x.0).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|x| x.1).sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in &pairs {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    let denom = (var_x * var_y).sqrt();
+    if denom <= f64::EPSILON {
+        0.0
+    } else {
+        cov / denom
+    }
+}
+
+fn ratio(flags: impl Iterator<Item = bool>) -> f64 {
+    let (hits, total) = flags.fold((0usize, 0usize), |(hits, total), x| {
+        (hits + x as usize, total + 1)
+    });
+    if total == 0 {
+        0.0
+    } else {
+        hits as f64 / total as f64
+    }
+}
+
+/// `sorted` 升序，线性插值。
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let pos = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lower, upper) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64)
+}
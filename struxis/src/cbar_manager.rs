@@ -3,105 +3,109 @@
 //! 负责：
 //! - 基于 SBar 增量构建 CBar；
 //! - 处理包含关系与回溯链；
-//! - 分型检测与 dataframe cache 维护。
+//! - 分型检测与 dataframe 导出；
+//! - 每根 SBar 只处理尾部变化区间，单次更新与历史长度无关。
+
+use std::sync::Arc;
 
 use chrono::Utc;
 use polars::df;
 use polars::prelude::DataFrame;
+use serde::{Deserialize, Serialize};
 
 use crate::IdGenerator;
 use crate::bar::{CBar, Fractal, SBar};
 use crate::constant::{Direction, FractalType, Timeframe};
-use crate::utils::{approx_eq_f64, first_changed_id};
+use crate::utils::{
+    RowChanges, approx_eq_f64, diff_rows_by_id, first_changed_id, first_changed_index,
+};
 
+#[derive(Serialize, Deserialize)]
 pub(crate) struct CBarManager {
     rows: Vec<CBar>,
-    id_generator: &'static IdGenerator,
-    df_cache: DataFrame,
+    #[serde(skip, default = "crate::id_generator::cbar_id_generator")]
+    id_generator: Arc<IdGenerator>,
     backtrack_id: Option<u64>,
+    backtrack_index: usize,
+    /// 最近一次 `on_sbar` 前自 `replaced_start` 起的行（含被合并的 CBar）。
+    #[serde(skip)]
+    replaced: Vec<CBar>,
+    #[serde(skip)]
+    replaced_start: usize,
 }
 
- 
 impl CBarManager {
-    pub(crate) fn new(timeframe: Timeframe) -> Self {
+    pub(crate) fn new(timeframe: Timeframe, id_generator: Arc<IdGenerator>) -> Self {
         let _ = timeframe;
         Self {
             rows: Vec::new(),
-            id_generator: crate::id_generator::cbar_id_generator(),
-            df_cache: DataFrame::default(),
+            id_generator,
             backtrack_id: None,
+            backtrack_index: 0,
+            replaced: Vec::new(),
+            replaced_start: 0,
         }
     }
 
+    pub(crate) fn set_id_generator(&mut self, id_generator: Arc<IdGenerator>) {
+        self.id_generator = id_generator;
+    }
+
     pub(crate) fn on_sbar(&mut self, sbar: &SBar) -> CBar {
-        let previous_rows = self.rows.clone();
         self.backtrack_id = None;
 
         let curr_id = sbar.id.unwrap_or_default();
         let curr_high = sbar.high_price;
         let curr_low = sbar.low_price;
 
-        // 1. 先插入新cbar
-        let new_cbar = CBar {
-            id: None, // id稍后分配
-            sbar_start_id: curr_id,
-            sbar_end_id: curr_id,
-            high_price: curr_high,
-            low_price: curr_low,
-            fractal_type: crate::constant::FractalType::None,
-            created_at: chrono::Utc::now(),
-        };
-        self.rows.push(new_cbar.clone());
+        // 1. 持续向前合并，直到与前一 CBar 无包含关系；被合并的 CBar 按原顺序收集在 popped 中
+        let (start_id, merged_high, merged_low, created_at, popped) =
+            self.merge_with_last_if_needed(curr_id, curr_high, curr_low);
+        let kept = self.rows.len();
 
-        // 2. 使用 helper 进行持续向前合并，直到无包含关系
-        // merge_with_last_if_needed 会弹出被包含的项并返回合并后的 start_id/high/low/created_at
-        let last = self.rows.last().cloned().expect("just pushed");
-        let (start_id, merged_high, merged_low, created_at) =
-            self.merge_with_last_if_needed(&last, curr_id, curr_high, curr_low);
+        // 2. 仅有 kept-1 之后的行可能变化（kept-1 的分型依赖新的右侧 CBar）
+        let tail_start = kept.saturating_sub(1);
+        let mut previous_tail: Vec<CBar> = self.rows[tail_start..].to_vec();
+        previous_tail.extend(popped);
 
-        // push 合并结果（无论是否真的合并，helper 会弹出原来的条目并返回合并范围）
+        // 3. push 合并结果，并分配 id，保证唯一递增
         let merged = CBar {
-            id: None,
+            id: Some(self.id_generator.get_id()),
             sbar_start_id: start_id,
             sbar_end_id: curr_id,
             high_price: merged_high,
             low_price: merged_low,
-            fractal_type: crate::constant::FractalType::None,
+            fractal_type: FractalType::None,
             created_at,
         };
         self.rows.push(merged);
 
-        // 3. 分配id，保证唯一递增
-        for cbar in &mut self.rows {
-            if cbar.id.is_none() {
-                cbar.id = Some(self.id_generator.get_id());
-            }
-        }
-
-        self.recompute_fractals();
-        self.backtrack_id = first_backtrack_cbar_id(&previous_rows, &self.rows);
-        self.rebuild_cache();
+        self.recompute_fractals_from(tail_start);
+        let current_tail = &self.rows[tail_start..];
+        self.backtrack_id = first_backtrack_cbar_id(&previous_tail, current_tail);
+        self.backtrack_index =
+            first_changed_index(&previous_tail, current_tail, cbar_eq_wo_created_at, true)
+                .map(|offset| tail_start + offset)
+                .unwrap_or(self.rows.len() - 1);
+        self.replaced = previous_tail;
+        self.replaced_start = tail_start;
         self.rows.last().cloned().expect("cbar must exist")
     }
 
     fn merge_with_last_if_needed(
         &mut self,
-        _last: &CBar,
         curr_id: u64,
         curr_high: f64,
         curr_low: f64,
-    ) -> (u64, f64, f64, chrono::DateTime<Utc>) {
+    ) -> (u64, f64, f64, chrono::DateTime<Utc>, Vec<CBar>) {
         let mut start_id = curr_id;
         let mut merged_high = curr_high;
         let mut merged_low = curr_low;
         let mut created_at = chrono::Utc::now();
+        let mut popped = Vec::new();
 
         // 持续向前合并，直到与前一CBar无包含关系为止
-        loop {
-            if self.rows.is_empty() {
-                break;
-            }
-            let last = self.rows.last().unwrap().clone();
+        while let Some(last) = self.rows.last() {
             if !is_inclusive(last.high_price, last.low_price, merged_high, merged_low) {
                 break;
             }
@@ -111,9 +115,10 @@ impl CBarManager {
             // 合并高低点
             merged_high = merged_high.max(last.high_price);
             merged_low = merged_low.min(last.low_price);
-            self.rows.pop();
+            popped.push(self.rows.pop().expect("last exists"));
         }
-        (start_id, merged_high, merged_low, created_at)
+        popped.reverse();
+        (start_id, merged_high, merged_low, created_at, popped)
     }
 
     #[allow(dead_code)]
@@ -169,6 +174,21 @@ impl CBarManager {
         self.backtrack_id
     }
 
+    /// 本次 `on_sbar` 后第一个发生变化（含新追加）的 CBar 下标。
+    pub(crate) fn backtrack_index(&self) -> usize {
+        self.backtrack_index
+    }
+
+    /// 最近一次 `on_sbar` 的行变化；被合并的 CBar 记为 removed。
+    pub(crate) fn changes(&self) -> RowChanges<'_, CBar> {
+        diff_rows_by_id(
+            &self.replaced,
+            &self.rows[self.replaced_start.min(self.rows.len())..],
+            |x| x.id,
+            cbar_eq_wo_created_at,
+        )
+    }
+
     #[allow(dead_code)]
     fn append_cbar(&mut self, start_id: u64, end_id: u64, high_price: f64, low_price: f64) -> CBar {
         let cbar = CBar {
@@ -200,8 +220,9 @@ impl CBarManager {
         }
     }
 
-    fn recompute_fractals(&mut self) {
-        for row in &mut self.rows {
+    /// 重新计算 `from` 及之后各行的分型；更早的行只依赖未变化的右侧 CBar。
+    fn recompute_fractals_from(&mut self, from: usize) {
+        for row in &mut self.rows[from..] {
             row.fractal_type = FractalType::None;
         }
 
@@ -209,7 +230,7 @@ impl CBarManager {
             return;
         }
 
-        for pivot in 1..(self.rows.len() - 1) {
+        for pivot in from.max(1)..(self.rows.len() - 1) {
             let left = self.rows[pivot - 1].clone();
             let middle = self.rows[pivot].clone();
             let right = self.rows[pivot + 1].clone();
@@ -295,7 +316,9 @@ impl CBarManager {
     }
 
     fn get_index(&self, id: u64) -> Option<usize> {
-        self.rows.iter().position(|x| x.id == Some(id))
+        // CBar id 单调递增，可二分查找
+        let index = self.rows.partition_point(|x| x.id.unwrap_or_default() < id);
+        (self.rows.get(index)?.id == Some(id)).then_some(index)
     }
 
     pub(crate) fn last_n(&self, n: usize) -> Vec<CBar> {
@@ -310,15 +333,11 @@ impl CBarManager {
             .collect::<Vec<_>>()
     }
 
-    pub(crate) fn all_rows(&self) -> Vec<CBar> {
-        self.rows.clone()
+    pub(crate) fn rows(&self) -> &[CBar] {
+        &self.rows
     }
 
     pub(crate) fn dataframe(&self) -> DataFrame {
-        self.df_cache.clone()
-    }
-
-    fn rebuild_cache(&mut self) {
         let ids: Vec<u64> = self.rows.iter().map(|x| x.id.unwrap_or_default()).collect();
         let sbar_start_id: Vec<u64> = self.rows.iter().map(|x| x.sbar_start_id).collect();
         let sbar_end_id: Vec<u64> = self.rows.iter().map(|x| x.sbar_end_id).collect();
@@ -339,7 +358,7 @@ impl CBarManager {
             .map(|x| x.created_at.timestamp_millis())
             .collect();
 
-        self.df_cache = df!(
+        df!(
             "id" => ids,
             "sbar_start_id" => sbar_start_id,
             "sbar_end_id" => sbar_end_id,
@@ -348,7 +367,7 @@ impl CBarManager {
             "fractal_type" => fractal_type,
             "created_at" => created_at
         )
-        .expect("failed to rebuild cbar dataframe cache");
+        .expect("failed to build cbar dataframe")
     }
 }
 
@@ -394,7 +413,7 @@ mod tests {
 
     #[test]
     fn test_multiple_inclusive_merge() {
-        let mut mgr = CBarManager::new(Timeframe::M15);
+        let mut mgr = CBarManager::new(Timeframe::M15, crate::id_generator::cbar_id_generator());
 
         // s1 包含 s2，随后 s3 包含合并后的 cbar -> 最终只有一条 cbar
         let s1 = mk_sbar(1, 10.0, 1.0);
@@ -403,12 +422,12 @@ mod tests {
 
         mgr.on_sbar(&s1);
         // after first, one cbar exists
-        assert_eq!(mgr.all_rows().len(), 1);
+        assert_eq!(mgr.rows().len(), 1);
 
         mgr.on_sbar(&s2);
         // s1 contains s2 -> merged into one cbar
-        assert_eq!(mgr.all_rows().len(), 1);
-        let c = mgr.all_rows()[0].clone();
+        assert_eq!(mgr.rows().len(), 1);
+        let c = mgr.rows()[0].clone();
         assert_eq!(c.sbar_start_id, 1);
         assert_eq!(c.sbar_end_id, 2);
         assert_eq!(c.high_price, 10.0);
@@ -416,8 +435,8 @@ mod tests {
 
         mgr.on_sbar(&s3);
         // s3 contains previous merged -> result should be single cbar covering 1..3
-        assert_eq!(mgr.all_rows().len(), 1);
-        let c2 = mgr.all_rows()[0].clone();
+        assert_eq!(mgr.rows().len(), 1);
+        let c2 = mgr.rows()[0].clone();
         assert_eq!(c2.sbar_start_id, 1);
         assert_eq!(c2.sbar_end_id, 3);
         assert_eq!(c2.high_price, 12.0);
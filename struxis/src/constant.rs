@@ -1,13 +1,17 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FractalType {
     Top,
     Bottom,
     None,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
@@ -25,60 +29,360 @@ impl Direction {
     }
 }
 
+/// 结构事件类型，对应 `EventPayload` 的变体。
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EventType {
     SBarCreated,
-    CBarChanged,
-    SwingChanged,
-    TrendChanged,
+    CBarCreated,
+    CBarMerged,
+    FractalConfirmed,
+    FractalInvalidated,
+    SwingCreated,
+    SwingUpdated,
+    SwingStateChanged,
+    SwingRemoved,
+    TrendCreated,
+    TrendUpdated,
+    TrendCompleted,
+    TrendRemoved,
+    KeyZoneCreated,
+    KeyZoneInvalidated,
+    KeyZoneApproached,
+    KeyZoneTouched,
+    KeyZoneAccepted,
+    KeyZoneRejected,
+    InvariantViolated,
     MtcNewBar,
     TimeframeEnd,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// 信息驱动 bar 的构造方式，与阈值一起组成伪周期 `Timeframe::Bars`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum BarKind {
+    /// 每根 bar 的高低价差达到 N 个最小变动价位。
+    Range,
+    /// 每块砖高 N 个最小变动价位，反转需走出两块砖。
+    Renko,
+    /// 每根 bar 累计成交量达到 N。
+    Volume,
+    /// 每根 bar 含 N 笔 tick。
+    TickCount,
+}
+
+impl BarKind {
+    const ALL: [Self; 4] = [Self::Range, Self::Renko, Self::Volume, Self::TickCount];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Range => "range",
+            Self::Renko => "renko",
+            Self::Volume => "volume",
+            Self::TickCount => "tick",
+        }
+    }
+}
+
+/// bar 周期。
+///
+/// 常用周期为具名变体；`Seconds`/`Minutes` 为自定义的 N 秒 / N 分钟周期（不足一日）。
+/// 请用 `parse`/`from_seconds` 构造，与具名周期等长的自定义值会归一为具名变体。
+/// `Bars` 为由 tick 按价格、成交量或笔数切分的伪周期，没有固定时长。
+/// 排序按名义时长由短到长，伪周期排在最前。反序列化时拒绝长度为 0 的自定义周期。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "TimeframeRepr")]
 pub enum Timeframe {
+    S1,
+    S5,
+    S15,
+    S30,
     M1,
+    M3,
     M5,
     M15,
+    M30,
     H1,
+    H2,
+    H4,
     D1,
+    W1,
+    MN1,
+    Seconds(u32),
+    Minutes(u32),
+    Bars(BarKind, u32),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// 结构链路（CBar -> Swing -> Trend）的计算模式。
+///
+/// - `Batch`：每根 SBar 后全量重建 Swing/Trend；
+/// - `Incremental`：从回溯点回滚并重新推进，输出与 `Batch` 一致。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum StructureMode {
+    Batch,
+    #[default]
+    Incremental,
+}
+
+/// 结构 id（SBar/CBar/Swing/Trend/KeyZone）的分配策略。
+///
+/// - `Snowflake`：墙钟 snowflake，进程内全局共享，实盘默认；
+/// - `Sequential`：每个 context 独立自增，从 1 开始，可复现；
+/// - `Content`：以当前 SBar 的数据时间代替墙钟，可复现且与之前的历史长度无关。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum IdStrategy {
+    #[default]
+    Snowflake,
+    Sequential,
+    Content,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum KeyZoneOrigin {
     Swing,
     Trend,
     Channel,
     Ema,
+    /// 外部 `KeyZoneBuilder` 使用的来源编号，避免与内置来源冲突。
+    Custom(u16),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum KeyZoneOrientation {
     Horizontal,
     Trendline,
     Channel,
 }
 
+/// 与 `Timeframe` 同构，仅用于反序列化后校验。
+#[derive(Deserialize)]
+enum TimeframeRepr {
+    S1,
+    S5,
+    S15,
+    S30,
+    M1,
+    M3,
+    M5,
+    M15,
+    M30,
+    H1,
+    H2,
+    H4,
+    D1,
+    W1,
+    MN1,
+    Seconds(u32),
+    Minutes(u32),
+    Bars(BarKind, u32),
+}
+
+impl TryFrom<TimeframeRepr> for Timeframe {
+    type Error = DataError;
+
+    fn try_from(value: TimeframeRepr) -> Result<Self, Self::Error> {
+        let timeframe = match value {
+            TimeframeRepr::S1 => Self::S1,
+            TimeframeRepr::S5 => Self::S5,
+            TimeframeRepr::S15 => Self::S15,
+            TimeframeRepr::S30 => Self::S30,
+            TimeframeRepr::M1 => Self::M1,
+            TimeframeRepr::M3 => Self::M3,
+            TimeframeRepr::M5 => Self::M5,
+            TimeframeRepr::M15 => Self::M15,
+            TimeframeRepr::M30 => Self::M30,
+            TimeframeRepr::H1 => Self::H1,
+            TimeframeRepr::H2 => Self::H2,
+            TimeframeRepr::H4 => Self::H4,
+            TimeframeRepr::D1 => Self::D1,
+            TimeframeRepr::W1 => Self::W1,
+            TimeframeRepr::MN1 => Self::MN1,
+            TimeframeRepr::Seconds(n) => Self::Seconds(n),
+            TimeframeRepr::Minutes(n) => Self::Minutes(n),
+            TimeframeRepr::Bars(kind, n) => Self::Bars(kind, n),
+        };
+        timeframe.validate()
+    }
+}
+
+impl Ord for Timeframe {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl PartialOrd for Timeframe {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Timeframe {
-    pub fn as_str(self) -> &'static str {
+    const NAMED: [Self; 15] = [
+        Self::S1,
+        Self::S5,
+        Self::S15,
+        Self::S30,
+        Self::M1,
+        Self::M3,
+        Self::M5,
+        Self::M15,
+        Self::M30,
+        Self::H1,
+        Self::H2,
+        Self::H4,
+        Self::D1,
+        Self::W1,
+        Self::MN1,
+    ];
+
+    /// 名义时长（秒）；`W1`/`MN1` 按 7/30 日计，实际聚合以交易日历为界，伪周期为 0。
+    pub fn seconds(self) -> i64 {
         match self {
+            Self::S1 => 1,
+            Self::S5 => 5,
+            Self::S15 => 15,
+            Self::S30 => 30,
+            Self::M1 => 60,
+            Self::M3 => 3 * 60,
+            Self::M5 => 5 * 60,
+            Self::M15 => 15 * 60,
+            Self::M30 => 30 * 60,
+            Self::H1 => 3600,
+            Self::H2 => 2 * 3600,
+            Self::H4 => 4 * 3600,
+            Self::D1 => 86400,
+            Self::W1 => 7 * 86400,
+            Self::MN1 => 30 * 86400,
+            Self::Seconds(n) => n as i64,
+            Self::Minutes(n) => n as i64 * 60,
+            Self::Bars(..) => 0,
+        }
+    }
+
+    /// 时长相同的具名周期与未归一的自定义周期、各伪周期之间仍有确定的先后。
+    fn sort_key(self) -> (i64, Option<(BarKind, u32)>, u8) {
+        let rank = match self {
+            Self::Seconds(_) => 1,
+            Self::Minutes(_) => 2,
+            _ => 0,
+        };
+        let bars = match self {
+            Self::Bars(kind, n) => Some((kind, n)),
+            _ => None,
+        };
+        (self.seconds(), bars, rank)
+    }
+
+    /// 自定义周期须长于 0 且短于一日，伪周期阈值须大于 0；
+    /// 直接构造的变体在用于聚合前应先经此校验。
+    pub fn validate(self) -> Result<Self, DataError> {
+        let valid = match self {
+            Self::Seconds(_) | Self::Minutes(_) => (1..86400).contains(&self.seconds()),
+            Self::Bars(_, n) => n > 0,
+            _ => true,
+        };
+        if valid {
+            Ok(self)
+        } else {
+            Err(DataError::InvalidTimeframe(self.as_str().into_owned()))
+        }
+    }
+
+    /// 名义时长（分钟），不足一分钟的周期为 0。
+    pub fn minutes(self) -> i64 {
+        self.seconds() / 60
+    }
+
+    /// 日内周期（短于 `D1`），伪周期除外。
+    pub fn is_intraday(self) -> bool {
+        !self.is_pseudo() && self.seconds() < 86400
+    }
+
+    /// 秒级周期，只能由 tick 聚合得到。
+    pub fn is_sub_minute(self) -> bool {
+        self.seconds() % 60 != 0
+    }
+
+    /// 非时间驱动的伪周期，只能由 tick 构造。
+    pub fn is_pseudo(self) -> bool {
+        matches!(self, Self::Bars(..))
+    }
+
+    /// 按时长构造，优先返回具名周期；超过一日的非具名时长返回 `None`。
+    pub fn from_seconds(seconds: i64) -> Option<Self> {
+        if seconds <= 0 {
+            return None;
+        }
+        if let Some(named) = Self::NAMED.iter().find(|x| x.seconds() == seconds) {
+            return Some(*named);
+        }
+        if seconds >= 86400 {
+            return None;
+        }
+        let seconds = u32::try_from(seconds).ok()?;
+        if seconds % 60 == 0 {
+            Some(Self::Minutes(seconds / 60))
+        } else {
+            Some(Self::Seconds(seconds))
+        }
+    }
+
+    pub fn as_str(self) -> Cow<'static, str> {
+        let label = match self {
+            Self::S1 => "1s",
+            Self::S5 => "5s",
+            Self::S15 => "15s",
+            Self::S30 => "30s",
             Self::M1 => "1m",
+            Self::M3 => "3m",
             Self::M5 => "5m",
             Self::M15 => "15m",
+            Self::M30 => "30m",
             Self::H1 => "1h",
+            Self::H2 => "2h",
+            Self::H4 => "4h",
             Self::D1 => "1d",
-        }
+            Self::W1 => "1w",
+            Self::MN1 => "1mo",
+            Self::Seconds(n) => return Cow::Owned(format!("{n}s")),
+            Self::Minutes(n) => return Cow::Owned(format!("{n}m")),
+            Self::Bars(kind, n) => return Cow::Owned(format!("{}{n}", kind.as_str())),
+        };
+        Cow::Borrowed(label)
     }
 
+    /// 接受 `30s`、`3m`/`3min`、`60m`、`4h`、`1d`、`1w`、`1mo`/`mn1` 等写法，大小写不敏感；
+    /// 伪周期写作 `range20`、`renko10`、`volume500`、`tick100`。
     pub fn parse(value: &str) -> Result<Self, DataError> {
-        match value.trim().to_ascii_lowercase().as_str() {
-            "1m" => Ok(Self::M1),
-            "5m" => Ok(Self::M5),
-            "15m" => Ok(Self::M15),
-            "1h" => Ok(Self::H1),
-            "1d" => Ok(Self::D1),
-            _ => Err(DataError::InvalidTimeframe(value.to_string())),
+        let invalid = || DataError::InvalidTimeframe(value.to_string());
+        let raw = value.trim().to_ascii_lowercase();
+        if raw == "mn1" {
+            return Ok(Self::MN1);
         }
+        for kind in BarKind::ALL {
+            if let Some(count) = raw.strip_prefix(kind.as_str()) {
+                return match count.parse::<u32>() {
+                    Ok(n) if n > 0 => Ok(Self::Bars(kind, n)),
+                    _ => Err(invalid()),
+                };
+            }
+        }
+
+        let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+        let (count, unit) = raw.split_at(split);
+        let count = match count {
+            "" => 1,
+            x => x.parse::<i64>().map_err(|_| invalid())?,
+        };
+        let unit_seconds = match unit {
+            "s" | "sec" => 1,
+            "m" | "min" => 60,
+            "h" => 3600,
+            "d" => 86400,
+            "w" if count == 1 => return Ok(Self::W1),
+            "mo" | "mn" if count == 1 => return Ok(Self::MN1),
+            _ => return Err(invalid()),
+        };
+        Self::from_seconds(count * unit_seconds).ok_or_else(invalid)
     }
 }
 
@@ -89,6 +393,13 @@ pub enum DataError {
     Io(std::io::Error),
     Csv(csv::Error),
     Polars(polars::error::PolarsError),
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
+    CheckpointVersion(u32),
+    /// 可用样本数不足，携带实际样本数。
+    InsufficientSamples(usize),
+    /// 目标 profile 含手写注释，重写会丢失注释，携带文件路径。
+    ProfileHasComments(String),
 }
 
 pub struct Const;
@@ -96,6 +407,8 @@ pub struct Const;
 impl Const {
     pub const DEBUG: bool = true;
     pub const LOOKBACK_LIMIT: usize = 300;
+    /// checkpoint 文件格式版本；结构或状态字段变化时递增。
+    pub const CHECKPOINT_VERSION: u32 = 11;
 }
 
 impl Display for DataError {
@@ -106,6 +419,13 @@ impl Display for DataError {
             Self::Io(e) => write!(f, "io error: {e}"),
             Self::Csv(e) => write!(f, "csv error: {e}"),
             Self::Polars(e) => write!(f, "polars error: {e}"),
+            Self::Json(e) => write!(f, "json error: {e}"),
+            Self::Yaml(e) => write!(f, "yaml error: {e}"),
+            Self::CheckpointVersion(v) => write!(f, "unsupported checkpoint version: {v}"),
+            Self::InsufficientSamples(v) => write!(f, "insufficient samples: {v}"),
+            Self::ProfileHasComments(v) => {
+                write!(f, "profile has comments and would be rewritten without them: {v}")
+            }
         }
     }
 }
@@ -129,3 +449,15 @@ impl From<polars::error::PolarsError> for DataError {
         Self::Polars(value)
     }
 }
+
+impl From<serde_json::Error> for DataError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+impl From<serde_yaml::Error> for DataError {
+    fn from(value: serde_yaml::Error) -> Self {
+        Self::Yaml(value)
+    }
+}
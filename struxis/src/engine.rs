@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+
+use crate::indicator::IndicatorOutput;
 use crate::keyzone::KeyZoneSignal;
 use crate::mtc::MultiTimeframeContext;
 use crate::sd::SupplyDemandResult;
@@ -14,6 +17,8 @@ pub struct TimeframeAnalysis {
     pub latest_trend: Option<Trend>,
     pub keyzone_signal: Option<KeyZoneSignal>,
     pub sd: Option<SupplyDemandResult>,
+    /// 已注册指标在最新 SBar 上的输出。
+    pub indicators: BTreeMap<String, IndicatorOutput>,
 }
 
 #[derive(Debug, Clone)]
@@ -89,13 +94,20 @@ impl AnalysisEngine {
     }
 
     fn timeframe_analysis(&self, timeframe: Timeframe) -> TimeframeAnalysis {
-        TimeframeAnalysis {
+        TimeframeAnalysis::from_mtc(&self.mtc, timeframe)
+    }
+}
+
+impl TimeframeAnalysis {
+    pub fn from_mtc(mtc: &MultiTimeframeContext, timeframe: Timeframe) -> Self {
+        Self {
             timeframe,
-            latest_cbar: self.mtc.get_cbar_window(timeframe, 1).into_iter().next(),
-            latest_swing: self.mtc.get_swing_window(timeframe, 1).into_iter().next(),
-            latest_trend: self.mtc.get_trend_window(timeframe, 1).into_iter().next(),
-            keyzone_signal: self.mtc.get_keyzone_signal(timeframe).cloned(),
-            sd: self.mtc.get_sd(timeframe).cloned(),
+            latest_cbar: mtc.get_cbar_window(timeframe, 1).into_iter().next(),
+            latest_swing: mtc.get_swing_window(timeframe, 1).into_iter().next(),
+            latest_trend: mtc.get_trend_window(timeframe, 1).into_iter().next(),
+            keyzone_signal: mtc.get_keyzone_signal(timeframe).cloned(),
+            sd: mtc.get_sd(timeframe).cloned(),
+            indicators: mtc.get_latest_indicators(timeframe),
         }
     }
 }
@@ -1,12 +1,94 @@
+//! 结构变化事件。
+//!
+//! `MultiTimeframeContext::append` 在结构确实变化时按层级顺序派发：
+//! SBar -> CBar/分型 -> Swing -> Trend -> KeyZone，开启持续校验时其后为新发现的 `InvariantViolated`，
+//! 最后是 `TimeframeEnd` 与 `MtcNewBar`。
+//! 同一层内先派发移除，再派发新增与更新。
+
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::bar::{CBar, Fractal, SBar};
 use crate::constant::{EventType, Timeframe};
+use crate::keyzone::KeyZone;
+use crate::swing::{Swing, SwingState};
+use crate::trend::Trend;
+use crate::validation::Violation;
+
+/// 一次 append 中各层第一个变化的结构 id。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BacktrackIds {
+    pub cbar_backtrack_id: Option<u64>,
+    pub swing_backtrack_id: Option<u64>,
+    pub trend_backtrack_id: Option<u64>,
+}
 
-#[derive(Debug, Clone, Default)]
-pub struct EventPayload {
-    pub backtrack_id: Option<u64>,
-    pub note: Option<String>,
+/// 事件内容，变体与 `EventType` 一一对应。
+#[derive(Debug, Clone)]
+pub enum EventPayload {
+    SBarCreated(SBar),
+    CBarCreated(CBar),
+    /// 新 SBar 与尾部 CBar 存在包含关系，`merged` 为被并入（已移除）的 CBar。
+    CBarMerged {
+        cbar: CBar,
+        merged: Vec<CBar>,
+    },
+    FractalConfirmed(Fractal),
+    /// 分型因右侧 CBar 合并而消失，携带变化前的中间 CBar。
+    FractalInvalidated(CBar),
+    SwingCreated(Swing),
+    SwingUpdated(Swing),
+    SwingStateChanged {
+        swing: Swing,
+        previous: SwingState,
+    },
+    /// 回溯重算后不再存在的 swing。
+    SwingRemoved(Swing),
+    TrendCreated(Trend),
+    TrendUpdated(Trend),
+    TrendCompleted(Trend),
+    TrendRemoved(Trend),
+    KeyZoneCreated(KeyZone),
+    KeyZoneInvalidated(KeyZone),
+    /// 以下四个为关键区互动状态转换（on_approach/on_touch/on_accept/on_reject），
+    /// 携带转换后的关键区，`reactions` 末尾为本次转换。
+    KeyZoneApproached(KeyZone),
+    KeyZoneTouched(KeyZone),
+    KeyZoneAccepted(KeyZone),
+    KeyZoneRejected(KeyZone),
+    /// 持续校验新发现的结构不变量违例，同一规则与结构只派发一次。
+    InvariantViolated(Violation),
+    TimeframeEnd(BacktrackIds),
+    MtcNewBar(BacktrackIds),
+}
+
+impl EventPayload {
+    pub fn event_type(&self) -> EventType {
+        match self {
+            Self::SBarCreated(_) => EventType::SBarCreated,
+            Self::CBarCreated(_) => EventType::CBarCreated,
+            Self::CBarMerged { .. } => EventType::CBarMerged,
+            Self::FractalConfirmed(_) => EventType::FractalConfirmed,
+            Self::FractalInvalidated(_) => EventType::FractalInvalidated,
+            Self::SwingCreated(_) => EventType::SwingCreated,
+            Self::SwingUpdated(_) => EventType::SwingUpdated,
+            Self::SwingStateChanged { .. } => EventType::SwingStateChanged,
+            Self::SwingRemoved(_) => EventType::SwingRemoved,
+            Self::TrendCreated(_) => EventType::TrendCreated,
+            Self::TrendUpdated(_) => EventType::TrendUpdated,
+            Self::TrendCompleted(_) => EventType::TrendCompleted,
+            Self::TrendRemoved(_) => EventType::TrendRemoved,
+            Self::KeyZoneCreated(_) => EventType::KeyZoneCreated,
+            Self::KeyZoneInvalidated(_) => EventType::KeyZoneInvalidated,
+            Self::KeyZoneApproached(_) => EventType::KeyZoneApproached,
+            Self::KeyZoneTouched(_) => EventType::KeyZoneTouched,
+            Self::KeyZoneAccepted(_) => EventType::KeyZoneAccepted,
+            Self::KeyZoneRejected(_) => EventType::KeyZoneRejected,
+            Self::InvariantViolated(_) => EventType::InvariantViolated,
+            Self::TimeframeEnd(_) => EventType::TimeframeEnd,
+            Self::MtcNewBar(_) => EventType::MtcNewBar,
+        }
+    }
 }
 
 pub type Subscriber = Arc<dyn Fn(Timeframe, EventType, &EventPayload) + Send + Sync>;
@@ -29,15 +111,20 @@ impl Observable {
         }
     }
 
-    pub fn notify(&self, timeframe: Timeframe, event_type: EventType, payload: EventPayload) {
+    pub fn has_subscribers(&self) -> bool {
+        !self.all_subscribers.is_empty() || self.subscribers.values().any(|x| !x.is_empty())
+    }
+
+    pub fn notify(&self, timeframe: Timeframe, payload: &EventPayload) {
+        let event_type = payload.event_type();
         if let Some(subscribers) = self.subscribers.get(&event_type) {
             for subscriber in subscribers {
-                subscriber(timeframe, event_type, &payload);
+                subscriber(timeframe, event_type, payload);
             }
         }
 
         for subscriber in &self.all_subscribers {
-            subscriber(timeframe, event_type, &payload);
+            subscriber(timeframe, event_type, payload);
         }
     }
 }
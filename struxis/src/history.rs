@@ -0,0 +1,176 @@
+//! 结构的时点（双时态）历史。
+//!
+//! 回溯会改写已有的分型、Swing 与 Trend，事后导出的结构是最终版本，含有未来信息。
+//! `StructureHistory` 记录每个结构的每一版及其有效区间（均为 SBar id）：
+//! - `known_at`：该版本出现时的 SBar；
+//! - `revised_at`：该版本被改写或移除时的 SBar，仍有效时为 `None`。
+//!
+//! `as_of(n)` 返回 `known_at <= n` 且未在 `n` 及之前被改写的版本，即实时管线在 SBar `n` 收盘时所见的结构。
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bar::CBar;
+use crate::constant::FractalType;
+use crate::swing::Swing;
+use crate::trend::Trend;
+use crate::utils::RowChanges;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructureVersion<T> {
+    pub known_at: u64,
+    pub revised_at: Option<u64>,
+    pub value: T,
+}
+
+impl<T> StructureVersion<T> {
+    pub fn is_known_at(&self, sbar_id: u64) -> bool {
+        self.known_at <= sbar_id && self.revised_at.is_none_or(|x| x > sbar_id)
+    }
+}
+
+/// 某一 SBar 收盘时可见的结构，均按起点排序。
+#[derive(Debug, Clone, Default)]
+pub struct StructureSnapshot {
+    pub sbar_id: u64,
+    /// 分型中间的 CBar（`fractal_type` 非 `None`）。
+    pub fractals: Vec<CBar>,
+    pub swings: Vec<Swing>,
+    pub trends: Vec<Trend>,
+}
+
+/// 单个结构类型的版本序列，`open` 为各结构 id 当前有效版本的下标。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VersionLog<T> {
+    versions: Vec<StructureVersion<T>>,
+    open: HashMap<u64, usize>,
+}
+
+impl<T> Default for VersionLog<T> {
+    fn default() -> Self {
+        Self {
+            versions: Vec::new(),
+            open: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Clone> VersionLog<T> {
+    fn close(&mut self, id: u64, sbar_id: u64) {
+        if let Some(idx) = self.open.remove(&id) {
+            self.versions[idx].revised_at = Some(sbar_id);
+        }
+    }
+
+    fn open(&mut self, id: u64, sbar_id: u64, value: T) {
+        self.close(id, sbar_id);
+        self.open.insert(id, self.versions.len());
+        self.versions.push(StructureVersion {
+            known_at: sbar_id,
+            revised_at: None,
+            value,
+        });
+    }
+
+    fn as_of(&self, sbar_id: u64) -> Vec<T> {
+        self.versions
+            .iter()
+            .filter(|x| x.is_known_at(sbar_id))
+            .map(|x| x.value.clone())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StructureHistory {
+    fractals: VersionLog<CBar>,
+    swings: VersionLog<Swing>,
+    trends: VersionLog<Trend>,
+}
+
+impl StructureHistory {
+    /// 按一次 append 的行变化记录新版本，`sbar_id` 为本次 append 的 SBar。
+    pub(crate) fn record(
+        &mut self,
+        sbar_id: u64,
+        cbars: &RowChanges<'_, CBar>,
+        swings: &RowChanges<'_, Swing>,
+        trends: &RowChanges<'_, Trend>,
+    ) {
+        for cbar in &cbars.removed {
+            if let Some(id) = cbar.id {
+                self.fractals.close(id, sbar_id);
+            }
+        }
+        let touched = cbars
+            .added
+            .iter()
+            .copied()
+            .chain(cbars.updated.iter().map(|(_, x)| *x));
+        for cbar in touched {
+            let Some(id) = cbar.id else {
+                continue;
+            };
+            if cbar.fractal_type == FractalType::None {
+                self.fractals.close(id, sbar_id);
+            } else {
+                self.fractals.open(id, sbar_id, cbar.clone());
+            }
+        }
+
+        record_rows(&mut self.swings, sbar_id, swings, |x| x.id);
+        record_rows(&mut self.trends, sbar_id, trends, |x| x.id);
+    }
+
+    pub fn as_of(&self, sbar_id: u64) -> StructureSnapshot {
+        let mut fractals = self.fractals.as_of(sbar_id);
+        fractals.sort_by_key(|x| x.sbar_start_id);
+        let mut swings = self.swings.as_of(sbar_id);
+        swings.sort_by_key(|x| x.sbar_start_id);
+        let mut trends = self.trends.as_of(sbar_id);
+        trends.sort_by_key(|x| x.sbar_start_id);
+        StructureSnapshot {
+            sbar_id,
+            fractals,
+            swings,
+            trends,
+        }
+    }
+
+    /// 全部分型版本，按出现先后排列。
+    pub fn fractal_versions(&self) -> &[StructureVersion<CBar>] {
+        &self.fractals.versions
+    }
+
+    pub fn swing_versions(&self) -> &[StructureVersion<Swing>] {
+        &self.swings.versions
+    }
+
+    pub fn trend_versions(&self) -> &[StructureVersion<Trend>] {
+        &self.trends.versions
+    }
+}
+
+fn record_rows<T: Clone>(
+    log: &mut VersionLog<T>,
+    sbar_id: u64,
+    changes: &RowChanges<'_, T>,
+    id_of: impl Fn(&T) -> Option<u64>,
+) {
+    for row in &changes.removed {
+        if let Some(id) = id_of(row) {
+            log.close(id, sbar_id);
+        }
+    }
+    let touched = changes
+        .added
+        .iter()
+        .copied()
+        .chain(changes.updated.iter().map(|(_, x)| *x));
+    for row in touched {
+        if let Some(id) = id_of(row) {
+            log.open(id, sbar_id, row.clone());
+        }
+    }
+}
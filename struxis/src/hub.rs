@@ -0,0 +1,177 @@
+//! 多品种分析入口。
+//!
+//! 每个品种独立持有一个 `DataReceiver`（及其 `MultiTimeframeContext`），
+//! tick / bar 输入按 `symbol.exchange` 路由，聚合器互不共享。
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::constant::Timeframe;
+use crate::engine::{AnalysisSnapshot, TimeframeAnalysis};
+use crate::mtc::{ContextConfig, MultiTimeframeContext};
+use crate::quality::QualityReport;
+use crate::receiver::{DataReceiver, MarketBarInput, instrument_key};
+use crate::tick::TickInput;
+
+pub struct MarketHub {
+    config: ContextConfig,
+    timeframes: Vec<Timeframe>,
+    receivers: BTreeMap<String, DataReceiver>,
+}
+
+impl Default for MarketHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarketHub {
+    pub fn new() -> Self {
+        Self::with_config(ContextConfig::default())
+    }
+
+    /// 新品种的 context 均按 `config` 创建。
+    pub fn with_config(config: ContextConfig) -> Self {
+        Self {
+            config,
+            timeframes: Vec::new(),
+            receivers: BTreeMap::new(),
+        }
+    }
+
+    /// 注册到已有品种，并对之后出现的品种生效。
+    pub fn register_timeframe(&mut self, timeframe: Timeframe) {
+        if !self.timeframes.contains(&timeframe) {
+            self.timeframes.push(timeframe);
+        }
+        for receiver in self.receivers.values_mut() {
+            receiver.register_timeframe(timeframe);
+        }
+    }
+
+    /// 显式登记品种；输入中首次出现的品种也会自动登记。
+    pub fn add_symbol(&mut self, symbol: &str, exchange: &str) -> &mut MultiTimeframeContext {
+        self.receiver_entry(instrument_key(symbol, exchange))
+            .mtc_mut()
+    }
+
+    pub fn ingest_bar(&mut self, input: MarketBarInput) {
+        let key = instrument_key(&input.symbol, &input.exchange);
+        self.receiver_entry(key).ingest_bar(input);
+    }
+
+    pub fn ingest_batch(&mut self, inputs: Vec<MarketBarInput>) {
+        for input in inputs {
+            self.ingest_bar(input);
+        }
+    }
+
+    pub fn ingest_tick(&mut self, tick: TickInput) -> usize {
+        let key = instrument_key(&tick.symbol, &tick.exchange);
+        self.receiver_entry(key).ingest_tick(tick)
+    }
+
+    /// 定时器入口，见 `DataReceiver::on_clock`。
+    pub fn on_clock(&mut self, now: DateTime<Utc>) -> usize {
+        self.receivers
+            .values_mut()
+            .map(|receiver| receiver.on_clock(now))
+            .sum()
+    }
+
+    pub fn flush_ticks(&mut self) -> usize {
+        self.receivers
+            .values_mut()
+            .map(DataReceiver::flush_ticks)
+            .sum()
+    }
+
+    /// 各品种 bar 输入的质量报告，按品种、周期排列。
+    pub fn quality_reports(&self) -> Vec<&QualityReport> {
+        self.receivers
+            .values()
+            .flat_map(DataReceiver::quality_reports)
+            .collect()
+    }
+
+    /// 已登记品种，按 key 排序。
+    pub fn symbols(&self) -> Vec<String> {
+        self.receivers.keys().cloned().collect()
+    }
+
+    pub fn context(&self, key: &str) -> Option<&MultiTimeframeContext> {
+        self.receivers.get(key).map(DataReceiver::mtc)
+    }
+
+    pub fn context_mut(&mut self, key: &str) -> Option<&mut MultiTimeframeContext> {
+        self.receivers.get_mut(key).map(DataReceiver::mtc_mut)
+    }
+
+    pub fn receiver(&self, key: &str) -> Option<&DataReceiver> {
+        self.receivers.get(key)
+    }
+
+    /// 单一周期下各品种的最新结构。
+    pub fn timeframe_snapshot(&self, timeframe: Timeframe) -> Vec<(String, TimeframeAnalysis)> {
+        self.receivers
+            .iter()
+            .map(|(key, receiver)| {
+                (
+                    key.clone(),
+                    TimeframeAnalysis::from_mtc(receiver.mtc(), timeframe),
+                )
+            })
+            .collect()
+    }
+
+    pub fn snapshot(
+        &self,
+        key: &str,
+        higher_tf: Timeframe,
+        trade_tf: Timeframe,
+        entry_tf: Timeframe,
+    ) -> Option<AnalysisSnapshot> {
+        self.context(key)
+            .map(|mtc| build_snapshot(mtc, higher_tf, trade_tf, entry_tf))
+    }
+
+    pub fn snapshots(
+        &self,
+        higher_tf: Timeframe,
+        trade_tf: Timeframe,
+        entry_tf: Timeframe,
+    ) -> Vec<AnalysisSnapshot> {
+        self.receivers
+            .values()
+            .map(|receiver| build_snapshot(receiver.mtc(), higher_tf, trade_tf, entry_tf))
+            .collect()
+    }
+
+    fn receiver_entry(&mut self, key: String) -> &mut DataReceiver {
+        let config = self.config;
+        let timeframes = &self.timeframes;
+        self.receivers.entry(key).or_insert_with_key(|key| {
+            let mut receiver =
+                DataReceiver::new(MultiTimeframeContext::with_config(key.clone(), config));
+            for timeframe in timeframes {
+                receiver.register_timeframe(*timeframe);
+            }
+            receiver
+        })
+    }
+}
+
+fn build_snapshot(
+    mtc: &MultiTimeframeContext,
+    higher_tf: Timeframe,
+    trade_tf: Timeframe,
+    entry_tf: Timeframe,
+) -> AnalysisSnapshot {
+    AnalysisSnapshot {
+        symbol: mtc.symbol().to_string(),
+        higher: TimeframeAnalysis::from_mtc(mtc, higher_tf),
+        trade: TimeframeAnalysis::from_mtc(mtc, trade_tf),
+        entry: TimeframeAnalysis::from_mtc(mtc, entry_tf),
+    }
+}
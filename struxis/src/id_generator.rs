@@ -1,8 +1,13 @@
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use std::sync::OnceLock;
 
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::constant::IdStrategy;
+
 const WORKER_ID_BITS: u64 = 10;
 const SEQUENCE_BITS: u64 = 12;
 const MAX_SEQUENCE: u64 = (1 << SEQUENCE_BITS) - 1;
@@ -15,11 +20,21 @@ const EPOCH_MS: u64 = 1735689600000;
 struct Inner {
     sequence: u64,
     last_timestamp: u64,
+    data_timestamp: u64,
+}
+
+/// 生成器内部计数器的快照，用于 checkpoint 恢复后继续发号。
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub(crate) struct IdGeneratorState {
+    sequence: u64,
+    last_timestamp: u64,
+    data_timestamp: u64,
 }
 
 #[derive(Debug)]
 pub struct IdGenerator {
     worker_id: u64,
+    strategy: IdStrategy,
     inner: Mutex<Inner>,
 }
 
@@ -36,45 +51,196 @@ const WORKER_SWING: u64 = 3;
 const WORKER_TREND: u64 = 4;
 const WORKER_KEYZONE: u64 = 5;
 
-static SBAR_ID_GENERATOR: OnceLock<IdGenerator> = OnceLock::new();
-static CBAR_ID_GENERATOR: OnceLock<IdGenerator> = OnceLock::new();
-static SWING_ID_GENERATOR: OnceLock<IdGenerator> = OnceLock::new();
-static TREND_ID_GENERATOR: OnceLock<IdGenerator> = OnceLock::new();
-static KEYZONE_ID_GENERATOR: OnceLock<IdGenerator> = OnceLock::new();
+static SBAR_ID_GENERATOR: OnceLock<Arc<IdGenerator>> = OnceLock::new();
+static CBAR_ID_GENERATOR: OnceLock<Arc<IdGenerator>> = OnceLock::new();
+static SWING_ID_GENERATOR: OnceLock<Arc<IdGenerator>> = OnceLock::new();
+static TREND_ID_GENERATOR: OnceLock<Arc<IdGenerator>> = OnceLock::new();
+static KEYZONE_ID_GENERATOR: OnceLock<Arc<IdGenerator>> = OnceLock::new();
+
+pub fn sbar_id_generator() -> Arc<IdGenerator> {
+    SBAR_ID_GENERATOR
+        .get_or_init(|| Arc::new(IdGenerator::new(WORKER_SBAR)))
+        .clone()
+}
 
-pub fn sbar_id_generator() -> &'static IdGenerator {
-    SBAR_ID_GENERATOR.get_or_init(|| IdGenerator::new(WORKER_SBAR))
+pub fn cbar_id_generator() -> Arc<IdGenerator> {
+    CBAR_ID_GENERATOR
+        .get_or_init(|| Arc::new(IdGenerator::new(WORKER_CBAR)))
+        .clone()
 }
 
-pub fn cbar_id_generator() -> &'static IdGenerator {
-    CBAR_ID_GENERATOR.get_or_init(|| IdGenerator::new(WORKER_CBAR))
+pub fn swing_id_generator() -> Arc<IdGenerator> {
+    SWING_ID_GENERATOR
+        .get_or_init(|| Arc::new(IdGenerator::new(WORKER_SWING)))
+        .clone()
 }
 
-pub fn swing_id_generator() -> &'static IdGenerator {
-    SWING_ID_GENERATOR.get_or_init(|| IdGenerator::new(WORKER_SWING))
+pub fn trend_id_generator() -> Arc<IdGenerator> {
+    TREND_ID_GENERATOR
+        .get_or_init(|| Arc::new(IdGenerator::new(WORKER_TREND)))
+        .clone()
 }
 
-pub fn trend_id_generator() -> &'static IdGenerator {
-    TREND_ID_GENERATOR.get_or_init(|| IdGenerator::new(WORKER_TREND))
+pub fn keyzone_id_generator() -> Arc<IdGenerator> {
+    KEYZONE_ID_GENERATOR
+        .get_or_init(|| Arc::new(IdGenerator::new(WORKER_KEYZONE)))
+        .clone()
 }
 
-pub fn keyzone_id_generator() -> &'static IdGenerator {
-    KEYZONE_ID_GENERATOR.get_or_init(|| IdGenerator::new(WORKER_KEYZONE))
+/// 一个 context 内各类结构的 id 生成器。
+///
+/// `Snowflake` 复用进程级全局生成器；其余策略每个 context 独立一套，
+/// 同一数据重放两次得到相同的 id。
+#[derive(Debug, Clone)]
+pub(crate) struct StructureIdGenerators {
+    pub(crate) sbar: Arc<IdGenerator>,
+    pub(crate) cbar: Arc<IdGenerator>,
+    pub(crate) swing: Arc<IdGenerator>,
+    pub(crate) trend: Arc<IdGenerator>,
+    pub(crate) keyzone: Arc<IdGenerator>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub(crate) struct StructureIdState {
+    sbar: IdGeneratorState,
+    cbar: IdGeneratorState,
+    swing: IdGeneratorState,
+    trend: IdGeneratorState,
+    keyzone: IdGeneratorState,
+}
+
+impl StructureIdGenerators {
+    pub(crate) fn new(strategy: IdStrategy) -> Self {
+        if strategy == IdStrategy::Snowflake {
+            return Self {
+                sbar: sbar_id_generator(),
+                cbar: cbar_id_generator(),
+                swing: swing_id_generator(),
+                trend: trend_id_generator(),
+                keyzone: keyzone_id_generator(),
+            };
+        }
+
+        let make = |worker_id| Arc::new(IdGenerator::with_strategy(worker_id, strategy));
+        Self {
+            sbar: make(WORKER_SBAR),
+            cbar: make(WORKER_CBAR),
+            swing: make(WORKER_SWING),
+            trend: make(WORKER_TREND),
+            keyzone: make(WORKER_KEYZONE),
+        }
+    }
+
+    /// 恢复 checkpoint 中的计数器；`Snowflake` 复用全局生成器，不回拨其状态。
+    pub(crate) fn restore(strategy: IdStrategy, state: &StructureIdState) -> Self {
+        let ids = Self::new(strategy);
+        if strategy != IdStrategy::Snowflake {
+            ids.sbar.restore(state.sbar);
+            ids.cbar.restore(state.cbar);
+            ids.swing.restore(state.swing);
+            ids.trend.restore(state.trend);
+            ids.keyzone.restore(state.keyzone);
+        }
+        ids
+    }
+
+    pub(crate) fn state(&self) -> StructureIdState {
+        StructureIdState {
+            sbar: self.sbar.state(),
+            cbar: self.cbar.state(),
+            swing: self.swing.state(),
+            trend: self.trend.state(),
+            keyzone: self.keyzone.state(),
+        }
+    }
+
+    /// 推进 `Content` 策略使用的数据时间；其他策略忽略。
+    pub(crate) fn set_data_time(&self, datetime: DateTime<Utc>) {
+        for generator in [&self.sbar, &self.cbar, &self.swing, &self.trend, &self.keyzone] {
+            generator.set_data_time(datetime);
+        }
+    }
 }
 
 impl IdGenerator {
     pub fn new(worker_id: u64) -> Self {
+        Self::with_strategy(worker_id, IdStrategy::Snowflake)
+    }
+
+    pub fn with_strategy(worker_id: u64, strategy: IdStrategy) -> Self {
         assert!(worker_id <= 1023, "worker_id must be <= 1023");
         Self {
             worker_id,
+            strategy,
             inner: Mutex::new(Inner {
                 sequence: 0,
                 last_timestamp: 0,
+                data_timestamp: 0,
             }),
         }
     }
 
+    pub fn strategy(&self) -> IdStrategy {
+        self.strategy
+    }
+
+    /// 设置 `Content` 策略的当前数据时间（通常为正在处理的 SBar 时间）。
+    pub fn set_data_time(&self, datetime: DateTime<Utc>) {
+        if self.strategy != IdStrategy::Content {
+            return;
+        }
+        let mut guard = self.inner.lock().expect("id generator mutex poisoned");
+        guard.data_timestamp = datetime.timestamp_millis().max(0) as u64;
+    }
+
+    pub(crate) fn state(&self) -> IdGeneratorState {
+        let guard = self.inner.lock().expect("id generator mutex poisoned");
+        IdGeneratorState {
+            sequence: guard.sequence,
+            last_timestamp: guard.last_timestamp,
+            data_timestamp: guard.data_timestamp,
+        }
+    }
+
+    pub(crate) fn restore(&self, state: IdGeneratorState) {
+        let mut guard = self.inner.lock().expect("id generator mutex poisoned");
+        guard.sequence = state.sequence;
+        guard.last_timestamp = state.last_timestamp;
+        guard.data_timestamp = state.data_timestamp;
+    }
+
     pub fn get_id(&self) -> u64 {
+        match self.strategy {
+            IdStrategy::Snowflake => self.next_snowflake_id(),
+            IdStrategy::Sequential => {
+                let mut guard = self.inner.lock().expect("id generator mutex poisoned");
+                guard.sequence += 1;
+                guard.sequence
+            }
+            IdStrategy::Content => self.next_content_id(),
+        }
+    }
+
+    /// 以数据时间代替墙钟的 snowflake 布局；序号溢出时顺延到下一毫秒，保持单调。
+    fn next_content_id(&self) -> u64 {
+        let mut guard = self.inner.lock().expect("id generator mutex poisoned");
+        let mut ts = guard.data_timestamp.max(guard.last_timestamp);
+
+        if ts == guard.last_timestamp {
+            guard.sequence = (guard.sequence + 1) & MAX_SEQUENCE;
+            if guard.sequence == 0 {
+                ts += 1;
+            }
+        } else {
+            guard.sequence = 0;
+        }
+
+        guard.last_timestamp = ts;
+
+        (ts << TIMESTAMP_SHIFT) | (self.worker_id << WORKER_ID_SHIFT) | guard.sequence
+    }
+
+    fn next_snowflake_id(&self) -> u64 {
         let mut guard = self.inner.lock().expect("id generator mutex poisoned");
         let mut ts = current_timestamp_ms();
 
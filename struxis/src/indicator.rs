@@ -1,9 +1,29 @@
 pub mod core;
 pub mod ema;
 pub mod atr;
+pub mod sma;
+pub mod wma;
+pub mod rsi;
+pub mod macd;
+pub mod bollinger;
+pub mod donchian;
+pub mod vwap;
+pub mod obv;
+pub mod adx;
+pub mod volatility;
 pub mod manager;
 
+pub use adx::Adx;
 pub use atr::Atr;
-pub use core::Indicator;
+pub use bollinger::Bollinger;
+pub use core::{Indicator, IndicatorOutput, IndicatorSpec};
+pub use donchian::Donchian;
 pub use ema::Ema;
+pub use macd::Macd;
 pub use manager::IndicatorManager;
+pub use obv::Obv;
+pub use rsi::Rsi;
+pub use sma::Sma;
+pub use volatility::Volatility;
+pub use vwap::Vwap;
+pub use wma::Wma;
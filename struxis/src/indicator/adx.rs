@@ -0,0 +1,115 @@
+use crate::bar::SBar;
+
+use super::core::{Indicator, IndicatorOutput, IndicatorSpec};
+
+const FIELDS: &[&str] = &["adx", "plus_di", "minus_di"];
+
+/// Wilder ADX 与 ±DI：
+/// - TR/±DM 前 `period` 个取和，之后按 `x - x / period + 当前值` 平滑，由此得到 ±DI；
+/// - 前 `period` 个 DX 的均值为首个 ADX，之后按 `1 / period` 平滑。
+#[derive(Debug, Clone)]
+pub struct Adx {
+    name: String,
+    period: usize,
+    prev: Option<(f64, f64, f64)>,
+    count: usize,
+    tr: f64,
+    plus_dm: f64,
+    minus_dm: f64,
+    dx_count: usize,
+    adx: f64,
+}
+
+impl Adx {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "period must be > 0");
+        Self {
+            name: format!("adx_{period}"),
+            period,
+            prev: None,
+            count: 0,
+            tr: 0.0,
+            plus_dm: 0.0,
+            minus_dm: 0.0,
+            dx_count: 0,
+            adx: 0.0,
+        }
+    }
+}
+
+impl Indicator for Adx {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn spec(&self) -> Option<IndicatorSpec> {
+        Some(IndicatorSpec::Adx(self.period))
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        FIELDS
+    }
+
+    fn reset(&mut self) {
+        self.prev = None;
+        self.count = 0;
+        self.tr = 0.0;
+        self.plus_dm = 0.0;
+        self.minus_dm = 0.0;
+        self.dx_count = 0;
+        self.adx = 0.0;
+    }
+
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
+        let current = (bar.high_price, bar.low_price, bar.close_price);
+        let Some((prev_high, prev_low, prev_close)) = self.prev.replace(current) else {
+            return IndicatorOutput::pending(FIELDS);
+        };
+        let tr = (bar.high_price - bar.low_price)
+            .max((bar.high_price - prev_close).abs())
+            .max((bar.low_price - prev_close).abs());
+        let up = bar.high_price - prev_high;
+        let down = prev_low - bar.low_price;
+        let plus_dm = if up > down && up > 0.0 { up } else { 0.0 };
+        let minus_dm = if down > up && down > 0.0 { down } else { 0.0 };
+
+        let period = self.period as f64;
+        self.count += 1;
+        if self.count <= self.period {
+            self.tr += tr;
+            self.plus_dm += plus_dm;
+            self.minus_dm += minus_dm;
+            if self.count < self.period {
+                return IndicatorOutput::pending(FIELDS);
+            }
+        } else {
+            self.tr = self.tr - self.tr / period + tr;
+            self.plus_dm = self.plus_dm - self.plus_dm / period + plus_dm;
+            self.minus_dm = self.minus_dm - self.minus_dm / period + minus_dm;
+        }
+
+        let (plus_di, minus_di) = if self.tr > 0.0 {
+            (
+                100.0 * self.plus_dm / self.tr,
+                100.0 * self.minus_dm / self.tr,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        let di_sum = plus_di + minus_di;
+        let dx = if di_sum > 0.0 {
+            100.0 * (plus_di - minus_di).abs() / di_sum
+        } else {
+            0.0
+        };
+        self.dx_count += 1;
+        let adx = if self.dx_count <= self.period {
+            self.adx += dx / period;
+            (self.dx_count == self.period).then_some(self.adx)
+        } else {
+            self.adx = (self.adx * (period - 1.0) + dx) / period;
+            Some(self.adx)
+        };
+        IndicatorOutput::new(FIELDS, vec![adx, Some(plus_di), Some(minus_di)])
+    }
+}
@@ -1,6 +1,6 @@
 use crate::bar::SBar;
 
-use super::core::Indicator;
+use super::core::{Indicator, IndicatorOutput, IndicatorSpec};
 
 #[derive(Debug, Clone)]
 pub struct Atr {
@@ -37,41 +37,22 @@ impl Indicator for Atr {
         &self.name
     }
 
+    fn spec(&self) -> Option<IndicatorSpec> {
+        Some(IndicatorSpec::Atr(self.period))
+    }
+
     fn reset(&mut self) {
         self.value = None;
         self.prev_close = None;
     }
 
-    fn update(&mut self, bar: &SBar) -> Option<f64> {
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
         let tr = self.true_range(bar.high_price, bar.low_price, self.prev_close);
         self.prev_close = Some(bar.close_price);
         self.value = Some(match self.value {
             None => tr,
             Some(prev) => (prev * (self.period as f64 - 1.0) + tr) / self.period as f64,
         });
-        self.value
-    }
-
-    fn backfill(
-        &mut self,
-        highs: &[f64],
-        lows: &[f64],
-        closes: &[f64],
-        start_index: usize,
-    ) -> Vec<Option<f64>> {
-        if start_index == 0 {
-            self.reset();
-        }
-        let mut vals = Vec::new();
-        for i in start_index..closes.len() {
-            let tr = self.true_range(highs[i], lows[i], self.prev_close);
-            self.prev_close = Some(closes[i]);
-            self.value = Some(match self.value {
-                None => tr,
-                Some(prev) => (prev * (self.period as f64 - 1.0) + tr) / self.period as f64,
-            });
-            vals.push(self.value);
-        }
-        vals
+        IndicatorOutput::single(self.value)
     }
 }
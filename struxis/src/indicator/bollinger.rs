@@ -0,0 +1,67 @@
+use std::collections::VecDeque;
+
+use crate::bar::SBar;
+
+use super::core::{Indicator, IndicatorOutput, IndicatorSpec};
+
+const FIELDS: &[&str] = &["middle", "upper", "lower"];
+
+/// 布林带：收盘价 `period` 均值上下各 `multiple` 倍总体标准差。
+#[derive(Debug, Clone)]
+pub struct Bollinger {
+    name: String,
+    period: usize,
+    multiple: f64,
+    window: VecDeque<f64>,
+}
+
+impl Bollinger {
+    pub fn new(period: usize, multiple: f64) -> Self {
+        assert!(period > 0, "period must be > 0");
+        Self {
+            name: format!("boll_{period}_{multiple}"),
+            period,
+            multiple,
+            window: VecDeque::with_capacity(period + 1),
+        }
+    }
+}
+
+impl Indicator for Bollinger {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn spec(&self) -> Option<IndicatorSpec> {
+        Some(IndicatorSpec::Bollinger {
+            period: self.period,
+            multiple: self.multiple,
+        })
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        FIELDS
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
+        self.window.push_back(bar.close_price);
+        if self.window.len() > self.period {
+            self.window.pop_front();
+        }
+        if self.window.len() < self.period {
+            return IndicatorOutput::pending(FIELDS);
+        }
+        let n = self.period as f64;
+        let mean = self.window.iter().sum::<f64>() / n;
+        let variance = self.window.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+        let width = variance.sqrt() * self.multiple;
+        IndicatorOutput::new(
+            FIELDS,
+            vec![Some(mean), Some(mean + width), Some(mean - width)],
+        )
+    }
+}
@@ -1,14 +1,122 @@
+use serde::{Deserialize, Serialize};
+
 use crate::bar::SBar;
 
-pub trait Indicator: Send {
+/// 单根 SBar 上的指标输出，`values` 与 `fields` 一一对应，首个字段为主值。
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct IndicatorOutput {
+    fields: &'static [&'static str],
+    values: Vec<Option<f64>>,
+}
+
+impl IndicatorOutput {
+    pub fn new(fields: &'static [&'static str], values: Vec<Option<f64>>) -> Self {
+        assert_eq!(fields.len(), values.len(), "fields and values mismatch");
+        Self { fields, values }
+    }
+
+    pub fn single(value: Option<f64>) -> Self {
+        Self::new(SINGLE, vec![value])
+    }
+
+    /// 各字段均未就绪。
+    pub fn pending(fields: &'static [&'static str]) -> Self {
+        Self::new(fields, vec![None; fields.len()])
+    }
+
+    /// 主值。
+    pub fn value(&self) -> Option<f64> {
+        self.values.first().copied().flatten()
+    }
+
+    pub fn get(&self, field: &str) -> Option<f64> {
+        self.fields
+            .iter()
+            .position(|x| *x == field)
+            .and_then(|idx| self.values[idx])
+    }
+
+    pub fn fields(&self) -> &'static [&'static str] {
+        self.fields
+    }
+
+    pub fn values(&self) -> &[Option<f64>] {
+        &self.values
+    }
+}
+
+impl From<Option<f64>> for IndicatorOutput {
+    fn from(value: Option<f64>) -> Self {
+        Self::single(value)
+    }
+}
+
+/// 单值指标的字段名。
+pub const SINGLE: &[&str] = &["value"];
+
+pub trait Indicator: Send + Sync {
     fn name(&self) -> &str;
+
+    /// 输出字段名，与 `IndicatorOutput::values` 一一对应。
+    fn fields(&self) -> &'static [&'static str] {
+        SINGLE
+    }
+
+    /// 可序列化的构造参数，用于随 checkpoint 保存；返回 `None` 的指标恢复后需重新注册。
+    fn spec(&self) -> Option<IndicatorSpec> {
+        None
+    }
+
     fn reset(&mut self);
-    fn update(&mut self, bar: &SBar) -> Option<f64>;
-    fn backfill(
-        &mut self,
-        highs: &[f64],
-        lows: &[f64],
-        closes: &[f64],
-        start_index: usize,
-    ) -> Vec<Option<f64>>;
+
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput;
+
+    /// 从 `start_index` 起计算 `bars`：为 0 时先重置，否则要求状态已推进到 `start_index - 1`。
+    /// 结果与逐根 `update` 一致。
+    fn backfill(&mut self, bars: &[SBar], start_index: usize) -> Vec<IndicatorOutput> {
+        if start_index == 0 {
+            self.reset();
+        }
+        bars.iter()
+            .skip(start_index)
+            .map(|x| self.update(x))
+            .collect()
+    }
+}
+
+/// 内置指标的构造参数。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndicatorSpec {
+    Sma(usize),
+    Ema(usize),
+    Wma(usize),
+    Atr(usize),
+    Rsi(usize),
+    Adx(usize),
+    Donchian(usize),
+    Volatility(usize),
+    Bollinger { period: usize, multiple: f64 },
+    Macd { fast: usize, slow: usize, signal: usize },
+    Obv,
+}
+
+impl IndicatorSpec {
+    pub fn build(&self) -> Box<dyn Indicator> {
+        use super::{Adx, Atr, Bollinger, Donchian, Ema, Macd, Obv, Rsi, Sma, Volatility, Wma};
+
+        match *self {
+            Self::Sma(period) => Box::new(Sma::new(period)),
+            Self::Ema(period) => Box::new(Ema::new(period)),
+            Self::Wma(period) => Box::new(Wma::new(period)),
+            Self::Atr(period) => Box::new(Atr::new(period)),
+            Self::Rsi(period) => Box::new(Rsi::new(period)),
+            Self::Adx(period) => Box::new(Adx::new(period)),
+            Self::Donchian(period) => Box::new(Donchian::new(period)),
+            Self::Volatility(period) => Box::new(Volatility::new(period)),
+            Self::Bollinger { period, multiple } => Box::new(Bollinger::new(period, multiple)),
+            Self::Macd { fast, slow, signal } => Box::new(Macd::new(fast, slow, signal)),
+            Self::Obv => Box::new(Obv::new()),
+        }
+    }
 }
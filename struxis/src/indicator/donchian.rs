@@ -0,0 +1,60 @@
+use std::collections::VecDeque;
+
+use crate::bar::SBar;
+
+use super::core::{Indicator, IndicatorOutput, IndicatorSpec};
+
+const FIELDS: &[&str] = &["middle", "upper", "lower"];
+
+/// 唐奇安通道：最近 `period` 根（含当前）的最高价与最低价。
+#[derive(Debug, Clone)]
+pub struct Donchian {
+    name: String,
+    period: usize,
+    window: VecDeque<(f64, f64)>,
+}
+
+impl Donchian {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "period must be > 0");
+        Self {
+            name: format!("donchian_{period}"),
+            period,
+            window: VecDeque::with_capacity(period + 1),
+        }
+    }
+}
+
+impl Indicator for Donchian {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn spec(&self) -> Option<IndicatorSpec> {
+        Some(IndicatorSpec::Donchian(self.period))
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        FIELDS
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
+        self.window.push_back((bar.high_price, bar.low_price));
+        if self.window.len() > self.period {
+            self.window.pop_front();
+        }
+        if self.window.len() < self.period {
+            return IndicatorOutput::pending(FIELDS);
+        }
+        let upper = self.window.iter().map(|x| x.0).fold(f64::MIN, f64::max);
+        let lower = self.window.iter().map(|x| x.1).fold(f64::MAX, f64::min);
+        IndicatorOutput::new(
+            FIELDS,
+            vec![Some((upper + lower) / 2.0), Some(upper), Some(lower)],
+        )
+    }
+}
@@ -1,10 +1,11 @@
 use crate::bar::SBar;
 
-use super::core::Indicator;
+use super::core::{Indicator, IndicatorOutput, IndicatorSpec};
 
 #[derive(Debug, Clone)]
 pub struct Ema {
     name: String,
+    period: usize,
     alpha: f64,
     value: Option<f64>,
 }
@@ -14,10 +15,25 @@ impl Ema {
         assert!(period > 0, "period must be > 0");
         Self {
             name: format!("ema_{period}"),
+            period,
             alpha: 2.0 / (period as f64 + 1.0),
             value: None,
         }
     }
+
+    pub(crate) fn period(&self) -> usize {
+        self.period
+    }
+
+    /// 以任意序列值推进，首个值作为初值。
+    pub(crate) fn push(&mut self, price: f64) -> f64 {
+        let value = match self.value {
+            None => price,
+            Some(prev) => prev + self.alpha * (price - prev),
+        };
+        self.value = Some(value);
+        value
+    }
 }
 
 impl Indicator for Ema {
@@ -25,37 +41,15 @@ impl Indicator for Ema {
         &self.name
     }
 
-    fn reset(&mut self) {
-        self.value = None;
+    fn spec(&self) -> Option<IndicatorSpec> {
+        Some(IndicatorSpec::Ema(self.period))
     }
 
-    fn update(&mut self, bar: &SBar) -> Option<f64> {
-        let price = bar.close_price;
-        self.value = Some(match self.value {
-            None => price,
-            Some(prev) => prev + self.alpha * (price - prev),
-        });
-        self.value
+    fn reset(&mut self) {
+        self.value = None;
     }
 
-    fn backfill(
-        &mut self,
-        _highs: &[f64],
-        _lows: &[f64],
-        closes: &[f64],
-        start_index: usize,
-    ) -> Vec<Option<f64>> {
-        if start_index == 0 {
-            self.reset();
-        }
-        let mut vals = Vec::new();
-        for price in closes.iter().skip(start_index) {
-            self.value = Some(match self.value {
-                None => *price,
-                Some(prev) => prev + self.alpha * (*price - prev),
-            });
-            vals.push(self.value);
-        }
-        vals
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
+        IndicatorOutput::single(Some(self.push(bar.close_price)))
     }
 }
@@ -0,0 +1,63 @@
+use crate::bar::SBar;
+
+use super::core::{Indicator, IndicatorOutput, IndicatorSpec};
+use super::ema::Ema;
+
+const FIELDS: &[&str] = &["macd", "signal", "hist"];
+
+/// MACD：快慢 EMA 之差为 `macd`，其 EMA 为 `signal`，两者之差为 `hist`。
+#[derive(Debug, Clone)]
+pub struct Macd {
+    name: String,
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+}
+
+impl Macd {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        assert!(fast < slow, "fast period must be < slow period");
+        Self {
+            name: format!("macd_{fast}_{slow}_{signal}"),
+            fast: Ema::new(fast),
+            slow: Ema::new(slow),
+            signal: Ema::new(signal),
+        }
+    }
+}
+
+impl Default for Macd {
+    fn default() -> Self {
+        Self::new(12, 26, 9)
+    }
+}
+
+impl Indicator for Macd {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn spec(&self) -> Option<IndicatorSpec> {
+        Some(IndicatorSpec::Macd {
+            fast: self.fast.period(),
+            slow: self.slow.period(),
+            signal: self.signal.period(),
+        })
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        FIELDS
+    }
+
+    fn reset(&mut self) {
+        self.fast.reset();
+        self.slow.reset();
+        self.signal.reset();
+    }
+
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
+        let macd = self.fast.push(bar.close_price) - self.slow.push(bar.close_price);
+        let signal = self.signal.push(macd);
+        IndicatorOutput::new(FIELDS, vec![Some(macd), Some(signal), Some(macd - signal)])
+    }
+}
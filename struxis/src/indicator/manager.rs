@@ -1,17 +1,24 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+use polars::prelude::{DataFrame, NamedFrom, Series};
 
 use crate::bar::SBar;
 
-use super::core::Indicator;
+use super::core::{Indicator, IndicatorOutput, IndicatorSpec};
 
+/// 按 SBar 序号对齐保存各指标的输出。
+///
+/// 常规路径逐根 `update`；新注册的指标与 `mark_dirty` 之后的区间由 `backfill` 补算。
+/// dataframe 中单值指标占一列（列名为指标名），多值指标每个字段一列（`{name}_{field}`）。
 #[derive(Default)]
 pub struct IndicatorManager {
     indicators: Vec<Box<dyn Indicator>>,
-    outputs: HashMap<String, Vec<Option<f64>>>,
+    outputs: HashMap<String, Vec<IndicatorOutput>>,
     dirty_index: usize,
 }
 
 impl IndicatorManager {
+    /// 同名指标只保留先注册的；新指标的历史在下次 `backfill`/`sync` 时补齐。
     pub fn register(&mut self, indicator: Box<dyn Indicator>) {
         let name = indicator.name().to_string();
         if self.outputs.contains_key(&name) {
@@ -21,12 +28,15 @@ impl IndicatorManager {
         self.indicators.push(indicator);
     }
 
-    pub fn update(&mut self, bar: &SBar) -> HashMap<String, Option<f64>> {
+    pub fn update(&mut self, bar: &SBar) -> HashMap<String, IndicatorOutput> {
         let mut row = HashMap::new();
         for indicator in &mut self.indicators {
             let name = indicator.name().to_string();
             let value = indicator.update(bar);
-            self.outputs.entry(name.clone()).or_default().push(value);
+            self.outputs
+                .entry(name.clone())
+                .or_default()
+                .push(value.clone());
             row.insert(name, value);
         }
         self.dirty_index = self.outputs.values().next().map_or(0, Vec::len);
@@ -36,4 +46,125 @@ impl IndicatorManager {
     pub fn mark_dirty(&mut self, idx: usize) {
         self.dirty_index = self.dirty_index.min(idx);
     }
+
+    /// 第一个需要重算的 SBar 序号，包括新注册指标尚未补齐的历史。
+    pub fn dirty_index(&self) -> usize {
+        self.outputs
+            .values()
+            .map(Vec::len)
+            .fold(self.dirty_index, usize::min)
+    }
+
+    /// 按完整的 SBar 序列重算 `dirty_index` 之后的输出，沿用 `Indicator::backfill` 的约定：
+    /// 从 0 开始时由指标自行重置；从中间开始时先回放之前的区间恢复状态。
+    pub fn backfill(&mut self, sbars: &[SBar]) {
+        for indicator in &mut self.indicators {
+            let output = self
+                .outputs
+                .entry(indicator.name().to_string())
+                .or_default();
+            let start = self.dirty_index.min(output.len()).min(sbars.len());
+            // 输出已越过 start 时指标状态不在 start - 1，需要回放
+            if start > 0 && start < output.len() {
+                indicator.backfill(&sbars[..start], 0);
+            }
+            output.truncate(start);
+            output.extend(indicator.backfill(sbars, start));
+        }
+        self.dirty_index = sbars.len();
+    }
+
+    /// `sbars` 末尾追加一根 SBar 后调用：无待补算区间时逐根更新，否则补算。
+    pub fn sync(&mut self, sbars: &[SBar]) {
+        let Some(last) = sbars.last() else {
+            return;
+        };
+        if self.indicators.is_empty() {
+            return;
+        }
+        if self.dirty_index() + 1 < sbars.len() {
+            self.backfill(sbars);
+        } else {
+            self.update(last);
+        }
+    }
+
+    /// 按注册顺序导出内置指标的参数，同时返回无法保存的指标名。
+    pub(crate) fn specs(&self) -> (Vec<IndicatorSpec>, Vec<String>) {
+        let mut specs = Vec::new();
+        let mut omitted = Vec::new();
+        for indicator in &self.indicators {
+            match indicator.spec() {
+                Some(spec) => specs.push(spec),
+                None => omitted.push(indicator.name().to_string()),
+            }
+        }
+        (specs, omitted)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indicators.is_empty()
+    }
+
+    /// 按注册顺序返回指标名。
+    pub fn names(&self) -> Vec<&str> {
+        self.indicators.iter().map(|x| x.name()).collect()
+    }
+
+    pub fn values(&self, name: &str) -> Option<&[IndicatorOutput]> {
+        self.outputs.get(name).map(Vec::as_slice)
+    }
+
+    pub fn last_n(&self, name: &str, n: usize) -> Vec<IndicatorOutput> {
+        self.values(name)
+            .map(|x| x[x.len().saturating_sub(n)..].to_vec())
+            .unwrap_or_default()
+    }
+
+    /// 各指标的最新输出。
+    pub fn latest(&self) -> BTreeMap<String, IndicatorOutput> {
+        self.outputs
+            .iter()
+            .filter_map(|(name, values)| Some((name.clone(), values.last()?.clone())))
+            .collect()
+    }
+
+    /// 与 `sbars` 逐行对齐的指标表：`id`、`datetime` 之后每个指标一列。
+    pub fn dataframe(&self, sbars: &[SBar]) -> DataFrame {
+        let mut columns = vec![
+            Series::new(
+                "id",
+                sbars
+                    .iter()
+                    .map(|x| x.id.unwrap_or_default())
+                    .collect::<Vec<_>>(),
+            ),
+            Series::new(
+                "datetime",
+                sbars
+                    .iter()
+                    .map(|x| x.datetime.timestamp_millis())
+                    .collect::<Vec<_>>(),
+            ),
+        ];
+        for indicator in &self.indicators {
+            let name = indicator.name();
+            let fields = indicator.fields();
+            let outputs = self.last_n(name, sbars.len());
+            for (idx, field) in fields.iter().enumerate() {
+                let mut values = outputs
+                    .iter()
+                    .map(|x| x.values().get(idx).copied().flatten())
+                    .collect::<Vec<_>>();
+                values.resize(sbars.len(), None);
+                let column = if fields.len() == 1 {
+                    name.to_string()
+                } else {
+                    format!("{name}_{field}")
+                };
+                columns.push(Series::new(&column, values));
+            }
+        }
+        DataFrame::new(columns).expect("failed to build indicator dataframe")
+    }
 }
@@ -0,0 +1,43 @@
+use crate::bar::SBar;
+
+use super::core::{Indicator, IndicatorOutput, IndicatorSpec};
+
+/// 能量潮：收盘上涨累加成交量、下跌累减，首根为 0。
+#[derive(Debug, Clone, Default)]
+pub struct Obv {
+    prev_close: Option<f64>,
+    value: f64,
+}
+
+impl Obv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Indicator for Obv {
+    fn name(&self) -> &str {
+        "obv"
+    }
+
+    fn spec(&self) -> Option<IndicatorSpec> {
+        Some(IndicatorSpec::Obv)
+    }
+
+    fn reset(&mut self) {
+        self.prev_close = None;
+        self.value = 0.0;
+    }
+
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
+        if let Some(prev_close) = self.prev_close {
+            if bar.close_price > prev_close {
+                self.value += bar.volume;
+            } else if bar.close_price < prev_close {
+                self.value -= bar.volume;
+            }
+        }
+        self.prev_close = Some(bar.close_price);
+        IndicatorOutput::single(Some(self.value))
+    }
+}
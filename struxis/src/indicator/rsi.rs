@@ -0,0 +1,72 @@
+use crate::bar::SBar;
+
+use super::core::{Indicator, IndicatorOutput, IndicatorSpec};
+
+/// Wilder RSI：前 `period` 个涨跌取简单平均，之后按 `1 / period` 平滑。
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    name: String,
+    period: usize,
+    prev_close: Option<f64>,
+    count: usize,
+    avg_gain: f64,
+    avg_loss: f64,
+}
+
+impl Rsi {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "period must be > 0");
+        Self {
+            name: format!("rsi_{period}"),
+            period,
+            prev_close: None,
+            count: 0,
+            avg_gain: 0.0,
+            avg_loss: 0.0,
+        }
+    }
+}
+
+impl Indicator for Rsi {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn spec(&self) -> Option<IndicatorSpec> {
+        Some(IndicatorSpec::Rsi(self.period))
+    }
+
+    fn reset(&mut self) {
+        self.prev_close = None;
+        self.count = 0;
+        self.avg_gain = 0.0;
+        self.avg_loss = 0.0;
+    }
+
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
+        let Some(prev_close) = self.prev_close.replace(bar.close_price) else {
+            return IndicatorOutput::single(None);
+        };
+        let change = bar.close_price - prev_close;
+        let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+        let period = self.period as f64;
+        self.count += 1;
+        if self.count <= self.period {
+            self.avg_gain += gain / period;
+            self.avg_loss += loss / period;
+            if self.count < self.period {
+                return IndicatorOutput::single(None);
+            }
+        } else {
+            self.avg_gain = (self.avg_gain * (period - 1.0) + gain) / period;
+            self.avg_loss = (self.avg_loss * (period - 1.0) + loss) / period;
+        }
+
+        let rsi = if self.avg_loss == 0.0 {
+            if self.avg_gain == 0.0 { 50.0 } else { 100.0 }
+        } else {
+            100.0 - 100.0 / (1.0 + self.avg_gain / self.avg_loss)
+        };
+        IndicatorOutput::single(Some(rsi))
+    }
+}
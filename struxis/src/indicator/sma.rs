@@ -0,0 +1,52 @@
+use std::collections::VecDeque;
+
+use crate::bar::SBar;
+
+use super::core::{Indicator, IndicatorOutput, IndicatorSpec};
+
+/// 收盘价简单移动平均，满 `period` 根后输出。
+#[derive(Debug, Clone)]
+pub struct Sma {
+    name: String,
+    period: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Sma {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "period must be > 0");
+        Self {
+            name: format!("sma_{period}"),
+            period,
+            window: VecDeque::with_capacity(period + 1),
+            sum: 0.0,
+        }
+    }
+}
+
+impl Indicator for Sma {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn spec(&self) -> Option<IndicatorSpec> {
+        Some(IndicatorSpec::Sma(self.period))
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.sum = 0.0;
+    }
+
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
+        self.window.push_back(bar.close_price);
+        self.sum += bar.close_price;
+        if self.window.len() > self.period {
+            self.sum -= self.window.pop_front().unwrap_or_default();
+        }
+        IndicatorOutput::single(
+            (self.window.len() == self.period).then(|| self.sum / self.period as f64),
+        )
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::VecDeque;
+
+use crate::bar::SBar;
+
+use super::core::{Indicator, IndicatorOutput, IndicatorSpec};
+
+/// 滚动波动率：最近 `period` 个收盘对数收益率的样本标准差（不年化）。
+#[derive(Debug, Clone)]
+pub struct Volatility {
+    name: String,
+    period: usize,
+    prev_close: Option<f64>,
+    returns: VecDeque<f64>,
+}
+
+impl Volatility {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 1, "period must be > 1");
+        Self {
+            name: format!("volatility_{period}"),
+            period,
+            prev_close: None,
+            returns: VecDeque::with_capacity(period + 1),
+        }
+    }
+}
+
+impl Indicator for Volatility {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn spec(&self) -> Option<IndicatorSpec> {
+        Some(IndicatorSpec::Volatility(self.period))
+    }
+
+    fn reset(&mut self) {
+        self.prev_close = None;
+        self.returns.clear();
+    }
+
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
+        let prev_close = self.prev_close.replace(bar.close_price);
+        if let Some(prev_close) = prev_close.filter(|x| *x > 0.0 && bar.close_price > 0.0) {
+            self.returns.push_back((bar.close_price / prev_close).ln());
+            if self.returns.len() > self.period {
+                self.returns.pop_front();
+            }
+        }
+        if self.returns.len() < self.period {
+            return IndicatorOutput::single(None);
+        }
+        let n = self.period as f64;
+        let mean = self.returns.iter().sum::<f64>() / n;
+        let variance = self.returns.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        IndicatorOutput::single(Some(variance.sqrt()))
+    }
+}
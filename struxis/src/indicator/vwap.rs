@@ -0,0 +1,52 @@
+use chrono::NaiveDate;
+
+use crate::bar::SBar;
+use crate::session::SessionCalendar;
+
+use super::core::{Indicator, IndicatorOutput};
+
+/// 按交易日锚定的 VWAP：典型价 `(high + low + close) / 3` 以成交量加权，交易日切换时重新累计。
+///
+/// 交易日由 `SessionCalendar` 判定，夜盘计入下一交易日；当日尚无成交量时无输出。
+#[derive(Debug, Clone, Default)]
+pub struct Vwap {
+    calendar: SessionCalendar,
+    trading_day: Option<NaiveDate>,
+    volume: f64,
+    amount: f64,
+}
+
+impl Vwap {
+    pub fn new(calendar: SessionCalendar) -> Self {
+        Self {
+            calendar,
+            ..Self::default()
+        }
+    }
+}
+
+impl Indicator for Vwap {
+    fn name(&self) -> &str {
+        "vwap"
+    }
+
+    fn reset(&mut self) {
+        self.trading_day = None;
+        self.volume = 0.0;
+        self.amount = 0.0;
+    }
+
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
+        let day = self.calendar.trading_day(bar.datetime);
+        if self.trading_day != Some(day) {
+            self.trading_day = Some(day);
+            self.volume = 0.0;
+            self.amount = 0.0;
+        }
+        let volume = bar.volume.max(0.0);
+        let typical = (bar.high_price + bar.low_price + bar.close_price) / 3.0;
+        self.volume += volume;
+        self.amount += typical * volume;
+        IndicatorOutput::single((self.volume > 0.0).then(|| self.amount / self.volume))
+    }
+}
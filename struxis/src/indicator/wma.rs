@@ -0,0 +1,56 @@
+use std::collections::VecDeque;
+
+use crate::bar::SBar;
+
+use super::core::{Indicator, IndicatorOutput, IndicatorSpec};
+
+/// 收盘价线性加权移动平均，最新一根权重为 `period`。
+#[derive(Debug, Clone)]
+pub struct Wma {
+    name: String,
+    period: usize,
+    window: VecDeque<f64>,
+}
+
+impl Wma {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "period must be > 0");
+        Self {
+            name: format!("wma_{period}"),
+            period,
+            window: VecDeque::with_capacity(period + 1),
+        }
+    }
+}
+
+impl Indicator for Wma {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn spec(&self) -> Option<IndicatorSpec> {
+        Some(IndicatorSpec::Wma(self.period))
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
+        self.window.push_back(bar.close_price);
+        if self.window.len() > self.period {
+            self.window.pop_front();
+        }
+        if self.window.len() < self.period {
+            return IndicatorOutput::single(None);
+        }
+        let weighted = self
+            .window
+            .iter()
+            .enumerate()
+            .map(|(idx, price)| (idx + 1) as f64 * price)
+            .sum::<f64>();
+        let weights = (self.period * (self.period + 1)) as f64 / 2.0;
+        IndicatorOutput::single(Some(weighted / weights))
+    }
+}
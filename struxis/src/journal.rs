@@ -0,0 +1,212 @@
+//! 带撤销日志的结构行存储。
+//!
+//! 增量结构引擎按"步"推进（Swing 以 CBar pivot 序号为步，Trend 以已确认 Swing 序号为步）：
+//! - 每步开始前记录检查点（撤销日志长度 + 该步所需的旁路状态）；
+//! - 上游在某处发生变化时回滚到对应检查点，再从该步重新推进；
+//! - 重新推进与全量重建走同一套逐步逻辑，因此结果与全量路径一致。
+//!
+//! 仅保留最近 `Const::LOOKBACK_LIMIT` 步的检查点；更早的回滚请求返回 `None`，由调用方退回全量重建。
+
+use std::collections::VecDeque;
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constant::Const;
+
+#[derive(Serialize, Deserialize)]
+enum Undo<T> {
+    Push(usize),
+    Set(usize, T),
+    Pop(usize, T),
+}
+
+impl<T> Undo<T> {
+    fn index(&self) -> usize {
+        match self {
+            Self::Push(idx) | Self::Set(idx, _) | Self::Pop(idx, _) => *idx,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Checkpoint<S> {
+    undo_len: usize,
+    side: S,
+}
+
+/// 回滚结果：检查点中的旁路状态，以及回滚前自 `start` 起被改写的行。
+pub(crate) struct Rollback<T, S> {
+    pub(crate) side: S,
+    pub(crate) start: usize,
+    pub(crate) replaced: Vec<T>,
+}
+
+/// 检查点与撤销日志随行一起序列化，恢复后增量回滚仍可用。
+#[derive(Serialize, Deserialize)]
+pub(crate) struct JournaledRows<T, S> {
+    rows: Vec<T>,
+    undo: VecDeque<Undo<T>>,
+    undo_base: usize,
+    checkpoints: VecDeque<Checkpoint<S>>,
+    first_step: usize,
+}
+
+impl<T: Clone, S> Default for JournaledRows<T, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, S> JournaledRows<T, S> {
+    pub(crate) fn new() -> Self {
+        Self {
+            rows: Vec::new(),
+            undo: VecDeque::new(),
+            undo_base: 0,
+            checkpoints: VecDeque::new(),
+            first_step: 0,
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.rows.clear();
+        self.forget_history();
+    }
+
+    /// 丢弃全部检查点；之后的回滚请求都会失败并触发全量重建。
+    pub(crate) fn forget_history(&mut self) {
+        self.undo.clear();
+        self.undo_base = 0;
+        self.checkpoints.clear();
+        self.first_step = 0;
+    }
+
+    pub(crate) fn begin_step(&mut self, step: usize, side: S) {
+        if self.checkpoints.is_empty() {
+            self.first_step = step;
+        }
+        debug_assert_eq!(step, self.first_step + self.checkpoints.len());
+        self.checkpoints.push_back(Checkpoint {
+            undo_len: self.undo_base + self.undo.len(),
+            side,
+        });
+
+        if self.checkpoints.len() > Const::LOOKBACK_LIMIT {
+            self.checkpoints.pop_front();
+            self.first_step += 1;
+            let keep_from = self.checkpoints.front().map(|x| x.undo_len).unwrap_or_default();
+            while self.undo_base < keep_from && self.undo.pop_front().is_some() {
+                self.undo_base += 1;
+            }
+        }
+    }
+
+    /// 回滚到 `step` 开始前的状态，并移除该步及之后的检查点。
+    pub(crate) fn rollback_to(&mut self, step: usize) -> Option<Rollback<T, S>> {
+        if step < self.first_step || step >= self.first_step + self.checkpoints.len() {
+            return None;
+        }
+        let offset = step - self.first_step;
+        let target = self.checkpoints[offset].undo_len - self.undo_base;
+
+        let start = self
+            .undo
+            .range(target..)
+            .map(Undo::index)
+            .min()
+            .unwrap_or(self.rows.len())
+            .min(self.rows.len());
+        let replaced = self.rows[start..].to_vec();
+
+        while self.undo.len() > target {
+            match self.undo.pop_back().expect("undo entry") {
+                Undo::Push(_) => {
+                    self.rows.pop();
+                }
+                Undo::Set(idx, old) => self.rows[idx] = old,
+                Undo::Pop(_, old) => self.rows.push(old),
+            }
+        }
+
+        self.checkpoints.truncate(offset + 1);
+        let checkpoint = self.checkpoints.pop_back().expect("checkpoint exists");
+        Some(Rollback {
+            side: checkpoint.side,
+            start,
+            replaced,
+        })
+    }
+
+    pub(crate) fn push(&mut self, row: T) {
+        if !self.checkpoints.is_empty() {
+            self.undo.push_back(Undo::Push(self.rows.len()));
+        }
+        self.rows.push(row);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        let row = self.rows.pop()?;
+        if !self.checkpoints.is_empty() {
+            self.undo.push_back(Undo::Pop(self.rows.len(), row.clone()));
+        }
+        Some(row)
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        while self.rows.len() > len {
+            self.pop();
+        }
+    }
+
+    pub(crate) fn set(&mut self, idx: usize, row: T) {
+        let old = std::mem::replace(&mut self.rows[idx], row);
+        if !self.checkpoints.is_empty() {
+            self.undo.push_back(Undo::Set(idx, old));
+        }
+    }
+
+    pub(crate) fn update(&mut self, idx: usize, f: impl FnOnce(&mut T)) {
+        let mut row = self.rows[idx].clone();
+        f(&mut row);
+        self.set(idx, row);
+    }
+}
+
+impl<T, S> Deref for JournaledRows<T, S> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        &self.rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JournaledRows;
+
+    #[test]
+    fn rollback_restores_rows_and_side_state() {
+        let mut rows: JournaledRows<i32, &'static str> = JournaledRows::new();
+        rows.begin_step(1, "a");
+        rows.push(10);
+        rows.push(20);
+        rows.begin_step(2, "b");
+        rows.set(1, 21);
+        rows.pop();
+        rows.push(30);
+        rows.begin_step(3, "c");
+        rows.push(40);
+
+        let rollback = rows.rollback_to(2).expect("step 2 retained");
+        assert_eq!(rollback.side, "b");
+        assert_eq!(rollback.start, 1);
+        assert_eq!(rollback.replaced, vec![30, 40]);
+        assert_eq!(&rows[..], &[10, 20]);
+
+        assert!(rows.rollback_to(3).is_none());
+        let rollback = rows.rollback_to(1).expect("step 1 retained");
+        assert_eq!(rollback.side, "a");
+        assert!(rows.is_empty());
+    }
+}
@@ -2,17 +2,27 @@ pub mod builder;
 pub mod factory;
 
 pub use builder::{
-    ChannelKeyZoneBuilder, KeyZoneBuilder, SwingKeyZoneBuilder, TrendKeyZoneBuilder,
+    ChannelKeyZoneBuilder, EmaKeyZoneBuilder, EmaKeyZoneConfig, KeyZoneBuilder,
+    KeyZoneBuilderSpec, SwingKeyZoneBuilder, TrendKeyZoneBuilder,
 };
 pub use factory::KeyZoneFactory;
 
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
 use crate::constant::{Direction, KeyZoneOrientation, KeyZoneOrigin, Timeframe};
 use crate::bar::SBar;
 use crate::swing::Swing;
 use crate::trend::Trend;
 use crate::IdGenerator;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// 价格与关键区的互动状态；`None` 表示价格远离关键区。
+///
+/// 转换约束：未触及只能是 Approach（或远离），触及后先进入 Touch，
+/// Touch 的下一根 bar 只能转向 Accept 或 Reject；直接穿透视为快速 Accept。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum KeyZoneState {
     Approach,
     Touch,
@@ -20,7 +30,20 @@ pub enum KeyZoneState {
     Reject,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl KeyZoneState {
+    pub fn can_transition(from: Option<Self>, to: Option<Self>) -> bool {
+        use KeyZoneState::*;
+        matches!(
+            (from, to),
+            (None | Some(Approach), Some(Approach | Touch) | None)
+                | (Some(Touch), Some(Accept | Reject))
+                | (Some(Accept), Some(Approach) | None)
+                | (Some(Reject), Some(Approach | Touch) | None)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum KeyZoneBehavior {
     StrongAccept,
     WeakAccept,
@@ -30,7 +53,16 @@ pub enum KeyZoneBehavior {
     BreakoutFailure,
 }
 
-#[derive(Debug, Clone)]
+impl KeyZoneBehavior {
+    pub fn state(self) -> KeyZoneState {
+        match self {
+            Self::StrongAccept | Self::WeakAccept | Self::SecondPush => KeyZoneState::Accept,
+            Self::StrongReject | Self::WeakReject | Self::BreakoutFailure => KeyZoneState::Reject,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyZoneSignal {
     pub zone_id: Option<u64>,
     pub behavior: KeyZoneBehavior,
@@ -58,14 +90,51 @@ impl KeyZoneSignal {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyZoneReaction {
     pub sbar_id: u64,
     pub state: KeyZoneState,
     pub strength: f64,
 }
 
-#[derive(Debug, Clone)]
+/// 关键区生命周期：Create/Update 期间为 `Active`，失效后转入归档保留复盘。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyZoneStatus {
+    Active,
+    /// 收盘价连续越过关键区边界。
+    Broken,
+    /// 长时间未被触碰。
+    Expired,
+}
+
+/// 关键区生命周期参数。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyZoneConfig {
+    /// 确认突破所需的连续收盘根数。
+    pub break_confirm_bars: u32,
+    /// 突破幅度阈值，按关键区高度的比例计。
+    pub break_margin_ratio: f64,
+    /// 连续未触碰达到该根数即失效。
+    pub max_idle_bars: u32,
+    /// 归档区保留的最大数量，超出时丢弃最早的。
+    pub archive_limit: usize,
+    /// 与关键区的距离不超过其高度的该倍数时视为 Approach。
+    pub approach_ratio: f64,
+}
+
+impl Default for KeyZoneConfig {
+    fn default() -> Self {
+        Self {
+            break_confirm_bars: 2,
+            break_margin_ratio: 0.1,
+            max_idle_bars: 200,
+            archive_limit: 500,
+            approach_ratio: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyZone {
     pub id: Option<u64>,
     pub timeframe: Timeframe,
@@ -79,18 +148,233 @@ pub struct KeyZone {
     pub sbar_end_id: u64,
     pub direction_hint: Direction,
     pub reactions: Vec<KeyZoneReaction>,
+    pub status: KeyZoneStatus,
+    /// 触发失效的 SBar。
+    pub invalidated_sbar_id: Option<u64>,
+    /// 形成之后连续未触碰的根数。
+    pub idle_bars: u32,
+    /// 当前连续越过边界的收盘根数。
+    pub break_bars: u32,
+    pub state: Option<KeyZoneState>,
+    /// 最近一次 Touch 前价格所在一侧：`Up` 为区上方，`Down` 为区下方。
+    pub touch_side: Option<Direction>,
+    /// 边界斜率（价格/根 SBar），水平区为 0；按 bar 计数外推，休市时段不移动。
+    pub slope: f64,
+    /// 斜向区锚点 SBar 在本周期序列中的序号（从 0 起），`upper`/`lower` 为该 bar 上的边界。
+    pub anchor_index: Option<usize>,
+    /// 动态关键区所依据的指标名（如 `ema_20`），结构来源的关键区为 `None`。
+    pub indicator: Option<String>,
 }
 
 impl KeyZone {
     pub fn contains(&self, price: f64) -> bool {
         self.lower <= price && price <= self.upper
     }
+
+    /// 序号为 `index` 的 SBar 上的 `(lower, upper)`；水平区恒为锚定边界。
+    pub fn bounds_at(&self, index: usize) -> (f64, f64) {
+        let shift = self
+            .anchor_index
+            .map_or(0.0, |at| self.slope * (index as f64 - at as f64));
+        (self.lower + shift, self.upper + shift)
+    }
+
+    pub fn contains_at(&self, price: f64, index: usize) -> bool {
+        let (lower, upper) = self.bounds_at(index);
+        lower <= price && price <= upper
+    }
+
+    pub fn is_sloped(&self) -> bool {
+        self.anchor_index.is_some() && self.slope != 0.0
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == KeyZoneStatus::Active
+    }
+
+    fn touched_by(&self, bar: &SBar, index: usize) -> bool {
+        let (lower, upper) = self.bounds_at(index);
+        bar.low_price <= upper && bar.high_price >= lower
+    }
+
+    fn distance_to(&self, bar: &SBar, index: usize) -> f64 {
+        let (lower, upper) = self.bounds_at(index);
+        (bar.low_price - upper).max(lower - bar.high_price).max(0.0)
+    }
+
+    fn overlap_ratio(&self, bar: &SBar, index: usize) -> f64 {
+        let (lower, upper) = self.bounds_at(index);
+        let overlap = bar.high_price.min(upper) - bar.low_price.max(lower);
+        (overlap / (upper - lower).max(1e-6)).clamp(0.0, 1.0)
+    }
+
+    /// 按序号为 `index` 的新 bar 推进互动状态机，转入 Accept/Reject 时返回对应信号。
+    fn react(
+        &mut self,
+        bar: &SBar,
+        index: usize,
+        prev_bar: Option<&SBar>,
+        approach_ratio: f64,
+    ) -> Option<KeyZoneSignal> {
+        let touched = self.touched_by(bar, index);
+        let approach_distance = (self.upper - self.lower).max(f64::EPSILON) * approach_ratio;
+        let mut signal = None;
+        let next = match self.state {
+            Some(KeyZoneState::Touch) => {
+                let resolved = resolve_touch(self, bar, index, prev_bar);
+                let state = resolved.behavior.state();
+                signal = Some(resolved);
+                Some(state)
+            }
+            Some(KeyZoneState::Accept) if touched => Some(KeyZoneState::Accept),
+            _ if touched => Some(KeyZoneState::Touch),
+            _ if self.distance_to(bar, index) <= approach_distance => {
+                Some(KeyZoneState::Approach)
+            }
+            _ => None,
+        };
+        if next == self.state || !KeyZoneState::can_transition(self.state, next) {
+            return None;
+        }
+
+        if next == Some(KeyZoneState::Touch) {
+            let (reference, at) = prev_bar.map_or((bar.open_price, index), |x| {
+                (x.close_price, index.saturating_sub(1))
+            });
+            let (lower, upper) = self.bounds_at(at);
+            self.touch_side = Some(if reference > (upper + lower) / 2.0 {
+                Direction::Up
+            } else {
+                Direction::Down
+            });
+        }
+        self.state = next;
+        if let Some(state) = next {
+            let strength = match state {
+                KeyZoneState::Approach => 1.0 - self.distance_to(bar, index) / approach_distance,
+                KeyZoneState::Touch => self.overlap_ratio(bar, index),
+                _ => signal.as_ref().map_or(0.0, |x| x.strength),
+            };
+            self.reactions.push(KeyZoneReaction {
+                sbar_id: bar.id.unwrap_or_default(),
+                state,
+                strength: strength.clamp(0.0, 1.0),
+            });
+        }
+        signal
+    }
+
+    /// 向上 swing/trend 形成的区在顶部，向上收盘越过才算突破；向下同理，无方向时两侧均可。
+    /// 趋势线/通道沿趋势方向延伸，逆趋势方向收盘越过才算突破。
+    fn broken_by(&self, bar: &SBar, index: usize, margin_ratio: f64) -> bool {
+        let (lower, upper) = self.bounds_at(index);
+        let margin = (upper - lower).max(0.0) * margin_ratio;
+        let above = bar.close_price > upper + margin;
+        let below = bar.close_price < lower - margin;
+        let along_trend = self.orientation != KeyZoneOrientation::Horizontal;
+        match (self.direction_hint, along_trend) {
+            (Direction::Up, false) | (Direction::Down, true) => above,
+            (Direction::Down, false) | (Direction::Up, true) => below,
+            _ => above || below,
+        }
+    }
+
+    /// 动态关键区按指标名识别，其余按来源、形态、起点与方向识别。
+    fn same_built(&self, other: &KeyZone) -> bool {
+        if self.origin_type != other.origin_type || self.indicator != other.indicator {
+            return false;
+        }
+        self.indicator.is_some()
+            || (self.orientation == other.orientation
+                && self.sbar_start_id == other.sbar_start_id
+                && self.direction_hint == other.direction_hint)
+    }
 }
 
+/// 生成关键区的来源结构（swing/trend），以起点 SBar 与方向识别同一来源。
+struct ZoneSource {
+    origin_type: KeyZoneOrigin,
+    direction: Direction,
+    sbar_start_id: u64,
+    sbar_end_id: u64,
+    low_price: f64,
+    high_price: f64,
+}
+
+impl ZoneSource {
+    fn build(&self, timeframe: Timeframe, sbars: &[SBar]) -> KeyZone {
+        let (lower, upper, touch_count, last_touch_id) = refine_zone_bounds(
+            sbars,
+            self.sbar_start_id,
+            self.sbar_end_id,
+            self.direction,
+            self.low_price,
+            self.high_price,
+        );
+        KeyZone {
+            id: None,
+            timeframe,
+            origin_type: self.origin_type,
+            orientation: KeyZoneOrientation::Horizontal,
+            upper,
+            lower,
+            touch_count,
+            last_touch_id,
+            sbar_start_id: self.sbar_start_id,
+            sbar_end_id: self.sbar_end_id,
+            direction_hint: self.direction,
+            reactions: Vec::new(),
+            status: KeyZoneStatus::Active,
+            invalidated_sbar_id: None,
+            idle_bars: 0,
+            break_bars: 0,
+            state: None,
+            touch_side: None,
+            slope: 0.0,
+            anchor_index: None,
+            indicator: None,
+        }
+    }
+}
+
+impl From<&Swing> for ZoneSource {
+    fn from(swing: &Swing) -> Self {
+        Self {
+            origin_type: KeyZoneOrigin::Swing,
+            direction: swing.direction,
+            sbar_start_id: swing.sbar_start_id,
+            sbar_end_id: swing.sbar_end_id,
+            low_price: swing.low_price,
+            high_price: swing.high_price,
+        }
+    }
+}
+
+impl From<&Trend> for ZoneSource {
+    fn from(trend: &Trend) -> Self {
+        Self {
+            origin_type: KeyZoneOrigin::Trend,
+            direction: trend.direction,
+            sbar_start_id: trend.sbar_start_id,
+            sbar_end_id: trend.sbar_end_id,
+            low_price: trend.low_price,
+            high_price: trend.high_price,
+        }
+    }
+}
+
+/// 关键区按 Create -> Update -> Invalidate -> Archive 的生命周期维护，id 跨 bar 保持不变。
+#[derive(Serialize, Deserialize)]
 pub struct KeyZoneManager {
     rows: Vec<KeyZone>,
-    id_generator: &'static IdGenerator,
+    archived: VecDeque<KeyZone>,
+    #[serde(skip, default = "crate::id_generator::keyzone_id_generator")]
+    id_generator: Arc<IdGenerator>,
     latest_signal: Option<KeyZoneSignal>,
+    config: KeyZoneConfig,
+    /// 已计入触碰/突破/老化的 SBar 数，即下一根待处理 SBar 的序号；
+    /// 按序号而非时间判断，时间相同的 SBar（如一笔 tick 生成的多块 renko）同样逐根推进。
+    processed_bars: Option<usize>,
 }
 
 impl Default for KeyZoneManager {
@@ -104,14 +388,30 @@ impl KeyZoneManager {
         Self::new_with_generator(crate::id_generator::keyzone_id_generator())
     }
 
-    pub fn new_with_generator(id_generator: &'static IdGenerator) -> Self {
+    pub fn new_with_generator(id_generator: Arc<IdGenerator>) -> Self {
         Self {
             rows: Vec::new(),
+            archived: VecDeque::new(),
             id_generator,
             latest_signal: None,
+            config: KeyZoneConfig::default(),
+            processed_bars: None,
         }
     }
 
+    pub(crate) fn set_id_generator(&mut self, id_generator: Arc<IdGenerator>) {
+        self.id_generator = id_generator;
+    }
+
+    pub fn set_config(&mut self, config: KeyZoneConfig) {
+        self.config = config;
+    }
+
+    pub fn config(&self) -> &KeyZoneConfig {
+        &self.config
+    }
+
+    /// 丢弃全部关键区（含归档）后按当前窗口重新创建。
     pub fn rebuild_from(
         &mut self,
         timeframe: Timeframe,
@@ -120,197 +420,246 @@ impl KeyZoneManager {
         sbars: &[SBar],
     ) {
         self.rows.clear();
-        self.latest_signal = None;
-        for swing in swings.iter().rev().take(5) {
-            self.push_zone_from_swing(timeframe, swing, sbars);
-        }
-        for trend in trends.iter().rev().take(5) {
-            self.push_zone_from_trend(timeframe, trend, sbars);
-        }
+        self.archived.clear();
+        self.processed_bars = None;
+        self.update_from(timeframe, swings, trends, sbars);
     }
 
-    fn push_zone_from_swing(&mut self, timeframe: Timeframe, swing: &Swing, sbars: &[SBar]) {
-        let id = self.id_generator.get_id();
-        let (lower, upper, touch_count, last_touch_id) = refine_zone_bounds(
-            sbars,
-            swing.sbar_start_id,
-            swing.sbar_end_id,
-            swing.direction,
-            swing.low_price,
-            swing.high_price,
-        );
-        self.rows.push(KeyZone {
-            id: Some(id),
-            timeframe,
-            origin_type: KeyZoneOrigin::Swing,
-            orientation: KeyZoneOrientation::Horizontal,
-            upper,
-            lower,
-            touch_count,
-            last_touch_id,
-            sbar_start_id: swing.sbar_start_id,
-            sbar_end_id: swing.sbar_end_id,
-            direction_hint: swing.direction,
-            reactions: Vec::new(),
-        });
+    /// 增量推进生命周期：
+    /// - 新到的 SBar 推进已有关键区的互动状态机与触碰计数，并按突破/老化判定失效、转入归档；
+    /// - 最近 5 个 swing 与 trend 中新出现的来源创建关键区，来源延伸时更新边界；
+    /// - 已失效且来源未再变化的关键区不会重建。
+    ///
+    /// 即 `advance` 之后合并 `SwingKeyZoneBuilder` 与 `TrendKeyZoneBuilder` 的产出。
+    pub fn update_from(
+        &mut self,
+        timeframe: Timeframe,
+        swings: &[Swing],
+        trends: &[Trend],
+        sbars: &[SBar],
+    ) {
+        self.advance_window(sbars, 0);
+        let mut zones = swing_zones(timeframe, swings, sbars);
+        zones.extend(trend_zones(timeframe, trends, sbars));
+        self.merge_built(zones);
     }
 
-    fn push_zone_from_trend(&mut self, timeframe: Timeframe, trend: &Trend, sbars: &[SBar]) {
-        let id = self.id_generator.get_id();
-        let (lower, upper, touch_count, last_touch_id) = refine_zone_bounds(
-            sbars,
-            trend.sbar_start_id,
-            trend.sbar_end_id,
-            trend.direction,
-            trend.low_price,
-            trend.high_price,
-        );
-        self.rows.push(KeyZone {
-            id: Some(id),
-            timeframe,
-            origin_type: KeyZoneOrigin::Trend,
-            orientation: KeyZoneOrientation::Horizontal,
-            upper,
-            lower,
-            touch_count,
-            last_touch_id,
-            sbar_start_id: trend.sbar_start_id,
-            sbar_end_id: trend.sbar_end_id,
-            direction_hint: trend.direction,
-            reactions: Vec::new(),
-        });
+    /// 用 `sbars` 中尚未处理的 SBar 推进已有关键区的互动状态机与生命周期；
+    /// `sbars` 为从本周期首根 SBar 起的完整序列。
+    pub fn advance(&mut self, sbars: &[SBar]) {
+        self.advance_window(sbars, 0);
     }
 
-    pub fn rows(&self) -> &[KeyZone] {
-        &self.rows
+    /// 同 `advance`，`sbars` 为序列末尾的窗口，首根的序号为 `first_index`。
+    pub fn advance_window(&mut self, sbars: &[SBar], first_index: usize) {
+        self.latest_signal = None;
+        if let Some(processed) = self.processed_bars {
+            let skip = processed.saturating_sub(first_index);
+            for (idx, bar) in sbars.iter().enumerate().skip(skip) {
+                let prev_bar = idx.checked_sub(1).map(|x| &sbars[x]);
+                self.latest_signal = self.advance_lifecycle(bar, first_index + idx, prev_bar);
+            }
+        }
+        if !sbars.is_empty() {
+            self.processed_bars = Some(first_index + sbars.len());
+        }
     }
 
-    pub fn evaluate_latest_signal(
+    /// 返回该 bar 上最强的 Accept/Reject 信号。
+    fn advance_lifecycle(
         &mut self,
-        latest_bar: &SBar,
+        bar: &SBar,
+        index: usize,
         prev_bar: Option<&SBar>,
     ) -> Option<KeyZoneSignal> {
+        let config = self.config.clone();
+        let sbar_id = bar.id.unwrap_or_default();
         let mut best: Option<KeyZoneSignal> = None;
         for zone in &mut self.rows {
-            if let Some(signal) = classify_zone_signal(zone, latest_bar, prev_bar) {
-                let state = match signal.behavior {
-                    KeyZoneBehavior::StrongAccept
-                    | KeyZoneBehavior::WeakAccept
-                    | KeyZoneBehavior::SecondPush => KeyZoneState::Accept,
-                    KeyZoneBehavior::StrongReject
-                    | KeyZoneBehavior::WeakReject
-                    | KeyZoneBehavior::BreakoutFailure => KeyZoneState::Reject,
-                };
-                zone.reactions.push(KeyZoneReaction {
-                    sbar_id: latest_bar.id.unwrap_or_default(),
-                    state,
-                    strength: signal.strength,
-                });
-                let should_replace = best
-                    .as_ref()
-                    .map(|x| signal.strength > x.strength)
-                    .unwrap_or(true);
-                if should_replace {
-                    best = Some(signal);
-                }
+            if let Some(signal) = zone.react(bar, index, prev_bar, config.approach_ratio)
+                && best.as_ref().is_none_or(|x| signal.strength > x.strength)
+            {
+                best = Some(signal);
+            }
+            if zone.touched_by(bar, index) {
+                zone.touch_count += 1;
+                zone.last_touch_id = bar.id;
+                zone.idle_bars = 0;
+            } else {
+                zone.idle_bars += 1;
+            }
+            if zone.broken_by(bar, index, config.break_margin_ratio) {
+                zone.break_bars += 1;
+            } else {
+                zone.break_bars = 0;
+            }
+
+            // 动态关键区随指标移动，不因突破或老化失效
+            if zone.indicator.is_some() {
+                continue;
+            }
+            if zone.break_bars >= config.break_confirm_bars.max(1) {
+                zone.status = KeyZoneStatus::Broken;
+            } else if zone.idle_bars >= config.max_idle_bars {
+                zone.status = KeyZoneStatus::Expired;
+            }
+            if !zone.is_active() {
+                zone.invalidated_sbar_id = Some(sbar_id);
             }
         }
-        self.latest_signal = best.clone();
+
+        let (active, invalidated): (Vec<_>, Vec<_>) = std::mem::take(&mut self.rows)
+            .into_iter()
+            .partition(KeyZone::is_active);
+        self.rows = active;
+        self.archived.extend(invalidated);
+        while self.archived.len() > config.archive_limit {
+            self.archived.pop_front();
+        }
         best
     }
 
+    /// 合并 `KeyZoneBuilder` 的产出，保留已有关键区的 id 与互动状态：
+    /// - 动态关键区每次更新边界，计数沿用生命周期的累计；
+    /// - 结构来源延伸（`sbar_end_id` 变化）时按新范围更新边界并重置计数；
+    /// - 新来源创建关键区，已失效且未再变化的来源不重建。
+    pub fn merge_built(&mut self, zones: Vec<KeyZone>) {
+        for zone in zones {
+            if let Some(row) = self.rows.iter_mut().find(|x| x.same_built(&zone)) {
+                if row.indicator.is_none() {
+                    if row.sbar_end_id == zone.sbar_end_id {
+                        continue;
+                    }
+                    row.touch_count = zone.touch_count;
+                    row.last_touch_id = zone.last_touch_id;
+                    row.idle_bars = 0;
+                    row.break_bars = 0;
+                }
+                row.upper = zone.upper;
+                row.lower = zone.lower;
+                row.slope = zone.slope;
+                row.anchor_index = zone.anchor_index;
+                row.sbar_start_id = zone.sbar_start_id;
+                row.sbar_end_id = zone.sbar_end_id;
+                continue;
+            }
+            // 失效后来源未再变化则不重建；来源延伸出新端点时视为新的关键区
+            if self
+                .archived
+                .iter()
+                .any(|x| x.same_built(&zone) && x.sbar_end_id == zone.sbar_end_id)
+            {
+                continue;
+            }
+            self.track(zone);
+        }
+    }
+
+    /// 纳入外部构建的关键区（如 `ChannelKeyZoneBuilder`），之后随新 SBar 推进生命周期。
+    pub fn track(&mut self, mut zone: KeyZone) -> u64 {
+        let id = *zone.id.get_or_insert_with(|| self.id_generator.get_id());
+        self.rows.push(zone);
+        id
+    }
+
+    /// 已失效的关键区，按失效先后排列。
+    pub fn archived(&self) -> Vec<KeyZone> {
+        self.archived.iter().cloned().collect()
+    }
+
+    pub(crate) fn archived_by_id(&self, id: u64) -> Option<&KeyZone> {
+        self.archived.iter().rev().find(|x| x.id == Some(id))
+    }
+
+    pub fn rows(&self) -> &[KeyZone] {
+        &self.rows
+    }
+
     pub fn latest_signal(&self) -> Option<&KeyZoneSignal> {
         self.latest_signal.as_ref()
     }
 }
 
-fn classify_zone_signal(
+/// Touch 后一根 bar 收盘回到触及前一侧为 Reject，停留在区内或穿越到另一侧为 Accept。
+///
+/// `touch_bar` 为触及的那根 bar（序号 `index - 1`）：其收盘已穿越到另一侧而随后收回时记为 BreakoutFailure。
+fn resolve_touch(
     zone: &KeyZone,
-    latest_bar: &SBar,
-    prev_bar: Option<&SBar>,
-) -> Option<KeyZoneSignal> {
-    let overlap_low = latest_bar.low_price.max(zone.lower);
-    let overlap_high = latest_bar.high_price.min(zone.upper);
-    if overlap_high <= overlap_low {
-        return None;
-    }
-
-    let zone_span = (zone.upper - zone.lower).max(1e-6);
-    let overlap_ratio = ((overlap_high - overlap_low) / zone_span).clamp(0.0, 1.0);
-    let body_ratio = (latest_bar.body() / latest_bar.total_range().max(1e-6)).clamp(0.0, 1.0);
-    let closes_inside = zone.contains(latest_bar.close_price);
-    let closes_opposite_side = match zone.direction_hint {
-        Direction::Up => latest_bar.close_price < zone.lower,
-        Direction::Down => latest_bar.close_price > zone.upper,
-        _ => !closes_inside,
+    bar: &SBar,
+    index: usize,
+    touch_bar: Option<&SBar>,
+) -> KeyZoneSignal {
+    let touch_index = index.saturating_sub(1);
+    let overlap_ratio = touch_bar.map_or(0.0, |x| zone.overlap_ratio(x, touch_index));
+    let body_ratio = (bar.body() / bar.total_range().max(1e-6)).clamp(0.0, 1.0);
+    let closes_above = |x: &SBar, at: usize| x.close_price > zone.bounds_at(at).1;
+    let closes_below = |x: &SBar, at: usize| x.close_price < zone.bounds_at(at).0;
+    let (returned, crossed) = match zone.touch_side {
+        Some(Direction::Up) => (
+            closes_above(bar, index),
+            touch_bar.is_some_and(|x| closes_below(x, touch_index)),
+        ),
+        Some(Direction::Down) => (
+            closes_below(bar, index),
+            touch_bar.is_some_and(|x| closes_above(x, touch_index)),
+        ),
+        _ => (false, false),
     };
     let directional_body = match zone.direction_hint {
-        Direction::Up => latest_bar.close_price >= latest_bar.open_price,
-        Direction::Down => latest_bar.close_price <= latest_bar.open_price,
+        Direction::Up => bar.close_price >= bar.open_price,
+        Direction::Down => bar.close_price <= bar.open_price,
         _ => true,
     };
 
-    let prev_broke_with_hint = prev_bar
-        .map(|bar| match zone.direction_hint {
-            Direction::Up => bar.close_price > zone.upper,
-            Direction::Down => bar.close_price < zone.lower,
-            _ => false,
-        })
-        .unwrap_or(false);
-    if prev_broke_with_hint && closes_inside {
-        return Some(KeyZoneSignal {
-            zone_id: zone.id,
-            behavior: KeyZoneBehavior::BreakoutFailure,
-            direction: zone.direction_hint,
-            strength: (0.65 + 0.35 * overlap_ratio).clamp(0.0, 1.0),
-            sbar_id: latest_bar.id.unwrap_or_default(),
-        });
-    }
-
-    let prev_touched = prev_bar
-        .map(|bar| bar.low_price <= zone.upper && bar.high_price >= zone.lower)
-        .unwrap_or(false);
-    if prev_touched && zone.touch_count >= 2 && directional_body {
-        return Some(KeyZoneSignal {
-            zone_id: zone.id,
-            behavior: KeyZoneBehavior::SecondPush,
-            direction: zone.direction_hint,
-            strength: (0.55 + 0.45 * body_ratio).clamp(0.0, 1.0),
-            sbar_id: latest_bar.id.unwrap_or_default(),
-        });
-    }
-
-    if closes_inside && directional_body {
+    let (behavior, strength) = if returned && crossed {
+        (
+            KeyZoneBehavior::BreakoutFailure,
+            0.65 + 0.35 * overlap_ratio,
+        )
+    } else if returned {
+        let behavior = if body_ratio >= 0.45 {
+            KeyZoneBehavior::StrongReject
+        } else {
+            KeyZoneBehavior::WeakReject
+        };
+        (behavior, 0.5 * overlap_ratio + 0.5 * body_ratio)
+    } else if zone.touch_count >= 2 && directional_body {
+        (KeyZoneBehavior::SecondPush, 0.55 + 0.45 * body_ratio)
+    } else {
         let behavior = if overlap_ratio >= 0.55 && body_ratio >= 0.45 {
             KeyZoneBehavior::StrongAccept
         } else {
             KeyZoneBehavior::WeakAccept
         };
-        return Some(KeyZoneSignal {
-            zone_id: zone.id,
-            behavior,
-            direction: zone.direction_hint,
-            strength: (0.4 * overlap_ratio + 0.6 * body_ratio).clamp(0.0, 1.0),
-            sbar_id: latest_bar.id.unwrap_or_default(),
-        });
+        (behavior, 0.4 * overlap_ratio + 0.6 * body_ratio)
+    };
+    KeyZoneSignal {
+        zone_id: zone.id,
+        behavior,
+        direction: zone.direction_hint,
+        strength: strength.clamp(0.0, 1.0),
+        sbar_id: bar.id.unwrap_or_default(),
     }
+}
 
-    if closes_opposite_side || !directional_body {
-        let behavior = if closes_opposite_side || body_ratio >= 0.45 {
-            KeyZoneBehavior::StrongReject
-        } else {
-            KeyZoneBehavior::WeakReject
-        };
-        return Some(KeyZoneSignal {
-            zone_id: zone.id,
-            behavior,
-            direction: zone.direction_hint,
-            strength: (0.5 * overlap_ratio + 0.5 * body_ratio).clamp(0.0, 1.0),
-            sbar_id: latest_bar.id.unwrap_or_default(),
-        });
-    }
+/// 最近 5 个 swing 生成的水平关键区（新的在前），边界按 SBar 影线细化。
+pub(crate) fn swing_zones(timeframe: Timeframe, swings: &[Swing], sbars: &[SBar]) -> Vec<KeyZone> {
+    swings
+        .iter()
+        .rev()
+        .take(5)
+        .map(|x| ZoneSource::from(x).build(timeframe, sbars))
+        .collect()
+}
 
-    None
+/// 最近 5 个 trend 生成的水平关键区（新的在前）。
+pub(crate) fn trend_zones(timeframe: Timeframe, trends: &[Trend], sbars: &[SBar]) -> Vec<KeyZone> {
+    trends
+        .iter()
+        .rev()
+        .take(5)
+        .map(|x| ZoneSource::from(x).build(timeframe, sbars))
+        .collect()
 }
 
 fn refine_zone_bounds(
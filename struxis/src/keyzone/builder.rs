@@ -1,16 +1,52 @@
 mod channel;
+mod ema;
 mod swing;
 mod trend;
 
 pub use channel::ChannelKeyZoneBuilder;
+pub use ema::{EmaKeyZoneBuilder, EmaKeyZoneConfig};
 pub use swing::SwingKeyZoneBuilder;
 pub use trend::TrendKeyZoneBuilder;
 
+use serde::{Deserialize, Serialize};
+
 use crate::constant::{KeyZoneOrigin, Timeframe};
+use crate::indicator::Indicator;
 use crate::keyzone::KeyZone;
 use crate::mtc::MultiTimeframeContext;
 
 pub trait KeyZoneBuilder {
     fn origin_type(&self) -> KeyZoneOrigin;
     fn build(&self, mtc: &MultiTimeframeContext, timeframe: Timeframe) -> Vec<KeyZone>;
+
+    /// 可序列化的构造参数，用于随 checkpoint 保存；返回 `None` 的 builder 恢复后需重新注册。
+    fn spec(&self) -> Option<KeyZoneBuilderSpec> {
+        None
+    }
+
+    /// `build` 读取的指标，注册 builder 时一并注册到该周期的 `IndicatorManager`。
+    fn indicators(&self) -> Vec<Box<dyn Indicator>> {
+        Vec::new()
+    }
+}
+
+/// 内置 builder 的构造参数。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyZoneBuilderSpec {
+    Swing,
+    Trend,
+    Channel,
+    Ema(EmaKeyZoneConfig),
+}
+
+impl KeyZoneBuilderSpec {
+    pub fn build(&self) -> Box<dyn KeyZoneBuilder + Send + Sync> {
+        match self {
+            Self::Swing => Box::new(SwingKeyZoneBuilder),
+            Self::Trend => Box::new(TrendKeyZoneBuilder),
+            Self::Channel => Box::new(ChannelKeyZoneBuilder),
+            Self::Ema(config) => Box::new(EmaKeyZoneBuilder::new(config.clone())),
+        }
+    }
 }
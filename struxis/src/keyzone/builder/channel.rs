@@ -1,29 +1,214 @@
+use std::collections::HashMap;
+
+use crate::bar::SBar;
 use crate::constant::{Direction, KeyZoneOrientation, KeyZoneOrigin, Timeframe};
-use crate::keyzone::builder::KeyZoneBuilder;
-use crate::keyzone::KeyZone;
+use crate::keyzone::builder::{KeyZoneBuilder, KeyZoneBuilderSpec};
+use crate::keyzone::{KeyZone, KeyZoneStatus};
 use crate::mtc::MultiTimeframeContext;
+use crate::swing::Swing;
+use crate::trend::Trend;
 
+/// 以当前 trend 内的 swing 拐点构造斜向关键区：
+/// - 趋势线：上涨连接最近两个低点，下跌连接最近两个高点；
+/// - 通道：趋势线与穿过对侧最远拐点的平行线之间的区域。
+///
+/// 斜率按拐点之间的 SBar 根数计算，休市时段不计入。
 pub struct ChannelKeyZoneBuilder;
 
+#[derive(Debug, Clone, Copy)]
+struct Pivot {
+    sbar_id: u64,
+    /// 在本周期 SBar 序列中的绝对序号。
+    index: usize,
+    price: f64,
+}
+
+impl ChannelKeyZoneBuilder {
+    /// `swings` 需包含 `trend` 的起止 swing；`sbars` 为从本周期第 `first_index` 根起的连续
+    /// SBar，需覆盖 trend 起点，`first_index + 窗口内序号` 即关键区的 `anchor_index`。
+    pub fn build_from(
+        timeframe: Timeframe,
+        trend: &Trend,
+        swings: &[Swing],
+        sbars: &[SBar],
+        first_index: usize,
+    ) -> Vec<KeyZone> {
+        let (Some(start), Some(end)) = (
+            swings
+                .iter()
+                .position(|x| x.id == Some(trend.swing_start_id)),
+            swings.iter().position(|x| x.id == Some(trend.swing_end_id)),
+        ) else {
+            return Vec::new();
+        };
+        if start > end {
+            return Vec::new();
+        }
+
+        let indexes = sbars
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, x)| x.id.map(|id| (id, idx)))
+            .collect::<HashMap<_, _>>();
+        let mut highs = Vec::new();
+        let mut lows = Vec::new();
+        for swing in &swings[start..=end] {
+            let (start_price, end_price) = match swing.direction {
+                Direction::Up => (swing.low_price, swing.high_price),
+                _ => (swing.high_price, swing.low_price),
+            };
+            for (sbar_id, price, is_high) in [
+                (
+                    swing.sbar_start_id,
+                    start_price,
+                    swing.direction != Direction::Up,
+                ),
+                (
+                    swing.sbar_end_id,
+                    end_price,
+                    swing.direction == Direction::Up,
+                ),
+            ] {
+                let Some(index) = indexes.get(&sbar_id).map(|x| first_index + x) else {
+                    continue;
+                };
+                let side = if is_high { &mut highs } else { &mut lows };
+                // 相邻 swing 共用端点
+                if side.last().is_none_or(|x: &Pivot| x.sbar_id != sbar_id) {
+                    side.push(Pivot {
+                        sbar_id,
+                        index,
+                        price,
+                    });
+                }
+            }
+        }
+
+        let (base, opposite) = match trend.direction {
+            Direction::Up => (&lows, &highs),
+            Direction::Down => (&highs, &lows),
+            _ => return Vec::new(),
+        };
+        let [.., anchor, latest] = base.as_slice() else {
+            return Vec::new();
+        };
+        if latest.index <= anchor.index {
+            return Vec::new();
+        }
+        let slope = (latest.price - anchor.price) / (latest.index - anchor.index) as f64;
+        let line_at = |index: usize| anchor.price + slope * (index as f64 - anchor.index as f64);
+
+        let trendline = new_zone(
+            timeframe,
+            trend,
+            KeyZoneOrientation::Trendline,
+            anchor,
+            (anchor.price, anchor.price),
+            slope,
+        );
+        let mut zones = vec![trendline];
+        let width = opposite
+            .iter()
+            .filter(|x| x.index >= anchor.index)
+            .map(|x| (x.price - line_at(x.index)).abs())
+            .fold(0.0, f64::max);
+        if width > 0.0 {
+            let bounds = match trend.direction {
+                Direction::Up => (anchor.price, anchor.price + width),
+                _ => (anchor.price - width, anchor.price),
+            };
+            zones.push(new_zone(
+                timeframe,
+                trend,
+                KeyZoneOrientation::Channel,
+                anchor,
+                bounds,
+                slope,
+            ));
+        }
+
+        for zone in &mut zones {
+            for (idx, bar) in sbars.iter().enumerate().skip(anchor.index - first_index) {
+                // 只计趋势线一侧的触及
+                let line = line_at(first_index + idx);
+                if bar.low_price <= line && line <= bar.high_price {
+                    zone.touch_count += 1;
+                    zone.last_touch_id = bar.id;
+                }
+            }
+        }
+        zones
+    }
+}
+
 impl KeyZoneBuilder for ChannelKeyZoneBuilder {
     fn origin_type(&self) -> KeyZoneOrigin {
         KeyZoneOrigin::Channel
     }
 
-    fn build(&self, _mtc: &MultiTimeframeContext, timeframe: Timeframe) -> Vec<KeyZone> {
-        vec![KeyZone {
-            id: None,
-            timeframe,
-            origin_type: KeyZoneOrigin::Channel,
-            orientation: KeyZoneOrientation::Channel,
-            upper: 0.0,
-            lower: 0.0,
-            touch_count: 0,
-            last_touch_id: None,
-            sbar_start_id: 0,
-            sbar_end_id: 0,
-            direction_hint: Direction::None,
-            reactions: vec![],
-        }]
+    fn spec(&self) -> Option<KeyZoneBuilderSpec> {
+        Some(KeyZoneBuilderSpec::Channel)
+    }
+
+    fn build(&self, mtc: &MultiTimeframeContext, timeframe: Timeframe) -> Vec<KeyZone> {
+        let Some(trend) = mtc.get_trend_window(timeframe, 1).pop() else {
+            return Vec::new();
+        };
+        // 只取覆盖当前 trend 的窗口，避免每根 bar 复制全部历史
+        let swings = window_covering(
+            |n| mtc.get_swing_window(timeframe, n),
+            |x| x.id == Some(trend.swing_start_id),
+        );
+        let sbars = window_covering(
+            |n| mtc.get_sbar_window(timeframe, n),
+            |x| x.id == Some(trend.sbar_start_id),
+        );
+        let first_index = mtc.count(timeframe) - sbars.len();
+        Self::build_from(timeframe, &trend, &swings, &sbars, first_index)
+    }
+}
+
+/// 从最近的若干行取起，窗口逐次加倍，直到首段包含 `found` 命中的行或已取完全部历史。
+fn window_covering<T>(fetch: impl Fn(usize) -> Vec<T>, found: impl Fn(&T) -> bool) -> Vec<T> {
+    let mut length = 64;
+    loop {
+        let rows = fetch(length);
+        if rows.len() < length || rows.iter().any(&found) {
+            return rows;
+        }
+        length *= 2;
+    }
+}
+
+fn new_zone(
+    timeframe: Timeframe,
+    trend: &Trend,
+    orientation: KeyZoneOrientation,
+    anchor: &Pivot,
+    (lower, upper): (f64, f64),
+    slope: f64,
+) -> KeyZone {
+    KeyZone {
+        id: None,
+        timeframe,
+        origin_type: KeyZoneOrigin::Channel,
+        orientation,
+        upper,
+        lower,
+        touch_count: 0,
+        last_touch_id: None,
+        sbar_start_id: anchor.sbar_id,
+        sbar_end_id: trend.sbar_end_id,
+        direction_hint: trend.direction,
+        reactions: vec![],
+        status: KeyZoneStatus::Active,
+        invalidated_sbar_id: None,
+        idle_bars: 0,
+        break_bars: 0,
+        state: None,
+        touch_side: None,
+        slope,
+        anchor_index: Some(anchor.index),
+        indicator: None,
     }
 }
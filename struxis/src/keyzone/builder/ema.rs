@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+use crate::constant::{Direction, KeyZoneOrientation, KeyZoneOrigin, Timeframe};
+use crate::indicator::{Atr, Ema, Indicator, IndicatorOutput};
+use crate::keyzone::builder::{KeyZoneBuilder, KeyZoneBuilderSpec};
+use crate::keyzone::{KeyZone, KeyZoneStatus};
+use crate::mtc::MultiTimeframeContext;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmaKeyZoneConfig {
+    /// 每个周期生成一条均线带。
+    pub periods: Vec<usize>,
+    pub atr_period: usize,
+    /// 带宽为均线上下各 `ATR * band_atr_multiple`。
+    pub band_atr_multiple: f64,
+}
+
+impl Default for EmaKeyZoneConfig {
+    fn default() -> Self {
+        Self {
+            periods: vec![20, 60],
+            atr_period: 14,
+            band_atr_multiple: 0.5,
+        }
+    }
+}
+
+/// 以 EMA 为中轴、ATR 为带宽的动态关键区，边界随每根 SBar 更新，按指标名识别同一关键区。
+///
+/// EMA 与 ATR 以指标形式注册到该周期，`build` 只读取最新值；触碰计数交给生命周期累计。
+#[derive(Debug, Clone, Default)]
+pub struct EmaKeyZoneBuilder {
+    config: EmaKeyZoneConfig,
+}
+
+impl EmaKeyZoneBuilder {
+    pub fn new(config: EmaKeyZoneConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &EmaKeyZoneConfig {
+        &self.config
+    }
+
+    fn atr(&self) -> Atr {
+        Atr::new(self.config.atr_period.max(1))
+    }
+
+    fn emas(&self) -> Vec<Ema> {
+        self.config
+            .periods
+            .iter()
+            .filter(|x| **x > 0)
+            .map(|x| Ema::new(*x))
+            .collect()
+    }
+}
+
+impl KeyZoneBuilder for EmaKeyZoneBuilder {
+    fn origin_type(&self) -> KeyZoneOrigin {
+        KeyZoneOrigin::Ema
+    }
+
+    fn spec(&self) -> Option<KeyZoneBuilderSpec> {
+        Some(KeyZoneBuilderSpec::Ema(self.config.clone()))
+    }
+
+    fn indicators(&self) -> Vec<Box<dyn Indicator>> {
+        let mut indicators: Vec<Box<dyn Indicator>> = vec![Box::new(self.atr())];
+        for ema in self.emas() {
+            indicators.push(Box::new(ema));
+        }
+        indicators
+    }
+
+    fn build(&self, mtc: &MultiTimeframeContext, timeframe: Timeframe) -> Vec<KeyZone> {
+        let Some(last) = mtc.get_sbar_window(timeframe, 1).pop() else {
+            return Vec::new();
+        };
+        let latest = mtc.get_latest_indicators(timeframe);
+        let Some(atr) = latest
+            .get(self.atr().name())
+            .and_then(IndicatorOutput::value)
+        else {
+            return Vec::new();
+        };
+        let width = atr * self.config.band_atr_multiple;
+        self.emas()
+            .iter()
+            .filter_map(|ema| {
+                let center = latest.get(ema.name()).and_then(IndicatorOutput::value)?;
+                Some(KeyZone {
+                    id: None,
+                    timeframe,
+                    origin_type: KeyZoneOrigin::Ema,
+                    orientation: KeyZoneOrientation::Horizontal,
+                    upper: center + width,
+                    lower: center - width,
+                    touch_count: 0,
+                    last_touch_id: None,
+                    sbar_start_id: last.id.unwrap_or_default(),
+                    sbar_end_id: last.id.unwrap_or_default(),
+                    direction_hint: Direction::None,
+                    reactions: vec![],
+                    status: KeyZoneStatus::Active,
+                    invalidated_sbar_id: None,
+                    idle_bars: 0,
+                    break_bars: 0,
+                    state: None,
+                    touch_side: None,
+                    slope: 0.0,
+                    anchor_index: None,
+                    indicator: Some(ema.name().to_string()),
+                })
+            })
+            .collect()
+    }
+}
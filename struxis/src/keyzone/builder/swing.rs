@@ -1,8 +1,9 @@
-use crate::constant::{KeyZoneOrientation, KeyZoneOrigin, Timeframe};
-use crate::keyzone::builder::KeyZoneBuilder;
-use crate::keyzone::KeyZone;
+use crate::constant::{KeyZoneOrigin, Timeframe};
+use crate::keyzone::builder::{KeyZoneBuilder, KeyZoneBuilderSpec};
+use crate::keyzone::{swing_zones, KeyZone};
 use crate::mtc::MultiTimeframeContext;
 
+/// 最近 5 个 swing 的价格区间，按区间内 SBar 的影线与实体细化边界。
 pub struct SwingKeyZoneBuilder;
 
 impl KeyZoneBuilder for SwingKeyZoneBuilder {
@@ -10,23 +11,15 @@ impl KeyZoneBuilder for SwingKeyZoneBuilder {
         KeyZoneOrigin::Swing
     }
 
+    fn spec(&self) -> Option<KeyZoneBuilderSpec> {
+        Some(KeyZoneBuilderSpec::Swing)
+    }
+
     fn build(&self, mtc: &MultiTimeframeContext, timeframe: Timeframe) -> Vec<KeyZone> {
-        mtc.get_swing_window(timeframe, 5)
-            .into_iter()
-            .map(|swing| KeyZone {
-                id: None,
-                timeframe,
-                origin_type: KeyZoneOrigin::Swing,
-                orientation: KeyZoneOrientation::Horizontal,
-                upper: swing.high_price,
-                lower: swing.low_price,
-                touch_count: 0,
-                last_touch_id: None,
-                sbar_start_id: swing.sbar_start_id,
-                sbar_end_id: swing.sbar_end_id,
-                direction_hint: swing.direction,
-                reactions: vec![],
-            })
-            .collect()
+        swing_zones(
+            timeframe,
+            &mtc.get_swing_window(timeframe, 5),
+            &mtc.get_sbar_window(timeframe, 200),
+        )
     }
 }
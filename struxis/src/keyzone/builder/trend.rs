@@ -1,8 +1,9 @@
-use crate::constant::{KeyZoneOrientation, KeyZoneOrigin, Timeframe};
-use crate::keyzone::builder::KeyZoneBuilder;
-use crate::keyzone::KeyZone;
+use crate::constant::{KeyZoneOrigin, Timeframe};
+use crate::keyzone::builder::{KeyZoneBuilder, KeyZoneBuilderSpec};
+use crate::keyzone::{trend_zones, KeyZone};
 use crate::mtc::MultiTimeframeContext;
 
+/// 最近 5 个 trend 的价格区间，边界细化方式同 `SwingKeyZoneBuilder`。
 pub struct TrendKeyZoneBuilder;
 
 impl KeyZoneBuilder for TrendKeyZoneBuilder {
@@ -10,23 +11,15 @@ impl KeyZoneBuilder for TrendKeyZoneBuilder {
         KeyZoneOrigin::Trend
     }
 
+    fn spec(&self) -> Option<KeyZoneBuilderSpec> {
+        Some(KeyZoneBuilderSpec::Trend)
+    }
+
     fn build(&self, mtc: &MultiTimeframeContext, timeframe: Timeframe) -> Vec<KeyZone> {
-        mtc.get_trend_window(timeframe, 5)
-            .into_iter()
-            .map(|trend| KeyZone {
-                id: None,
-                timeframe,
-                origin_type: KeyZoneOrigin::Trend,
-                orientation: KeyZoneOrientation::Horizontal,
-                upper: trend.high_price,
-                lower: trend.low_price,
-                touch_count: 0,
-                last_touch_id: None,
-                sbar_start_id: trend.sbar_start_id,
-                sbar_end_id: trend.sbar_end_id,
-                direction_hint: trend.direction,
-                reactions: vec![],
-            })
-            .collect()
+        trend_zones(
+            timeframe,
+            &mtc.get_trend_window(timeframe, 5),
+            &mtc.get_sbar_window(timeframe, 200),
+        )
     }
 }
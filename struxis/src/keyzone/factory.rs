@@ -1,10 +1,26 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::constant::KeyZoneOrigin;
-use super::builder::KeyZoneBuilder;
+use serde::{Deserialize, Serialize};
 
+use crate::constant::{KeyZoneOrigin, Timeframe};
+use crate::indicator::Indicator;
+use crate::keyzone::KeyZone;
+use crate::mtc::MultiTimeframeContext;
+use super::builder::{
+    KeyZoneBuilder, KeyZoneBuilderSpec, SwingKeyZoneBuilder, TrendKeyZoneBuilder,
+};
+
+/// 单个 timeframe 的关键区生成配置：按来源注册 builder，并可逐个来源启用/停用。
 pub struct KeyZoneFactory {
     builders: HashMap<KeyZoneOrigin, Box<dyn KeyZoneBuilder + Send + Sync>>,
+    disabled: HashSet<KeyZoneOrigin>,
+}
+
+/// factory 中可随 checkpoint 保存的部分。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct KeyZoneFactoryState {
+    builders: Vec<KeyZoneBuilderSpec>,
+    disabled: Vec<KeyZoneOrigin>,
 }
 
 impl Default for KeyZoneFactory {
@@ -17,14 +33,97 @@ impl KeyZoneFactory {
     pub fn new() -> Self {
         Self {
             builders: HashMap::new(),
+            disabled: HashSet::new(),
         }
     }
 
+    /// 注册了 `SwingKeyZoneBuilder` 与 `TrendKeyZoneBuilder`，即 `register` 后的默认配置。
+    pub fn with_defaults() -> Self {
+        let mut factory = Self::new();
+        factory.register(Box::new(SwingKeyZoneBuilder));
+        factory.register(Box::new(TrendKeyZoneBuilder));
+        factory
+    }
+
+    /// 同一来源只保留最后注册的 builder。
     pub fn register(&mut self, builder: Box<dyn KeyZoneBuilder + Send + Sync>) {
         self.builders.insert(builder.origin_type(), builder);
     }
 
+    pub fn unregister(&mut self, origin_type: KeyZoneOrigin) -> bool {
+        self.builders.remove(&origin_type).is_some()
+    }
+
     pub fn create(&self, origin_type: KeyZoneOrigin) -> Option<&(dyn KeyZoneBuilder + Send + Sync)> {
         self.builders.get(&origin_type).map(|x| x.as_ref())
     }
+
+    /// 停用的来源保留 builder，但 `build_all` 不再调用。
+    pub fn set_enabled(&mut self, origin_type: KeyZoneOrigin, enabled: bool) {
+        if enabled {
+            self.disabled.remove(&origin_type);
+        } else {
+            self.disabled.insert(origin_type);
+        }
+    }
+
+    pub fn is_enabled(&self, origin_type: KeyZoneOrigin) -> bool {
+        self.builders.contains_key(&origin_type) && !self.disabled.contains(&origin_type)
+    }
+
+    /// 已注册且启用的来源，按来源顺序排列。
+    pub fn origins(&self) -> Vec<KeyZoneOrigin> {
+        let mut origins = self
+            .builders
+            .keys()
+            .copied()
+            .filter(|x| !self.disabled.contains(x))
+            .collect::<Vec<_>>();
+        origins.sort();
+        origins
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.builders.is_empty()
+    }
+
+    /// 导出内置 builder 的参数与停用的来源，同时返回无法保存的 builder 来源。
+    pub(crate) fn state(&self) -> (KeyZoneFactoryState, Vec<KeyZoneOrigin>) {
+        let mut origins = self.builders.keys().copied().collect::<Vec<_>>();
+        origins.sort();
+        let mut builders = Vec::new();
+        let mut omitted = Vec::new();
+        for origin in origins {
+            match self.builders[&origin].spec() {
+                Some(spec) => builders.push(spec),
+                None => omitted.push(origin),
+            }
+        }
+        let mut disabled = self.disabled.iter().copied().collect::<Vec<_>>();
+        disabled.sort();
+        (KeyZoneFactoryState { builders, disabled }, omitted)
+    }
+
+    /// 停用状态对之后重新注册的同来源 builder 同样生效。
+    pub(crate) fn restore(state: &KeyZoneFactoryState) -> Self {
+        let mut factory = Self::new();
+        for spec in &state.builders {
+            factory.register(spec.build());
+        }
+        factory.disabled.extend(state.disabled.iter().copied());
+        factory
+    }
+
+    /// 按来源顺序调用启用的 builder，保证同一输入下的产出顺序稳定。
+    pub fn build_all(&self, mtc: &MultiTimeframeContext, timeframe: Timeframe) -> Vec<KeyZone> {
+        self.origins()
+            .into_iter()
+            .flat_map(|x| self.builders[&x].build(mtc, timeframe))
+            .collect()
+    }
+
+    /// 各 builder 依赖的指标，停用的来源也包括在内，重新启用时无需补注册。
+    pub(crate) fn indicators(&self) -> Vec<Box<dyn Indicator>> {
+        self.builders.values().flat_map(|x| x.indicators()).collect()
+    }
 }
@@ -1,42 +1,73 @@
 pub mod bar;
+pub mod bar_builder;
+pub mod calibration;
 pub mod constant;
 pub mod engine;
 pub mod events;
+pub mod history;
+pub mod hub;
 pub mod id_generator;
 pub mod indicator;
 pub mod keyzone;
 pub mod logging;
 pub mod mtc;
 mod cbar_manager;
+mod journal;
 mod sbar_manager;
 mod timeframe_manager;
+pub mod quality;
 pub mod receiver;
 pub mod sd;
+pub mod session;
 pub mod symbol;
+pub mod synthetic;
 pub mod swing;
 pub mod tick;
 pub mod trend;
 pub mod utils;
+pub mod validation;
 
 pub use bar::{CBar, Fractal, SBar};
+pub use bar_builder::{
+	BarBuilder, RangeBarBuilder, RenkoBarBuilder, TickCountBarBuilder, VolumeBarBuilder,
+};
+pub use calibration::{
+	CalibrationConfig, CalibrationReport, CalibrationSample, CalibrationStats, StageStats,
+};
 pub use constant::{
-	DataError, Direction, EventType, FractalType, KeyZoneOrientation,
-	KeyZoneOrigin, Timeframe,
+	BarKind, DataError, Direction, EventType, FractalType, IdStrategy, KeyZoneOrientation,
+	KeyZoneOrigin, StructureMode, Timeframe,
 };
 pub use engine::{AnalysisEngine, AnalysisSnapshot, TimeframeAnalysis};
+pub use events::{BacktrackIds, EventPayload};
+pub use history::{StructureHistory, StructureSnapshot, StructureVersion};
+pub use hub::MarketHub;
 pub use keyzone::{
-	ChannelKeyZoneBuilder, KeyZone, KeyZoneBehavior, KeyZoneBuilder, KeyZoneFactory,
-	KeyZoneManager, KeyZoneSignal, SwingKeyZoneBuilder, TrendKeyZoneBuilder,
+	ChannelKeyZoneBuilder, EmaKeyZoneBuilder, EmaKeyZoneConfig, KeyZone, KeyZoneBehavior,
+	KeyZoneBuilder, KeyZoneBuilderSpec, KeyZoneConfig, KeyZoneFactory, KeyZoneManager, KeyZoneSignal, KeyZoneState,
+	KeyZoneStatus, SwingKeyZoneBuilder, TrendKeyZoneBuilder,
 };
 pub use logging::init_logging;
-pub use mtc::MultiTimeframeContext;
-pub use receiver::{DataReceiver, MarketBarInput};
+pub use mtc::{CheckpointOmission, ContextConfig, MultiTimeframeContext};
+pub use quality::{
+	QualityAction, QualityCheck, QualityGate, QualityIssue, QualityPolicy, QualityReport,
+};
+pub use receiver::{instrument_key, load_market_bar_inputs, DataReceiver, MarketBarInput};
 pub use sd::{
-	SupplyDemand, SupplyDemandConfig, SupplyDemandFactors, SupplyDemandProfileConfig,
-	SupplyDemandResult, SupplyDemandStage,
+	SupplyDemand, SupplyDemandAnchor, SupplyDemandAnchorMode, SupplyDemandConfig,
+	SupplyDemandFactors, SupplyDemandProfileConfig, SupplyDemandResult, SupplyDemandStage,
+	TimeframeStructure,
 };
 pub use swing::{Swing, SwingManager, SwingState};
-pub use symbol::{Symbol, SymbolLoader, SymbolRegistry};
-pub use tick::{BarWindowAggregator, TickBarAggregator, TickInput};
+pub use session::SessionCalendar;
+pub use symbol::{Symbol, SymbolLoader, SymbolRegistry, TradingSession};
+pub use synthetic::{
+	MarketScript, RandomWalk, RegimeSwitching, SeriesSpec, StructureOutline, SyntheticSeries,
+};
+pub use tick::{
+	BarWindowAggregator, OrderFlow, SessionBarAggregator, TickAnomalies, TickBarAggregator, TickInput,
+	TradeSide,
+};
 pub use trend::{Trend, TrendManager};
 pub use id_generator::IdGenerator;
+pub use validation::{InvariantRule, ValidationReport, Violation};
@@ -3,109 +3,328 @@
 //! 该模块负责：
 //! - 对外提供 `MultiTimeframeContext` API；
 //! - 协调各子模块完成追加、回溯、快照导出与事件通知；
-//! - 组合内部管理器完成单周期处理链路。
+//! - 组合内部管理器完成单周期处理链路；
+//! - 以带版本号的 checkpoint 落盘与恢复全部结构状态。
 
-use std::collections::HashMap;
-use std::fs::{create_dir_all, File};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{create_dir_all, rename, File};
+use std::io::{BufReader, BufWriter, Write};
 use std::path::Path;
 
 use polars::prelude::DataFrame;
 use polars::prelude::ParquetWriter;
+use serde::{Deserialize, Serialize};
 
 use crate::bar::{CBar, Fractal, SBar};
-use crate::constant::{DataError, EventType, Timeframe};
+use crate::constant::{
+    Const, DataError, EventType, IdStrategy, KeyZoneOrigin, StructureMode, Timeframe,
+};
 use crate::events::{EventPayload, Observable, Subscriber};
-use crate::keyzone::{KeyZone, KeyZoneSignal};
-use crate::sd::{SupplyDemandConfig, SupplyDemandResult};
+use crate::history::{StructureHistory, StructureSnapshot};
+use crate::id_generator::{StructureIdGenerators, StructureIdState};
+use crate::indicator::{Indicator, IndicatorOutput, IndicatorSpec};
+use crate::keyzone::factory::KeyZoneFactoryState;
+use crate::keyzone::{KeyZone, KeyZoneBuilder, KeyZoneConfig, KeyZoneFactory, KeyZoneSignal};
+use crate::sd::{
+    SupplyDemandConfig, SupplyDemandProfileConfig, SupplyDemandResult, TimeframeStructure,
+};
 use crate::swing::Swing;
 use crate::trend::Trend;
 use crate::timeframe_manager::TimeframeManager;
+use crate::validation::{ValidationReport, Violation, ViolationLog};
+
+/// 构造 `MultiTimeframeContext` 时的可选项。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ContextConfig {
+    pub structure_mode: StructureMode,
+    pub id_strategy: IdStrategy,
+    /// 记录分型/Swing/Trend 的全部版本，以支持 `get_structures_as_of` 时点查询。
+    #[serde(default)]
+    pub record_history: bool,
+    /// 每次 append 后校验受影响的结构，新违例记入 `get_violations` 并派发 `InvariantViolated`。
+    #[serde(default)]
+    pub validate_on_append: bool,
+}
 
 pub struct MultiTimeframeContext {
     symbol: String,
+    config: ContextConfig,
+    ids: StructureIdGenerators,
     managers: HashMap<Timeframe, TimeframeManager>,
+    /// 各周期的关键区生成配置，checkpoint 只保存内置 builder 与启停状态。
+    keyzone_factories: HashMap<Timeframe, KeyZoneFactory>,
+    /// 设置后各周期的 SD 配置按 symbol 与 timeframe 从中解析。
+    sd_profile: Option<SupplyDemandProfileConfig>,
+    /// 持续校验累计的违例，不写入 checkpoint。
+    violations: HashMap<Timeframe, ViolationLog>,
     observable: Observable,
 }
 
+/// checkpoint 文件内容；`M` 写出时为引用，读回时为所有权值。
+#[derive(Serialize, Deserialize)]
+struct ContextCheckpoint<M> {
+    version: u32,
+    symbol: String,
+    config: ContextConfig,
+    ids: StructureIdState,
+    timeframes: Vec<M>,
+    keyzone_factories: Vec<(Timeframe, KeyZoneFactoryState)>,
+    indicators: Vec<(Timeframe, Vec<IndicatorSpec>)>,
+    sd_profile: Option<SupplyDemandProfileConfig>,
+}
+
+/// checkpoint 无法保存、恢复后需重新注册的配置。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointOmission {
+    /// 没有 `KeyZoneBuilder::spec` 的 builder；其启停状态仍会保存。
+    KeyZoneBuilder(Timeframe, KeyZoneOrigin),
+    /// 没有 `Indicator::spec` 的指标，携带指标名。
+    Indicator(Timeframe, String),
+    Subscribers,
+}
+
+impl std::fmt::Display for CheckpointOmission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::KeyZoneBuilder(tf, origin) => {
+                write!(f, "{} keyzone builder {origin:?}", tf.as_str())
+            }
+            Self::Indicator(tf, name) => write!(f, "{} indicator {name}", tf.as_str()),
+            Self::Subscribers => write!(f, "subscribers"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CheckpointHeader {
+    version: u32,
+}
+
 impl MultiTimeframeContext {
     pub fn new(symbol: impl Into<String>) -> Self {
+        Self::with_config(symbol, ContextConfig::default())
+    }
+
+    pub fn with_structure_mode(symbol: impl Into<String>, structure_mode: StructureMode) -> Self {
+        Self::with_config(
+            symbol,
+            ContextConfig {
+                structure_mode,
+                ..ContextConfig::default()
+            },
+        )
+    }
+
+    pub fn with_id_strategy(symbol: impl Into<String>, id_strategy: IdStrategy) -> Self {
+        Self::with_config(
+            symbol,
+            ContextConfig {
+                id_strategy,
+                ..ContextConfig::default()
+            },
+        )
+    }
+
+    /// 各 timeframe 共用同一套 id 生成器，非 `Snowflake` 策略下 id 在 context 内唯一且可复现。
+    pub fn with_config(symbol: impl Into<String>, config: ContextConfig) -> Self {
         Self {
             symbol: symbol.into(),
+            config,
+            ids: StructureIdGenerators::new(config.id_strategy),
             managers: HashMap::new(),
+            keyzone_factories: HashMap::new(),
+            sd_profile: None,
+            violations: HashMap::new(),
             observable: Observable::default(),
         }
     }
 
+    /// 以 SD profile 构造，之后注册的 timeframe 自动使用解析出的 SD 配置。
+    pub fn with_sd_profile(symbol: impl Into<String>, profile: SupplyDemandProfileConfig) -> Self {
+        let mut mtc = Self::new(symbol);
+        mtc.set_sd_profile(profile);
+        mtc
+    }
+
+    /// 注册 timeframe，关键区按 `KeyZoneFactory::with_defaults` 生成；
+    /// 设置了 SD profile 时 SD 使用其解析结果。长度为 0 的无效周期忽略。
     pub fn register(&mut self, timeframe: Timeframe) {
-        self.managers
+        if let Err(err) = timeframe.validate() {
+            tracing::warn!("{} ignore timeframe registration: {err}", self.symbol);
+            return;
+        }
+        if !self.managers.contains_key(&timeframe) {
+            let mut manager =
+                TimeframeManager::new(timeframe, self.config.structure_mode, self.ids.clone());
+            if self.config.record_history {
+                manager.enable_history();
+            }
+            if let Some(config) = self.resolve_sd_config(timeframe) {
+                manager.set_sd_config(config);
+            }
+            self.managers.insert(timeframe, manager);
+        }
+        self.keyzone_factories
             .entry(timeframe)
-            .or_insert_with(|| TimeframeManager::new(timeframe));
+            .or_insert_with(KeyZoneFactory::with_defaults);
+        self.register_builder_indicators(timeframe);
     }
 
     pub fn subscribe(&mut self, event_type: Option<EventType>, subscriber: Subscriber) {
         self.observable.subscribe(event_type, subscriber);
     }
 
+    /// 注册额外的关键区 builder（如 `EmaKeyZoneBuilder`），同一来源只保留最后注册的；
+    /// builder 依赖的指标同时注册到该周期。
+    ///
+    /// 内置 builder 随 checkpoint 保存；自定义 builder 恢复后需重新注册。
+    pub fn register_keyzone_builder(
+        &mut self,
+        timeframe: Timeframe,
+        builder: Box<dyn KeyZoneBuilder + Send + Sync>,
+    ) {
+        self.keyzone_factories
+            .entry(timeframe)
+            .or_insert_with(KeyZoneFactory::with_defaults)
+            .register(builder);
+        self.register_builder_indicators(timeframe);
+    }
+
+    /// 注册指标并按已有 SBar 补算历史，之后随每根 SBar 更新；同名指标只保留先注册的。
+    ///
+    /// 内置指标随 checkpoint 保存并在恢复时补算；自定义指标恢复后需重新注册。
+    pub fn register_indicator(&mut self, timeframe: Timeframe, indicator: Box<dyn Indicator>) {
+        if let Some(manager) = self.managers.get_mut(&timeframe) {
+            manager.register_indicator(indicator);
+        }
+    }
+
+    /// 整体替换该周期的关键区生成配置，空 factory 表示不再生成关键区。
+    pub fn set_keyzone_factory(&mut self, timeframe: Timeframe, factory: KeyZoneFactory) {
+        self.keyzone_factories.insert(timeframe, factory);
+        self.register_builder_indicators(timeframe);
+    }
+
+    /// 注册 builder 依赖的指标；timeframe 尚未注册时推迟到 `register`。
+    fn register_builder_indicators(&mut self, timeframe: Timeframe) {
+        let (Some(factory), Some(manager)) = (
+            self.keyzone_factories.get(&timeframe),
+            self.managers.get_mut(&timeframe),
+        ) else {
+            return;
+        };
+        for indicator in factory.indicators() {
+            manager.register_indicator(indicator);
+        }
+    }
+
+    /// 启用/停用某一来源：停用后不再生成该来源的新关键区，已有关键区照常推进生命周期。
+    pub fn set_keyzone_origin_enabled(
+        &mut self,
+        timeframe: Timeframe,
+        origin_type: KeyZoneOrigin,
+        enabled: bool,
+    ) {
+        self.keyzone_factories
+            .entry(timeframe)
+            .or_insert_with(KeyZoneFactory::with_defaults)
+            .set_enabled(origin_type, enabled);
+    }
+
+    pub fn keyzone_factory(&self, timeframe: Timeframe) -> Option<&KeyZoneFactory> {
+        self.keyzone_factories.get(&timeframe)
+    }
+
+    /// 追加 SBar 并按层级顺序派发实际发生的结构变化；无订阅者时不收集事件。
     pub fn append(&mut self, timeframe: Timeframe, sbar: SBar) {
+        let Some(manager) = self.managers.get_mut(&timeframe) else {
+            return;
+        };
+        let (sbar, backtrack) = manager.append_structures(sbar);
+        // builder 读取的是本根 SBar 更新后的结构
+        let built = self
+            .keyzone_factories
+            .get(&timeframe)
+            .map(|x| x.build_all(self, timeframe))
+            .unwrap_or_default();
+        let others = self.timeframe_structures(timeframe);
+        let Some(manager) = self.managers.get_mut(&timeframe) else {
+            return;
+        };
+        let mut events = Vec::new();
+        let collect = self.observable.has_subscribers();
+        manager.finish_append(sbar, built, &others, collect.then_some(&mut events));
+        if self.config.validate_on_append {
+            let found = manager.validate_recent(backtrack);
+            for violation in self.violations.entry(timeframe).or_default().record(found) {
+                tracing::warn!("{} {} invariant violated: {violation}", self.symbol, timeframe.as_str());
+                events.push(EventPayload::InvariantViolated(violation));
+            }
+        }
+        events.push(EventPayload::TimeframeEnd(backtrack));
+        events.push(EventPayload::MtcNewBar(backtrack));
+        for event in &events {
+            self.observable.notify(timeframe, event);
+        }
+    }
+
+    /// 除 `timeframe` 外各已注册周期的最新结构方向，按周期由短到长排列。
+    pub fn timeframe_structures(&self, timeframe: Timeframe) -> Vec<TimeframeStructure> {
+        let mut structures = self
+            .managers
+            .values()
+            .filter(|x| x.timeframe != timeframe)
+            .map(TimeframeManager::structure)
+            .collect::<Vec<_>>();
+        structures.sort_by_key(|x| x.timeframe);
+        structures
+    }
+
+    pub fn set_keyzone_config(&mut self, timeframe: Timeframe, config: KeyZoneConfig) {
         if let Some(manager) = self.managers.get_mut(&timeframe) {
-            let evt = manager.append(sbar);
-            self.observable.notify(
-                timeframe,
-                EventType::SBarCreated,
-                EventPayload {
-                    backtrack_id: None,
-                    note: Some("sbar appended".to_string()),
-                },
-            );
-            self.observable.notify(
-                timeframe,
-                EventType::CBarChanged,
-                EventPayload {
-                    backtrack_id: evt.cbar_backtrack_id,
-                    note: Some("cbar changed".to_string()),
-                },
-            );
-            self.observable.notify(
-                timeframe,
-                EventType::SwingChanged,
-                EventPayload {
-                    backtrack_id: evt.swing_backtrack_id,
-                    note: Some("swing changed".to_string()),
-                },
-            );
-            self.observable.notify(
-                timeframe,
-                EventType::TrendChanged,
-                EventPayload {
-                    backtrack_id: evt.trend_backtrack_id,
-                    note: Some("trend changed".to_string()),
-                },
-            );
-            self.observable.notify(
-                timeframe,
-                EventType::TimeframeEnd,
-                EventPayload {
-                    backtrack_id: evt.cbar_backtrack_id,
-                    note: Some("timeframe pipeline done".to_string()),
-                },
-            );
-            self.observable.notify(
-                timeframe,
-                EventType::MtcNewBar,
-                EventPayload {
-                    backtrack_id: evt.cbar_backtrack_id,
-                    note: Some("timeframe pipeline completed".to_string()),
-                },
-            );
+            manager.set_keyzone_config(config);
         }
     }
 
+    /// 手动指定的配置会在下次 `set_sd_profile` 时被 profile 的解析结果覆盖。
     pub fn set_sd_config(&mut self, timeframe: Timeframe, config: SupplyDemandConfig) {
         if let Some(manager) = self.managers.get_mut(&timeframe) {
             manager.set_sd_config(config);
         }
     }
 
+    /// 设置或重新加载 SD profile，并为所有已注册 timeframe 重新解析配置。
+    ///
+    /// 只替换 SD 配置，已有的 SBar/结构/关键区状态不变，新配置从下一根 SBar 起生效。
+    pub fn set_sd_profile(&mut self, profile: SupplyDemandProfileConfig) {
+        self.sd_profile = Some(profile);
+        let timeframes = self.managers.keys().copied().collect::<Vec<_>>();
+        for timeframe in timeframes {
+            if let (Some(config), Some(manager)) = (
+                self.resolve_sd_config(timeframe),
+                self.managers.get_mut(&timeframe),
+            ) {
+                manager.set_sd_config(config);
+            }
+        }
+    }
+
+    pub fn sd_profile(&self) -> Option<&SupplyDemandProfileConfig> {
+        self.sd_profile.as_ref()
+    }
+
+    pub fn get_sd_config(&self, timeframe: Timeframe) -> Option<&SupplyDemandConfig> {
+        self.managers.get(&timeframe).map(|m| m.sd.config())
+    }
+
+    /// 以 symbol 中交易所之前的部分（如 `I2601.DCE` 取 `I2601`）匹配 profile。
+    fn resolve_sd_config(&self, timeframe: Timeframe) -> Option<SupplyDemandConfig> {
+        let code = self.symbol.split('.').next().unwrap_or_default();
+        self.sd_profile
+            .as_ref()
+            .map(|x| x.resolve_for(code, timeframe))
+    }
+
     pub fn get_sbar_window(&self, timeframe: Timeframe, length: usize) -> Vec<SBar> {
         self.managers
             .get(&timeframe)
@@ -120,6 +339,30 @@ impl MultiTimeframeContext {
             .unwrap_or_default()
     }
 
+    /// 指标最近 `length` 个值，与 `get_sbar_window` 逐根对齐。
+    pub fn get_indicator_window(
+        &self,
+        timeframe: Timeframe,
+        name: &str,
+        length: usize,
+    ) -> Vec<IndicatorOutput> {
+        self.managers
+            .get(&timeframe)
+            .map(|m| m.indicator_manager.last_n(name, length))
+            .unwrap_or_default()
+    }
+
+    /// 各指标在最新 SBar 上的输出。
+    pub fn get_latest_indicators(
+        &self,
+        timeframe: Timeframe,
+    ) -> BTreeMap<String, IndicatorOutput> {
+        self.managers
+            .get(&timeframe)
+            .map(|m| m.indicator_manager.latest())
+            .unwrap_or_default()
+    }
+
     pub fn get_sd(&self, timeframe: Timeframe) -> Option<&SupplyDemandResult> {
         self.managers
             .get(&timeframe)
@@ -150,6 +393,17 @@ impl MultiTimeframeContext {
             .unwrap_or_default()
     }
 
+    /// 已失效（突破/过期）的关键区，按失效先后取最近 `length` 个。
+    pub fn get_archived_keyzones(&self, timeframe: Timeframe, length: usize) -> Vec<KeyZone> {
+        self.managers
+            .get(&timeframe)
+            .map(|m| {
+                let archived = m.keyzone_manager.archived();
+                archived[archived.len().saturating_sub(length)..].to_vec()
+            })
+            .unwrap_or_default()
+    }
+
     pub fn get_swing_window(&self, timeframe: Timeframe, length: usize) -> Vec<Swing> {
         self.managers
             .get(&timeframe)
@@ -188,6 +442,47 @@ impl MultiTimeframeContext {
             .and_then(|m| m.cbar_manager.next_fractal(cbar_id))
     }
 
+    /// SBar `sbar_id` 收盘时实时管线可见的分型/Swing/Trend，不含之后回溯带来的改写。
+    ///
+    /// 需以 `ContextConfig::record_history` 构造；未记录历史或 timeframe 未注册时返回 `None`。
+    pub fn get_structures_as_of(
+        &self,
+        timeframe: Timeframe,
+        sbar_id: u64,
+    ) -> Option<StructureSnapshot> {
+        self.get_structure_history(timeframe)
+            .map(|x| x.as_of(sbar_id))
+    }
+
+    /// 全部结构版本及其 `known_at`/`revised_at`，用于导出时点一致的结构序列。
+    pub fn get_structure_history(&self, timeframe: Timeframe) -> Option<&StructureHistory> {
+        self.managers
+            .get(&timeframe)
+            .and_then(|m| m.history.as_ref())
+    }
+
+    /// 按路线图的各层验收要点校验该 timeframe 的全部结构；timeframe 未注册时返回 `None`。
+    pub fn validate(&self, timeframe: Timeframe) -> Option<ValidationReport> {
+        let manager = self.managers.get(&timeframe)?;
+        Some(ValidationReport {
+            timeframe,
+            sbar_id: manager.sbar_manager.rows().last().and_then(|x| x.id),
+            sbars: manager.sbar_manager.rows().len(),
+            cbars: manager.cbar_manager.rows().len(),
+            swings: manager.swing_manager.rows().len(),
+            trends: manager.trend_manager.rows().len(),
+            violations: manager.validate(),
+        })
+    }
+
+    /// `validate_on_append` 开启后累计发现的违例，按发现先后排列。
+    pub fn get_violations(&self, timeframe: Timeframe) -> &[Violation] {
+        self.violations
+            .get(&timeframe)
+            .map(|x| x.violations())
+            .unwrap_or_default()
+    }
+
     pub fn count(&self, timeframe: Timeframe) -> usize {
         self.managers
             .get(&timeframe)
@@ -219,6 +514,12 @@ impl MultiTimeframeContext {
             .map(|m| m.trend_manager.dataframe())
     }
 
+    pub fn get_indicator_dataframe(&self, timeframe: Timeframe) -> Option<DataFrame> {
+        self.managers
+            .get(&timeframe)
+            .map(|m| m.indicator_manager.dataframe(m.sbar_manager.rows()))
+    }
+
     pub fn write_parquet_snapshot(
         &self,
         timeframe: Timeframe,
@@ -231,9 +532,144 @@ impl MultiTimeframeContext {
         Ok(())
     }
 
+    /// 将全部 timeframe 的结构状态、id 生成器、关键区与指标配置及 SD profile 写入 checkpoint（JSON）。
+    ///
+    /// 先写临时文件再改名，进程中途退出不会留下半个 checkpoint。
+    /// 返回未能保存、恢复后需重新注册的部分（订阅者、自定义 builder 与指标）。
+    pub fn save_checkpoint(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<CheckpointOmission>, DataError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|x| !x.as_os_str().is_empty()) {
+            create_dir_all(parent)?;
+        }
+
+        let mut omitted = Vec::new();
+        let mut timeframes = self.managers.values().collect::<Vec<_>>();
+        timeframes.sort_by_key(|x| x.timeframe);
+        let mut indicators = Vec::new();
+        for manager in &timeframes {
+            let (specs, names) = manager.indicator_manager.specs();
+            omitted.extend(
+                names
+                    .into_iter()
+                    .map(|x| CheckpointOmission::Indicator(manager.timeframe, x)),
+            );
+            if !specs.is_empty() {
+                indicators.push((manager.timeframe, specs));
+            }
+        }
+        let mut keyzone_factories = Vec::new();
+        for (timeframe, factory) in &self.keyzone_factories {
+            let (state, origins) = factory.state();
+            omitted.extend(
+                origins
+                    .into_iter()
+                    .map(|x| CheckpointOmission::KeyZoneBuilder(*timeframe, x)),
+            );
+            keyzone_factories.push((*timeframe, state));
+        }
+        keyzone_factories.sort_by_key(|x| x.0);
+        omitted.sort_by_key(|x| match x {
+            CheckpointOmission::KeyZoneBuilder(tf, _) | CheckpointOmission::Indicator(tf, _) => {
+                Some(*tf)
+            }
+            CheckpointOmission::Subscribers => None,
+        });
+        if self.observable.has_subscribers() {
+            omitted.push(CheckpointOmission::Subscribers);
+        }
+
+        let checkpoint = ContextCheckpoint {
+            version: Const::CHECKPOINT_VERSION,
+            symbol: self.symbol.clone(),
+            config: self.config,
+            ids: self.ids.state(),
+            timeframes,
+            keyzone_factories,
+            indicators,
+            sd_profile: self.sd_profile.clone(),
+        };
+
+        // 在完整文件名后追加后缀，目标本身以 .tmp 结尾或仅扩展名不同时也不会冲突
+        let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = path.with_file_name(tmp_name);
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer(&mut writer, &checkpoint)?;
+        writer.flush()?;
+        drop(writer);
+        rename(&tmp_path, path)?;
+        for item in &omitted {
+            tracing::warn!("{} checkpoint omits {item}", self.symbol);
+        }
+        Ok(omitted)
+    }
+
+    /// 从 checkpoint 恢复 context；重新注册 `save_checkpoint` 报告遗漏的部分后，
+    /// 之后追加的 SBar 与不中断运行的结果一致。
+    ///
+    /// 版本号不匹配时返回 `DataError::CheckpointVersion`。
+    pub fn load_checkpoint(path: impl AsRef<Path>) -> Result<Self, DataError> {
+        let content = std::io::read_to_string(BufReader::new(File::open(path)?))?;
+        let header: CheckpointHeader = serde_json::from_str(&content)?;
+        if header.version != Const::CHECKPOINT_VERSION {
+            return Err(DataError::CheckpointVersion(header.version));
+        }
+
+        let checkpoint: ContextCheckpoint<TimeframeManager> = serde_json::from_str(&content)?;
+        let ids = StructureIdGenerators::restore(checkpoint.config.id_strategy, &checkpoint.ids);
+        let mut managers = checkpoint
+            .timeframes
+            .into_iter()
+            .map(|mut manager| {
+                manager.attach(ids.clone());
+                (manager.timeframe, manager)
+            })
+            .collect::<HashMap<_, _>>();
+        for (timeframe, specs) in checkpoint.indicators {
+            if let Some(manager) = managers.get_mut(&timeframe) {
+                for spec in specs {
+                    manager.register_indicator(spec.build());
+                }
+            }
+        }
+        let keyzone_factories = checkpoint
+            .keyzone_factories
+            .iter()
+            .map(|(timeframe, state)| (*timeframe, KeyZoneFactory::restore(state)))
+            .collect();
+
+        let mut mtc = Self {
+            symbol: checkpoint.symbol,
+            config: checkpoint.config,
+            ids,
+            managers,
+            keyzone_factories,
+            sd_profile: checkpoint.sd_profile,
+            violations: HashMap::new(),
+            observable: Observable::default(),
+        };
+        // builder 依赖的内置指标通常已随 IndicatorSpec 恢复，同名注册不会重复
+        let timeframes = mtc.keyzone_factories.keys().copied().collect::<Vec<_>>();
+        for timeframe in timeframes {
+            mtc.register_builder_indicators(timeframe);
+        }
+        Ok(mtc)
+    }
+
     pub fn symbol(&self) -> &str {
         &self.symbol
     }
+
+    pub fn structure_mode(&self) -> StructureMode {
+        self.config.structure_mode
+    }
+
+    pub fn id_strategy(&self) -> IdStrategy {
+        self.config.id_strategy
+    }
 }
 
 fn write_parquet_snapshot_for_timeframe(
@@ -244,7 +680,7 @@ fn write_parquet_snapshot_for_timeframe(
     let output_dir = output_dir.as_ref();
     create_dir_all(output_dir)?;
 
-    let tf = format!("{:?}", timeframe).to_lowercase();
+    let tf = timeframe.as_str();
 
     let mut sbar_file = File::create(output_dir.join(format!("sbar_{tf}.parquet")))?;
     let mut sbar_df = manager.sbar_manager.dataframe();
@@ -262,6 +698,14 @@ fn write_parquet_snapshot_for_timeframe(
     let mut trend_df = manager.trend_manager.dataframe();
     ParquetWriter::new(&mut trend_file).finish(&mut trend_df)?;
 
+    if !manager.indicator_manager.is_empty() {
+        let mut indicator_file = File::create(output_dir.join(format!("indicator_{tf}.parquet")))?;
+        let mut indicator_df = manager
+            .indicator_manager
+            .dataframe(manager.sbar_manager.rows());
+        ParquetWriter::new(&mut indicator_file).finish(&mut indicator_df)?;
+    }
+
     Ok(())
 }
 
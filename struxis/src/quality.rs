@@ -0,0 +1,409 @@
+//! 行情 bar 数据质量检查。
+//!
+//! `DataReceiver` 在 bar 进入 context 前按 (品种, 周期) 逐根检查，
+//! 每类问题按 `QualityPolicy` 拒绝、修复或仅标记，并汇总为 `QualityReport`：
+//! - 价格非正/非有限、OHLC 不自洽（`high < low`、开收盘越界）；
+//! - 成交量、成交额、持仓量为负；
+//! - 时间戳重复或倒退；
+//! - 相对上一根收盘价的跳变超过阈值；
+//! - 按交易日历缺失的 bar（只标记，仅对整分钟的日内周期检查）。
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::bar::SBar;
+use crate::constant::Timeframe;
+use crate::session::SessionCalendar;
+
+/// 报告中保留的最近问题条数，更早的只计入计数。
+const MAX_ISSUES: usize = 500;
+/// 缺口检查最多向前扫描的时长，超出部分不计入缺失 bar。
+const MAX_GAP_SCAN_DAYS: i64 = 31;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum QualityCheck {
+    /// 价格非正或非有限值，无法修复。
+    InvalidPrice,
+    /// `high`/`low` 未包住开收盘或 `high < low`；修复为四价的最大/最小值。
+    Ohlc,
+    /// 成交量、成交额或持仓量为负；修复为 0。
+    NegativeVolume,
+    /// 与上一根 bar 时间戳相同，无法修复。
+    Duplicate,
+    /// 时间戳早于上一根 bar，无法修复。
+    NonMonotonic,
+    /// 相对上一根收盘价的跳变超过 `max_jump`；修复为截断到阈值内。
+    Outlier,
+    /// 与上一根 bar 之间按交易日历缺失 bar，只标记。
+    Gap,
+}
+
+/// 检出问题后的处理方式；无法修复的问题按 `Reject` 处理。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum QualityAction {
+    Reject,
+    Repair,
+    Flag,
+}
+
+/// 各类问题的处理策略。默认全部只标记，不改变输入；`strict` 拒绝或修复问题 bar。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QualityPolicy {
+    pub invalid_price: QualityAction,
+    pub ohlc: QualityAction,
+    pub negative_volume: QualityAction,
+    pub duplicate: QualityAction,
+    pub non_monotonic: QualityAction,
+    pub outlier: QualityAction,
+    /// 相对上一根收盘价的最大跳变比例。
+    pub max_jump: f64,
+}
+
+impl Default for QualityPolicy {
+    fn default() -> Self {
+        Self {
+            invalid_price: QualityAction::Flag,
+            ohlc: QualityAction::Flag,
+            negative_volume: QualityAction::Flag,
+            duplicate: QualityAction::Flag,
+            non_monotonic: QualityAction::Flag,
+            outlier: QualityAction::Flag,
+            max_jump: 0.1,
+        }
+    }
+}
+
+impl QualityPolicy {
+    pub fn strict() -> Self {
+        Self {
+            invalid_price: QualityAction::Reject,
+            ohlc: QualityAction::Repair,
+            negative_volume: QualityAction::Repair,
+            duplicate: QualityAction::Reject,
+            non_monotonic: QualityAction::Reject,
+            outlier: QualityAction::Flag,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityIssue {
+    pub check: QualityCheck,
+    /// 实际采取的处理方式。
+    pub action: QualityAction,
+    pub datetime: DateTime<Utc>,
+    pub message: String,
+}
+
+impl Display for QualityIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} {:?} at {}: {}",
+            self.check, self.action, self.datetime, self.message
+        )
+    }
+}
+
+/// 单一品种、周期的数据质量汇总。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityReport {
+    pub symbol: String,
+    pub timeframe: Timeframe,
+    /// 收到的 bar 数。
+    pub bars: usize,
+    pub accepted: usize,
+    pub rejected: usize,
+    /// 至少修复过一项的 bar 数。
+    pub repaired: usize,
+    /// 带有仅标记问题、原样放行的 bar 数。
+    pub flagged: usize,
+    /// 缺失 bar 的总根数（`Gap` 计数为缺口段数）。
+    pub missing_bars: usize,
+    pub counts: BTreeMap<QualityCheck, usize>,
+    /// 最近的问题明细，至多保留 500 条。
+    pub issues: VecDeque<QualityIssue>,
+}
+
+impl QualityReport {
+    pub fn new(symbol: impl Into<String>, timeframe: Timeframe) -> Self {
+        Self {
+            symbol: symbol.into(),
+            timeframe,
+            bars: 0,
+            accepted: 0,
+            rejected: 0,
+            repaired: 0,
+            flagged: 0,
+            missing_bars: 0,
+            counts: BTreeMap::new(),
+            issues: VecDeque::new(),
+        }
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    pub fn count(&self, check: QualityCheck) -> usize {
+        self.counts.get(&check).copied().unwrap_or_default()
+    }
+
+    fn record(&mut self, issue: QualityIssue) {
+        *self.counts.entry(issue.check).or_default() += 1;
+        if self.issues.len() == MAX_ISSUES {
+            self.issues.pop_front();
+        }
+        self.issues.push_back(issue);
+    }
+}
+
+impl Display for QualityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: bars={} accepted={} rejected={} repaired={} flagged={} missing_bars={}",
+            self.symbol,
+            self.timeframe.as_str(),
+            self.bars,
+            self.accepted,
+            self.rejected,
+            self.repaired,
+            self.flagged,
+            self.missing_bars
+        )?;
+        for (check, count) in &self.counts {
+            write!(f, " {check:?}={count}")?;
+        }
+        Ok(())
+    }
+}
+
+/// 单一品种、周期的质量闸门，记住上一根放行的 bar 用于时间戳、跳变与缺口检查。
+///
+/// 缺口按 `calendar` 的交易时段计算，默认 7x24。
+#[derive(Debug, Clone)]
+pub struct QualityGate {
+    policy: QualityPolicy,
+    calendar: SessionCalendar,
+    report: QualityReport,
+    last: Option<(DateTime<Utc>, f64)>,
+}
+
+impl QualityGate {
+    pub fn new(symbol: impl Into<String>, timeframe: Timeframe, policy: QualityPolicy) -> Self {
+        Self {
+            policy,
+            calendar: SessionCalendar::continuous(),
+            report: QualityReport::new(symbol, timeframe),
+            last: None,
+        }
+    }
+
+    pub fn with_calendar(mut self, calendar: SessionCalendar) -> Self {
+        self.calendar = calendar;
+        self
+    }
+
+    pub fn set_policy(&mut self, policy: QualityPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn set_calendar(&mut self, calendar: SessionCalendar) {
+        self.calendar = calendar;
+    }
+
+    pub fn report(&self) -> &QualityReport {
+        &self.report
+    }
+
+    /// 返回放行（可能已修复）的 bar，被拒绝时返回 `None`。
+    pub fn check(&mut self, mut bar: SBar) -> Option<SBar> {
+        self.report.bars += 1;
+        let mut found = Vec::new();
+
+        let prices = [
+            bar.open_price,
+            bar.high_price,
+            bar.low_price,
+            bar.close_price,
+        ];
+        if prices.iter().any(|x| !x.is_finite() || *x <= 0.0) {
+            found.push((
+                QualityCheck::InvalidPrice,
+                self.policy.invalid_price,
+                format!("prices {prices:?}"),
+            ));
+        }
+        if let Some((last, _)) = self.last {
+            if bar.datetime == last {
+                found.push((
+                    QualityCheck::Duplicate,
+                    self.policy.duplicate,
+                    "same timestamp as previous bar".to_string(),
+                ));
+            } else if bar.datetime < last {
+                found.push((
+                    QualityCheck::NonMonotonic,
+                    self.policy.non_monotonic,
+                    format!("previous bar at {last}"),
+                ));
+            }
+        }
+        let high = prices.iter().copied().fold(f64::MIN, f64::max);
+        let low = prices.iter().copied().fold(f64::MAX, f64::min);
+        if bar.high_price < high || bar.low_price > low {
+            found.push((
+                QualityCheck::Ohlc,
+                self.policy.ohlc,
+                format!("high={} low={}", bar.high_price, bar.low_price),
+            ));
+        }
+        if [bar.volume, bar.turnover, bar.open_interest]
+            .iter()
+            .any(|x| *x < 0.0)
+        {
+            found.push((
+                QualityCheck::NegativeVolume,
+                self.policy.negative_volume,
+                format!(
+                    "volume={} turnover={} open_interest={}",
+                    bar.volume, bar.turnover, bar.open_interest
+                ),
+            ));
+        }
+        let band = self.last.map(|(_, close)| {
+            (
+                close * (1.0 - self.policy.max_jump),
+                close * (1.0 + self.policy.max_jump),
+            )
+        });
+        if let Some((floor, cap)) = band
+            && (high > cap || low < floor)
+        {
+            found.push((
+                QualityCheck::Outlier,
+                self.policy.outlier,
+                format!("range {low}..{high} outside {floor}..{cap}"),
+            ));
+        }
+
+        let mut rejected = false;
+        let (mut repaired, mut flagged) = (false, false);
+        for (check, action, message) in found {
+            let action = match (check, action) {
+                (
+                    QualityCheck::InvalidPrice
+                    | QualityCheck::Duplicate
+                    | QualityCheck::NonMonotonic,
+                    QualityAction::Repair,
+                ) => QualityAction::Reject,
+                _ => action,
+            };
+            match action {
+                QualityAction::Reject => rejected = true,
+                QualityAction::Repair => repaired = true,
+                QualityAction::Flag => flagged = true,
+            }
+            if action == QualityAction::Repair {
+                repair(&mut bar, check, band);
+            }
+            self.report.record(QualityIssue {
+                check,
+                action,
+                datetime: bar.datetime,
+                message,
+            });
+        }
+        if rejected {
+            self.report.rejected += 1;
+            return None;
+        }
+
+        if let Some((last, _)) = self.last
+            && bar.datetime > last
+        {
+            let missing = missing_bars(&self.calendar, self.report.timeframe, last, bar.datetime);
+            if missing > 0 {
+                self.report.missing_bars += missing;
+                flagged = true;
+                self.report.record(QualityIssue {
+                    check: QualityCheck::Gap,
+                    action: QualityAction::Flag,
+                    datetime: bar.datetime,
+                    message: format!("{missing} bars missing since {last}"),
+                });
+            }
+        }
+
+        self.report.accepted += 1;
+        self.report.repaired += repaired as usize;
+        self.report.flagged += flagged as usize;
+        self.last = Some((
+            self.last.map_or(bar.datetime, |(x, _)| x.max(bar.datetime)),
+            bar.close_price,
+        ));
+        Some(bar)
+    }
+}
+
+fn repair(bar: &mut SBar, check: QualityCheck, band: Option<(f64, f64)>) {
+    match check {
+        QualityCheck::Ohlc => {
+            let prices = [
+                bar.open_price,
+                bar.high_price,
+                bar.low_price,
+                bar.close_price,
+            ];
+            bar.high_price = prices.iter().copied().fold(f64::MIN, f64::max);
+            bar.low_price = prices.iter().copied().fold(f64::MAX, f64::min);
+        }
+        QualityCheck::NegativeVolume => {
+            bar.volume = bar.volume.max(0.0);
+            bar.turnover = bar.turnover.max(0.0);
+            bar.open_interest = bar.open_interest.max(0.0);
+        }
+        QualityCheck::Outlier => {
+            if let Some((floor, cap)) = band {
+                for price in [
+                    &mut bar.open_price,
+                    &mut bar.high_price,
+                    &mut bar.low_price,
+                    &mut bar.close_price,
+                ] {
+                    *price = price.clamp(floor, cap);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 两根 bar 之间缺失的根数：区间 `[prev, cur)` 内的交易分钟应恰好构成一根 bar，
+/// 多出的整根数即缺失数。与 bar 时间戳标在区间起点还是终点无关。
+fn missing_bars(
+    calendar: &SessionCalendar,
+    timeframe: Timeframe,
+    prev: DateTime<Utc>,
+    cur: DateTime<Utc>,
+) -> usize {
+    let step = timeframe.minutes();
+    if !timeframe.is_intraday() || timeframe.is_sub_minute() || step <= 0 {
+        return 0;
+    }
+    let end = cur.min(prev + Duration::days(MAX_GAP_SCAN_DAYS));
+    let mut open = 0i64;
+    let mut t = prev;
+    while t < end {
+        if calendar.is_open(t) {
+            open += 1;
+        }
+        t += Duration::minutes(1);
+    }
+    (open / step - 1).max(0) as usize
+}
@@ -1,13 +1,17 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, btree_map, hash_map};
 use std::path::Path;
 
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use serde::Deserialize;
 
 use crate::bar::SBar;
-use crate::constant::{DataError, Timeframe};
+use crate::bar_builder::{BarBuilder, bar_builder};
+use crate::constant::{Const, DataError, Timeframe};
 use crate::mtc::MultiTimeframeContext;
-use crate::tick::{BarWindowAggregator, TickBarAggregator, TickInput};
+use crate::quality::{QualityGate, QualityPolicy, QualityReport};
+use crate::session::SessionCalendar;
+use crate::symbol::{SymbolRegistry, TradingSession};
+use crate::tick::{OrderFlow, SessionBarAggregator, TickAnomalies, TickBarAggregator, TickInput};
 
 /// 标准化的市场 bar 输入。
 #[derive(Debug, Clone)]
@@ -46,31 +50,119 @@ impl MarketBarInput {
 
 pub struct DataReceiver {
     mtc: MultiTimeframeContext,
-    registered_timeframes: HashSet<Timeframe>,
-    tick_aggregators: HashMap<String, TickBarAggregator>,
-    window_aggregators: HashMap<Timeframe, BarWindowAggregator>,
+    /// 有序集合保证各周期按相同先后追加，共享的 id 生成器与 F9 结果可复现。
+    registered_timeframes: BTreeSet<Timeframe>,
+    /// 每个品种一个 M1 tick 聚合器，另为注册的秒级周期各建一个。
+    tick_aggregators: BTreeMap<(String, Timeframe), TickBarAggregator>,
+    late_tick_tolerance: Duration,
+    /// 伪周期（range / renko / volume / tick 笔数）按 (品种, 周期) 各建一个构造器。
+    bar_builders: BTreeMap<(String, Timeframe), Box<dyn BarBuilder>>,
+    /// 按 (品种, 周期) 分开聚合，不同品种的 M1 不会混进同一根高周期 bar。
+    window_aggregators: HashMap<(String, Timeframe), SessionBarAggregator>,
+    sessions: HashMap<String, SessionCalendar>,
+    quality_policy: QualityPolicy,
+    /// `ingest_bar` 的质量闸门，按 (品种, 周期) 分开统计。
+    quality_gates: HashMap<(String, Timeframe), QualityGate>,
+    /// tick 合成 bar 的主动买卖量，按周期与 SBar id 索引；高周期为所含 M1 之和，伪周期不记录。
+    /// 每个周期只保留最近 `Const::LOOKBACK_LIMIT` 根，与结构回溯的范围一致。
+    order_flows: HashMap<Timeframe, BTreeMap<u64, OrderFlow>>,
 }
 
 impl DataReceiver {
     pub fn new(mtc: MultiTimeframeContext) -> Self {
         Self {
             mtc,
-            registered_timeframes: HashSet::new(),
-            tick_aggregators: HashMap::new(),
+            registered_timeframes: BTreeSet::new(),
+            tick_aggregators: BTreeMap::new(),
+            late_tick_tolerance: Duration::zero(),
+            bar_builders: BTreeMap::new(),
             window_aggregators: HashMap::new(),
+            sessions: HashMap::new(),
+            quality_policy: QualityPolicy::default(),
+            quality_gates: HashMap::new(),
+            order_flows: HashMap::new(),
         }
     }
 
+    /// 指定品种的交易时段；未指定时取 `SymbolRegistry` 中的 `sessions`，都没有则按 7x24 聚合。
+    pub fn set_session(
+        &mut self,
+        symbol: &str,
+        exchange: &str,
+        session: &TradingSession,
+    ) -> Result<(), DataError> {
+        let key = instrument_key(symbol, exchange);
+        let calendar = SessionCalendar::from_session(session)?;
+        self.window_aggregators.retain(|(x, _), _| *x != key);
+        for ((x, _), gate) in &mut self.quality_gates {
+            if *x == key {
+                gate.set_calendar(calendar.clone());
+            }
+        }
+        self.sessions.insert(key, calendar);
+        Ok(())
+    }
+
+    /// 伪周期 `Timeframe::Bars` 的 bar 只由 `ingest_tick` 构造；无效周期忽略。
     pub fn register_timeframe(&mut self, timeframe: Timeframe) {
+        if let Err(err) = timeframe.validate() {
+            tracing::warn!("ignore timeframe registration: {err}");
+            return;
+        }
         self.mtc.register(timeframe);
         self.registered_timeframes.insert(timeframe);
-        if let Some(agg) = BarWindowAggregator::new(timeframe) {
-            self.window_aggregators.insert(timeframe, agg);
+    }
+
+    /// 外部 bar 入口的质量策略，对已有闸门同样生效；默认只标记不改动输入。
+    pub fn set_quality_policy(&mut self, policy: QualityPolicy) {
+        self.quality_policy = policy;
+        for gate in self.quality_gates.values_mut() {
+            gate.set_policy(policy);
         }
     }
 
+    /// 经质量闸门检查后写入 context，被拒绝的 bar 只计入质量报告；周期无效的 bar 直接丢弃。
     pub fn ingest_bar(&mut self, input: MarketBarInput) {
-        self.mtc.append(input.timeframe, input.into_sbar());
+        let key = instrument_key(&input.symbol, &input.exchange);
+        let timeframe = input.timeframe;
+        if let Err(err) = timeframe.validate() {
+            tracing::warn!("drop {key} bar: {err}");
+            return;
+        }
+        let gate = match self.quality_gates.entry((key.clone(), timeframe)) {
+            hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            hash_map::Entry::Vacant(entry) => {
+                let calendar = session_calendar(&self.sessions, &key, &input.symbol);
+                entry.insert(
+                    QualityGate::new(key, timeframe, self.quality_policy).with_calendar(calendar),
+                )
+            }
+        };
+        if let Some(sbar) = gate.check(input.into_sbar()) {
+            self.mtc.append(timeframe, sbar);
+        }
+    }
+
+    pub fn quality_report(
+        &self,
+        symbol: &str,
+        exchange: &str,
+        timeframe: Timeframe,
+    ) -> Option<&QualityReport> {
+        self.quality_gates
+            .get(&(instrument_key(symbol, exchange), timeframe))
+            .map(QualityGate::report)
+    }
+
+    /// 全部质量报告，按品种、周期排列。
+    pub fn quality_reports(&self) -> Vec<&QualityReport> {
+        let mut reports = self
+            .quality_gates
+            .values()
+            .map(QualityGate::report)
+            .collect::<Vec<_>>();
+        reports.sort_by(|a, b| (&a.symbol, a.timeframe).cmp(&(&b.symbol, b.timeframe)));
+        reports
     }
 
     pub fn ingest_batch(&mut self, inputs: Vec<MarketBarInput>) {
@@ -79,46 +171,190 @@ impl DataReceiver {
         }
     }
 
+    /// tick 聚合 bar 在区间结束后继续等待迟到 tick 的时长，对已有聚合器同样生效。
+    pub fn set_late_tick_tolerance(&mut self, tolerance: Duration) {
+        self.late_tick_tolerance = tolerance;
+        for agg in self.tick_aggregators.values_mut() {
+            agg.set_late_tolerance(tolerance);
+        }
+    }
+
     pub fn ingest_tick(&mut self, tick: TickInput) -> usize {
-        let key = format!("{}::{}", tick.symbol, tick.exchange);
+        let key = instrument_key(&tick.symbol, &tick.exchange);
+        let mut closed = Vec::new();
+        for tf in &self.registered_timeframes {
+            if !tf.is_sub_minute() {
+                continue;
+            }
+            let aggregator = match self.tick_aggregators.entry((key.clone(), *tf)) {
+                btree_map::Entry::Occupied(entry) => entry.into_mut(),
+                btree_map::Entry::Vacant(entry) => match TickBarAggregator::try_with_timeframe(*tf)
+                {
+                    Ok(agg) => entry.insert(agg.with_late_tolerance(self.late_tick_tolerance)),
+                    Err(_) => continue,
+                },
+            };
+            closed.extend(
+                aggregator
+                    .update_with_flow(tick.clone())
+                    .into_iter()
+                    .map(|(bar, flow)| (*tf, bar, Some(flow))),
+            );
+        }
+        for tf in &self.registered_timeframes {
+            if !tf.is_pseudo() {
+                continue;
+            }
+            let builder = match self.bar_builders.entry((key.clone(), *tf)) {
+                btree_map::Entry::Occupied(entry) => entry.into_mut(),
+                btree_map::Entry::Vacant(entry) => {
+                    match bar_builder(*tf, tick_size(&tick.symbol)) {
+                        Some(builder) => entry.insert(builder),
+                        None => continue,
+                    }
+                }
+            };
+            closed.extend(builder.update(&tick).into_iter().map(|x| (*tf, x, None)));
+        }
+
         let aggregator = self
             .tick_aggregators
-            .entry(key)
-            .or_insert_with(TickBarAggregator::new);
+            .entry((key, Timeframe::M1))
+            .or_insert_with(|| {
+                TickBarAggregator::new().with_late_tolerance(self.late_tick_tolerance)
+            });
+        closed.extend(
+            aggregator
+                .update_with_flow(tick)
+                .into_iter()
+                .map(|(bar, flow)| (Timeframe::M1, bar, Some(flow))),
+        );
+        self.forward_tick_bars(closed)
+    }
 
-        let mut emitted = 0usize;
-        if let Some(m1_bar) = aggregator.update(tick) {
-            emitted += self.forward_m1_bar(m1_bar);
+    /// 定时器入口：关闭 `now` 时已到期、但尚无后续 tick 触发的 bar。
+    pub fn on_clock(&mut self, now: DateTime<Utc>) -> usize {
+        let mut closed = Vec::new();
+        for ((_, tf), agg) in &mut self.tick_aggregators {
+            closed.extend(
+                agg.on_clock_with_flow(now)
+                    .into_iter()
+                    .map(|(bar, flow)| (*tf, bar, Some(flow))),
+            );
         }
-        emitted
+        self.forward_tick_bars(closed)
     }
 
     pub fn flush_ticks(&mut self) -> usize {
+        let mut closed = Vec::new();
+        for ((_, tf), agg) in &mut self.tick_aggregators {
+            closed.extend(
+                agg.flush_with_flow()
+                    .into_iter()
+                    .map(|(bar, flow)| (*tf, bar, Some(flow))),
+            );
+        }
+        for ((_, tf), builder) in &mut self.bar_builders {
+            closed.extend(builder.flush().map(|x| (*tf, x, None)));
+        }
+        self.forward_tick_bars(closed)
+    }
+
+    /// 各品种 M1 聚合器的异常计数之和；M1 聚合器接收全部 tick，秒级聚合器不重复计入。
+    pub fn tick_anomalies(&self) -> TickAnomalies {
+        self.tick_aggregators
+            .iter()
+            .filter(|((_, tf), _)| *tf == Timeframe::M1)
+            .map(|(_, agg)| agg.anomalies())
+            .fold(TickAnomalies::default(), |acc, x| TickAnomalies {
+                volume_resets: acc.volume_resets + x.volume_resets,
+                out_of_order: acc.out_of_order + x.out_of_order,
+                late_dropped: acc.late_dropped + x.late_dropped,
+                invalid: acc.invalid + x.invalid,
+            })
+    }
+
+    /// 指定周期 SBar 的主动买卖量；只有由 tick 合成的最近 `Const::LOOKBACK_LIMIT` 根 bar 有记录。
+    pub fn order_flow(&self, timeframe: Timeframe, sbar_id: u64) -> Option<OrderFlow> {
+        self.order_flows.get(&timeframe)?.get(&sbar_id).copied()
+    }
+
+    /// 指定周期保留的 `(SBar id, 主动买卖量)`，按 id 升序。
+    pub fn order_flows(&self, timeframe: Timeframe) -> Vec<(u64, OrderFlow)> {
+        self.order_flows
+            .get(&timeframe)
+            .map(|x| x.iter().map(|(id, flow)| (*id, *flow)).collect())
+            .unwrap_or_default()
+    }
+
+    /// 按 bar 时间、再按周期由短到长追加，与聚合器的遍历先后无关。
+    fn forward_tick_bars(&mut self, mut bars: Vec<(Timeframe, SBar, Option<OrderFlow>)>) -> usize {
+        bars.sort_by_key(|(tf, bar, _)| (bar.datetime, *tf));
         let mut emitted = 0usize;
-        let mut bars = Vec::new();
-        for agg in self.tick_aggregators.values_mut() {
-            if let Some(bar) = agg.flush() {
-                bars.push(bar);
+        for (tf, bar, flow) in bars {
+            if tf == Timeframe::M1 {
+                emitted += self.forward_m1_bar(bar, flow.unwrap_or_default());
+            } else {
+                self.append_tick_bar(tf, bar, flow);
+                emitted += 1;
             }
         }
-        for bar in bars {
-            emitted += self.forward_m1_bar(bar);
-        }
         emitted
     }
 
-    fn forward_m1_bar(&mut self, m1_bar: SBar) -> usize {
+    /// 追加后以新 SBar 的 id 记录买卖量。
+    fn append_tick_bar(&mut self, timeframe: Timeframe, bar: SBar, flow: Option<OrderFlow>) {
+        self.mtc.append(timeframe, bar);
+        let Some(flow) = flow else {
+            return;
+        };
+        if let Some(id) = self
+            .mtc
+            .get_sbar_window(timeframe, 1)
+            .pop()
+            .and_then(|x| x.id)
+        {
+            let flows = self.order_flows.entry(timeframe).or_default();
+            flows.insert(id, flow);
+            // id 随追加递增，最早的记录在前
+            while flows.len() > Const::LOOKBACK_LIMIT {
+                flows.pop_first();
+            }
+        }
+    }
+
+    fn forward_m1_bar(&mut self, m1_bar: SBar, flow: OrderFlow) -> usize {
         let mut emitted = 0usize;
         if self.registered_timeframes.contains(&Timeframe::M1) {
-            self.mtc.append(Timeframe::M1, m1_bar.clone());
+            self.append_tick_bar(Timeframe::M1, m1_bar.clone(), Some(flow));
             emitted += 1;
         }
 
-        for (tf, agg) in &mut self.window_aggregators {
-            if let Some(tf_bar) = agg.update(m1_bar.clone()) {
-                self.mtc.append(*tf, tf_bar);
-                emitted += 1;
+        let key = instrument_key(&m1_bar.symbol, &m1_bar.exchange);
+        let mut finished = Vec::new();
+        for tf in &self.registered_timeframes {
+            if tf.is_sub_minute() || tf.is_pseudo() {
+                continue;
             }
+            let agg = match self.window_aggregators.entry((key.clone(), *tf)) {
+                hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                hash_map::Entry::Vacant(entry) => {
+                    let calendar = session_calendar(&self.sessions, &key, &m1_bar.symbol);
+                    match SessionBarAggregator::new(*tf, calendar) {
+                        Some(agg) => entry.insert(agg),
+                        None => continue,
+                    }
+                }
+            };
+            finished.extend(
+                agg.update_with_flow(m1_bar.clone(), flow)
+                    .into_iter()
+                    .map(|(bar, flow)| (*tf, bar, flow)),
+            );
+        }
+        for (tf, bar, flow) in finished {
+            self.append_tick_bar(tf, bar, Some(flow));
+            emitted += 1;
         }
 
         emitted
@@ -146,6 +382,39 @@ impl DataReceiver {
     }
 }
 
+fn session_calendar(
+    sessions: &HashMap<String, SessionCalendar>,
+    key: &str,
+    symbol: &str,
+) -> SessionCalendar {
+    if let Some(calendar) = sessions.get(key) {
+        return calendar.clone();
+    }
+    let Some(registered) = SymbolRegistry::get(symbol) else {
+        return SessionCalendar::continuous();
+    };
+    SessionCalendar::from_session(&registered.sessions).unwrap_or_else(|err| {
+        tracing::warn!("invalid trading session for {symbol}: {err}, fallback to 24x7");
+        SessionCalendar::continuous()
+    })
+}
+
+/// 伪周期 range / renko 的价位单位：`SymbolRegistry` 中的 `tick_size`，未登记时按 1。
+fn tick_size(symbol: &str) -> f64 {
+    SymbolRegistry::get(symbol)
+        .map(|x| x.tick_size)
+        .filter(|x| *x > 0.0)
+        .unwrap_or(1.0)
+}
+
+/// 品种标识，与 `MultiTimeframeContext` 的 symbol 约定一致（如 `I2601.DCE`）。
+pub fn instrument_key(symbol: &str, exchange: &str) -> String {
+    if exchange.is_empty() {
+        symbol.to_string()
+    } else {
+        format!("{symbol}.{exchange}")
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct CsvBarRow {
@@ -166,7 +435,8 @@ struct CsvBarRow {
     turnover: f64,
 }
 
-fn load_market_bar_inputs(
+/// 读取 `datetime,open,high,low,close[,volume,open_interest,money]` 格式的 CSV。
+pub fn load_market_bar_inputs(
     file_path: impl AsRef<Path>,
     symbol: impl Into<String>,
     exchange: impl Into<String>,
@@ -4,47 +4,52 @@
 
 use polars::df;
 use polars::prelude::DataFrame;
+use serde::{Deserialize, Serialize};
 
 use crate::constant::Timeframe;
 use crate::bar::SBar;
+use std::sync::Arc;
+
 use crate::IdGenerator;
 
+#[derive(Serialize, Deserialize)]
 pub(crate) struct SBarManager {
     timeframe: Timeframe,
     rows: Vec<SBar>,
-    id_generator: &'static IdGenerator,
+    #[serde(skip, default = "crate::id_generator::sbar_id_generator")]
+    id_generator: Arc<IdGenerator>,
+    #[serde(skip)]
     df_cache: DataFrame,
 }
 
 impl SBarManager {
-    pub(crate) fn new(timeframe: Timeframe) -> Self {
+    pub(crate) fn new(timeframe: Timeframe, id_generator: Arc<IdGenerator>) -> Self {
         Self {
             timeframe,
             rows: Vec::new(),
-            id_generator: crate::id_generator::sbar_id_generator(),
+            id_generator,
             df_cache: DataFrame::default(),
         }
     }
 
+    pub(crate) fn set_id_generator(&mut self, id_generator: Arc<IdGenerator>) {
+        self.id_generator = id_generator;
+    }
+
+    /// 反序列化后按 rows 重建 dataframe cache。
+    pub(crate) fn rebuild_dataframe(&mut self) {
+        self.df_cache = if self.rows.is_empty() {
+            DataFrame::default()
+        } else {
+            sbar_frame(&self.rows)
+        };
+    }
+
     pub(crate) fn append(&mut self, mut sbar: SBar) -> SBar {
         sbar.id = Some(self.id_generator.get_id());
         sbar.timeframe = self.timeframe;
         self.rows.push(sbar.clone());
-        let row = df!(
-            "id" => vec![sbar.id.unwrap_or_default()],
-            "datetime" => vec![sbar.datetime.timestamp_millis()],
-            "symbol" => vec![sbar.symbol.clone()],
-            "exchange" => vec![sbar.exchange.clone()],
-            "timeframe" => vec![format!("{:?}", sbar.timeframe).to_lowercase()],
-            "open_price" => vec![sbar.open_price],
-            "high_price" => vec![sbar.high_price],
-            "low_price" => vec![sbar.low_price],
-            "close_price" => vec![sbar.close_price],
-            "volume" => vec![sbar.volume],
-            "open_interest" => vec![sbar.open_interest],
-            "turnover" => vec![sbar.turnover]
-        )
-        .expect("failed to build sbar dataframe row");
+        let row = sbar_frame(std::slice::from_ref(&sbar));
         if self.df_cache.height() == 0 {
             self.df_cache = row;
         } else {
@@ -55,6 +60,10 @@ impl SBarManager {
         sbar
     }
 
+    pub(crate) fn rows(&self) -> &[SBar] {
+        &self.rows
+    }
+
     pub(crate) fn last_n(&self, n: usize) -> Vec<SBar> {
         self.rows
             .iter()
@@ -75,3 +84,24 @@ impl SBarManager {
         self.rows.len()
     }
 }
+
+fn sbar_frame(rows: &[SBar]) -> DataFrame {
+    df!(
+        "id" => rows.iter().map(|x| x.id.unwrap_or_default()).collect::<Vec<_>>(),
+        "datetime" => rows.iter().map(|x| x.datetime.timestamp_millis()).collect::<Vec<_>>(),
+        "symbol" => rows.iter().map(|x| x.symbol.clone()).collect::<Vec<_>>(),
+        "exchange" => rows.iter().map(|x| x.exchange.clone()).collect::<Vec<_>>(),
+        "timeframe" => rows
+            .iter()
+            .map(|x| x.timeframe.as_str().into_owned())
+            .collect::<Vec<_>>(),
+        "open_price" => rows.iter().map(|x| x.open_price).collect::<Vec<_>>(),
+        "high_price" => rows.iter().map(|x| x.high_price).collect::<Vec<_>>(),
+        "low_price" => rows.iter().map(|x| x.low_price).collect::<Vec<_>>(),
+        "close_price" => rows.iter().map(|x| x.close_price).collect::<Vec<_>>(),
+        "volume" => rows.iter().map(|x| x.volume).collect::<Vec<_>>(),
+        "open_interest" => rows.iter().map(|x| x.open_interest).collect::<Vec<_>>(),
+        "turnover" => rows.iter().map(|x| x.turnover).collect::<Vec<_>>()
+    )
+    .expect("failed to build sbar dataframe")
+}
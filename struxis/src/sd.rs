@@ -2,11 +2,54 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
-use crate::constant::Timeframe;
+use crate::constant::{Direction, Timeframe};
 use crate::bar::SBar;
-use serde::Deserialize;
+use crate::swing::{Swing, SwingState};
+use crate::trend::Trend;
+use serde::{Deserialize, Serialize};
+
+/// SD 评估窗口起点的选取方式（阶段重置，见 docs/trading-system-thinking.md §5.6）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupplyDemandAnchorMode {
+    /// 固定取最近 `anchor_window` 根 SBar。
+    #[default]
+    Fixed,
+    /// 当前 trend 的起点，即最近一次趋势翻转。
+    TrendFlip,
+    /// 最近一个主要 swing 的起点。
+    MajorSwing,
+    /// 最近一根放量或持仓突变的 SBar。
+    VolumeCluster,
+    /// 依次尝试 `TrendFlip`、`MajorSwing`、`VolumeCluster`，都没有时退回 `Fixed`。
+    Auto,
+}
+
+/// 一次评估实际使用的窗口起点。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SupplyDemandAnchor {
+    /// 命中的起点类型，结构或量能起点不存在时为 `Fixed`。
+    pub mode: SupplyDemandAnchorMode,
+    /// 窗口首根 SBar 的 id。
+    pub sbar_id: Option<u64>,
+    /// 窗口内的 SBar 数。
+    pub bars: usize,
+    /// 起点被 `anchor_min_bars`/`anchor_max_bars` 截断时为 true，此时窗口并非从 `mode` 的起点开始。
+    #[serde(default)]
+    pub clamped: bool,
+}
+
+/// 某一周期最新的结构方向，作为其他周期计算 F9 多周期共振的输入。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeframeStructure {
+    pub timeframe: Timeframe,
+    /// 最新 trend 的方向，尚无 trend 时为 `Direction::None`。
+    pub trend_direction: Direction,
+    /// 最新 swing（含未确认）的方向，尚无 swing 时为 `Direction::None`。
+    pub swing_direction: Direction,
+}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SupplyDemandConfig {
     pub layer1_weight: f64,
     pub layer2_weight: f64,
@@ -27,9 +70,30 @@ pub struct SupplyDemandConfig {
     pub critical_threshold: f64,
 
     pub keyzone_bias_scale: f64,
+
+    pub anchor_mode: SupplyDemandAnchorMode,
+    /// `Fixed` 模式的窗口长度。
+    pub anchor_window: usize,
+    /// 起点过近时向前补足到的最少 SBar 数。
+    pub anchor_min_bars: usize,
+    /// 起点过远时截断到的最多 SBar 数。
+    pub anchor_max_bars: usize,
+    /// 判断主要 swing 时参考的已确认 swing 个数。
+    pub anchor_swing_lookback: usize,
+    /// 幅度不低于参考 swing 平均幅度的该倍数即为主要 swing。
+    pub anchor_major_swing_ratio: f64,
+    /// 检测量能集聚时参考的 SBar 数。
+    pub anchor_cluster_lookback: usize,
+    /// 成交量或持仓变化不低于参考均值的该倍数即为集聚点。
+    pub anchor_cluster_multiple: f64,
+
+    /// F9 中其他周期 trend 方向的权重。
+    pub mtf_trend_weight: f64,
+    /// F9 中其他周期 swing 方向的权重。
+    pub mtf_swing_weight: f64,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SupplyDemandConfigPatch {
     pub layer1_weight: Option<f64>,
     pub layer2_weight: Option<f64>,
@@ -50,9 +114,21 @@ pub struct SupplyDemandConfigPatch {
     pub critical_threshold: Option<f64>,
 
     pub keyzone_bias_scale: Option<f64>,
+
+    pub anchor_mode: Option<SupplyDemandAnchorMode>,
+    pub anchor_window: Option<usize>,
+    pub anchor_min_bars: Option<usize>,
+    pub anchor_max_bars: Option<usize>,
+    pub anchor_swing_lookback: Option<usize>,
+    pub anchor_major_swing_ratio: Option<f64>,
+    pub anchor_cluster_lookback: Option<usize>,
+    pub anchor_cluster_multiple: Option<f64>,
+
+    pub mtf_trend_weight: Option<f64>,
+    pub mtf_swing_weight: Option<f64>,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SupplyDemandProfileConfig {
     #[serde(default)]
     pub default: SupplyDemandConfigPatch,
@@ -86,6 +162,18 @@ impl Default for SupplyDemandConfig {
             critical_threshold: 0.25,
 
             keyzone_bias_scale: 0.35,
+
+            anchor_mode: SupplyDemandAnchorMode::Fixed,
+            anchor_window: 50,
+            anchor_min_bars: 5,
+            anchor_max_bars: 300,
+            anchor_swing_lookback: 5,
+            anchor_major_swing_ratio: 1.0,
+            anchor_cluster_lookback: 100,
+            anchor_cluster_multiple: 2.5,
+
+            mtf_trend_weight: 0.6,
+            mtf_swing_weight: 0.4,
         }
     }
 }
@@ -142,6 +230,38 @@ impl SupplyDemandConfig {
         if let Some(v) = patch.keyzone_bias_scale {
             self.keyzone_bias_scale = v;
         }
+
+        if let Some(v) = patch.anchor_mode {
+            self.anchor_mode = v;
+        }
+        if let Some(v) = patch.anchor_window {
+            self.anchor_window = v;
+        }
+        if let Some(v) = patch.anchor_min_bars {
+            self.anchor_min_bars = v;
+        }
+        if let Some(v) = patch.anchor_max_bars {
+            self.anchor_max_bars = v;
+        }
+        if let Some(v) = patch.anchor_swing_lookback {
+            self.anchor_swing_lookback = v;
+        }
+        if let Some(v) = patch.anchor_major_swing_ratio {
+            self.anchor_major_swing_ratio = v;
+        }
+        if let Some(v) = patch.anchor_cluster_lookback {
+            self.anchor_cluster_lookback = v;
+        }
+        if let Some(v) = patch.anchor_cluster_multiple {
+            self.anchor_cluster_multiple = v;
+        }
+
+        if let Some(v) = patch.mtf_trend_weight {
+            self.mtf_trend_weight = v;
+        }
+        if let Some(v) = patch.mtf_swing_weight {
+            self.mtf_swing_weight = v;
+        }
         self
     }
 
@@ -211,7 +331,7 @@ fn find_patch<'a>(
         .map(|(_, v)| v)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SupplyDemandStage {
     Stable,
     Weakening,
@@ -219,7 +339,7 @@ pub enum SupplyDemandStage {
     Failed,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SupplyDemandFactors {
     pub f1_rejection_acceptance: f64,
     pub f2_advancement_efficiency: f64,
@@ -247,15 +367,16 @@ pub struct SupplyDemandFactors {
     pub volatility_adjustment: f64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SupplyDemandResult {
     pub score: f64,
     pub stage: SupplyDemandStage,
     pub factors: SupplyDemandFactors,
     pub explanation: String,
+    pub anchor: SupplyDemandAnchor,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SupplyDemand {
     config: SupplyDemandConfig,
 }
@@ -273,13 +394,25 @@ impl SupplyDemand {
         &self.config
     }
 
+    /// 仅基于 SBar 窗口评估，缺少结构输入的 F7/F9 记为 0。
     pub fn evaluate_window_with_bias(&self, bars: &[SBar], keyzone_bias: f64) -> SupplyDemandResult {
+        self.evaluate(bars, keyzone_bias, 0.0, 0.0)
+    }
+
+    fn evaluate(
+        &self,
+        bars: &[SBar],
+        keyzone_bias: f64,
+        swing_relative_strength: f64,
+        mtf_alignment: f64,
+    ) -> SupplyDemandResult {
         if bars.is_empty() {
             return SupplyDemandResult {
                 score: 0.0,
                 stage: SupplyDemandStage::Failed,
                 factors: SupplyDemandFactors::default(),
                 explanation: "empty window".to_string(),
+                anchor: SupplyDemandAnchor::default(),
             };
         }
 
@@ -295,20 +428,6 @@ impl SupplyDemand {
             (displacement / total_range).clamp(-1.0, 1.0)
         };
 
-        let up_count = bars
-            .iter()
-            .filter(|bar| bar.close_price > bar.open_price)
-            .count() as f64;
-        let down_count = bars
-            .iter()
-            .filter(|bar| bar.close_price < bar.open_price)
-            .count() as f64;
-        let signed_count = if up_count + down_count <= f64::EPSILON {
-            0.0
-        } else {
-            (up_count - down_count) / (up_count + down_count)
-        };
-
         let mut wick_sum = 0.0;
         let mut body_ratio_sum = 0.0;
         let mut direction_flip = 0u32;
@@ -357,11 +476,9 @@ impl SupplyDemand {
 
         let vol_oi_alignment = (volume_confirmation * oi_nature).clamp(-1.0, 1.0);
 
-        let swing_relative_strength = directional_efficiency;
         let keyzone_reaction = ((avg_body_ratio - 0.5).clamp(-1.0, 1.0) * price_direction
             + keyzone_bias.clamp(-1.0, 1.0) * self.config.keyzone_bias_scale)
             .clamp(-1.0, 1.0);
-        let mtf_alignment = signed_count.signum() * price_direction;
 
         let rejection_acceptance = (avg_body_ratio - (wick_sum / (total_range + 1e-9))).clamp(-1.0, 1.0);
         let advancement_efficiency = directional_efficiency;
@@ -383,21 +500,6 @@ impl SupplyDemand {
         };
         let i_opponent_response_quality = (-momentum_consistency).clamp(-1.0, 1.0);
 
-        let layer1 = (self.config.f1_weight * rejection_acceptance)
-            + (self.config.f2_weight * advancement_efficiency)
-            + (self.config.f3_weight * momentum_consistency);
-        let layer2 = (self.config.f4_weight * volume_confirmation)
-            + (self.config.f5_weight * oi_nature)
-            + (self.config.f6_weight * vol_oi_alignment);
-        let layer3 = (self.config.f7_weight * swing_relative_strength)
-            + (self.config.f8_weight * keyzone_reaction)
-            + (self.config.f9_weight * mtf_alignment);
-
-        let score = (self.config.layer1_weight * layer1
-            + self.config.layer2_weight * layer2
-            + self.config.layer3_weight * layer3)
-            .clamp(-1.0, 1.0);
-
         let dominance = ((a_initiative + b_direction_consistency + c_pullback_role) / 3.0).clamp(-1.0, 1.0);
         let efficiency = ((d_time_efficiency + e_body_wick_efficiency + f_vol_oi_cost_effectiveness) / 3.0)
             .clamp(-1.0, 1.0);
@@ -409,7 +511,67 @@ impl SupplyDemand {
             .clamp(0.0, 1.0);
         let volatility_adjustment = (1.0 - (total_range / (bars.len() as f64 + 1.0)).tanh()).clamp(0.0, 1.0);
 
-        let stage = if score.abs() >= self.config.stable_threshold {
+        let factors = SupplyDemandFactors {
+            f1_rejection_acceptance: rejection_acceptance,
+            f2_advancement_efficiency: advancement_efficiency,
+            f3_momentum_consistency: momentum_consistency,
+            f4_volume_confirmation: volume_confirmation,
+            f5_oi_nature: oi_nature,
+            f6_vol_oi_alignment: vol_oi_alignment,
+            f7_swing_relative_strength: swing_relative_strength,
+            f8_keyzone_reaction: keyzone_reaction,
+            f9_mtf_alignment: mtf_alignment,
+
+            a_initiative,
+            b_direction_consistency,
+            c_pullback_role,
+            d_time_efficiency,
+            e_body_wick_efficiency,
+            f_vol_oi_cost_effectiveness,
+            g_marginal_deterioration,
+            h_key_behavior_mismatch,
+            i_opponent_response_quality,
+
+            dominance,
+            efficiency,
+            sustainability,
+            volatility_adjustment,
+        };
+        let score = self.score_factors(&factors);
+        SupplyDemandResult {
+            score,
+            stage: self.stage(score),
+            factors,
+            explanation: "sd scored by 3-layer 9-factor model with A-I atoms".to_string(),
+            anchor: SupplyDemandAnchor {
+                mode: SupplyDemandAnchorMode::Fixed,
+                sbar_id: first.id,
+                bars: bars.len(),
+                clamped: false,
+            },
+        }
+    }
+
+    /// F1-F9 按因子权重合成三层得分，再按层权重合成总分并截断到 `[-1, 1]`。
+    pub fn score_factors(&self, factors: &SupplyDemandFactors) -> f64 {
+        let config = &self.config;
+        let layer1 = (config.f1_weight * factors.f1_rejection_acceptance)
+            + (config.f2_weight * factors.f2_advancement_efficiency)
+            + (config.f3_weight * factors.f3_momentum_consistency);
+        let layer2 = (config.f4_weight * factors.f4_volume_confirmation)
+            + (config.f5_weight * factors.f5_oi_nature)
+            + (config.f6_weight * factors.f6_vol_oi_alignment);
+        let layer3 = (config.f7_weight * factors.f7_swing_relative_strength)
+            + (config.f8_weight * factors.f8_keyzone_reaction)
+            + (config.f9_weight * factors.f9_mtf_alignment);
+
+        (config.layer1_weight * layer1 + config.layer2_weight * layer2 + config.layer3_weight * layer3)
+            .clamp(-1.0, 1.0)
+    }
+
+    /// 按 `|score|` 与阶段阈值划分阶段。
+    pub fn stage(&self, score: f64) -> SupplyDemandStage {
+        if score.abs() >= self.config.stable_threshold {
             SupplyDemandStage::Stable
         } else if score.abs() >= self.config.weakening_threshold {
             SupplyDemandStage::Weakening
@@ -417,39 +579,182 @@ impl SupplyDemand {
             SupplyDemandStage::Critical
         } else {
             SupplyDemandStage::Failed
-        };
+        }
+    }
 
-        SupplyDemandResult {
-            score,
-            stage,
-            factors: SupplyDemandFactors {
-                f1_rejection_acceptance: rejection_acceptance,
-                f2_advancement_efficiency: advancement_efficiency,
-                f3_momentum_consistency: momentum_consistency,
-                f4_volume_confirmation: volume_confirmation,
-                f5_oi_nature: oi_nature,
-                f6_vol_oi_alignment: vol_oi_alignment,
-                f7_swing_relative_strength: swing_relative_strength,
-                f8_keyzone_reaction: keyzone_reaction,
-                f9_mtf_alignment: mtf_alignment,
-
-                a_initiative,
-                b_direction_consistency,
-                c_pullback_role,
-                d_time_efficiency,
-                e_body_wick_efficiency,
-                f_vol_oi_cost_effectiveness,
-                g_marginal_deterioration,
-                h_key_behavior_mismatch,
-                i_opponent_response_quality,
-
-                dominance,
-                efficiency,
-                sustainability,
-                volatility_adjustment,
+    /// 按 `anchor_mode` 选取窗口起点后评估，结果的 `anchor` 记录实际使用的起点。
+    ///
+    /// `sbars`/`swings`/`trends` 为同一 timeframe 的完整序列（时间升序），
+    /// `others` 为其他周期的结构方向，F7/F9 分别由 `swings` 与 `others` 计算。
+    pub fn evaluate_anchored(
+        &self,
+        sbars: &[SBar],
+        swings: &[Swing],
+        trends: &[Trend],
+        others: &[TimeframeStructure],
+        keyzone_bias: f64,
+    ) -> SupplyDemandResult {
+        let (start, anchor) = self.anchor_window(sbars, swings, trends);
+        let mut result = self.evaluate(
+            &sbars[start..],
+            keyzone_bias,
+            Self::swing_relative_strength(swings),
+            self.mtf_alignment(others),
+        );
+        result.anchor = anchor;
+        result
+    }
+
+    /// 返回窗口在 `sbars` 中的起始下标与起点信息；
+    /// 窗口长度限制在 `[anchor_min_bars, anchor_max_bars]` 内。
+    pub fn anchor_window(
+        &self,
+        sbars: &[SBar],
+        swings: &[Swing],
+        trends: &[Trend],
+    ) -> (usize, SupplyDemandAnchor) {
+        let config = &self.config;
+        let candidates: &[SupplyDemandAnchorMode] = match config.anchor_mode {
+            SupplyDemandAnchorMode::Auto => &[
+                SupplyDemandAnchorMode::TrendFlip,
+                SupplyDemandAnchorMode::MajorSwing,
+                SupplyDemandAnchorMode::VolumeCluster,
+            ],
+            SupplyDemandAnchorMode::Fixed => &[],
+            _ => std::slice::from_ref(&config.anchor_mode),
+        };
+        let max_bars = config.anchor_max_bars.max(1);
+        let min_bars = config.anchor_min_bars.min(max_bars);
+        let floor = sbars.len().saturating_sub(max_bars);
+        let ceiling = sbars.len().saturating_sub(min_bars);
+        // 只在最近 `anchor_max_bars` 根内查找起点，更早的起点直接按截断处理
+        let recent = &sbars[floor..];
+        let found = candidates.iter().find_map(|mode| {
+            let sbar_id = match mode {
+                SupplyDemandAnchorMode::TrendFlip => trends.last().map(|x| x.sbar_start_id),
+                SupplyDemandAnchorMode::MajorSwing => self.major_swing_start(swings),
+                SupplyDemandAnchorMode::VolumeCluster => self.volume_cluster(sbars),
+                _ => None,
+            }?;
+            match recent.iter().rposition(|x| x.id == Some(sbar_id)) {
+                Some(idx) => Some((floor + idx, *mode, false)),
+                None => recent
+                    .first()
+                    .and_then(|x| x.id)
+                    .filter(|first| sbar_id < *first)
+                    .map(|_| (floor, *mode, true)),
+            }
+        });
+        let (start, mode, older) = found.unwrap_or((
+            sbars.len().saturating_sub(config.anchor_window.max(1)),
+            SupplyDemandAnchorMode::Fixed,
+            false,
+        ));
+        let clamped_start = start.min(ceiling).max(floor);
+        (
+            clamped_start,
+            SupplyDemandAnchor {
+                mode,
+                sbar_id: sbars.get(clamped_start).and_then(|x| x.id),
+                bars: sbars.len() - clamped_start,
+                clamped: older || clamped_start != start,
             },
-            explanation: "sd scored by 3-layer 9-factor model with A-I atoms".to_string(),
+        )
+    }
+
+    /// F7：最新已确认 swing 与前一个同向已确认 swing 的幅度比，`(当前 / 前一个 - 1)` 截断到 `[-1, 1]`。
+    ///
+    /// 上涨 swing 走强为正、走弱为负，下跌 swing 相反；仍在形成中的 swing 不参与，
+    /// 同向已确认 swing 不足两个时为 0。
+    pub fn swing_relative_strength(swings: &[Swing]) -> f64 {
+        let mut confirmed = swings
+            .iter()
+            .rev()
+            .filter(|x| x.state == SwingState::Confirmed);
+        let Some(current) = confirmed.next() else {
+            return 0.0;
+        };
+        let sign = direction_sign(current.direction);
+        let Some(previous) = confirmed.find(|x| x.direction == current.direction) else {
+            return 0.0;
+        };
+        let previous_distance = previous.distance();
+        if sign == 0.0 || previous_distance <= f64::EPSILON {
+            return 0.0;
+        }
+        ((current.distance() / previous_distance - 1.0).clamp(-1.0, 1.0)) * sign
+    }
+
+    /// F9：其他周期 trend/swing 方向按 `mtf_trend_weight`/`mtf_swing_weight` 加权后的平均，
+    /// 全部向上为 1、全部向下为 -1；没有其他周期时为 0。
+    pub fn mtf_alignment(&self, others: &[TimeframeStructure]) -> f64 {
+        let trend_weight = self.config.mtf_trend_weight.max(0.0);
+        let swing_weight = self.config.mtf_swing_weight.max(0.0);
+        let total_weight = trend_weight + swing_weight;
+        if others.is_empty() || total_weight <= f64::EPSILON {
+            return 0.0;
+        }
+        let sum = others
+            .iter()
+            .map(|x| {
+                trend_weight * direction_sign(x.trend_direction)
+                    + swing_weight * direction_sign(x.swing_direction)
+            })
+            .sum::<f64>();
+        (sum / (total_weight * others.len() as f64)).clamp(-1.0, 1.0)
+    }
+
+    /// 最近的已确认 swing 中，幅度不低于平均幅度 `anchor_major_swing_ratio` 倍的最新一个。
+    fn major_swing_start(&self, swings: &[Swing]) -> Option<u64> {
+        let recent = swings
+            .iter()
+            .rev()
+            .filter(|x| x.state == SwingState::Confirmed)
+            .take(self.config.anchor_swing_lookback.max(1))
+            .collect::<Vec<_>>();
+        if recent.is_empty() {
+            return None;
         }
+        let mean = recent.iter().map(|x| x.distance()).sum::<f64>() / recent.len() as f64;
+        recent
+            .iter()
+            .find(|x| x.distance() >= mean * self.config.anchor_major_swing_ratio)
+            .map(|x| x.sbar_start_id)
+    }
+
+    /// 最近 `anchor_cluster_lookback` 根中，成交量或持仓变化达到均值 `anchor_cluster_multiple` 倍的最新一根。
+    fn volume_cluster(&self, sbars: &[SBar]) -> Option<u64> {
+        let lookback = &sbars[sbars.len().saturating_sub(self.config.anchor_cluster_lookback)..];
+        if lookback.len() < 2 {
+            return None;
+        }
+        let n = lookback.len() as f64;
+        let volume_mean = lookback.iter().map(|x| x.volume).sum::<f64>() / n;
+        let oi_deltas = std::iter::once(0.0)
+            .chain(
+                lookback
+                    .windows(2)
+                    .map(|x| (x[1].open_interest - x[0].open_interest).abs()),
+            )
+            .collect::<Vec<_>>();
+        let oi_mean = oi_deltas.iter().sum::<f64>() / (n - 1.0);
+        let multiple = self.config.anchor_cluster_multiple;
+        lookback
+            .iter()
+            .zip(&oi_deltas)
+            .rev()
+            .find(|(bar, oi_delta)| {
+                (volume_mean > 0.0 && bar.volume >= volume_mean * multiple)
+                    || (oi_mean > 0.0 && **oi_delta >= oi_mean * multiple)
+            })
+            .and_then(|(bar, _)| bar.id)
     }
 }
 
+fn direction_sign(direction: Direction) -> f64 {
+    match direction {
+        Direction::Up => 1.0,
+        Direction::Down => -1.0,
+        _ => 0.0,
+    }
+}
@@ -0,0 +1,278 @@
+//! 交易时段日历。
+//!
+//! 把 `TradingSession` 解析为日内分钟区间，负责：
+//! - 判断时间是否处于交易时段；
+//! - 计算交易日（CTP 夜盘归属下一交易日，周末顺延到周一）；
+//! - 给出按墙钟对齐、在时段边界截断的聚合区间。
+
+use chrono::{
+    DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc,
+    Weekday,
+};
+
+use crate::constant::{DataError, Timeframe};
+use crate::symbol::TradingSession;
+
+const MINUTES_PER_DAY: i64 = 1440;
+
+/// 日内分钟区间 `[start, end)`；`end <= start` 表示跨午夜。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Section {
+    start: i64,
+    end: i64,
+}
+
+impl Section {
+    fn wraps(self) -> bool {
+        self.end <= self.start
+    }
+
+    fn contains(self, minute: i64) -> bool {
+        if self.wraps() {
+            minute >= self.start || minute < self.end
+        } else {
+            self.start <= minute && minute < self.end
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionCalendar {
+    sections: Vec<Section>,
+    offset: Duration,
+}
+
+impl Default for SessionCalendar {
+    fn default() -> Self {
+        Self::continuous()
+    }
+}
+
+impl SessionCalendar {
+    /// 7x24 连续交易（如加密货币），交易日即自然日。
+    pub fn continuous() -> Self {
+        Self {
+            sections: Vec::new(),
+            offset: Duration::zero(),
+        }
+    }
+
+    pub fn from_session(session: &TradingSession) -> Result<Self, DataError> {
+        let sections = session
+            .sections
+            .iter()
+            .map(|(start, end)| {
+                Ok(Section {
+                    start: parse_minute(start)?,
+                    end: parse_minute(end)?,
+                })
+            })
+            .collect::<Result<Vec<_>, DataError>>()?;
+        Ok(Self {
+            sections,
+            offset: Duration::minutes(session.utc_offset_minutes as i64),
+        })
+    }
+
+    pub fn is_continuous(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    pub fn is_trading(&self, datetime: DateTime<Utc>) -> bool {
+        let minute = minute_of_day(self.to_local(datetime));
+        self.is_continuous() || self.sections.iter().any(|x| x.contains(minute))
+    }
+
+    /// 在交易时段内且不处于周末休市：日盘只在周一至周五，夜盘只在周一至周五晚开盘，
+    /// 跨午夜的部分按开盘当日计（周五夜盘延续到周六凌晨的部分仍算交易）。不处理节假日。
+    pub fn is_open(&self, datetime: DateTime<Utc>) -> bool {
+        if self.is_continuous() {
+            return true;
+        }
+        if !self.is_trading(datetime) {
+            return false;
+        }
+        let local = self.to_local(datetime);
+        let minute = minute_of_day(local);
+        let mut opened_on = local.date();
+        if self.night_start().is_some_and(|night| {
+            minute < night && self.night_block().iter().any(|x| x.contains(minute))
+        }) {
+            opened_on -= Duration::days(1);
+        }
+        !matches!(opened_on.weekday(), Weekday::Sat | Weekday::Sun)
+    }
+
+    /// 交易日：夜盘（首段开始之后）归属下一交易日，落在周末时顺延到周一；不处理节假日。
+    ///
+    /// 时段外的时间按下一时段开盘计。
+    pub fn trading_day(&self, datetime: DateTime<Utc>) -> NaiveDate {
+        let mut local = self.to_local(datetime);
+        if !self.is_continuous() {
+            local = self.enclosing_section(local).0;
+        }
+        let date = local.date();
+        let Some(first_start) = self.night_start() else {
+            return date;
+        };
+
+        let mut day = if minute_of_day(local) >= first_start {
+            date + Duration::days(1)
+        } else {
+            date
+        };
+        while matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+            day += Duration::days(1);
+        }
+        day
+    }
+
+    /// `datetime` 所在 M1 所属的聚合区间 `[start, end)`。
+    ///
+    /// 日内周期按自然时钟切分，区间收缩到其中的交易时间：start 为区间内首个交易分钟，
+    /// end 为最后交易分钟的结束，跨越小节休息（如 10:15-10:30）的区间不拆分。
+    /// 时段外的分钟（如集合竞价）并入下一时段首个区间。
+    /// `D1`/`W1`/`MN1` 的 start 为交易日、周一、月初的 0 点，end 为区间内最后交易日的收盘。
+    /// 秒级、伪周期与长度为 0 的周期返回 `DataError::InvalidTimeframe`。
+    pub fn bucket(
+        &self,
+        datetime: DateTime<Utc>,
+        timeframe: Timeframe,
+    ) -> Result<(DateTime<Utc>, DateTime<Utc>), DataError> {
+        let timeframe = timeframe.validate()?;
+        if timeframe.is_sub_minute() || timeframe.is_pseudo() {
+            return Err(DataError::InvalidTimeframe(timeframe.as_str().into_owned()));
+        }
+        if !timeframe.is_intraday() {
+            return Ok(self.period_bucket(datetime, timeframe));
+        }
+
+        let mut local = self.to_local(datetime);
+        if !self.is_continuous() {
+            local = self.enclosing_section(local).0;
+        }
+        let step = timeframe.minutes();
+        let midnight = local.date().and_time(NaiveTime::MIN);
+        let floor = midnight + Duration::minutes(minute_of_day(local) / step * step);
+        let ceil = floor + Duration::minutes(step);
+        if self.is_continuous() {
+            return Ok((self.to_utc(floor), self.to_utc(ceil)));
+        }
+
+        let (start, end) = self
+            .absolute_sections(midnight)
+            .filter_map(|(start, end)| {
+                let start = start.max(floor);
+                let end = end.min(ceil);
+                (start < end).then_some((start, end))
+            })
+            .fold((ceil, floor), |acc, x| (acc.0.min(x.0), acc.1.max(x.1)));
+        Ok((self.to_utc(start), self.to_utc(end)))
+    }
+
+    fn period_bucket(
+        &self,
+        datetime: DateTime<Utc>,
+        timeframe: Timeframe,
+    ) -> (DateTime<Utc>, DateTime<Utc>) {
+        let day = self.trading_day(datetime);
+        let (first, last) = match timeframe {
+            Timeframe::W1 => {
+                let monday = day - Duration::days(day.weekday().num_days_from_monday() as i64);
+                (monday, monday + Duration::days(6))
+            }
+            Timeframe::MN1 => {
+                let first = day.with_day(1).expect("first day of month");
+                let next = first
+                    .checked_add_months(Months::new(1))
+                    .expect("next month");
+                (first, next - Duration::days(1))
+            }
+            _ => (day, day),
+        };
+        let start = first.and_time(NaiveTime::MIN);
+        (self.to_utc(start), self.to_utc(self.day_close(last)))
+    }
+
+    /// `day` 及之前最近一个交易日的收盘；连续交易时为次日 0 点。
+    fn day_close(&self, mut day: NaiveDate) -> NaiveDateTime {
+        let Some(last) = self.sections.last() else {
+            return (day + Duration::days(1)).and_time(NaiveTime::MIN);
+        };
+        while matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+            day -= Duration::days(1);
+        }
+        day.and_time(NaiveTime::MIN) + Duration::minutes(last.end)
+    }
+
+    /// 时段内的时间原样返回，时段外的时间移到下一时段开盘。
+    fn enclosing_section(&self, local: NaiveDateTime) -> (NaiveDateTime, NaiveDateTime) {
+        let midnight = local.date().and_time(NaiveTime::MIN);
+        self.absolute_sections(midnight)
+            .filter(|(_, end)| *end > local)
+            .map(|(start, end)| (start.max(local), end))
+            .min()
+            .expect("session calendar has sections")
+    }
+
+    /// `midnight` 前一日至后一日的各时段绝对区间。
+    fn absolute_sections(
+        &self,
+        midnight: NaiveDateTime,
+    ) -> impl Iterator<Item = (NaiveDateTime, NaiveDateTime)> + '_ {
+        (-1..=1).flat_map(move |day_offset| {
+            let day = midnight + Duration::days(day_offset);
+            self.sections.iter().map(move |x| {
+                let end_day = if x.wraps() {
+                    day + Duration::days(1)
+                } else {
+                    day
+                };
+                (
+                    day + Duration::minutes(x.start),
+                    end_day + Duration::minutes(x.end),
+                )
+            })
+        })
+    }
+
+    /// 存在夜盘时返回首段开始分钟。
+    fn night_start(&self) -> Option<i64> {
+        let first = self.sections.first()?;
+        let last = self.sections.last()?;
+        (first.start >= last.end).then_some(first.start)
+    }
+
+    /// 从夜盘首段起首尾相接的各段，即一个完整夜盘（可能跨午夜拆成多段）。
+    fn night_block(&self) -> &[Section] {
+        if self.night_start().is_none() {
+            return &[];
+        }
+        let mut n = 1;
+        while n < self.sections.len() && self.sections[n].start == self.sections[n - 1].end {
+            n += 1;
+        }
+        &self.sections[..n]
+    }
+
+    fn to_local(&self, datetime: DateTime<Utc>) -> NaiveDateTime {
+        datetime.naive_utc() + self.offset
+    }
+
+    fn to_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        DateTime::<Utc>::from_naive_utc_and_offset(local - self.offset, Utc)
+    }
+}
+
+fn minute_of_day(local: NaiveDateTime) -> i64 {
+    (local.hour() * 60 + local.minute()) as i64
+}
+
+fn parse_minute(value: &str) -> Result<i64, DataError> {
+    let value = value.trim();
+    let time = NaiveTime::parse_from_str(value, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+        .map_err(|_| DataError::InvalidDatetime(value.to_string()))?;
+    let minute = (time.hour() * 60 + time.minute()) as i64;
+    Ok(minute % MINUTES_PER_DAY)
+}
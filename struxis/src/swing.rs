@@ -1,20 +1,26 @@
+use std::sync::Arc;
+
 use chrono::{DateTime, Utc};
 use polars::df;
 use polars::prelude::DataFrame;
+use serde::{Deserialize, Serialize};
 
 use crate::bar::{CBar, Fractal};
 use crate::constant::{Direction, FractalType};
-use crate::utils::{approx_eq_f64, first_changed_id};
+use crate::journal::JournaledRows;
+use crate::utils::{
+    RowChanges, approx_eq_f64, diff_rows_by_id, first_changed_id, first_changed_index,
+};
 use crate::IdGenerator;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SwingState {
     Forming,
     PendingReverse,
     Confirmed,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Swing {
     pub id: Option<u64>,
     pub direction: Direction,
@@ -42,11 +48,21 @@ impl Swing {
     }
 }
 
+/// 逐 pivot 扫描时跨步携带的候选终点（swing 下标 + 对应分型）。
+type PendingCandidate = Option<(usize, Fractal)>;
+
+#[derive(Serialize, Deserialize)]
 pub struct SwingManager {
-    rows: Vec<Swing>,
-    id_generator: &'static IdGenerator,
-    df_cache: DataFrame,
+    rows: JournaledRows<Swing, PendingCandidate>,
+    #[serde(skip, default = "crate::id_generator::swing_id_generator")]
+    id_generator: Arc<IdGenerator>,
     backtrack_id: Option<u64>,
+    backtrack_index: Option<usize>,
+    /// 最近一次更新前自 `replaced_start` 起的行。
+    #[serde(skip)]
+    replaced: Vec<Swing>,
+    #[serde(skip)]
+    replaced_start: usize,
 }
 
 impl Default for SwingManager {
@@ -57,36 +73,47 @@ impl Default for SwingManager {
 
 impl SwingManager {
     pub fn new() -> Self {
+        Self::with_id_generator(crate::id_generator::swing_id_generator())
+    }
+
+    pub fn with_id_generator(id_generator: Arc<IdGenerator>) -> Self {
         Self {
-            rows: Vec::new(),
-            id_generator: crate::id_generator::swing_id_generator(),
-            df_cache: DataFrame::default(),
+            rows: JournaledRows::new(),
+            id_generator,
             backtrack_id: None,
+            backtrack_index: None,
+            replaced: Vec::new(),
+            replaced_start: 0,
         }
     }
 
+    pub(crate) fn set_id_generator(&mut self, id_generator: Arc<IdGenerator>) {
+        self.id_generator = id_generator;
+    }
+
     pub fn on_new_fractal(&mut self, fractal: &Fractal, right_cbar: &CBar) -> Option<Swing> {
         let fractal_type = Fractal::verify(&fractal.left, &fractal.middle, &fractal.right);
         if fractal_type == FractalType::None {
             return None;
         }
 
+        // 逐分型追加不经过 pivot 扫描，之后的增量更新需要全量重建
+        self.rows.forget_history();
         if self.rows.is_empty() {
             let swing = self.append_from_fractal(fractal, right_cbar, false);
-            self.rebuild_cache();
             return Some(swing);
         }
 
-        if let Some(active) = self.rows.last_mut() {
+        let active_index = self.rows.len() - 1;
+        self.rows.update(active_index, |active| {
             active.cbar_end_id = fractal.middle.id.unwrap_or(active.cbar_end_id);
             active.sbar_end_id = fractal.middle.sbar_end_id;
             active.high_price = active.high_price.max(fractal.middle.high_price);
             active.low_price = active.low_price.min(fractal.middle.low_price);
             active.state = SwingState::Confirmed;
-        }
+        });
 
         let _ = self.append_from_fractal(fractal, right_cbar, false);
-        self.rebuild_cache();
         self.rows.last().cloned()
     }
 
@@ -99,225 +126,275 @@ impl SwingManager {
         cbars: &[CBar],
         cbar_backtrack_id: Option<u64>,
     ) -> Option<u64> {
-        let previous_rows = self.rows.clone();
+        let previous_rows = self.rows.to_vec();
         self.rows.clear();
+        self.scan_from(cbars, 1, None);
+
+        self.backtrack_index = first_changed_index(
+            &previous_rows,
+            &self.rows,
+            swing_eq_wo_created_at,
+            true,
+        );
+        self.backtrack_id = if cbar_backtrack_id.is_some() {
+            first_changed_id(
+                &previous_rows,
+                &self.rows,
+                |x| x.id,
+                swing_eq_wo_created_at,
+                true,
+            )
+            .or_else(|| self.rows.first().and_then(|x| x.id))
+        } else {
+            first_changed_id(
+                &previous_rows,
+                &self.rows,
+                |x| x.id,
+                swing_eq_wo_created_at,
+                false,
+            )
+        };
+        self.replaced = previous_rows;
+        self.replaced_start = 0;
+        self.backtrack_id
+    }
+
+    /// 增量更新：从 `cbar_backtrack_index`（第一个变化的 CBar 下标）对应的 pivot 起重新扫描。
+    ///
+    /// pivot `p` 只依赖 `cbars[..=p + 1]`，因此回滚到 pivot `cbar_backtrack_index - 1`
+    /// 的检查点后继续扫描，结果与 `rebuild_from_cbars` 一致。
+    /// 返回第一个变化的 swing id（含新追加），无变化时返回 `None`。
+    pub fn update_from_cbars(&mut self, cbars: &[CBar], cbar_backtrack_index: usize) -> Option<u64> {
+        let first_pivot = cbar_backtrack_index.saturating_sub(1).max(1);
+        let Some(rollback) = self.rows.rollback_to(first_pivot) else {
+            self.rebuild_from_cbars_with_backtrack(cbars, None);
+            self.backtrack_id = self
+                .backtrack_index
+                .and_then(|idx| self.rows.get(idx))
+                .and_then(|x| x.id);
+            return self.backtrack_id;
+        };
+
+        self.scan_from(cbars, first_pivot, rollback.side);
+        let current = &self.rows[rollback.start..];
+        self.backtrack_index =
+            first_changed_index(&rollback.replaced, current, swing_eq_wo_created_at, true)
+                .map(|offset| rollback.start + offset);
+        self.backtrack_id = first_changed_id(
+            &rollback.replaced,
+            current,
+            |x| x.id,
+            swing_eq_wo_created_at,
+            true,
+        );
+        self.replaced = rollback.replaced;
+        self.replaced_start = rollback.start;
+        self.backtrack_id
+    }
 
+    fn scan_from(&mut self, cbars: &[CBar], first_pivot: usize, mut pending_candidate: PendingCandidate) {
         // 新增：候选终点与后验延伸机制
-        let mut pending_candidate: Option<(usize, Fractal)> = None;
-        for pivot in 1..cbars.len().saturating_sub(1) {
-            let left = cbars[pivot - 1].clone();
-            let middle = cbars[pivot].clone();
-            let right = cbars[pivot + 1].clone();
-            let fractal = Fractal { left, middle, right };
-            let fractal_type = Fractal::verify(&fractal.left, &fractal.middle, &fractal.right);
-            if fractal_type == FractalType::None {
-                continue;
-            }
+        for pivot in first_pivot..cbars.len().saturating_sub(1) {
+            self.rows.begin_step(pivot, pending_candidate.clone());
+            self.scan_pivot(cbars, pivot, &mut pending_candidate);
+        }
 
+        // --- 新增：扫描结束后将最后一段 forming swing 标记为 confirmed ---
+        // 记为下一个 pivot 的一步，下次增量更新回滚时会自动撤销
+        let finalize_step = cbars.len().saturating_sub(1).max(first_pivot);
+        self.rows.begin_step(finalize_step, pending_candidate);
+        if let Some(last_index) = self.rows.len().checked_sub(1)
+            && self.rows[last_index].state == SwingState::Forming
+        {
+            self.rows.update(last_index, |last| last.state = SwingState::Confirmed);
+        }
+    }
 
-            if self.rows.is_empty() {
-                let _ = self.append_from_fractal(&fractal, &fractal.right, false);
-                continue;
-            }
+    fn scan_pivot(&mut self, cbars: &[CBar], pivot: usize, pending_candidate: &mut PendingCandidate) {
+        let left = cbars[pivot - 1].clone();
+        let middle = cbars[pivot].clone();
+        let right = cbars[pivot + 1].clone();
+        let fractal = Fractal { left, middle, right };
+        let fractal_type = Fractal::verify(&fractal.left, &fractal.middle, &fractal.right);
+        if fractal_type == FractalType::None {
+            return;
+        }
 
-            let active_index = self.rows.len() - 1;
-            let mut active = self.rows[active_index].clone();
+
+        if self.rows.is_empty() {
+            let _ = self.append_from_fractal(&fractal, &fractal.right, false);
+            return;
+        }
+
+        let active_index = self.rows.len() - 1;
+        let mut active = self.rows[active_index].clone();
+        active.high_price = active.high_price.max(fractal.high_price());
+        active.low_price = active.low_price.min(fractal.low_price());
+
+        // --- 修正版：仅重叠时禁止分段，否则允许分段并完成前一 swing ---
+        let start_fractal = find_fractal_by_middle_id(cbars, active.cbar_start_id);
+        let end_fractal = find_fractal_by_middle_id(cbars, active.cbar_end_id);
+        let overlap_with_start = start_fractal.as_ref().is_some_and(|sf| fractal_overlap(sf, &fractal, true));
+        let overlap_with_end = end_fractal.as_ref().is_some_and(|ef| fractal_overlap(ef, &fractal, true));
+        let fractal_type = Fractal::verify(&fractal.left, &fractal.middle, &fractal.right);
+        let direction_match = match active.direction {
+            Direction::Up => fractal_type == FractalType::Top,
+            Direction::Down => fractal_type == FractalType::Bottom,
+            _ => false,
+        };
+        if overlap_with_start || overlap_with_end || !direction_match {
+            // 只延长 active swing 区间，不生成新 swing
+            // 保证终点 fractal id 指向当前 fractal 的 middle（分型点）
+            active.cbar_end_id = fractal.middle.id.unwrap_or(active.cbar_end_id);
+            active.sbar_end_id = fractal.middle.sbar_end_id;
             active.high_price = active.high_price.max(fractal.high_price());
             active.low_price = active.low_price.min(fractal.low_price());
+            active.state = SwingState::Forming;
+            self.rows.set(active_index, active);
+            return;
+        } else {
+            // 非重叠且 fractal 类型与方向配对，允许分段，前一 swing 标记为 completed/confirmed
+            let mut completed = active.clone();
+            completed.cbar_end_id = fractal.middle.id.unwrap_or(completed.cbar_end_id);
+            completed.sbar_end_id = fractal.middle.sbar_end_id;
+            completed.state = SwingState::Confirmed;
+            self.rows.set(active_index, completed);
+        }
 
-            // --- 修正版：仅重叠时禁止分段，否则允许分段并完成前一 swing ---
-            let start_fractal = find_fractal_by_middle_id(cbars, active.cbar_start_id);
-            let end_fractal = find_fractal_by_middle_id(cbars, active.cbar_end_id);
-            let overlap_with_start = start_fractal.as_ref().map_or(false, |sf| fractal_overlap(sf, &fractal, true));
-            let overlap_with_end = end_fractal.as_ref().map_or(false, |ef| fractal_overlap(ef, &fractal, true));
-            let fractal_type = Fractal::verify(&fractal.left, &fractal.middle, &fractal.right);
-            let direction_match = match active.direction {
-                Direction::Up => fractal_type == FractalType::Top,
-                Direction::Down => fractal_type == FractalType::Bottom,
-                _ => false,
-            };
-            if overlap_with_start || overlap_with_end || !direction_match {
-                // 只延长 active swing 区间，不生成新 swing
-                // 保证终点 fractal id 指向当前 fractal 的 middle（分型点）
-                active.cbar_end_id = fractal.middle.id.unwrap_or(active.cbar_end_id);
-                active.sbar_end_id = fractal.middle.sbar_end_id;
-                active.high_price = active.high_price.max(fractal.high_price());
-                active.low_price = active.low_price.min(fractal.low_price());
-                active.state = SwingState::Forming;
-                self.rows[active_index] = active;
-                continue;
-            } else {
-                // 非重叠且 fractal 类型与方向配对，允许分段，前一 swing 标记为 completed/confirmed
-                let mut completed = active.clone();
-                completed.cbar_end_id = fractal.middle.id.unwrap_or(completed.cbar_end_id);
-                completed.sbar_end_id = fractal.middle.sbar_end_id;
-                completed.state = SwingState::Confirmed;
-                self.rows[active_index] = completed;
-            }
+        let prev_reference_swing = self
+            .rows
+            .iter()
+            .rev()
+            .find(|x| x.state != SwingState::Forming)
+            .cloned();
+        let in_bootstrap_phase = self.rows.len() == 1;
+        let bootstrap_reference_break_ok = if in_bootstrap_phase {
+            start_fractal
+                .as_ref()
+                .map(|start| {
+                    end_breaks_start_reference(cbars, start, &fractal, active.direction)
+                })
+                .unwrap_or(false)
+        } else {
+            true
+        };
 
-            let prev_reference_swing = self
-                .rows
-                .iter()
-                .rev()
-                .find(|x| x.state != SwingState::Forming)
-                .cloned();
-            let in_bootstrap_phase = self.rows.len() == 1;
-            let bootstrap_reference_break_ok = if in_bootstrap_phase {
-                start_fractal
-                    .as_ref()
-                    .map(|start| {
-                        end_breaks_start_reference(cbars, start, &fractal, active.direction)
-                    })
-                    .unwrap_or(false)
-            } else {
-                true
-            };
+        let pending_prev_index = if self.rows.len() >= 2 {
+            let idx = self.rows.len() - 2;
+            (self.rows[idx].state == SwingState::PendingReverse).then_some(idx)
+        } else {
+            None
+        };
 
-            let pending_prev_index = if self.rows.len() >= 2 {
-                let idx = self.rows.len() - 2;
-                (self.rows[idx].state == SwingState::PendingReverse).then_some(idx)
-            } else {
-                None
+        // 后验延伸：如果有候选终点，且当前 fractal 突破了候选终点，则延长 swing
+        if let Some((pending_idx, candidate_fractal)) = pending_candidate.clone() {
+            let candidate_ft = candidate_fractal.fractal_type();
+            let candidate_price = match candidate_ft {
+                FractalType::Top => candidate_fractal.high_price(),
+                FractalType::Bottom => candidate_fractal.low_price(),
+                _ => 0.0,
             };
-
-            // 后验延伸：如果有候选终点，且当前 fractal 突破了候选终点，则延长 swing
-            if let Some((pending_idx, candidate_fractal)) = &pending_candidate {
-                let candidate_ft = candidate_fractal.fractal_type();
-                let candidate_price = match candidate_ft {
-                    FractalType::Top => candidate_fractal.high_price(),
-                    FractalType::Bottom => candidate_fractal.low_price(),
-                    _ => 0.0,
-                };
-                let extend = match candidate_ft {
-                    FractalType::Top => fractal.high_price() > candidate_price,
-                    FractalType::Bottom => fractal.low_price() < candidate_price,
-                    _ => false,
-                };
-                if extend {
-                    // 恢复 swing 为 forming，延长终点
-                    let mut resumed = self.rows[*pending_idx].clone();
-                    resumed.state = SwingState::Forming;
-                    resumed.cbar_end_id = fractal.right.id.unwrap_or(resumed.cbar_end_id);
-                    resumed.sbar_end_id = fractal.right.sbar_end_id;
-                    resumed.high_price = resumed.high_price.max(fractal.high_price());
-                    resumed.low_price = resumed.low_price.min(fractal.low_price());
-                    self.rows[*pending_idx] = resumed;
-                    // 移除候选
-                    self.rows.truncate(*pending_idx + 1);
-                    pending_candidate = None;
-                    continue;
-                }
+            let extend = match candidate_ft {
+                FractalType::Top => fractal.high_price() > candidate_price,
+                FractalType::Bottom => fractal.low_price() < candidate_price,
+                _ => false,
+            };
+            if extend {
+                // 恢复 swing 为 forming，延长终点
+                let mut resumed = self.rows[pending_idx].clone();
+                resumed.state = SwingState::Forming;
+                resumed.cbar_end_id = fractal.right.id.unwrap_or(resumed.cbar_end_id);
+                resumed.sbar_end_id = fractal.right.sbar_end_id;
+                resumed.high_price = resumed.high_price.max(fractal.high_price());
+                resumed.low_price = resumed.low_price.min(fractal.low_price());
+                self.rows.set(pending_idx, resumed);
+                // 移除候选
+                self.rows.truncate(pending_idx + 1);
+                *pending_candidate = None;
+                return;
             }
+        }
 
-            if let Some(prev_index) = pending_prev_index {
-                let prev_pending = self.rows[prev_index].clone();
-                if should_resume_previous_swing(&prev_pending, &active, &fractal) {
-                    self.rows.pop();
-                    let mut resumed = self.rows[prev_index].clone();
-                    resumed.state = SwingState::Forming;
-                    resumed.cbar_end_id = fractal.right.id.unwrap_or(resumed.cbar_end_id);
-                    resumed.sbar_end_id = fractal.right.sbar_end_id;
-                    resumed.high_price = resumed.high_price.max(fractal.high_price());
-                    resumed.low_price = resumed.low_price.min(fractal.low_price());
-                    self.rows[prev_index] = resumed;
-                    pending_candidate = None;
-                    continue;
-                }
+        if let Some(prev_index) = pending_prev_index {
+            let prev_pending = self.rows[prev_index].clone();
+            if should_resume_previous_swing(&prev_pending, &active, &fractal) {
+                self.rows.pop();
+                let mut resumed = self.rows[prev_index].clone();
+                resumed.state = SwingState::Forming;
+                resumed.cbar_end_id = fractal.right.id.unwrap_or(resumed.cbar_end_id);
+                resumed.sbar_end_id = fractal.right.sbar_end_id;
+                resumed.high_price = resumed.high_price.max(fractal.high_price());
+                resumed.low_price = resumed.low_price.min(fractal.low_price());
+                self.rows.set(prev_index, resumed);
+                *pending_candidate = None;
+                return;
             }
+        }
 
-            if determine_swing(
-                start_fractal.as_ref(),
-                &fractal,
-                &active,
-                prev_reference_swing.as_ref(),
-                bootstrap_reference_break_ok,
+        if determine_swing(
+            start_fractal.as_ref(),
+            &fractal,
+            &active,
+            prev_reference_swing.as_ref(),
+            bootstrap_reference_break_ok,
+        ) {
+            let provisional_end_id = fractal.middle.id.unwrap_or(active.cbar_end_id);
+
+            if let Some(end_id) = find_swing_extreme_cbar_id(
+                cbars,
+                active.cbar_start_id,
+                provisional_end_id,
+                active.direction,
             ) {
-                let provisional_end_id = fractal.middle.id.unwrap_or(active.cbar_end_id);
-
-                if let Some(end_id) = find_swing_extreme_cbar_id(
-                    cbars,
-                    active.cbar_start_id,
-                    provisional_end_id,
-                    active.direction,
-                ) {
-                    active.cbar_end_id = end_id;
-                } else {
-                    active.cbar_end_id = provisional_end_id;
-                }
-
-                apply_cbar_range_stats(&mut active, cbars);
-                active.state = SwingState::PendingReverse;
-                self.rows[active_index] = active.clone();
-
-                // 记录候选终点
-                pending_candidate = Some((active_index, fractal.clone()));
-
-                let new_active = Swing {
-                    id: Some(self.id_generator.get_id()),
-                    direction: active.direction.opposite(),
-                    cbar_start_id: active.cbar_end_id,
-                    cbar_end_id: fractal.right.id.unwrap_or_default(),
-                    sbar_start_id: active.sbar_end_id,
-                    sbar_end_id: fractal.right.sbar_end_id,
-                    high_price: if active.direction == Direction::Up {
-                        active.high_price
-                    } else {
-                        fractal.right.high_price
-                    },
-                    low_price: if active.direction == Direction::Down {
-                        active.low_price
-                    } else {
-                        fractal.right.low_price
-                    },
-                    span: 1,
-                    volume: 0.0,
-                    start_oi: 0.0,
-                    end_oi: 0.0,
-                    state: SwingState::Forming,
-                    created_at: Utc::now(),
-                };
-                self.rows.push(new_active);
+                active.cbar_end_id = end_id;
             } else {
-                if in_bootstrap_phase
-                    && should_reanchor_start(start_fractal.as_ref(), &fractal, active.direction)
-                {
-                    active.cbar_start_id = fractal.middle.id.unwrap_or(active.cbar_start_id);
-                    active.sbar_start_id = fractal.middle.sbar_start_id;
-                }
-                active.cbar_end_id = fractal.right.id.unwrap_or(active.cbar_end_id);
-                apply_cbar_range_stats(&mut active, cbars);
-                active.state = SwingState::Forming;
-                self.rows[active_index] = active;
+                active.cbar_end_id = provisional_end_id;
             }
-        }
 
-        // --- 新增：循环结束后将最后一段 forming swing 标记为 confirmed ---
-        if let Some(last) = self.rows.last_mut() {
-            if last.state == SwingState::Forming {
-                last.state = SwingState::Confirmed;
+            apply_cbar_range_stats(&mut active, cbars);
+            active.state = SwingState::PendingReverse;
+            self.rows.set(active_index, active.clone());
+
+            // 记录候选终点
+            *pending_candidate = Some((active_index, fractal.clone()));
+
+            let new_active = Swing {
+                id: Some(self.id_generator.get_id()),
+                direction: active.direction.opposite(),
+                cbar_start_id: active.cbar_end_id,
+                cbar_end_id: fractal.right.id.unwrap_or_default(),
+                sbar_start_id: active.sbar_end_id,
+                sbar_end_id: fractal.right.sbar_end_id,
+                high_price: if active.direction == Direction::Up {
+                    active.high_price
+                } else {
+                    fractal.right.high_price
+                },
+                low_price: if active.direction == Direction::Down {
+                    active.low_price
+                } else {
+                    fractal.right.low_price
+                },
+                span: 1,
+                volume: 0.0,
+                start_oi: 0.0,
+                end_oi: 0.0,
+                state: SwingState::Forming,
+                created_at: Utc::now(),
+            };
+            self.rows.push(new_active);
+        } else {
+            if in_bootstrap_phase
+                && should_reanchor_start(start_fractal.as_ref(), &fractal, active.direction)
+            {
+                active.cbar_start_id = fractal.middle.id.unwrap_or(active.cbar_start_id);
+                active.sbar_start_id = fractal.middle.sbar_start_id;
             }
+            active.cbar_end_id = fractal.right.id.unwrap_or(active.cbar_end_id);
+            apply_cbar_range_stats(&mut active, cbars);
+            active.state = SwingState::Forming;
+            self.rows.set(active_index, active);
         }
-
-        self.backtrack_id = if cbar_backtrack_id.is_some() {
-            first_changed_id(
-                &previous_rows,
-                &self.rows,
-                |x| x.id,
-                swing_eq_wo_created_at,
-                true,
-            )
-            .or_else(|| self.rows.first().and_then(|x| x.id))
-        } else {
-            first_changed_id(
-                &previous_rows,
-                &self.rows,
-                |x| x.id,
-                swing_eq_wo_created_at,
-                false,
-            )
-        };
-        self.rebuild_cache();
-        self.backtrack_id
     }
 
     fn append_from_fractal(&mut self, fractal: &Fractal, right_cbar: &CBar, completed: bool) -> Swing {
@@ -364,19 +441,34 @@ impl SwingManager {
             .collect::<Vec<_>>()
     }
 
-    pub fn dataframe(&self) -> DataFrame {
-        self.df_cache.clone()
+    pub fn all_rows(&self) -> Vec<Swing> {
+        self.rows.to_vec()
     }
 
-    pub fn all_rows(&self) -> Vec<Swing> {
-        self.rows.clone()
+    pub fn rows(&self) -> &[Swing] {
+        &self.rows
     }
 
     pub fn backtrack_id(&self) -> Option<u64> {
         self.backtrack_id
     }
 
-    fn rebuild_cache(&mut self) {
+    /// 最近一次更新的行变化。
+    pub(crate) fn changes(&self) -> RowChanges<'_, Swing> {
+        diff_rows_by_id(
+            &self.replaced,
+            &self.rows[self.replaced_start.min(self.rows.len())..],
+            |x| x.id,
+            swing_eq_wo_created_at,
+        )
+    }
+
+    /// 最近一次更新中第一个变化（含新追加）的 swing 下标。
+    pub fn backtrack_index(&self) -> Option<usize> {
+        self.backtrack_index
+    }
+
+    pub fn dataframe(&self) -> DataFrame {
         let ids: Vec<u64> = self
             .rows
             .iter()
@@ -418,7 +510,7 @@ impl SwingManager {
             .map(|x| x.created_at.timestamp_millis())
             .collect();
 
-        self.df_cache = df!(
+        df!(
             "id" => ids,
             "direction" => directions,
             "cbar_start_id" => cbar_start_id,
@@ -431,11 +523,11 @@ impl SwingManager {
             "volume" => volume,
             "start_oi" => start_oi,
             "end_oi" => end_oi,
-                "is_completed" => is_completed,
+            "is_completed" => is_completed,
             "state" => state,
             "created_at" => created_at
         )
-        .expect("failed to rebuild swing dataframe cache");
+        .expect("failed to build swing dataframe")
     }
 }
 
@@ -465,23 +557,23 @@ impl FractalExt for Fractal {
     }
 }
 
+/// CBar id 单调递增，按 id 查找使用二分。
+fn cbar_index_by_id(cbars: &[CBar], id: u64) -> Option<usize> {
+    let index = cbars.partition_point(|x| x.id.unwrap_or_default() < id);
+    (cbars.get(index)?.id == Some(id)).then_some(index)
+}
+
 fn find_fractal_by_middle_id(cbars: &[CBar], middle_id: u64) -> Option<Fractal> {
-    if cbars.len() < 3 {
+    let pivot = cbar_index_by_id(cbars, middle_id)?;
+    if pivot == 0 || pivot + 1 >= cbars.len() {
         return None;
     }
-    for pivot in 1..cbars.len() - 1 {
-        if cbars[pivot].id == Some(middle_id) {
-            let fractal = Fractal {
-                left: cbars[pivot - 1].clone(),
-                middle: cbars[pivot].clone(),
-                right: cbars[pivot + 1].clone(),
-            };
-            if fractal.fractal_type() != FractalType::None {
-                return Some(fractal);
-            }
-        }
-    }
-    None
+    let fractal = Fractal {
+        left: cbars[pivot - 1].clone(),
+        middle: cbars[pivot].clone(),
+        right: cbars[pivot + 1].clone(),
+    };
+    (fractal.fractal_type() != FractalType::None).then_some(fractal)
 }
 
 fn fractal_overlap(start: &Fractal, end: &Fractal, strict: bool) -> bool {
@@ -588,26 +680,17 @@ fn latest_fractal_before(cbars: &[CBar], before_middle_id: u64, kind: FractalTyp
         return None;
     }
 
-    let mut prev: Option<Fractal> = None;
-    for pivot in 1..cbars.len().saturating_sub(1) {
-        let mid = &cbars[pivot];
-        let Some(mid_id) = mid.id else {
-            continue;
-        };
-        if mid_id >= before_middle_id {
-            break;
-        }
-
+    let end = cbars
+        .partition_point(|x| x.id.unwrap_or_default() < before_middle_id)
+        .min(cbars.len() - 1);
+    (1..end).rev().find_map(|pivot| {
         let fractal = Fractal {
             left: cbars[pivot - 1].clone(),
-            middle: mid.clone(),
+            middle: cbars[pivot].clone(),
             right: cbars[pivot + 1].clone(),
         };
-        if fractal.fractal_type() == kind {
-            prev = Some(fractal);
-        }
-    }
-    prev
+        (fractal.fractal_type() == kind).then_some(fractal)
+    })
 }
 
 fn end_breaks_start_reference(
@@ -665,20 +748,12 @@ fn should_resume_previous_swing(prev_pending: &Swing, active: &Swing, fractal: &
     }
 }
 
-fn cbar_by_id(cbars: &[CBar], id: u64) -> Option<&CBar> {
-    cbars.iter().find(|x| x.id == Some(id))
-}
-
-fn cbar_ids_in_range(cbars: &[CBar], start_id: u64, end_id: u64) -> Vec<u64> {
+fn cbars_in_range(cbars: &[CBar], start_id: u64, end_id: u64) -> &[CBar] {
     let lo = start_id.min(end_id);
     let hi = start_id.max(end_id);
-    cbars
-        .iter()
-        .filter_map(|x| {
-            let id = x.id?;
-            (lo <= id && id <= hi).then_some(id)
-        })
-        .collect::<Vec<_>>()
+    let from = cbars.partition_point(|x| x.id.unwrap_or_default() < lo);
+    let to = cbars.partition_point(|x| x.id.unwrap_or_default() <= hi);
+    &cbars[from..to.max(from)]
 }
 
 fn find_swing_extreme_cbar_id(
@@ -687,14 +762,9 @@ fn find_swing_extreme_cbar_id(
     end_id: u64,
     direction: Direction,
 ) -> Option<u64> {
-    let ids = cbar_ids_in_range(cbars, start_id, end_id);
-    if ids.is_empty() {
-        return None;
-    }
-
     let mut best: Option<(&CBar, u64)> = None;
-    for id in ids {
-        let cbar = cbar_by_id(cbars, id)?;
+    for cbar in cbars_in_range(cbars, start_id, end_id) {
+        let id = cbar.id.unwrap_or_default();
         best = match best {
             None => Some((cbar, id)),
             Some((prev, prev_id)) => {
@@ -721,8 +791,8 @@ fn find_swing_extreme_cbar_id(
 }
 
 fn apply_cbar_range_stats(swing: &mut Swing, cbars: &[CBar]) {
-    let ids = cbar_ids_in_range(cbars, swing.cbar_start_id, swing.cbar_end_id);
-    if ids.is_empty() {
+    let range = cbars_in_range(cbars, swing.cbar_start_id, swing.cbar_end_id);
+    if range.is_empty() {
         return;
     }
 
@@ -730,13 +800,11 @@ fn apply_cbar_range_stats(swing: &mut Swing, cbars: &[CBar]) {
     let mut low = f64::MAX;
     let mut sbar_start = u64::MAX;
     let mut sbar_end = 0_u64;
-    for id in ids {
-        if let Some(cbar) = cbar_by_id(cbars, id) {
-            high = high.max(cbar.high_price);
-            low = low.min(cbar.low_price);
-            sbar_start = sbar_start.min(cbar.sbar_start_id);
-            sbar_end = sbar_end.max(cbar.sbar_end_id);
-        }
+    for cbar in range {
+        high = high.max(cbar.high_price);
+        low = low.min(cbar.low_price);
+        sbar_start = sbar_start.min(cbar.sbar_start_id);
+        sbar_end = sbar_end.max(cbar.sbar_end_id);
     }
     if sbar_start != u64::MAX {
         swing.sbar_start_id = sbar_start;
@@ -7,9 +7,16 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use serde_yaml::Value as YamlValue;
 
+/// 交易时段，`sections` 为按交易日内先后排列的 `("HH:MM", "HH:MM")`，左闭右开。
+///
+/// 结束早于开始表示跨午夜；首段开始晚于末段结束（如 CTP 夜盘 21:00）时，
+/// 首段起的行情归属下一交易日。`sections` 为空视为 7x24 连续交易。
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TradingSession {
     pub sections: Vec<(String, String)>,
+    /// 时段所在时区相对 bar 时间的偏移（分钟）；bar 时间已是交易所本地时间时为 0。
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
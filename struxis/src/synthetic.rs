@@ -0,0 +1,559 @@
+//! 合成行情生成器。
+//!
+//! - `MarketScript`：按脚本（上涨 N 根、回调 M 根、包含簇、突破）生成 SBar，并附带预期的 CBar/分型/Swing/Trend，
+//!   其中 Swing/Trend 由预期 CBar 按管线的批量规则推出；
+//! - `RandomWalk`：带种子的随机游走；
+//! - `RegimeSwitching`：带种子的上涨/下跌/震荡切换，附带每段的状态标签。
+//!
+//! 预期结构以下标表示（`StructureOutline`），与 id 策略无关；
+//! `StructureOutline::from_structures` 把管线输出转成同一形式，可直接比较。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use crate::bar::{CBar, Fractal, SBar};
+use crate::constant::{Direction, FractalType, IdStrategy, Timeframe};
+use crate::id_generator::IdGenerator;
+use crate::swing::{Swing, SwingManager, SwingState};
+use crate::trend::{Trend, TrendManager};
+use crate::utils::Rng;
+
+/// 生成 SBar 的公共字段。
+#[derive(Debug, Clone)]
+pub struct SeriesSpec {
+    pub symbol: String,
+    pub exchange: String,
+    pub timeframe: Timeframe,
+    pub start: DateTime<Utc>,
+    pub volume: f64,
+    pub open_interest: f64,
+}
+
+impl Default for SeriesSpec {
+    fn default() -> Self {
+        Self {
+            symbol: "SYN".to_string(),
+            exchange: "SIM".to_string(),
+            timeframe: Timeframe::M15,
+            start: Utc
+                .with_ymd_and_hms(2024, 1, 2, 9, 0, 0)
+                .single()
+                .expect("valid dt"),
+            volume: 100.0,
+            open_interest: 1000.0,
+        }
+    }
+}
+
+impl SeriesSpec {
+    fn sbar(&self, index: usize, open: f64, high: f64, low: f64, close: f64) -> SBar {
+        SBar {
+            id: None,
+            symbol: self.symbol.clone(),
+            exchange: self.exchange.clone(),
+            timeframe: self.timeframe,
+            datetime: self.start + Duration::seconds(self.timeframe.seconds() * index as i64),
+            open_price: open,
+            high_price: high,
+            low_price: low,
+            close_price: close,
+            volume: self.volume,
+            open_interest: self.open_interest,
+            turnover: self.volume * close,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SyntheticSeries {
+    pub sbars: Vec<SBar>,
+    /// 脚本生成时的预期结构；随机模式没有真值，为 `None`。
+    pub expected: Option<StructureOutline>,
+    /// 状态切换模式的各段标签，按时间排列。
+    pub regimes: Vec<RegimeSpan>,
+}
+
+/// 一段行情状态，`start..=end` 为 SBar 下标。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegimeSpan {
+    /// `Up`/`Down` 为单边，`Range` 为震荡。
+    pub regime: Direction,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 以下标表示的结构：CBar 引用 SBar 下标，swing 引用 CBar 下标，trend 引用 swing 下标。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StructureOutline {
+    pub cbars: Vec<OutlineCBar>,
+    pub swings: Vec<OutlineSwing>,
+    pub trends: Vec<OutlineTrend>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutlineCBar {
+    pub sbar_start: usize,
+    pub sbar_end: usize,
+    pub high: f64,
+    pub low: f64,
+    pub fractal_type: FractalType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutlineSwing {
+    pub direction: Direction,
+    pub cbar_start: usize,
+    pub cbar_end: usize,
+    pub high: f64,
+    pub low: f64,
+    /// 最后一段尚未被反向分型确认的 swing 为 `false`。
+    pub confirmed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutlineTrend {
+    pub direction: Direction,
+    pub swing_start: usize,
+    pub swing_end: usize,
+    pub high: f64,
+    pub low: f64,
+    pub completed: bool,
+}
+
+impl StructureOutline {
+    /// 把管线输出转成下标形式；引用不到的结构被跳过。
+    pub fn from_structures(
+        sbars: &[SBar],
+        cbars: &[CBar],
+        swings: &[Swing],
+        trends: &[Trend],
+    ) -> Self {
+        let sbar_index = index_by_id(sbars.iter().map(|x| x.id));
+        let cbar_index = index_by_id(cbars.iter().map(|x| x.id));
+        let swing_index = index_by_id(swings.iter().map(|x| x.id));
+        Self {
+            cbars: cbars
+                .iter()
+                .filter_map(|x| {
+                    Some(OutlineCBar {
+                        sbar_start: *sbar_index.get(&x.sbar_start_id)?,
+                        sbar_end: *sbar_index.get(&x.sbar_end_id)?,
+                        high: x.high_price,
+                        low: x.low_price,
+                        fractal_type: x.fractal_type,
+                    })
+                })
+                .collect(),
+            swings: swings
+                .iter()
+                .filter_map(|x| {
+                    Some(OutlineSwing {
+                        direction: x.direction,
+                        cbar_start: *cbar_index.get(&x.cbar_start_id)?,
+                        cbar_end: *cbar_index.get(&x.cbar_end_id)?,
+                        high: x.high_price,
+                        low: x.low_price,
+                        confirmed: x.state == SwingState::Confirmed,
+                    })
+                })
+                .collect(),
+            trends: trends
+                .iter()
+                .filter_map(|x| {
+                    Some(OutlineTrend {
+                        direction: x.direction,
+                        swing_start: *swing_index.get(&x.swing_start_id)?,
+                        swing_end: *swing_index.get(&x.swing_end_id)?,
+                        high: x.high_price,
+                        low: x.low_price,
+                        completed: x.is_completed,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// 分型所在的 CBar 下标与类型。
+    pub fn fractals(&self) -> Vec<(usize, FractalType)> {
+        self.cbars
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.fractal_type != FractalType::None)
+            .map(|(i, x)| (i, x.fractal_type))
+            .collect()
+    }
+}
+
+fn index_by_id(ids: impl Iterator<Item = Option<u64>>) -> HashMap<u64, usize> {
+    ids.enumerate()
+        .filter_map(|(i, id)| id.map(|id| (id, i)))
+        .collect()
+}
+
+/// 结构脚本的一步。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScriptStep {
+    /// n 根逐根抬高 `step` 的 SBar，各成一根 CBar。
+    Up(usize),
+    /// n 根逐根降低 `step` 的 SBar，各成一根 CBar。
+    Down(usize),
+    /// n 根依次内缩、被上一根 CBar 包含的 SBar，全部并入该 CBar。
+    Inclusive(usize),
+    /// 一根向 `direction` 跳 `3 * step` 的 SBar。
+    Breakout(Direction),
+}
+
+/// 结构脚本：从一根种子 SBar 出发逐步生成行情，预期 CBar 与分型由脚本直接推出。
+///
+/// 相邻 CBar 整体平移 `step`（`step < range`，相互重叠但不包含），方向反转处即为分型。
+/// swing/trend 的成段规则（分型重叠、起点重锚、回溯）只在管线中维护一份，
+/// 预期值以预期 CBar 批量重建得到。
+#[derive(Debug, Clone)]
+pub struct MarketScript {
+    pub start_price: f64,
+    pub step: f64,
+    /// 单根 SBar 的高低点差。
+    pub range: f64,
+    pub steps: Vec<ScriptStep>,
+}
+
+impl MarketScript {
+    pub fn new(start_price: f64) -> Self {
+        Self {
+            start_price,
+            step: 1.0,
+            range: 1.6,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn with_step(mut self, step: f64, range: f64) -> Self {
+        self.step = step;
+        self.range = range;
+        self
+    }
+
+    pub fn up(mut self, cbars: usize) -> Self {
+        self.steps.push(ScriptStep::Up(cbars));
+        self
+    }
+
+    pub fn down(mut self, cbars: usize) -> Self {
+        self.steps.push(ScriptStep::Down(cbars));
+        self
+    }
+
+    pub fn inclusive(mut self, sbars: usize) -> Self {
+        self.steps.push(ScriptStep::Inclusive(sbars));
+        self
+    }
+
+    pub fn breakout(mut self, direction: Direction) -> Self {
+        self.steps.push(ScriptStep::Breakout(direction));
+        self
+    }
+
+    pub fn generate(&self, spec: &SeriesSpec) -> SyntheticSeries {
+        let half = self.range / 2.0;
+        let mut sbars = vec![spec.sbar(
+            0,
+            self.start_price,
+            self.start_price + half,
+            self.start_price - half,
+            self.start_price,
+        )];
+        let mut cbars = vec![OutlineCBar {
+            sbar_start: 0,
+            sbar_end: 0,
+            high: self.start_price + half,
+            low: self.start_price - half,
+            fractal_type: FractalType::None,
+        }];
+        // 当前 CBar 之后已生成的内缩 SBar 数
+        let mut insides = 0;
+
+        let shift = |sbars: &mut Vec<SBar>, cbars: &mut Vec<OutlineCBar>, delta: f64| {
+            let last = *cbars.last().expect("seed cbar");
+            let (high, low) = (last.high + delta, last.low + delta);
+            let (open, close) = if delta > 0.0 {
+                (low, high)
+            } else {
+                (high, low)
+            };
+            let index = sbars.len();
+            sbars.push(spec.sbar(index, open, high, low, close));
+            cbars.push(OutlineCBar {
+                sbar_start: index,
+                sbar_end: index,
+                high,
+                low,
+                fractal_type: FractalType::None,
+            });
+        };
+
+        for step in &self.steps {
+            match *step {
+                ScriptStep::Up(n) | ScriptStep::Down(n) => {
+                    let delta = if matches!(step, ScriptStep::Up(_)) {
+                        self.step
+                    } else {
+                        -self.step
+                    };
+                    for _ in 0..n {
+                        shift(&mut sbars, &mut cbars, delta);
+                        insides = 0;
+                    }
+                }
+                ScriptStep::Breakout(direction) => {
+                    let delta = match direction {
+                        Direction::Down => -3.0 * self.step,
+                        _ => 3.0 * self.step,
+                    };
+                    shift(&mut sbars, &mut cbars, delta);
+                    insides = 0;
+                }
+                ScriptStep::Inclusive(n) => {
+                    for _ in 0..n {
+                        insides += 1;
+                        let last = cbars.last_mut().expect("seed cbar");
+                        // 内缩量单调增加且小于半个 range，每根都被前一根包含
+                        let inset = half * (1.0 - 0.8_f64.powi(insides));
+                        let (high, low) = (last.high - inset, last.low + inset);
+                        let mid = (high + low) / 2.0;
+                        let index = sbars.len();
+                        sbars.push(spec.sbar(index, mid, high, low, mid));
+                        last.sbar_end = index;
+                    }
+                }
+            }
+        }
+
+        label_fractals(&mut cbars);
+        let expected = derive_structures(&sbars, cbars);
+        SyntheticSeries {
+            sbars,
+            expected: Some(expected),
+            regimes: Vec::new(),
+        }
+    }
+}
+
+fn label_fractals(cbars: &mut [OutlineCBar]) {
+    let as_cbar = |x: &OutlineCBar| CBar {
+        id: None,
+        sbar_start_id: x.sbar_start as u64,
+        sbar_end_id: x.sbar_end as u64,
+        high_price: x.high,
+        low_price: x.low,
+        fractal_type: FractalType::None,
+        created_at: DateTime::<Utc>::default(),
+    };
+    for i in 1..cbars.len().saturating_sub(1) {
+        cbars[i].fractal_type = Fractal::verify(
+            &as_cbar(&cbars[i - 1]),
+            &as_cbar(&cbars[i]),
+            &as_cbar(&cbars[i + 1]),
+        );
+    }
+}
+
+/// 以下标 + 1 为 id，按批量模式由 CBar 重建 swing 与 trend。
+fn derive_structures(sbars: &[SBar], cbars: Vec<OutlineCBar>) -> StructureOutline {
+    let sbars = sbars
+        .iter()
+        .enumerate()
+        .map(|(i, x)| SBar {
+            id: Some(i as u64 + 1),
+            ..x.clone()
+        })
+        .collect::<Vec<_>>();
+    let rows = cbars
+        .iter()
+        .enumerate()
+        .map(|(i, x)| CBar {
+            id: Some(i as u64 + 1),
+            sbar_start_id: x.sbar_start as u64 + 1,
+            sbar_end_id: x.sbar_end as u64 + 1,
+            high_price: x.high,
+            low_price: x.low,
+            fractal_type: x.fractal_type,
+            created_at: sbars[x.sbar_end].datetime,
+        })
+        .collect::<Vec<_>>();
+    let mut swings = SwingManager::with_id_generator(Arc::new(IdGenerator::with_strategy(
+        0,
+        IdStrategy::Sequential,
+    )));
+    swings.rebuild_from_cbars(&rows);
+    let mut trends = TrendManager::with_id_generator(Arc::new(IdGenerator::with_strategy(
+        0,
+        IdStrategy::Sequential,
+    )));
+    trends.rebuild_from_swings(swings.rows());
+    StructureOutline {
+        cbars,
+        ..StructureOutline::from_structures(&sbars, &rows, swings.rows(), trends.rows())
+    }
+}
+
+/// 带种子的随机游走，收盘价按 `tick` 取整。
+#[derive(Debug, Clone)]
+pub struct RandomWalk {
+    pub seed: u64,
+    pub bars: usize,
+    pub start_price: f64,
+    /// 每根 SBar 的期望涨跌。
+    pub drift: f64,
+    /// 每根 SBar 涨跌的标准差。
+    pub volatility: f64,
+    pub tick: f64,
+}
+
+impl Default for RandomWalk {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            bars: 200,
+            start_price: 100.0,
+            drift: 0.0,
+            volatility: 1.0,
+            tick: 0.5,
+        }
+    }
+}
+
+impl RandomWalk {
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn generate(&self, spec: &SeriesSpec) -> SyntheticSeries {
+        let mut walker = Walker::new(self.seed, self.start_price, self.volatility, self.tick);
+        let sbars = (0..self.bars)
+            .map(|i| walker.next_bar(spec, i, self.drift))
+            .collect();
+        SyntheticSeries {
+            sbars,
+            expected: None,
+            regimes: Vec::new(),
+        }
+    }
+}
+
+/// 带种子的状态切换行情：每段随机取上涨/下跌/震荡，长度在 `min_len..=max_len` 内。
+#[derive(Debug, Clone)]
+pub struct RegimeSwitching {
+    pub seed: u64,
+    pub bars: usize,
+    pub start_price: f64,
+    pub volatility: f64,
+    pub tick: f64,
+    /// 单边段每根 SBar 的漂移，以 `volatility` 的倍数计。
+    pub trend_strength: f64,
+    pub min_len: usize,
+    pub max_len: usize,
+}
+
+impl Default for RegimeSwitching {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            bars: 300,
+            start_price: 100.0,
+            volatility: 1.0,
+            tick: 0.5,
+            trend_strength: 0.5,
+            min_len: 20,
+            max_len: 60,
+        }
+    }
+}
+
+impl RegimeSwitching {
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn generate(&self, spec: &SeriesSpec) -> SyntheticSeries {
+        let mut walker = Walker::new(self.seed, self.start_price, self.volatility, self.tick);
+        let min_len = self.min_len.max(1);
+        let max_len = self.max_len.max(min_len);
+        let mut sbars = Vec::with_capacity(self.bars);
+        let mut regimes = Vec::new();
+        let mut previous = Direction::None;
+        while sbars.len() < self.bars {
+            // 与上一段不同的状态
+            let choices = [Direction::Up, Direction::Down, Direction::Range]
+                .into_iter()
+                .filter(|x| *x != previous)
+                .collect::<Vec<_>>();
+            let regime = choices[(walker.rng.next_f64() * choices.len() as f64) as usize];
+            let len = min_len + (walker.rng.next_f64() * (max_len - min_len + 1) as f64) as usize;
+            let start = sbars.len();
+            let end = (start + len).min(self.bars) - 1;
+            let anchor = walker.price;
+            for i in start..=end {
+                let drift = match regime {
+                    Direction::Up => self.trend_strength * self.volatility,
+                    Direction::Down => -self.trend_strength * self.volatility,
+                    // 震荡段向段首价格回归
+                    _ => (anchor - walker.price) * 0.3,
+                };
+                sbars.push(walker.next_bar(spec, i, drift));
+            }
+            regimes.push(RegimeSpan { regime, start, end });
+            previous = regime;
+        }
+        SyntheticSeries {
+            sbars,
+            expected: None,
+            regimes,
+        }
+    }
+}
+
+struct Walker {
+    rng: Rng,
+    price: f64,
+    volatility: f64,
+    tick: f64,
+}
+
+impl Walker {
+    fn new(seed: u64, price: f64, volatility: f64, tick: f64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            price,
+            volatility,
+            tick: if tick > 0.0 { tick } else { 0.01 },
+        }
+    }
+
+    /// 近似标准正态（四个均匀分布之和，方差归一）。
+    fn normal(&mut self) -> f64 {
+        let sum = (0..4).map(|_| self.rng.next_f64()).sum::<f64>();
+        (sum - 2.0) * 3.0_f64.sqrt()
+    }
+
+    fn round(&self, price: f64) -> f64 {
+        (price / self.tick).round() * self.tick
+    }
+
+    fn next_bar(&mut self, spec: &SeriesSpec, index: usize, drift: f64) -> SBar {
+        let open = self.price;
+        let close = open + drift + self.volatility * self.normal();
+        let close = self.round(close);
+        let upper = self.volatility * self.normal().abs() * 0.5;
+        let upper = self.round(upper);
+        let lower = self.volatility * self.normal().abs() * 0.5;
+        let lower = self.round(lower);
+        let high = open.max(close) + upper.max(self.tick);
+        let low = open.min(close) - lower;
+        self.price = close;
+        spec.sbar(index, open, high, low, close)
+    }
+}
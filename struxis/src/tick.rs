@@ -1,9 +1,22 @@
-use chrono::{DateTime, Timelike, Utc};
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Duration, Timelike, Utc};
 
 use crate::bar::SBar;
-use crate::constant::Timeframe;
+use crate::constant::{DataError, Timeframe};
+use crate::session::SessionCalendar;
+
+/// 成交的主动方向：`Buy` 为主动买（吃卖盘），`Sell` 为主动卖。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+    #[default]
+    Unknown,
+}
 
-#[derive(Debug, Clone)]
+/// `volume`/`turnover` 为累计值；盘口字段未提供时为 0。
+#[derive(Debug, Clone, Default)]
 pub struct TickInput {
     pub symbol: String,
     pub exchange: String,
@@ -12,14 +25,121 @@ pub struct TickInput {
     pub volume: f64,
     pub turnover: f64,
     pub open_interest: f64,
+    pub bid_price: f64,
+    pub bid_volume: f64,
+    pub ask_price: f64,
+    pub ask_volume: f64,
+    /// 行情给出的成交方向；`Unknown` 时由聚合器推断。
+    pub side: TradeSide,
 }
 
-pub struct TickBarAggregator {
-    current_bar: Option<SBar>,
+impl TickInput {
+    pub fn has_quote(&self) -> bool {
+        self.bid_price > 0.0 && self.ask_price >= self.bid_price
+    }
+
+    pub fn mid_price(&self) -> Option<f64> {
+        self.has_quote()
+            .then(|| (self.bid_price + self.ask_price) / 2.0)
+    }
+}
+
+/// 单根 bar 内按主动方向拆分的成交量；无法判定方向的成交不计入任一侧。
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct OrderFlow {
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+}
+
+impl OrderFlow {
+    pub fn delta(&self) -> f64 {
+        self.buy_volume - self.sell_volume
+    }
+
+    /// `delta / (buy + sell)`，取值 [-1, 1]；无成交时为 0。
+    pub fn imbalance(&self) -> f64 {
+        let total = self.buy_volume + self.sell_volume;
+        if total <= 0.0 {
+            0.0
+        } else {
+            self.delta() / total
+        }
+    }
+
+    fn add(&mut self, side: TradeSide, volume: f64) {
+        match side {
+            TradeSide::Buy => self.buy_volume += volume,
+            TradeSide::Sell => self.sell_volume += volume,
+            TradeSide::Unknown => {}
+        }
+    }
+}
+
+/// 累计成交量/成交额转增量；首笔只建立基准，累计量回落视为重置（如 CTP 换日清零），
+/// 增量取重置后的累计值。
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct CumulativeVolume {
     last_volume: Option<f64>,
     last_turnover: Option<f64>,
 }
 
+impl CumulativeVolume {
+    /// 返回成交量增量、成交额增量，以及是否发生重置。
+    pub(crate) fn delta(&mut self, tick: &TickInput) -> (f64, f64, bool) {
+        let mut reset = false;
+        let mut delta = |prev: Option<f64>, value: f64| match prev {
+            None => 0.0,
+            Some(prev) if value >= prev => value - prev,
+            Some(_) => {
+                reset = true;
+                value
+            }
+        };
+        let volume = delta(self.last_volume, tick.volume);
+        let turnover = delta(self.last_turnover, tick.turnover);
+        self.last_volume = Some(tick.volume);
+        self.last_turnover = Some(tick.turnover);
+        (volume, turnover, reset)
+    }
+}
+
+/// tick 聚合过程中的异常计数，均为累计值。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TickAnomalies {
+    /// 累计成交量/成交额回落（如 CTP 换日清零），按重置处理。
+    pub volume_resets: u64,
+    /// 时间早于上一笔、但所属 bar 尚未关闭的 tick。
+    pub out_of_order: u64,
+    /// 所属 bar 已关闭而被丢弃的 tick。
+    pub late_dropped: u64,
+    /// 价格非正或非有限值而被丢弃的 tick。
+    pub invalid: u64,
+}
+
+/// tick 合成日内 bar，默认 M1；秒级周期也由此聚合，区间按自然时钟对齐。
+///
+/// 同时按主动方向累计每根 bar 的 `OrderFlow`。tick 未给出方向时先按盘口中间价判定，
+/// 恰在中间价或无盘口时按与上一笔成交价的涨跌判定，价格不变沿用上一笔的方向。
+///
+/// bar 在 `区间终点 + late_tolerance` 不晚于最新时间（tick 时间或 `on_clock` 传入的时钟）时关闭，
+/// 默认容忍为 0，即下一区间的首笔 tick 到达即关闭。乱序 tick 只更新所属 bar 的高低价，
+/// 其成交量已包含在后续 tick 的累计量中；所属 bar 已关闭的 tick 被丢弃，
+/// 按时间顺序到达的迟到 tick 不更新累计量基准，其成交量由下一笔计入。
+pub struct TickBarAggregator {
+    timeframe: Timeframe,
+    late_tolerance: Duration,
+    /// 未关闭的 bar，按区间起点升序。
+    open: VecDeque<(SBar, OrderFlow)>,
+    /// 已关闭区间的终点，早于此的 tick 一律丢弃。
+    closed_until: Option<DateTime<Utc>>,
+    latest: Option<DateTime<Utc>>,
+    last_tick_at: Option<DateTime<Utc>>,
+    cumulative: CumulativeVolume,
+    last_price: Option<f64>,
+    last_side: TradeSide,
+    anomalies: TickAnomalies,
+}
+
 impl Default for TickBarAggregator {
     fn default() -> Self {
         Self::new()
@@ -28,84 +148,212 @@ impl Default for TickBarAggregator {
 
 impl TickBarAggregator {
     pub fn new() -> Self {
-        Self {
-            current_bar: None,
-            last_volume: None,
-            last_turnover: None,
+        Self::with_timeframe(Timeframe::M1)
+    }
+
+    /// 仅支持日内周期，传入 `D1` 及以上或无效周期时 panic；需要处理错误时用 `try_with_timeframe`。
+    pub fn with_timeframe(timeframe: Timeframe) -> Self {
+        Self::try_with_timeframe(timeframe).expect("tick aggregation needs an intraday timeframe")
+    }
+
+    /// 非日内或长度为 0 的周期返回 `DataError::InvalidTimeframe`。
+    pub fn try_with_timeframe(timeframe: Timeframe) -> Result<Self, DataError> {
+        let timeframe = timeframe.validate()?;
+        if !timeframe.is_intraday() {
+            return Err(DataError::InvalidTimeframe(timeframe.as_str().into_owned()));
         }
+        Ok(Self {
+            timeframe,
+            late_tolerance: Duration::zero(),
+            open: VecDeque::new(),
+            closed_until: None,
+            latest: None,
+            last_tick_at: None,
+            cumulative: CumulativeVolume::default(),
+            last_price: None,
+            last_side: TradeSide::Unknown,
+            anomalies: TickAnomalies::default(),
+        })
     }
 
-    pub fn update(&mut self, tick: TickInput) -> Option<SBar> {
-        let minute_dt = tick
-            .datetime
-            .with_second(0)
-            .and_then(|x| x.with_nanosecond(0))
-            .unwrap_or(tick.datetime);
+    /// 区间结束后继续等待迟到 tick 的时长，负值按 0 处理。
+    pub fn with_late_tolerance(mut self, tolerance: Duration) -> Self {
+        self.set_late_tolerance(tolerance);
+        self
+    }
 
-        let delta_volume = match self.last_volume {
-            Some(prev) if tick.volume >= prev => tick.volume - prev,
-            _ => 0.0,
-        };
-        let delta_turnover = match self.last_turnover {
-            Some(prev) if tick.turnover >= prev => tick.turnover - prev,
-            _ => 0.0,
-        };
+    pub fn set_late_tolerance(&mut self, tolerance: Duration) {
+        self.late_tolerance = tolerance.max(Duration::zero());
+    }
 
-        self.last_volume = Some(tick.volume);
-        self.last_turnover = Some(tick.turnover);
+    pub fn late_tolerance(&self) -> Duration {
+        self.late_tolerance
+    }
 
-        match self.current_bar.as_mut() {
-            None => {
-                self.current_bar = Some(SBar {
-                    id: None,
-                    symbol: tick.symbol,
-                    exchange: tick.exchange,
-                    timeframe: Timeframe::M1,
-                    datetime: minute_dt,
-                    open_price: tick.last_price,
-                    high_price: tick.last_price,
-                    low_price: tick.last_price,
-                    close_price: tick.last_price,
-                    volume: delta_volume,
-                    open_interest: tick.open_interest,
-                    turnover: delta_turnover,
-                });
-                None
+    pub fn anomalies(&self) -> TickAnomalies {
+        self.anomalies
+    }
+
+    /// 返回本次关闭的 bar，按区间先后排列。
+    pub fn update(&mut self, tick: TickInput) -> Vec<SBar> {
+        strip_flow(self.update_with_flow(tick))
+    }
+
+    /// 与 `update` 相同，额外返回每根 bar 的主动买卖量。
+    pub fn update_with_flow(&mut self, tick: TickInput) -> Vec<(SBar, OrderFlow)> {
+        if !tick.last_price.is_finite() || tick.last_price <= 0.0 {
+            self.anomalies.invalid += 1;
+            return Vec::new();
+        }
+        let start = self.bucket_start(tick.datetime);
+        if self.closed_until.is_some_and(|x| start < x) {
+            self.anomalies.late_dropped += 1;
+            return Vec::new();
+        }
+
+        let slot = self.open.iter().position(|(bar, _)| bar.datetime >= start);
+        let in_order = self.last_tick_at.is_none_or(|x| tick.datetime >= x);
+        if in_order {
+            let (volume, turnover, reset) = self.cumulative.delta(&tick);
+            if reset {
+                self.anomalies.volume_resets += 1;
             }
-            Some(bar) => {
-                if bar.datetime == minute_dt {
+            let side = self.classify(&tick);
+            self.last_tick_at = Some(tick.datetime);
+            match slot {
+                Some(i) if self.open[i].0.datetime == start => {
+                    let (bar, flow) = &mut self.open[i];
                     bar.high_price = bar.high_price.max(tick.last_price);
                     bar.low_price = bar.low_price.min(tick.last_price);
                     bar.close_price = tick.last_price;
-                    bar.volume += delta_volume;
-                    bar.turnover += delta_turnover;
+                    bar.volume += volume;
+                    bar.turnover += turnover;
                     bar.open_interest = tick.open_interest;
-                    None
-                } else {
-                    let finished = self.current_bar.take();
-                    self.current_bar = Some(SBar {
-                        id: None,
-                        symbol: tick.symbol,
-                        exchange: tick.exchange,
-                        timeframe: Timeframe::M1,
-                        datetime: minute_dt,
-                        open_price: tick.last_price,
-                        high_price: tick.last_price,
-                        low_price: tick.last_price,
-                        close_price: tick.last_price,
-                        volume: delta_volume,
-                        open_interest: tick.open_interest,
-                        turnover: delta_turnover,
-                    });
-                    finished
+                    flow.add(side, volume);
                 }
+                _ => {
+                    let mut flow = OrderFlow::default();
+                    flow.add(side, volume);
+                    let bar = self.new_bar(&tick, start, volume, turnover);
+                    self.open.push_back((bar, flow));
+                }
+            }
+        } else {
+            self.anomalies.out_of_order += 1;
+            match slot {
+                Some(i) if self.open[i].0.datetime == start => {
+                    let bar = &mut self.open[i].0;
+                    bar.high_price = bar.high_price.max(tick.last_price);
+                    bar.low_price = bar.low_price.min(tick.last_price);
+                }
+                _ => {
+                    let bar = self.new_bar(&tick, start, 0.0, 0.0);
+                    let at = slot.unwrap_or(self.open.len());
+                    self.open.insert(at, (bar, OrderFlow::default()));
+                }
+            }
+        }
+
+        self.advance(tick.datetime)
+    }
+
+    /// 时钟推进到 `now`（与 tick 同一时间基准），关闭已到期的 bar；无新 tick 时由定时器调用。
+    pub fn on_clock(&mut self, now: DateTime<Utc>) -> Vec<SBar> {
+        strip_flow(self.on_clock_with_flow(now))
+    }
+
+    pub fn on_clock_with_flow(&mut self, now: DateTime<Utc>) -> Vec<(SBar, OrderFlow)> {
+        self.advance(now)
+    }
+
+    /// 立即关闭所有未完成的 bar。
+    pub fn flush(&mut self) -> Vec<SBar> {
+        strip_flow(self.flush_with_flow())
+    }
+
+    pub fn flush_with_flow(&mut self) -> Vec<(SBar, OrderFlow)> {
+        if let Some((bar, _)) = self.open.back() {
+            self.closed_until = Some(self.bucket_end(bar.datetime));
+        }
+        self.open.drain(..).collect()
+    }
+
+    /// 最新一根未完成 bar 截至目前的主动买卖量。
+    pub fn current_flow(&self) -> Option<OrderFlow> {
+        self.open.back().map(|(_, flow)| *flow)
+    }
+
+    fn advance(&mut self, now: DateTime<Utc>) -> Vec<(SBar, OrderFlow)> {
+        let latest = self.latest.map_or(now, |x| x.max(now));
+        self.latest = Some(latest);
+        let mut closed = Vec::new();
+        while let Some((bar, _)) = self.open.front() {
+            let end = self.bucket_end(bar.datetime);
+            if end + self.late_tolerance > latest {
+                break;
             }
+            self.closed_until = Some(end);
+            closed.extend(self.open.pop_front());
         }
+        closed
     }
 
-    pub fn flush(&mut self) -> Option<SBar> {
-        self.current_bar.take()
+    fn bucket_start(&self, datetime: DateTime<Utc>) -> DateTime<Utc> {
+        let step = self.timeframe.seconds();
+        let second_of_day = datetime.num_seconds_from_midnight() as i64;
+        datetime
+            .with_hour(0)
+            .and_then(|x| x.with_minute(0))
+            .and_then(|x| x.with_second(0))
+            .and_then(|x| x.with_nanosecond(0))
+            .map(|x| x + Duration::seconds(second_of_day / step * step))
+            .unwrap_or(datetime)
+    }
+
+    fn bucket_end(&self, start: DateTime<Utc>) -> DateTime<Utc> {
+        start + Duration::seconds(self.timeframe.seconds())
     }
+
+    fn new_bar(&self, tick: &TickInput, start: DateTime<Utc>, volume: f64, turnover: f64) -> SBar {
+        SBar {
+            id: None,
+            symbol: tick.symbol.clone(),
+            exchange: tick.exchange.clone(),
+            timeframe: self.timeframe,
+            datetime: start,
+            open_price: tick.last_price,
+            high_price: tick.last_price,
+            low_price: tick.last_price,
+            close_price: tick.last_price,
+            volume,
+            open_interest: tick.open_interest,
+            turnover,
+        }
+    }
+
+    fn classify(&mut self, tick: &TickInput) -> TradeSide {
+        let mut side = tick.side;
+        if side == TradeSide::Unknown {
+            side = match tick.mid_price() {
+                Some(mid) if tick.last_price > mid => TradeSide::Buy,
+                Some(mid) if tick.last_price < mid => TradeSide::Sell,
+                _ => match self.last_price {
+                    Some(prev) if tick.last_price > prev => TradeSide::Buy,
+                    Some(prev) if tick.last_price < prev => TradeSide::Sell,
+                    _ => self.last_side,
+                },
+            };
+        }
+        self.last_price = Some(tick.last_price);
+        if side != TradeSide::Unknown {
+            self.last_side = side;
+        }
+        side
+    }
+}
+
+fn strip_flow(bars: Vec<(SBar, OrderFlow)>) -> Vec<SBar> {
+    bars.into_iter().map(|(bar, _)| bar).collect()
 }
 
 pub struct BarWindowAggregator {
@@ -116,13 +364,10 @@ pub struct BarWindowAggregator {
 
 impl BarWindowAggregator {
     pub fn new(timeframe: Timeframe) -> Option<Self> {
-        let window = match timeframe {
-            Timeframe::M1 => 1,
-            Timeframe::M5 => 5,
-            Timeframe::M15 => 15,
-            Timeframe::H1 => 60,
-            Timeframe::D1 => 1440,
-        };
+        if timeframe.is_sub_minute() {
+            return None;
+        }
+        let window = timeframe.minutes() as usize;
         if window <= 1 {
             return None;
         }
@@ -173,3 +418,77 @@ impl BarWindowAggregator {
         })
     }
 }
+
+/// 按墙钟与交易时段对齐的高周期聚合器。
+///
+/// 输入 M1 的 `datetime` 为分钟起点；输出 bar 的 `datetime` 为区间起点（`D1` 为交易日 0 点）。
+/// 区间最后一分钟到达、或下一区间的 M1 到达时输出，缺失分钟不影响后续对齐。
+pub struct SessionBarAggregator {
+    timeframe: Timeframe,
+    calendar: SessionCalendar,
+    current: Option<(SBar, OrderFlow)>,
+}
+
+impl SessionBarAggregator {
+    /// `M1` 无需聚合、秒级周期无法由 M1 合成，均返回 `None`。
+    pub fn new(timeframe: Timeframe, calendar: SessionCalendar) -> Option<Self> {
+        if timeframe.seconds() <= Timeframe::M1.seconds() || timeframe.is_sub_minute() {
+            return None;
+        }
+        Some(Self {
+            timeframe,
+            calendar,
+            current: None,
+        })
+    }
+
+    /// 返回本次完成的 bar（至多两根：被新区间顶掉的旧 bar 与恰好收盘的当前 bar）。
+    pub fn update(&mut self, m1_bar: SBar) -> Vec<SBar> {
+        strip_flow(self.update_with_flow(m1_bar, OrderFlow::default()))
+    }
+
+    /// 与 `update` 相同，`flow` 为该 M1 的主动买卖量，累加为所属区间的买卖量。
+    pub fn update_with_flow(&mut self, m1_bar: SBar, flow: OrderFlow) -> Vec<(SBar, OrderFlow)> {
+        let mut finished = Vec::new();
+        let (start, end) = self
+            .calendar
+            .bucket(m1_bar.datetime, self.timeframe)
+            .expect("timeframe checked in new");
+
+        match self.current.as_mut() {
+            Some((bar, current_flow)) if bar.datetime == start => {
+                bar.high_price = bar.high_price.max(m1_bar.high_price);
+                bar.low_price = bar.low_price.min(m1_bar.low_price);
+                bar.close_price = m1_bar.close_price;
+                bar.volume += m1_bar.volume;
+                bar.turnover += m1_bar.turnover;
+                bar.open_interest = m1_bar.open_interest;
+                current_flow.buy_volume += flow.buy_volume;
+                current_flow.sell_volume += flow.sell_volume;
+            }
+            _ => {
+                finished.extend(self.flush_with_flow());
+                let bar = SBar {
+                    id: None,
+                    timeframe: self.timeframe,
+                    datetime: start,
+                    ..m1_bar.clone()
+                };
+                self.current = Some((bar, flow));
+            }
+        }
+
+        if m1_bar.datetime + chrono::Duration::minutes(1) >= end {
+            finished.extend(self.flush_with_flow());
+        }
+        finished
+    }
+
+    pub fn flush(&mut self) -> Option<SBar> {
+        self.flush_with_flow().map(|(bar, _)| bar)
+    }
+
+    pub fn flush_with_flow(&mut self) -> Option<(SBar, OrderFlow)> {
+        self.current.take()
+    }
+}
@@ -1,22 +1,38 @@
 //! 单周期管理器。
 //!
 //! 职责：
-//! - 在单 timeframe 内串联 `SBar -> CBar -> Swing -> Trend -> KeyZone -> SD`；
+//! - 在单 timeframe 内串联 `SBar -> CBar -> Swing -> Trend -> KeyZone -> SD`，并随 SBar 更新指标；
 //! - 维护每个 timeframe 的状态管理器与最新信号；
-//! - 输出 append 过程中的回溯信息（`BacktrackIds`）。
+//! - 输出 append 过程中的回溯信息（`BacktrackIds`）与结构变化事件。
 
-use crate::keyzone::{KeyZoneManager, KeyZoneSignal};
-use crate::sd::{SupplyDemand, SupplyDemandConfig, SupplyDemandResult};
+use serde::{Deserialize, Serialize};
+
+use crate::keyzone::{KeyZone, KeyZoneConfig, KeyZoneManager, KeyZoneSignal, KeyZoneState};
+use crate::sd::{SupplyDemand, SupplyDemandConfig, SupplyDemandResult, TimeframeStructure};
 use crate::swing::SwingManager;
 use crate::trend::TrendManager;
-use crate::constant::Timeframe;
+use crate::constant::{Direction, FractalType, IdStrategy, StructureMode, Timeframe};
 use crate::bar::SBar;
 use crate::cbar_manager::CBarManager;
+use crate::history::StructureHistory;
+use crate::id_generator::StructureIdGenerators;
+use crate::events::{BacktrackIds, EventPayload};
+use crate::indicator::{Indicator, IndicatorManager};
+use crate::validation::{validate_structures, validate_window, Violation};
 use crate::sbar_manager::SBarManager;
+use crate::utils::diff_rows_by_id;
 
+#[derive(Serialize, Deserialize)]
 pub(crate) struct TimeframeManager {
     pub(crate) timeframe: Timeframe,
+    pub(crate) structure_mode: StructureMode,
+    #[serde(skip, default = "default_ids")]
+    pub(crate) ids: StructureIdGenerators,
     pub(crate) sbar_manager: SBarManager,
+    /// 指标实例不序列化：内置指标以 `IndicatorSpec` 随 checkpoint 保存，
+    /// `load_checkpoint` 据此重建并按 SBar 历史补算；自定义指标需重新注册。
+    #[serde(skip)]
+    pub(crate) indicator_manager: IndicatorManager,
     pub(crate) cbar_manager: CBarManager,
     pub(crate) swing_manager: SwingManager,
     pub(crate) trend_manager: TrendManager,
@@ -24,75 +40,319 @@ pub(crate) struct TimeframeManager {
     pub(crate) latest_keyzone_signal: Option<KeyZoneSignal>,
     pub(crate) sd: SupplyDemand,
     pub(crate) latest_sd: Option<SupplyDemandResult>,
-}
-
-pub(crate) struct BacktrackIds {
-    pub(crate) cbar_backtrack_id: Option<u64>,
-    pub(crate) swing_backtrack_id: Option<u64>,
-    pub(crate) trend_backtrack_id: Option<u64>,
+    /// 开启后记录结构的全部版本。
+    pub(crate) history: Option<StructureHistory>,
 }
 
 impl TimeframeManager {
-    pub(crate) fn new(timeframe: Timeframe) -> Self {
+    pub(crate) fn new(
+        timeframe: Timeframe,
+        structure_mode: StructureMode,
+        ids: StructureIdGenerators,
+    ) -> Self {
         Self {
             timeframe,
-            sbar_manager: SBarManager::new(timeframe),
-            cbar_manager: CBarManager::new(timeframe),
-            swing_manager: SwingManager::new(),
-            trend_manager: TrendManager::new(),
-            keyzone_manager: KeyZoneManager::new(),
+            structure_mode,
+            sbar_manager: SBarManager::new(timeframe, ids.sbar.clone()),
+            indicator_manager: IndicatorManager::default(),
+            cbar_manager: CBarManager::new(timeframe, ids.cbar.clone()),
+            swing_manager: SwingManager::with_id_generator(ids.swing.clone()),
+            trend_manager: TrendManager::with_id_generator(ids.trend.clone()),
+            keyzone_manager: KeyZoneManager::new_with_generator(ids.keyzone.clone()),
+            ids,
             latest_keyzone_signal: None,
             sd: SupplyDemand::default(),
             latest_sd: None,
+            history: None,
         }
     }
 
-    pub(crate) fn append(&mut self, sbar: SBar) -> BacktrackIds {
+    /// 从下一根 SBar 起记录结构版本。
+    pub(crate) fn enable_history(&mut self) {
+        self.history.get_or_insert_with(StructureHistory::default);
+    }
+
+    /// checkpoint 恢复后接回 context 的 id 生成器，并重建不落盘的缓存。
+    pub(crate) fn attach(&mut self, ids: StructureIdGenerators) {
+        self.sbar_manager.set_id_generator(ids.sbar.clone());
+        self.sbar_manager.rebuild_dataframe();
+        self.cbar_manager.set_id_generator(ids.cbar.clone());
+        self.swing_manager.set_id_generator(ids.swing.clone());
+        self.trend_manager.set_id_generator(ids.trend.clone());
+        self.keyzone_manager.set_id_generator(ids.keyzone.clone());
+        self.ids = ids;
+    }
+
+    /// 第一阶段：追加 SBar 并更新指标与 CBar/Swing/Trend，返回入库后的 SBar 与各层回溯 id。
+    ///
+    /// 之后由 context 基于更新后的结构调用 `KeyZoneFactory`，再以 `finish_append` 完成本次 append。
+    pub(crate) fn append_structures(&mut self, sbar: SBar) -> (SBar, BacktrackIds) {
+        self.ids.set_data_time(sbar.datetime);
         let sbar = self.sbar_manager.append(sbar);
+        self.indicator_manager.sync(self.sbar_manager.rows());
         let _cbar = self.cbar_manager.on_sbar(&sbar);
         let cbar_backtrack_id = self.cbar_manager.backtrack_id();
-        let cbars = self.cbar_manager.all_rows();
-        let swing_backtrack_id = self
-            .swing_manager
-            .rebuild_from_cbars_with_backtrack(&cbars, cbar_backtrack_id);
-        let swings = self.swing_manager.all_rows();
-        let trend_backtrack_id = self
-            .trend_manager
-            .rebuild_from_swings_with_backtrack(&swings, swing_backtrack_id);
-
-        self.keyzone_manager.rebuild_from(
-            self.timeframe,
-            &self.swing_manager.last_n(20),
-            &self.trend_manager.last_n(20),
-            &self.sbar_manager.last_n(200),
-        );
+        let (swing_backtrack_id, trend_backtrack_id) = match self.structure_mode {
+            StructureMode::Batch => {
+                let swing_backtrack_id = self
+                    .swing_manager
+                    .rebuild_from_cbars_with_backtrack(self.cbar_manager.rows(), cbar_backtrack_id);
+                let trend_backtrack_id = self
+                    .trend_manager
+                    .rebuild_from_swings_with_backtrack(self.swing_manager.rows(), swing_backtrack_id);
+                (swing_backtrack_id, trend_backtrack_id)
+            }
+            StructureMode::Incremental => {
+                let swing_backtrack_id = self
+                    .swing_manager
+                    .update_from_cbars(self.cbar_manager.rows(), self.cbar_manager.backtrack_index());
+                let trend_backtrack_id = self.trend_manager.update_from_swings(
+                    self.swing_manager.rows(),
+                    self.swing_manager.backtrack_index(),
+                );
+                (swing_backtrack_id, trend_backtrack_id)
+            }
+        };
+        (
+            sbar,
+            BacktrackIds {
+                cbar_backtrack_id,
+                swing_backtrack_id,
+                trend_backtrack_id,
+            },
+        )
+    }
 
-        let recent = self.sbar_manager.last_n(2);
-        if let Some(last_bar) = recent.last() {
-            self.latest_keyzone_signal = self
-                .keyzone_manager
-                .evaluate_latest_signal(last_bar, recent.first().filter(|_| recent.len() > 1));
-        } else {
-            self.latest_keyzone_signal = None;
-        }
+    /// 第二阶段：推进关键区生命周期，并入 `built`（`KeyZoneFactory` 的产出），再评估信号与 SD；
+    /// `others` 为其他周期的结构方向，`events` 为 `Some` 时按层级顺序收集本次 append 的结构变化事件。
+    pub(crate) fn finish_append(
+        &mut self,
+        sbar: SBar,
+        built: Vec<KeyZone>,
+        others: &[TimeframeStructure],
+        events: Option<&mut Vec<EventPayload>>,
+    ) {
+        let keyzones_before = events
+            .as_ref()
+            .map(|_| self.keyzone_manager.rows().to_vec());
+        let sbars = self.sbar_manager.rows();
+        let first_index = sbars.len().saturating_sub(200);
+        self.keyzone_manager
+            .advance_window(&sbars[first_index..], first_index);
+        self.keyzone_manager.merge_built(built);
+
+        self.latest_keyzone_signal = self.keyzone_manager.latest_signal().cloned();
 
         let keyzone_bias = self
             .latest_keyzone_signal
             .as_ref()
             .map(|x| x.signed_strength())
             .unwrap_or(0.0);
-        self.latest_sd = Some(
-            self.sd
-                .evaluate_window_with_bias(&self.sbar_manager.last_n(50), keyzone_bias),
+        self.latest_sd = Some(self.sd.evaluate_anchored(
+            self.sbar_manager.rows(),
+            self.swing_manager.rows(),
+            self.trend_manager.rows(),
+            others,
+            keyzone_bias,
+        ));
+        if let (Some(history), Some(sbar_id)) = (self.history.as_mut(), sbar.id) {
+            history.record(
+                sbar_id,
+                &self.cbar_manager.changes(),
+                &self.swing_manager.changes(),
+                &self.trend_manager.changes(),
+            );
+        }
+        if let (Some(events), Some(keyzones_before)) = (events, keyzones_before) {
+            events.push(EventPayload::SBarCreated(sbar));
+            self.collect_structure_events(events, &keyzones_before);
+        }
+    }
+
+    /// 最新 trend 与 swing 的方向。
+    pub(crate) fn structure(&self) -> TimeframeStructure {
+        let direction_of = |x: Option<Direction>| x.unwrap_or(Direction::None);
+        TimeframeStructure {
+            timeframe: self.timeframe,
+            trend_direction: direction_of(self.trend_manager.rows().last().map(|x| x.direction)),
+            swing_direction: direction_of(self.swing_manager.rows().last().map(|x| x.direction)),
+        }
+    }
+
+    /// 校验全部结构。
+    pub(crate) fn validate(&self) -> Vec<Violation> {
+        validate_structures(
+            self.sbar_manager.rows(),
+            self.cbar_manager.rows(),
+            self.swing_manager.rows(),
+            self.trend_manager.rows(),
+        )
+    }
+
+    /// 只校验本次 append 回溯影响到的尾部结构，以及它们引用的 swing 与 CBar。
+    pub(crate) fn validate_recent(&self, backtrack: BacktrackIds) -> Vec<Violation> {
+        let trends = self.trend_manager.rows();
+        let trends = &trends[tail_from(trends, backtrack.trend_backtrack_id, |x| x.id)..];
+
+        let swings = self.swing_manager.rows();
+        let mut start = tail_from(swings, backtrack.swing_backtrack_id, |x| x.id);
+        if let Some(first) = trends.first() {
+            start = start.min(swings.partition_point(|x| x.id.unwrap_or_default() < first.swing_start_id));
+        }
+        let swings = &swings[start..];
+
+        // 变化的 CBar 会改变前一根的分型，再多取一根作为左邻
+        let cbars = self.cbar_manager.rows();
+        let mut start = tail_from(cbars, backtrack.cbar_backtrack_id, |x| x.id).saturating_sub(2);
+        if let Some(first) = swings.first() {
+            start = start.min(cbars.partition_point(|x| x.id.unwrap_or_default() < first.cbar_start_id));
+        }
+        let cbars = &cbars[start..];
+
+        let sbars = self.sbar_manager.rows();
+        let start = cbars
+            .first()
+            .map_or(sbars.len(), |x| sbars.partition_point(|y| y.id.unwrap_or_default() < x.sbar_start_id));
+        validate_window(&sbars[start..], cbars, swings, trends)
+    }
+
+    fn collect_structure_events(
+        &self,
+        events: &mut Vec<EventPayload>,
+        keyzones_before: &[KeyZone],
+    ) {
+        let cbars = self.cbar_manager.changes();
+        for cbar in &cbars.removed {
+            if cbar.fractal_type != FractalType::None {
+                events.push(EventPayload::FractalInvalidated((*cbar).clone()));
+            }
+        }
+        for cbar in &cbars.added {
+            // 合并时被移除的 CBar 均已并入新 CBar
+            if cbars.removed.is_empty() {
+                events.push(EventPayload::CBarCreated((*cbar).clone()));
+            } else {
+                events.push(EventPayload::CBarMerged {
+                    cbar: (*cbar).clone(),
+                    merged: cbars.removed.iter().map(|x| (*x).clone()).collect(),
+                });
+            }
+        }
+        for (previous, cbar) in &cbars.updated {
+            if previous.fractal_type != FractalType::None {
+                events.push(EventPayload::FractalInvalidated((*previous).clone()));
+            }
+            if cbar.fractal_type != FractalType::None
+                && let Some(fractal) = cbar.id.and_then(|id| self.cbar_manager.fractal_at_id(id))
+            {
+                events.push(EventPayload::FractalConfirmed(fractal));
+            }
+        }
+
+        let swings = self.swing_manager.changes();
+        events.extend(
+            swings
+                .removed
+                .iter()
+                .map(|x| EventPayload::SwingRemoved((*x).clone())),
+        );
+        events.extend(
+            swings
+                .added
+                .iter()
+                .map(|x| EventPayload::SwingCreated((*x).clone())),
+        );
+        for (previous, swing) in swings.updated {
+            events.push(EventPayload::SwingUpdated(swing.clone()));
+            if previous.state != swing.state {
+                events.push(EventPayload::SwingStateChanged {
+                    swing: swing.clone(),
+                    previous: previous.state,
+                });
+            }
+        }
+
+        let trends = self.trend_manager.changes();
+        events.extend(
+            trends
+                .removed
+                .iter()
+                .map(|x| EventPayload::TrendRemoved((*x).clone())),
         );
-        BacktrackIds {
-            cbar_backtrack_id,
-            swing_backtrack_id,
-            trend_backtrack_id,
+        for trend in trends.added {
+            events.push(EventPayload::TrendCreated(trend.clone()));
+            if trend.is_completed {
+                events.push(EventPayload::TrendCompleted(trend.clone()));
+            }
         }
+        for (previous, trend) in trends.updated {
+            events.push(EventPayload::TrendUpdated(trend.clone()));
+            if trend.is_completed && !previous.is_completed {
+                events.push(EventPayload::TrendCompleted(trend.clone()));
+            }
+        }
+
+        let keyzones = diff_rows_by_id(
+            keyzones_before,
+            self.keyzone_manager.rows(),
+            |x| x.id,
+            |a, b| a.reactions.len() == b.reactions.len(),
+        );
+        for zone in keyzones.removed {
+            let archived = zone
+                .id
+                .and_then(|id| self.keyzone_manager.archived_by_id(id))
+                .unwrap_or(zone);
+            push_reaction_events(events, zone, archived);
+            events.push(EventPayload::KeyZoneInvalidated(archived.clone()));
+        }
+        events.extend(
+            keyzones
+                .added
+                .iter()
+                .map(|x| EventPayload::KeyZoneCreated((*x).clone())),
+        );
+        for (previous, zone) in keyzones.updated {
+            push_reaction_events(events, previous, zone);
+        }
+    }
+
+    /// 注册后立即按已有 SBar 补算历史。
+    pub(crate) fn register_indicator(&mut self, indicator: Box<dyn Indicator>) {
+        self.indicator_manager.register(indicator);
+        self.indicator_manager.backfill(self.sbar_manager.rows());
+    }
+
+    pub(crate) fn set_keyzone_config(&mut self, config: KeyZoneConfig) {
+        self.keyzone_manager.set_config(config);
     }
 
     pub(crate) fn set_sd_config(&mut self, config: SupplyDemandConfig) {
         self.sd = SupplyDemand::with_config(config);
     }
 }
+
+/// 关键区本次新增的互动状态转换，逐条派发 Approach/Touch/Accept/Reject 事件。
+fn push_reaction_events(events: &mut Vec<EventPayload>, previous: &KeyZone, zone: &KeyZone) {
+    for reaction in zone.reactions.iter().skip(previous.reactions.len()) {
+        let zone = zone.clone();
+        events.push(match reaction.state {
+            KeyZoneState::Approach => EventPayload::KeyZoneApproached(zone),
+            KeyZoneState::Touch => EventPayload::KeyZoneTouched(zone),
+            KeyZoneState::Accept => EventPayload::KeyZoneAccepted(zone),
+            KeyZoneState::Reject => EventPayload::KeyZoneRejected(zone),
+        });
+    }
+}
+
+fn default_ids() -> StructureIdGenerators {
+    StructureIdGenerators::new(IdStrategy::Snowflake)
+}
+
+/// 按单调递增的 id 定位回溯起点的下标；无回溯时只取最后一行。
+fn tail_from<T>(rows: &[T], backtrack_id: Option<u64>, id_of: impl Fn(&T) -> Option<u64>) -> usize {
+    match backtrack_id {
+        Some(id) => rows.partition_point(|x| id_of(x).unwrap_or_default() < id),
+        None => rows.len().saturating_sub(1),
+    }
+}
@@ -1,13 +1,17 @@
+use std::sync::Arc;
+
 use chrono::{DateTime, Utc};
 use polars::df;
 use polars::prelude::DataFrame;
+use serde::{Deserialize, Serialize};
 
 use crate::constant::Direction;
 use crate::swing::Swing;
-use crate::utils::{approx_eq_f64, first_changed_id};
+use crate::journal::JournaledRows;
+use crate::utils::{RowChanges, approx_eq_f64, diff_rows_by_id, first_changed_id};
 use crate::IdGenerator;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trend {
     pub id: Option<u64>,
     pub direction: Direction,
@@ -32,7 +36,7 @@ enum SfFractalType {
     None,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct TrendSfSeq {
     trend: Option<Trend>,
     sfs: Vec<Swing>,
@@ -84,10 +88,7 @@ impl TrendSfSeq {
         }
 
         let mut tmp = swing.clone();
-        loop {
-            let Some(prev) = self.sfs.last().cloned() else {
-                break;
-            };
+        while let Some(prev) = self.sfs.last().cloned() {
             let inclusive = (prev.high_price >= tmp.high_price && prev.low_price <= tmp.low_price)
                 || (prev.high_price <= tmp.high_price && prev.low_price >= tmp.low_price);
             if !inclusive {
@@ -146,13 +147,25 @@ impl TrendSfSeq {
     }
 }
 
+/// 逐步推进时跨步携带的 (active, pullback) 序列状态。
+type TrendSideState = (TrendSfSeq, TrendSfSeq);
+
+#[derive(Serialize, Deserialize)]
 pub struct TrendManager {
-    rows: Vec<Trend>,
-    id_generator: &'static IdGenerator,
-    df_cache: DataFrame,
+    rows: JournaledRows<Trend, TrendSideState>,
+    #[serde(skip, default = "crate::id_generator::trend_id_generator")]
+    id_generator: Arc<IdGenerator>,
     backtrack_id: Option<u64>,
     active_sfs: TrendSfSeq,
     pullback_sfs: TrendSfSeq,
+    /// 已确认 swing 序列及其在上游 swing 行中的下标，增量更新时据此截断重放。
+    completed: Vec<Swing>,
+    completed_source: Vec<usize>,
+    /// 最近一次更新前自 `replaced_start` 起的行。
+    #[serde(skip)]
+    replaced: Vec<Trend>,
+    #[serde(skip)]
+    replaced_start: usize,
 }
 
 impl Default for TrendManager {
@@ -163,18 +176,29 @@ impl Default for TrendManager {
 
 impl TrendManager {
     pub fn new() -> Self {
+        Self::with_id_generator(crate::id_generator::trend_id_generator())
+    }
+
+    pub fn with_id_generator(id_generator: Arc<IdGenerator>) -> Self {
         Self {
-            rows: Vec::new(),
-            id_generator: crate::id_generator::trend_id_generator(),
-            df_cache: DataFrame::default(),
+            rows: JournaledRows::new(),
+            id_generator,
             backtrack_id: None,
             active_sfs: TrendSfSeq::default(),
             pullback_sfs: TrendSfSeq::default(),
+            completed: Vec::new(),
+            completed_source: Vec::new(),
+            replaced: Vec::new(),
+            replaced_start: 0,
         }
     }
 
+    pub(crate) fn set_id_generator(&mut self, id_generator: Arc<IdGenerator>) {
+        self.id_generator = id_generator;
+    }
+
     pub fn on_swing_changed(&mut self, swing: &Swing) -> Option<Trend> {
-        self.rebuild_from_swings(&[swing.clone()]);
+        self.rebuild_from_swings(std::slice::from_ref(swing));
         self.rows.last().cloned()
     }
 
@@ -187,71 +211,22 @@ impl TrendManager {
         swings: &[Swing],
         swing_backtrack_id: Option<u64>,
     ) -> Option<u64> {
-        let previous_rows = self.rows.clone();
+        let previous_rows = self.rows.to_vec();
         self.rows.clear();
         self.active_sfs.clear();
         self.pullback_sfs.clear();
+        self.completed.clear();
+        self.completed_source.clear();
+        self.extend_completed(swings, 0);
+        self.replay_from(0);
 
-        let completed: Vec<Swing> = swings
-            .iter()
-            .filter(|x| x.state == crate::swing::SwingState::Confirmed)
-            .cloned()
-            .collect();
-        if completed.len() < 3 {
-            self.rebuild_cache();
-            self.backtrack_id = if swing_backtrack_id.is_some() {
+        self.backtrack_id = if self.rows.is_empty() {
+            if swing_backtrack_id.is_some() {
                 previous_rows.first().and_then(|x| x.id)
             } else {
                 None
-            };
-            return self.backtrack_id;
-        }
-
-        let mut seed_end_index: Option<usize> = None;
-        for i in 2..completed.len() {
-            let a = &completed[i - 2];
-            let b = &completed[i - 1];
-            let c = &completed[i];
-            if can_seed_trend(a, b, c) {
-                let trend = Trend {
-                    id: Some(self.next_id()),
-                    direction: a.direction,
-                    swing_start_id: a.id.unwrap_or_default(),
-                    swing_end_id: c.id.unwrap_or_default(),
-                    sbar_start_id: a.sbar_start_id,
-                    sbar_end_id: c.sbar_end_id,
-                    high_price: a.high_price.max(c.high_price),
-                    low_price: a.low_price.min(c.low_price),
-                    span: a.span + b.span + c.span,
-                    volume: a.volume + b.volume + c.volume,
-                    start_oi: a.start_oi,
-                    end_oi: c.end_oi,
-                    is_completed: false,
-                    created_at: Utc::now(),
-                };
-                self.rows.push(trend.clone());
-                self.active_sfs.trend = Some(trend);
-                self.active_sfs.agg_swing(b);
-                seed_end_index = Some(i);
-                break;
             }
-        }
-
-        let Some(seed_end_index) = seed_end_index else {
-            self.rebuild_cache();
-            self.backtrack_id = if swing_backtrack_id.is_some() {
-                previous_rows.first().and_then(|x| x.id)
-            } else {
-                None
-            };
-            return self.backtrack_id;
-        };
-
-        for swing in completed.iter().skip(seed_end_index + 1) {
-            self.build_trend_step(swing, &completed);
-        }
-
-        self.backtrack_id = if swing_backtrack_id.is_some() {
+        } else if swing_backtrack_id.is_some() {
             first_changed_id(
                 &previous_rows,
                 &self.rows,
@@ -269,10 +244,115 @@ impl TrendManager {
                 false,
             )
         };
-        self.rebuild_cache();
+        self.replaced = previous_rows;
+        self.replaced_start = 0;
         self.backtrack_id
     }
 
+    /// 增量更新：`swing_backtrack_index` 为第一个变化的 swing 下标，`None` 表示 swing 无变化。
+    ///
+    /// 第 i 个已确认 swing 的推进步会查看第 i + 1 个（`next_swing_by_id`），
+    /// 因此从第一个变化的已确认 swing 的前一步回滚重放，结果与 `rebuild_from_swings` 一致。
+    /// 返回第一个变化的 trend id（含新追加）。
+    pub fn update_from_swings(
+        &mut self,
+        swings: &[Swing],
+        swing_backtrack_index: Option<usize>,
+    ) -> Option<u64> {
+        let Some(swing_index) = swing_backtrack_index else {
+            self.backtrack_id = None;
+            self.replaced.clear();
+            self.replaced_start = self.rows.len();
+            return None;
+        };
+
+        let kept = self.completed_source.partition_point(|&idx| idx < swing_index);
+        let step = kept.saturating_sub(1);
+        let Some(rollback) = self.rows.rollback_to(step) else {
+            let previous_rows = self.rows.to_vec();
+            self.rebuild_from_swings_with_backtrack(swings, None);
+            self.backtrack_id = first_changed_id(
+                &previous_rows,
+                &self.rows,
+                |x| x.id,
+                trend_eq_wo_created_at,
+                true,
+            );
+            return self.backtrack_id;
+        };
+
+        (self.active_sfs, self.pullback_sfs) = rollback.side;
+        self.completed.truncate(kept);
+        self.completed_source.truncate(kept);
+        self.extend_completed(swings, swing_index);
+        self.replay_from(step);
+
+        self.backtrack_id = first_changed_id(
+            &rollback.replaced,
+            &self.rows[rollback.start..],
+            |x| x.id,
+            trend_eq_wo_created_at,
+            true,
+        );
+        self.replaced = rollback.replaced;
+        self.replaced_start = rollback.start;
+        self.backtrack_id
+    }
+
+    fn extend_completed(&mut self, swings: &[Swing], from: usize) {
+        for (idx, swing) in swings.iter().enumerate().skip(from) {
+            if swing.state == crate::swing::SwingState::Confirmed {
+                self.completed.push(swing.clone());
+                self.completed_source.push(idx);
+            }
+        }
+    }
+
+    fn replay_from(&mut self, step: usize) {
+        let completed = std::mem::take(&mut self.completed);
+        for index in step..completed.len() {
+            self.rows
+                .begin_step(index, (self.active_sfs.clone(), self.pullback_sfs.clone()));
+            self.trend_step(index, &completed);
+        }
+        self.completed = completed;
+    }
+
+    fn trend_step(&mut self, index: usize, completed: &[Swing]) {
+        if !self.rows.is_empty() {
+            self.build_trend_step(&completed[index], completed);
+            return;
+        }
+        if index < 2 {
+            return;
+        }
+
+        let a = &completed[index - 2];
+        let b = &completed[index - 1];
+        let c = &completed[index];
+        if can_seed_trend(a, b, c) {
+            let trend = Trend {
+                id: Some(self.next_id()),
+                direction: a.direction,
+                swing_start_id: a.id.unwrap_or_default(),
+                swing_end_id: c.id.unwrap_or_default(),
+                sbar_start_id: a.sbar_start_id,
+                sbar_end_id: c.sbar_end_id,
+                high_price: a.high_price.max(c.high_price),
+                low_price: a.low_price.min(c.low_price),
+                span: a.span + b.span + c.span,
+                volume: a.volume + b.volume + c.volume,
+                start_oi: a.start_oi,
+                end_oi: c.end_oi,
+                is_completed: false,
+                created_at: Utc::now(),
+            };
+            self.rows.push(trend.clone());
+            self.active_sfs.trend = Some(trend);
+            self.active_sfs.agg_swing(b);
+        }
+    }
+
     fn build_trend_step(&mut self, swing: &Swing, swings: &[Swing]) {
         if self.pullback_sfs.trend.is_some() {
             self.build_pullback_step(swing, swings);
@@ -540,7 +620,7 @@ impl TrendManager {
             return;
         }
         let idx = self.rows.len() - 1;
-        self.rows[idx] = trend;
+        self.rows.set(idx, trend);
     }
 
     fn confirm_trend(&mut self, mut trend: Trend, swings: &[Swing]) -> Trend {
@@ -557,33 +637,25 @@ impl TrendManager {
             trend.swing_end_id,
             trend.direction,
             start_limit_kind,
-        ) {
-            if start_swing.id.unwrap_or_default() != trend.swing_start_id {
-                trend.swing_start_id = start_swing.id.unwrap_or_default();
-                trend.sbar_start_id = start_swing.sbar_start_id;
-                trend.high_price = trend.high_price.max(start_swing.high_price);
-                trend.low_price = trend.low_price.min(start_swing.low_price);
-
-                if let Some(prev_trend_index) = self.rows.len().checked_sub(2) {
-                    if self.rows[prev_trend_index].is_completed {
-                        if let Some(prev_end_swing) = prev_swing_by_id(swings, trend.swing_start_id)
-                        {
-                            if self.rows[prev_trend_index].swing_end_id
-                                != prev_end_swing.id.unwrap_or_default()
-                            {
-                                self.rows[prev_trend_index].swing_end_id =
-                                    prev_end_swing.id.unwrap_or_default();
-                                self.rows[prev_trend_index].sbar_end_id = prev_end_swing.sbar_end_id;
-                                self.rows[prev_trend_index].high_price = self.rows[prev_trend_index]
-                                    .high_price
-                                    .max(prev_end_swing.high_price);
-                                self.rows[prev_trend_index].low_price = self.rows[prev_trend_index]
-                                    .low_price
-                                    .min(prev_end_swing.low_price);
-                            }
-                        }
-                    }
-                }
+        ) && start_swing.id.unwrap_or_default() != trend.swing_start_id
+        {
+            trend.swing_start_id = start_swing.id.unwrap_or_default();
+            trend.sbar_start_id = start_swing.sbar_start_id;
+            trend.high_price = trend.high_price.max(start_swing.high_price);
+            trend.low_price = trend.low_price.min(start_swing.low_price);
+
+            if let Some(prev_trend_index) = self.rows.len().checked_sub(2)
+                && self.rows[prev_trend_index].is_completed
+                && let Some(prev_end_swing) = prev_swing_by_id(swings, trend.swing_start_id)
+                && self.rows[prev_trend_index].swing_end_id
+                    != prev_end_swing.id.unwrap_or_default()
+            {
+                self.rows.update(prev_trend_index, |prev| {
+                    prev.swing_end_id = prev_end_swing.id.unwrap_or_default();
+                    prev.sbar_end_id = prev_end_swing.sbar_end_id;
+                    prev.high_price = prev.high_price.max(prev_end_swing.high_price);
+                    prev.low_price = prev.low_price.min(prev_end_swing.low_price);
+                });
             }
         }
 
@@ -636,19 +708,29 @@ impl TrendManager {
             .collect::<Vec<_>>()
     }
 
-    pub fn dataframe(&self) -> DataFrame {
-        self.df_cache.clone()
+    pub fn all_rows(&self) -> Vec<Trend> {
+        self.rows.to_vec()
     }
 
-    pub fn all_rows(&self) -> Vec<Trend> {
-        self.rows.clone()
+    pub fn rows(&self) -> &[Trend] {
+        &self.rows
     }
 
     pub fn backtrack_id(&self) -> Option<u64> {
         self.backtrack_id
     }
 
-    fn rebuild_cache(&mut self) {
+    /// 最近一次更新的行变化。
+    pub(crate) fn changes(&self) -> RowChanges<'_, Trend> {
+        diff_rows_by_id(
+            &self.replaced,
+            &self.rows[self.replaced_start.min(self.rows.len())..],
+            |x| x.id,
+            trend_eq_wo_created_at,
+        )
+    }
+
+    pub fn dataframe(&self) -> DataFrame {
         let ids: Vec<u64> = self.rows.iter().map(|x| x.id.unwrap_or_default()).collect();
         let directions: Vec<i8> = self
             .rows
@@ -677,7 +759,7 @@ impl TrendManager {
             .map(|x| x.created_at.timestamp_millis())
             .collect();
 
-        self.df_cache = df!(
+        df!(
             "id" => ids,
             "direction" => directions,
             "swing_start_id" => swing_start_id,
@@ -693,7 +775,7 @@ impl TrendManager {
             "is_completed" => is_completed,
             "created_at" => created_at
         )
-        .expect("failed to rebuild trend dataframe cache");
+        .expect("failed to build trend dataframe")
     }
 }
 
@@ -721,35 +803,25 @@ enum LimitKind {
     Min,
 }
 
+/// swing id 单调递增，按 id 查找使用二分。
+fn swing_index_by_id(swings: &[Swing], curr_id: u64) -> Option<usize> {
+    let index = swings.partition_point(|x| x.id.unwrap_or_default() < curr_id);
+    (swings.get(index)?.id.unwrap_or_default() == curr_id).then_some(index)
+}
+
 fn next_swing_by_id(swings: &[Swing], curr_id: u64) -> Option<&Swing> {
-    for (idx, swing) in swings.iter().enumerate() {
-        if swing.id.unwrap_or_default() == curr_id {
-            return swings.get(idx + 1);
-        }
-    }
-    None
+    swings.get(swing_index_by_id(swings, curr_id)? + 1)
 }
 
 fn prev_swing_by_id(swings: &[Swing], curr_id: u64) -> Option<&Swing> {
-    for (idx, swing) in swings.iter().enumerate() {
-        if swing.id.unwrap_or_default() == curr_id {
-            if idx == 0 {
-                return None;
-            }
-            return swings.get(idx - 1);
-        }
-    }
-    None
+    let index = swing_index_by_id(swings, curr_id)?;
+    index.checked_sub(1).and_then(|idx| swings.get(idx))
 }
 
-fn swings_in_range(swings: &[Swing], start_id: u64, end_id: u64) -> Vec<&Swing> {
-    swings
-        .iter()
-        .filter(|x| {
-            let id = x.id.unwrap_or_default();
-            start_id <= id && id <= end_id
-        })
-        .collect()
+fn swings_in_range(swings: &[Swing], start_id: u64, end_id: u64) -> &[Swing] {
+    let from = swings.partition_point(|x| x.id.unwrap_or_default() < start_id);
+    let to = swings.partition_point(|x| x.id.unwrap_or_default() <= end_id);
+    &swings[from..to.max(from)]
 }
 
 fn find_limit_swing(
@@ -760,7 +832,7 @@ fn find_limit_swing(
     limit_kind: LimitKind,
 ) -> Option<&Swing> {
     let mut candidates = swings_in_range(swings, start_id, end_id)
-        .into_iter()
+        .iter()
         .filter(|x| x.direction == direction)
         .collect::<Vec<_>>();
     if candidates.is_empty() {
@@ -1,3 +1,5 @@
+use std::collections::{HashMap, HashSet};
+
 pub(crate) fn approx_eq_f64(a: f64, b: f64) -> bool {
     (a - b).abs() <= f64::EPSILON
 }
@@ -12,21 +14,100 @@ pub(crate) fn first_changed_id<T, FId, FEq>(
 where
     FId: Fn(&T) -> Option<u64>,
     FEq: Fn(&T, &T) -> bool,
+{
+    let idx = first_changed_index(previous, current, equals, append_as_change)?;
+    previous
+        .get(idx)
+        .and_then(&id_of)
+        .or_else(|| current.get(idx).and_then(&id_of))
+}
+
+/// 返回 `previous` 与 `current` 第一个不一致的位置；
+/// 仅追加时是否视为变化由 `append_as_change` 决定。
+pub(crate) fn first_changed_index<T, FEq>(
+    previous: &[T],
+    current: &[T],
+    equals: FEq,
+    append_as_change: bool,
+) -> Option<usize>
+where
+    FEq: Fn(&T, &T) -> bool,
 {
     let min_len = previous.len().min(current.len());
     for idx in 0..min_len {
         if !equals(&previous[idx], &current[idx]) {
-            return id_of(&previous[idx]).or_else(|| id_of(&current[idx]));
+            return Some(idx);
         }
     }
 
     if previous.len() > current.len() {
-        return previous.get(min_len).and_then(id_of);
+        return Some(min_len);
     }
 
     if append_as_change && current.len() > previous.len() {
-        return current.get(min_len).and_then(id_of);
+        return Some(min_len);
     }
 
     None
 }
+
+/// 同一起点之后变化前后的行，按 id 对齐。
+pub(crate) struct RowChanges<'a, T> {
+    pub(crate) removed: Vec<&'a T>,
+    pub(crate) added: Vec<&'a T>,
+    pub(crate) updated: Vec<(&'a T, &'a T)>,
+}
+
+/// id 消失记为 removed，新出现记为 added，同 id 但 `equals` 不成立记为 updated。
+pub(crate) fn diff_rows_by_id<'a, T, FId, FEq>(
+    previous: &'a [T],
+    current: &'a [T],
+    id_of: FId,
+    equals: FEq,
+) -> RowChanges<'a, T>
+where
+    FId: Fn(&T) -> Option<u64>,
+    FEq: Fn(&T, &T) -> bool,
+{
+    let previous_by_id = previous
+        .iter()
+        .filter_map(|x| id_of(x).map(|id| (id, x)))
+        .collect::<HashMap<_, _>>();
+    let current_ids = current.iter().filter_map(&id_of).collect::<HashSet<_>>();
+
+    let removed = previous
+        .iter()
+        .filter(|x| id_of(x).is_none_or(|id| !current_ids.contains(&id)))
+        .collect();
+    let mut added = Vec::new();
+    let mut updated = Vec::new();
+    for row in current {
+        match id_of(row).and_then(|id| previous_by_id.get(&id)) {
+            Some(old) if !equals(old, row) => updated.push((*old, row)),
+            Some(_) => {}
+            None => added.push(row),
+        }
+    }
+    RowChanges {
+        removed,
+        added,
+        updated,
+    }
+}
+
+/// xorshift64，同一 seed 的序列可复现（用于参数搜索与合成行情）。
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    /// `[0, 1)` 均匀分布。
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
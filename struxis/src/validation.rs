@@ -0,0 +1,364 @@
+//! 结构不变量校验。
+//!
+//! 按 `docs/struxis-core-task-roadmap.md` 各层的验收要点检查结构序列，返回结构化的违例：
+//! - CBar：区间首尾相接且覆盖全部 SBar、相邻不包含、分型满足三根规则；
+//! - Swing：已确认的 swing 有方向，且起止于相反分型；
+//! - Trend：区间内有同向 swing，价格与 SBar 边界包住其 swing。
+//!
+//! `validate_structures` 用于事后整体校验；`ContextConfig::validate_on_append` 开启后，
+//! context 在每次 append 后只校验本次回溯影响的尾部结构（见 `validate_window`）。
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::bar::{CBar, Fractal, SBar};
+use crate::constant::{Direction, FractalType, Timeframe};
+use crate::swing::{Swing, SwingState};
+use crate::trend::Trend;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InvariantRule {
+    /// CBar 的 SBar 区间首尾相接，覆盖全部 SBar，无缺口、无重叠。
+    CBarCoverage,
+    /// CBar 满足 `high >= low` 且 `sbar_start_id <= sbar_end_id`。
+    CBarRange,
+    /// 相邻 CBar 不互相包含。
+    CBarInclusive,
+    /// 分型与相邻三根 CBar 的 `Fractal::verify` 一致，首尾 CBar 无分型。
+    FractalRule,
+    /// swing 满足 `high >= low`，cbar/sbar 起止有序。
+    SwingRange,
+    /// 已确认的 swing 方向为 `Up` 或 `Down`。
+    SwingDirectional,
+    /// `Up` 起于底分型止于顶分型，`Down` 相反。
+    SwingFractalEnds,
+    /// trend 满足 `high >= low`，swing/sbar 起止有序。
+    TrendRange,
+    /// 已完成的 trend 区间内至少有一个同向 swing。
+    TrendDirection,
+    /// trend 的价格与 SBar 边界包住区间内的 swing。
+    TrendEnvelope,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Violation {
+    pub rule: InvariantRule,
+    /// 违例结构的 id（CBar/Swing/Trend，视规则而定）。
+    pub structure_id: Option<u64>,
+    pub message: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} id={:?}: {}",
+            self.rule, self.structure_id, self.message
+        )
+    }
+}
+
+/// 某一 timeframe 的整体校验结果。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub timeframe: Timeframe,
+    /// 校验时最新的 SBar id。
+    pub sbar_id: Option<u64>,
+    pub sbars: usize,
+    pub cbars: usize,
+    pub swings: usize,
+    pub trends: usize,
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    pub fn count(&self, rule: InvariantRule) -> usize {
+        self.violations.iter().filter(|x| x.rule == rule).count()
+    }
+}
+
+/// 校验完整的结构序列（从第一根 SBar 起）。
+pub fn validate_structures(
+    sbars: &[SBar],
+    cbars: &[CBar],
+    swings: &[Swing],
+    trends: &[Trend],
+) -> Vec<Violation> {
+    let mut out = Vec::new();
+    check_coverage(sbars, cbars, true, &mut out);
+    check_cbars(cbars, true, &mut out);
+    check_swings(cbars, swings, &mut out);
+    check_trends(swings, trends, &mut out);
+    out
+}
+
+/// 只校验 CBar 序列本身（区间、包含、分型），用于没有 SBar 的导出数据。
+pub fn validate_cbars(cbars: &[CBar]) -> Vec<Violation> {
+    let mut out = Vec::new();
+    check_cbars(cbars, true, &mut out);
+    out
+}
+
+/// 校验序列尾部的一段：不检查窗口首根 CBar 的分型与 SBar 覆盖起点，其余规则同 `validate_structures`。
+///
+/// 调用方需保证窗口内的 swing 引用的 CBar、trend 引用的 swing 都在窗口内。
+pub(crate) fn validate_window(
+    sbars: &[SBar],
+    cbars: &[CBar],
+    swings: &[Swing],
+    trends: &[Trend],
+) -> Vec<Violation> {
+    let mut out = Vec::new();
+    check_coverage(sbars, cbars, false, &mut out);
+    check_cbars(cbars, false, &mut out);
+    check_swings(cbars, swings, &mut out);
+    check_trends(swings, trends, &mut out);
+    out
+}
+
+/// 持续校验时累计的违例，同一规则与结构只记录第一次。
+#[derive(Debug, Default)]
+pub(crate) struct ViolationLog {
+    violations: Vec<Violation>,
+    seen: HashSet<(InvariantRule, Option<u64>)>,
+}
+
+impl ViolationLog {
+    /// 记录并返回此前未出现过的违例。
+    pub(crate) fn record(&mut self, found: Vec<Violation>) -> Vec<Violation> {
+        let fresh = found
+            .into_iter()
+            .filter(|x| self.seen.insert((x.rule, x.structure_id)))
+            .collect::<Vec<_>>();
+        self.violations.extend(fresh.iter().cloned());
+        fresh
+    }
+
+    pub(crate) fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+}
+
+fn push(out: &mut Vec<Violation>, rule: InvariantRule, id: Option<u64>, message: String) {
+    out.push(Violation {
+        rule,
+        structure_id: id,
+        message,
+    });
+}
+
+fn check_coverage(sbars: &[SBar], cbars: &[CBar], from_head: bool, out: &mut Vec<Violation>) {
+    let position = sbars
+        .iter()
+        .enumerate()
+        .filter_map(|(i, x)| x.id.map(|id| (id, i)))
+        .collect::<HashMap<_, _>>();
+    let mut expected = if from_head { Some(0) } else { None };
+    for cbar in cbars {
+        let (Some(&start), Some(&end)) = (
+            position.get(&cbar.sbar_start_id),
+            position.get(&cbar.sbar_end_id),
+        ) else {
+            push(
+                out,
+                InvariantRule::CBarCoverage,
+                cbar.id,
+                format!(
+                    "sbar {}..={} not found",
+                    cbar.sbar_start_id, cbar.sbar_end_id
+                ),
+            );
+            expected = None;
+            continue;
+        };
+        if let Some(expected) = expected
+            && start != expected
+        {
+            let kind = if start > expected { "gap" } else { "overlap" };
+            push(
+                out,
+                InvariantRule::CBarCoverage,
+                cbar.id,
+                format!("{kind} before sbar {}", cbar.sbar_start_id),
+            );
+        }
+        expected = Some(end + 1);
+    }
+    if let Some(expected) = expected
+        && !cbars.is_empty()
+        && expected != sbars.len()
+    {
+        push(
+            out,
+            InvariantRule::CBarCoverage,
+            cbars.last().and_then(|x| x.id),
+            format!("last cbar ends before sbar #{}", sbars.len()),
+        );
+    }
+}
+
+fn check_cbars(cbars: &[CBar], from_head: bool, out: &mut Vec<Violation>) {
+    for cbar in cbars {
+        if cbar.high_price < cbar.low_price || cbar.sbar_start_id > cbar.sbar_end_id {
+            push(
+                out,
+                InvariantRule::CBarRange,
+                cbar.id,
+                format!(
+                    "high={} low={} sbar {}..={}",
+                    cbar.high_price, cbar.low_price, cbar.sbar_start_id, cbar.sbar_end_id
+                ),
+            );
+        }
+    }
+    for pair in cbars.windows(2) {
+        if pair[0].is_inclusive(&pair[1]) {
+            push(
+                out,
+                InvariantRule::CBarInclusive,
+                pair[1].id,
+                format!("inclusive with previous cbar {:?}", pair[0].id),
+            );
+        }
+    }
+    for (i, cbar) in cbars.iter().enumerate() {
+        let expected = if i == 0 || i + 1 == cbars.len() {
+            if i == 0 && !from_head {
+                continue;
+            }
+            FractalType::None
+        } else {
+            Fractal::verify(&cbars[i - 1], cbar, &cbars[i + 1])
+        };
+        if cbar.fractal_type != expected {
+            push(
+                out,
+                InvariantRule::FractalRule,
+                cbar.id,
+                format!("got {:?}, expected {:?}", cbar.fractal_type, expected),
+            );
+        }
+    }
+}
+
+fn check_swings(cbars: &[CBar], swings: &[Swing], out: &mut Vec<Violation>) {
+    let fractal_of = |id: u64| {
+        let index = cbars.partition_point(|x| x.id.unwrap_or_default() < id);
+        cbars
+            .get(index)
+            .filter(|x| x.id == Some(id))
+            .map_or(FractalType::None, |x| x.fractal_type)
+    };
+    for swing in swings {
+        if swing.high_price < swing.low_price
+            || swing.cbar_start_id > swing.cbar_end_id
+            || swing.sbar_start_id > swing.sbar_end_id
+        {
+            push(
+                out,
+                InvariantRule::SwingRange,
+                swing.id,
+                format!(
+                    "high={} low={} cbar {}..={} sbar {}..={}",
+                    swing.high_price,
+                    swing.low_price,
+                    swing.cbar_start_id,
+                    swing.cbar_end_id,
+                    swing.sbar_start_id,
+                    swing.sbar_end_id
+                ),
+            );
+        }
+        if swing.state != SwingState::Confirmed {
+            continue;
+        }
+        let ends = match swing.direction {
+            Direction::Up => (FractalType::Bottom, FractalType::Top),
+            Direction::Down => (FractalType::Top, FractalType::Bottom),
+            _ => {
+                push(
+                    out,
+                    InvariantRule::SwingDirectional,
+                    swing.id,
+                    format!("confirmed swing is {:?}", swing.direction),
+                );
+                continue;
+            }
+        };
+        let actual = (
+            fractal_of(swing.cbar_start_id),
+            fractal_of(swing.cbar_end_id),
+        );
+        if actual != ends {
+            push(
+                out,
+                InvariantRule::SwingFractalEnds,
+                swing.id,
+                format!(
+                    "{:?} swing runs {:?} -> {:?}",
+                    swing.direction, actual.0, actual.1
+                ),
+            );
+        }
+    }
+}
+
+fn check_trends(swings: &[Swing], trends: &[Trend], out: &mut Vec<Violation>) {
+    for trend in trends {
+        if trend.high_price < trend.low_price
+            || trend.swing_start_id > trend.swing_end_id
+            || trend.sbar_start_id > trend.sbar_end_id
+        {
+            push(
+                out,
+                InvariantRule::TrendRange,
+                trend.id,
+                format!(
+                    "high={} low={} swing {}..={} sbar {}..={}",
+                    trend.high_price,
+                    trend.low_price,
+                    trend.swing_start_id,
+                    trend.swing_end_id,
+                    trend.sbar_start_id,
+                    trend.sbar_end_id
+                ),
+            );
+            continue;
+        }
+        let inner = swings
+            .iter()
+            .filter(|x| {
+                x.id.is_some_and(|id| trend.swing_start_id <= id && id <= trend.swing_end_id)
+            })
+            .collect::<Vec<_>>();
+        if trend.is_completed && !inner.iter().any(|x| x.direction == trend.direction) {
+            push(
+                out,
+                InvariantRule::TrendDirection,
+                trend.id,
+                format!("no {:?} swing in range", trend.direction),
+            );
+        }
+        for swing in inner {
+            if swing.high_price > trend.high_price
+                || swing.low_price < trend.low_price
+                || swing.sbar_start_id < trend.sbar_start_id
+                || swing.sbar_end_id > trend.sbar_end_id
+            {
+                push(
+                    out,
+                    InvariantRule::TrendEnvelope,
+                    trend.id,
+                    format!("swing {:?} exceeds trend bounds", swing.id),
+                );
+                break;
+            }
+        }
+    }
+}
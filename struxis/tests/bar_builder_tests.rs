@@ -0,0 +1,177 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use struxis::bar_builder::bar_builder;
+use struxis::{
+    BarBuilder, BarKind, DataReceiver, IdStrategy, MultiTimeframeContext, RandomWalk,
+    RangeBarBuilder, RenkoBarBuilder, SBar, SeriesSpec, TickCountBarBuilder, TickInput, Timeframe,
+    VolumeBarBuilder,
+};
+
+#[test]
+fn pseudo_timeframes_parse_and_round_trip() {
+    let renko = Timeframe::parse("Renko10").expect("renko");
+    assert_eq!(renko, Timeframe::Bars(BarKind::Renko, 10));
+    assert!(renko.is_pseudo());
+    assert!(!renko.is_intraday() && !renko.is_sub_minute());
+    for tf in [
+        Timeframe::Bars(BarKind::Range, 20),
+        Timeframe::Bars(BarKind::Volume, 500),
+        Timeframe::Bars(BarKind::TickCount, 100),
+    ] {
+        assert_eq!(Timeframe::parse(&tf.as_str()).expect("round trip"), tf);
+    }
+    for invalid in ["range", "range0", "tickx", "renko-1"] {
+        assert!(Timeframe::parse(invalid).is_err(), "{invalid}");
+    }
+    assert!(bar_builder(Timeframe::M1, 1.0).is_none());
+    assert!(bar_builder(Timeframe::Bars(BarKind::Range, 4), 0.0).is_none());
+}
+
+#[test]
+fn range_bars_never_exceed_size() {
+    let mut builder = RangeBarBuilder::new(4, 0.5);
+    let prices = [100.0, 100.5, 101.0, 100.5, 102.0, 101.5, 101.0, 100.0, 99.0];
+    let bars = feed(&mut builder, &prices);
+    // 价差恰好达到 2.0 时收盘；99.0 会把价差撑到 2.5，先收掉当前 bar 再开新 bar
+    assert_eq!(
+        ohlc(&bars),
+        vec![(100.0, 102.0, 100.0, 102.0), (101.5, 101.5, 100.0, 100.0)]
+    );
+    assert_eq!(builder.timeframe(), Timeframe::Bars(BarKind::Range, 4));
+    let rest = builder.flush().expect("forming bar");
+    assert_eq!(rest.open_price, 99.0);
+    assert!(builder.flush().is_none());
+}
+
+#[test]
+fn renko_needs_two_bricks_to_reverse() {
+    let mut builder = RenkoBarBuilder::new(2, 0.5);
+    let prices = [100.0, 100.9, 101.0, 103.2, 102.5, 102.0, 101.9, 99.0];
+    let bars = feed(&mut builder, &prices);
+    let bricks = bars
+        .iter()
+        .map(|x| (x.open_price, x.close_price))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        bricks,
+        vec![
+            (100.0, 101.0),
+            (101.0, 102.0),
+            (102.0, 103.0),
+            (102.0, 101.0),
+            (101.0, 100.0),
+            (100.0, 99.0),
+        ]
+    );
+    // 第一块砖带上开盘以来的全部成交量，同一笔 tick 的后续砖为 0
+    assert_eq!(bars[0].volume, 2.0);
+    assert_eq!(bars[1].volume, 1.0);
+    assert_eq!(bars[2].volume, 0.0);
+    assert!(builder.flush().is_none());
+}
+
+#[test]
+fn volume_and_tick_count_bars_close_on_threshold() {
+    let mut volume = VolumeBarBuilder::new(3);
+    let bars = feed(
+        &mut volume,
+        &[100.0, 101.0, 102.0, 101.0, 100.0, 99.0, 98.0],
+    );
+    assert_eq!(
+        bars.iter().map(|x| x.volume).collect::<Vec<_>>(),
+        vec![3.0, 3.0]
+    );
+    assert_eq!(bars[1].open_price, 100.0);
+
+    let mut count = TickCountBarBuilder::new(3);
+    let bars = feed(&mut count, &[100.0, 101.0, 102.0, 101.0, 100.0, 99.0, 98.0]);
+    assert_eq!(
+        ohlc(&bars),
+        vec![(100.0, 102.0, 100.0, 102.0), (101.0, 101.0, 99.0, 99.0)]
+    );
+    // 乱序 tick 被忽略
+    assert!(count.update(&tick(0, 120.0, 100.0)).is_empty());
+    assert_eq!(count.flush().map(|x| x.high_price), Some(98.0));
+}
+
+#[test]
+fn receiver_runs_structures_on_pseudo_timeframes() {
+    let renko = Timeframe::Bars(BarKind::Renko, 2);
+    let volume = Timeframe::Bars(BarKind::Volume, 40);
+    let mut receiver = DataReceiver::new(MultiTimeframeContext::with_id_strategy(
+        "SYN.SIM",
+        IdStrategy::Sequential,
+    ));
+    receiver.register_timeframe(Timeframe::M1);
+    receiver.register_timeframe(renko);
+    receiver.register_timeframe(volume);
+
+    let walk = RandomWalk::default()
+        .with_seed(5)
+        .generate(&SeriesSpec::default());
+    let mut total = 0.0;
+    for (i, bar) in walk.sbars.iter().enumerate() {
+        for (j, price) in [
+            bar.open_price,
+            bar.high_price,
+            bar.low_price,
+            bar.close_price,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            total += 1.0;
+            let mut input = tick((i * 4 + j) as i64 * 15, price, total);
+            input.symbol = "SYN".to_string();
+            input.exchange = "SIM".to_string();
+            receiver.ingest_tick(input);
+        }
+    }
+    receiver.flush_ticks();
+
+    let mtc = receiver.mtc();
+    assert!(mtc.count(renko) > 20);
+    assert!(mtc.get_cbar_window(renko, usize::MAX).len() > 10);
+    assert!(!mtc.get_swing_window(renko, usize::MAX).is_empty());
+    // 首笔 tick 只建立累计量基准，共 799 手：19 根满量 bar 加 flush 出的 39 手
+    let bars = mtc.get_sbar_window(volume, usize::MAX);
+    assert_eq!(bars.len(), 20);
+    assert!(
+        bars[..19]
+            .iter()
+            .all(|x| x.timeframe == volume && x.volume == 40.0)
+    );
+    assert_eq!(bars[19].volume, 39.0);
+    assert_eq!(mtc.count(Timeframe::M1), walk.sbars.len());
+}
+
+fn feed(builder: &mut impl BarBuilder, prices: &[f64]) -> Vec<SBar> {
+    prices
+        .iter()
+        .enumerate()
+        .flat_map(|(i, price)| builder.update(&tick(i as i64, *price, i as f64)))
+        .collect()
+}
+
+fn ohlc(bars: &[SBar]) -> Vec<(f64, f64, f64, f64)> {
+    bars.iter()
+        .map(|x| (x.open_price, x.high_price, x.low_price, x.close_price))
+        .collect()
+}
+
+fn tick(second: i64, price: f64, volume: f64) -> TickInput {
+    TickInput {
+        symbol: "I2601".to_string(),
+        exchange: "DCE".to_string(),
+        datetime: at(second),
+        last_price: price,
+        volume,
+        turnover: volume * price,
+        open_interest: 100.0,
+        ..TickInput::default()
+    }
+}
+
+fn at(second: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 2, 9, 0, 0).unwrap() + Duration::seconds(second)
+}
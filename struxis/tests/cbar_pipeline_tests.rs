@@ -1,88 +1,103 @@
 use chrono::{Timelike, Utc};
 
-use struxis::{Fractal, SBar, Timeframe, MultiTimeframeContext};
+use struxis::{
+    ContextConfig, Fractal, IdStrategy, SBar, StructureMode, Timeframe, MultiTimeframeContext,
+};
 
 #[test]
 fn cbar_ranges_cover_all_sbars_without_gap_or_overlap() {
-    let mut mtc = MultiTimeframeContext::new("I8888.XDCE");
-    mtc.register(Timeframe::M15);
-
-    for bar in sample_bars(120) {
-        mtc.append(Timeframe::M15, bar);
-    }
-
-    let cbar_rows = mtc.get_cbar_window(Timeframe::M15, usize::MAX);
-    let sbar_count = mtc.count(Timeframe::M15) as u64;
-
-    assert!(!cbar_rows.is_empty(), "cbar sequence should not be empty");
+    for mode in [StructureMode::Batch, StructureMode::Incremental] {
+        let mut mtc = MultiTimeframeContext::with_config(
+            "I8888.XDCE",
+            ContextConfig {
+                structure_mode: mode,
+                id_strategy: IdStrategy::Sequential,
+                ..ContextConfig::default()
+            },
+        );
+        mtc.register(Timeframe::M15);
+
+        for bar in sample_bars(120) {
+            mtc.append(Timeframe::M15, bar);
+        }
+
+        let cbar_rows = mtc.get_cbar_window(Timeframe::M15, usize::MAX);
+        let sbar_count = mtc.count(Timeframe::M15) as u64;
+
+        assert!(!cbar_rows.is_empty(), "cbar sequence should not be empty");
+
+        let mut expected_start = 1_u64;
+        for row in &cbar_rows {
+            assert_eq!(
+                row.sbar_start_id, expected_start,
+                "cbar range should start from expected sbar id"
+            );
+            assert!(
+                row.sbar_end_id >= row.sbar_start_id,
+                "cbar range end should be >= start"
+            );
+            expected_start = row.sbar_end_id + 1;
+        }
 
-    let mut expected_start = 1_u64;
-    for row in &cbar_rows {
         assert_eq!(
-            row.sbar_start_id, expected_start,
-            "cbar range should start from expected sbar id"
+            expected_start,
+            sbar_count + 1,
+            "cbar ranges should cover all sbars exactly once"
         );
-        assert!(
-            row.sbar_end_id >= row.sbar_start_id,
-            "cbar range end should be >= start"
-        );
-        expected_start = row.sbar_end_id + 1;
     }
-
-    assert_eq!(
-        expected_start,
-        sbar_count + 1,
-        "cbar ranges should cover all sbars exactly once"
-    );
 }
 
 #[test]
 fn cbar_adjacent_rows_are_non_inclusive() {
-    let mut mtc = MultiTimeframeContext::new("I8888.XDCE");
-    mtc.register(Timeframe::M15);
-
-    for bar in sample_bars(120) {
-        mtc.append(Timeframe::M15, bar);
-    }
-
-    let cbar_rows = mtc.get_cbar_window(Timeframe::M15, usize::MAX);
-    assert!(cbar_rows.len() > 10, "need enough cbar rows for inclusion check");
-
-    for pair in cbar_rows.windows(2) {
-        let left = &pair[0];
-        let right = &pair[1];
-        let inclusive = (left.high_price >= right.high_price && left.low_price <= right.low_price)
-            || (left.high_price <= right.high_price && left.low_price >= right.low_price);
-
-        assert!(
-            !inclusive,
-            "adjacent cbar rows should not be inclusive: left#{:?} right#{:?}",
-            left.id,
-            right.id
-        );
+    for mode in [StructureMode::Batch, StructureMode::Incremental] {
+        let mut mtc = MultiTimeframeContext::with_structure_mode("I8888.XDCE", mode);
+        mtc.register(Timeframe::M15);
+
+        for bar in sample_bars(120) {
+            mtc.append(Timeframe::M15, bar);
+        }
+
+        let cbar_rows = mtc.get_cbar_window(Timeframe::M15, usize::MAX);
+        assert!(cbar_rows.len() > 10, "need enough cbar rows for inclusion check");
+
+        for pair in cbar_rows.windows(2) {
+            let left = &pair[0];
+            let right = &pair[1];
+            let inclusive = (left.high_price >= right.high_price && left.low_price <= right.low_price)
+                || (left.high_price <= right.high_price && left.low_price >= right.low_price);
+
+            assert!(
+                !inclusive,
+                "adjacent cbar rows should not be inclusive: left#{:?} right#{:?}",
+                left.id,
+                right.id
+            );
+        }
     }
 }
 
 #[test]
 fn cbar_fractal_labels_match_three_bar_rule() {
-    let mut mtc = MultiTimeframeContext::new("I8888.XDCE");
-    mtc.register(Timeframe::M15);
-
-    for bar in sample_bars(120) {
-        mtc.append(Timeframe::M15, bar);
-    }
-
-    let cbar_rows = mtc.get_cbar_window(Timeframe::M15, usize::MAX);
-    assert!(cbar_rows.len() > 10, "need enough cbar rows for fractal check");
-
-    for i in 1..(cbar_rows.len() - 1) {
-        let expected = Fractal::verify(&cbar_rows[i - 1], &cbar_rows[i], &cbar_rows[i + 1]);
-        assert_eq!(
-            cbar_rows[i].fractal_type,
-            expected,
-            "fractal label mismatch at cbar id {:?}",
-            cbar_rows[i].id
-        );
+    for mode in [StructureMode::Batch, StructureMode::Incremental] {
+        let mut mtc = MultiTimeframeContext::with_structure_mode("I8888.XDCE", mode);
+        mtc.register(Timeframe::M15);
+
+        for bar in sample_bars(120) {
+            mtc.append(Timeframe::M15, bar);
+        }
+
+        let cbar_rows = mtc.get_cbar_window(Timeframe::M15, usize::MAX);
+        assert!(cbar_rows.len() > 10, "need enough cbar rows for fractal check");
+
+        for i in 1..(cbar_rows.len() - 1) {
+            let expected = Fractal::verify(&cbar_rows[i - 1], &cbar_rows[i], &cbar_rows[i + 1]);
+            assert_eq!(
+                cbar_rows[i].fractal_type,
+                expected,
+                "fractal label mismatch at cbar id {:?}",
+                cbar_rows[i].id
+            );
+        }
     }
 }
 
@@ -0,0 +1,248 @@
+mod common;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use struxis::indicator::{Ema, Indicator, IndicatorOutput};
+use struxis::sd::SupplyDemandConfigPatch;
+use struxis::{
+    ChannelKeyZoneBuilder, CheckpointOmission, ContextConfig, DataError, IdStrategy, KeyZoneOrigin,
+    MultiTimeframeContext, SBar, StructureMode, SupplyDemandProfileConfig, Timeframe,
+};
+
+#[test]
+fn restored_context_continues_like_uninterrupted_run() {
+    let bars = wave_bars(360);
+    for structure_mode in [StructureMode::Batch, StructureMode::Incremental] {
+        for id_strategy in [IdStrategy::Sequential, IdStrategy::Content] {
+            let config = ContextConfig {
+                structure_mode,
+                id_strategy,
+                ..ContextConfig::default()
+            };
+            let mut uninterrupted = MultiTimeframeContext::with_config("I8888.XDCE", config);
+            let mut before = MultiTimeframeContext::with_config("I8888.XDCE", config);
+            uninterrupted.register(Timeframe::M15);
+            before.register(Timeframe::M15);
+
+            let (head, tail) = bars.split_at(200);
+            for bar in head {
+                uninterrupted.append(Timeframe::M15, bar.clone());
+                before.append(Timeframe::M15, bar.clone());
+            }
+
+            let path = checkpoint_path(&format!("{structure_mode:?}_{id_strategy:?}"));
+            before.save_checkpoint(&path).expect("save checkpoint");
+            drop(before);
+            let mut restored =
+                MultiTimeframeContext::load_checkpoint(&path).expect("load checkpoint");
+            assert_eq!(restored.symbol(), "I8888.XDCE");
+            assert_eq!(restored.structure_mode(), structure_mode);
+            assert_eq!(restored.id_strategy(), id_strategy);
+            assert_eq!(snapshot(&restored), snapshot(&uninterrupted));
+
+            for (idx, bar) in tail.iter().enumerate() {
+                uninterrupted.append(Timeframe::M15, bar.clone());
+                restored.append(Timeframe::M15, bar.clone());
+                assert_eq!(
+                    snapshot(&restored),
+                    snapshot(&uninterrupted),
+                    "{structure_mode:?}/{id_strategy:?} diverged after tail sbar #{idx}"
+                );
+            }
+            assert_eq!(
+                restored
+                    .get_sbar_dataframe(Timeframe::M15)
+                    .map(|x| x.height()),
+                Some(bars.len())
+            );
+            assert!(
+                !restored
+                    .get_swing_window(Timeframe::M15, usize::MAX)
+                    .is_empty()
+            );
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[test]
+fn restores_keyzone_origins_indicators_and_sd_profile() {
+    let tf = Timeframe::M15;
+    let bars = wave_bars(300);
+    let profile = SupplyDemandProfileConfig {
+        default: SupplyDemandConfigPatch {
+            f7_weight: Some(0.9),
+            ..SupplyDemandConfigPatch::default()
+        },
+        ..SupplyDemandProfileConfig::default()
+    };
+    let configure = |mtc: &mut MultiTimeframeContext| {
+        mtc.register(tf);
+        mtc.set_keyzone_origin_enabled(tf, KeyZoneOrigin::Trend, false);
+        mtc.register_keyzone_builder(tf, Box::new(ChannelKeyZoneBuilder));
+        mtc.register_indicator(tf, Box::new(Ema::new(20)));
+        mtc.register_indicator(tf, Box::new(LastClose));
+    };
+    let mut uninterrupted =
+        MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    let mut before = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    for mtc in [&mut uninterrupted, &mut before] {
+        mtc.set_sd_profile(profile.clone());
+        configure(mtc);
+    }
+    before.subscribe(None, Arc::new(|_, _, _| {}));
+
+    let (head, tail) = bars.split_at(150);
+    for bar in head {
+        uninterrupted.append(tf, bar.clone());
+        before.append(tf, bar.clone());
+    }
+    let path = checkpoint_path("configured");
+    let omitted = before.save_checkpoint(&path).expect("save checkpoint");
+    assert_eq!(
+        omitted,
+        vec![
+            CheckpointOmission::Indicator(tf, "last_close".to_string()),
+            CheckpointOmission::Subscribers,
+        ]
+    );
+
+    let mut restored = MultiTimeframeContext::load_checkpoint(&path).expect("load checkpoint");
+    let factory = restored.keyzone_factory(tf).expect("factory");
+    assert_eq!(
+        factory.origins(),
+        vec![KeyZoneOrigin::Swing, KeyZoneOrigin::Channel]
+    );
+    assert!(!factory.is_enabled(KeyZoneOrigin::Trend));
+    assert_eq!(restored.get_sd_config(tf).map(|x| x.f7_weight), Some(0.9));
+    assert!(restored.sd_profile().is_some());
+    let ema = |mtc: &MultiTimeframeContext| mtc.get_indicator_window(tf, "ema_20", usize::MAX);
+    assert_eq!(ema(&restored), ema(&uninterrupted));
+    restored.register_indicator(tf, Box::new(LastClose));
+
+    for bar in tail {
+        uninterrupted.append(tf, bar.clone());
+        restored.append(tf, bar.clone());
+    }
+    assert_eq!(snapshot(&restored), snapshot(&uninterrupted));
+    assert_eq!(ema(&restored), ema(&uninterrupted));
+    assert_eq!(
+        restored.get_latest_indicators(tf),
+        uninterrupted.get_latest_indicators(tf)
+    );
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn rejects_checkpoint_with_unknown_version() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    for bar in wave_bars(20) {
+        mtc.append(Timeframe::M15, bar);
+    }
+
+    let path = checkpoint_path("version");
+    mtc.save_checkpoint(&path).expect("save checkpoint");
+    let mut content: Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).expect("read checkpoint"))
+            .expect("checkpoint is json");
+    content["version"] = Value::from(999);
+    std::fs::write(&path, content.to_string()).expect("rewrite checkpoint");
+
+    let result = MultiTimeframeContext::load_checkpoint(&path);
+    assert!(matches!(result, Err(DataError::CheckpointVersion(999))));
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn temporary_file_does_not_clash_with_sibling_paths() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    for bar in wave_bars(20) {
+        mtc.append(Timeframe::M15, bar);
+    }
+
+    // 仅扩展名不同的文件不共用临时文件，也不被覆盖
+    let path = checkpoint_path("sibling");
+    let sibling = path.with_extension("tmp");
+    std::fs::create_dir_all(path.parent().expect("dir")).expect("create dir");
+    std::fs::write(&sibling, "other").expect("write sibling");
+    mtc.save_checkpoint(&path).expect("save checkpoint");
+    assert_eq!(
+        std::fs::read_to_string(&sibling).expect("sibling kept"),
+        "other"
+    );
+
+    // 目标本身以 .tmp 结尾时同样经临时文件写入
+    mtc.save_checkpoint(&sibling).expect("save to .tmp target");
+    let restored = MultiTimeframeContext::load_checkpoint(&sibling).expect("load checkpoint");
+    assert_eq!(snapshot(&restored), snapshot(&mtc));
+    let leftover = sibling.with_file_name("sibling.tmp.tmp");
+    assert!(!leftover.exists());
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(sibling);
+}
+
+/// 没有 `spec` 的自定义指标。
+struct LastClose;
+
+impl Indicator for LastClose {
+    fn name(&self) -> &str {
+        "last_close"
+    }
+
+    fn reset(&mut self) {}
+
+    fn update(&mut self, bar: &SBar) -> IndicatorOutput {
+        IndicatorOutput::single(Some(bar.close_price))
+    }
+}
+
+fn checkpoint_path(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("struxis_checkpoint_{}", std::process::id()))
+        .join(format!("{name}.json"))
+}
+
+/// 以 JSON 形式比较全部结构；`created_at` 为墙钟时间，不参与比较。
+fn snapshot(mtc: &MultiTimeframeContext) -> Value {
+    let tf = Timeframe::M15;
+    let mut value = serde_json::json!({
+        "sbars": mtc.get_sbar_window(tf, usize::MAX),
+        "cbars": mtc.get_cbar_window(tf, usize::MAX),
+        "swings": mtc.get_swing_window(tf, usize::MAX),
+        "trends": mtc.get_trend_window(tf, usize::MAX),
+        "keyzones": mtc.get_keyzone_window(tf, usize::MAX),
+        "keyzone_signal": mtc.get_keyzone_signal(tf),
+        "sd": mtc.get_sd(tf),
+    });
+    strip_created_at(&mut value);
+    value
+}
+
+fn strip_created_at(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.remove("created_at");
+            map.values_mut().for_each(strip_created_at);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_created_at),
+        _ => {}
+    }
+}
+
+/// 成交量与持仓逐根变化。
+fn wave_bars(count: usize) -> Vec<SBar> {
+    common::wave_bars(count)
+        .into_iter()
+        .enumerate()
+        .map(|(i, bar)| SBar {
+            volume: 100.0 + ((i * 13) % 40) as f64,
+            open_interest: 1000.0 + i as f64,
+            ..bar
+        })
+        .collect()
+}
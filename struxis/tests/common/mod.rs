@@ -0,0 +1,37 @@
+//! 集成测试共用的行情夹具。
+
+use chrono::{TimeZone, Utc};
+
+use struxis::{SBar, Timeframe};
+
+/// I8888.XDCE 的 M15 波动行情：两级正弦叠加固定扰动，价格取 0.5 整数倍，
+/// 足以产生分型、swing 与 trend；成交量与持仓恒定，需要变化的测试自行覆盖。
+pub fn wave_bars(count: usize) -> Vec<SBar> {
+    let base = Utc
+        .with_ymd_and_hms(2024, 3, 1, 9, 0, 0)
+        .single()
+        .expect("valid dt");
+    let mut price = 100.0_f64;
+    (0..count)
+        .map(|i| {
+            let wave = (i as f64 / 7.0).sin() * 5.0 + (i as f64 / 29.0).sin() * 12.0;
+            let open = price;
+            let close = ((100.0 + wave + ((i * 7919) % 11) as f64 * 0.3) * 2.0).round() / 2.0;
+            price = close;
+            SBar {
+                id: None,
+                symbol: "I8888".to_string(),
+                exchange: "XDCE".to_string(),
+                timeframe: Timeframe::M15,
+                datetime: base + chrono::Duration::minutes(i as i64 * 15),
+                open_price: open,
+                high_price: open.max(close) + ((i * 31) % 3) as f64 * 0.5,
+                low_price: open.min(close) - ((i * 17) % 3) as f64 * 0.5,
+                close_price: close,
+                volume: 100.0,
+                open_interest: 1000.0,
+                turnover: 100.0 * close,
+            }
+        })
+        .collect()
+}
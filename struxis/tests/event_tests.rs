@@ -0,0 +1,218 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use struxis::{
+    EventPayload, EventType, FractalType, IdStrategy, MultiTimeframeContext, SBar, StructureMode,
+    Swing, Timeframe, Trend,
+};
+
+type Recorded = Arc<Mutex<Vec<EventPayload>>>;
+
+#[test]
+fn events_only_report_actual_changes() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    let recorded = record(&mut mtc, None);
+
+    // 第 2 根被第 1 根包含，第 3 根抬高形成左侧，第 4 根回落确认顶分型
+    let steps = [(10.0, 5.0), (9.0, 6.0), (12.0, 8.0), (11.0, 7.0)];
+    let mut per_bar = Vec::new();
+    for (idx, (high, low)) in steps.into_iter().enumerate() {
+        mtc.append(Timeframe::M15, bar(idx, high, low));
+        per_bar.push(std::mem::take(&mut *recorded.lock().expect("lock")));
+    }
+
+    let types = |events: &[EventPayload]| {
+        events
+            .iter()
+            .map(EventPayload::event_type)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        types(&per_bar[0]),
+        vec![
+            EventType::SBarCreated,
+            EventType::CBarCreated,
+            EventType::TimeframeEnd,
+            EventType::MtcNewBar,
+        ]
+    );
+    assert_eq!(
+        types(&per_bar[1]),
+        vec![
+            EventType::SBarCreated,
+            EventType::CBarMerged,
+            EventType::TimeframeEnd,
+            EventType::MtcNewBar,
+        ]
+    );
+    let EventPayload::CBarMerged { cbar, merged } = &per_bar[1][1] else {
+        panic!("expected merge event");
+    };
+    assert_eq!(merged.len(), 1);
+    assert_eq!(
+        (cbar.sbar_start_id, cbar.sbar_end_id),
+        (merged[0].sbar_start_id, 2)
+    );
+
+    let fractal = per_bar[3]
+        .iter()
+        .find_map(|x| match x {
+            EventPayload::FractalConfirmed(fractal) => Some(fractal),
+            _ => None,
+        })
+        .expect("top fractal confirmed");
+    assert_eq!(fractal.middle.fractal_type, FractalType::Top);
+    assert_eq!(fractal.middle.high_price, 12.0);
+    assert!(matches!(
+        per_bar[3].last(),
+        Some(EventPayload::MtcNewBar(_))
+    ));
+}
+
+#[test]
+fn events_replay_to_current_structures() {
+    for structure_mode in [StructureMode::Batch, StructureMode::Incremental] {
+        let mut mtc = MultiTimeframeContext::with_config(
+            "I8888.XDCE",
+            struxis::ContextConfig {
+                structure_mode,
+                id_strategy: IdStrategy::Sequential,
+                ..struxis::ContextConfig::default()
+            },
+        );
+        mtc.register(Timeframe::M15);
+        let recorded = record(&mut mtc, None);
+
+        let mut cbars = BTreeMap::new();
+        let mut swings = BTreeMap::<u64, Swing>::new();
+        let mut trends = BTreeMap::<u64, Trend>::new();
+        for (idx, sbar) in common::wave_bars(300).into_iter().enumerate() {
+            mtc.append(Timeframe::M15, sbar);
+            for event in recorded.lock().expect("lock").drain(..) {
+                match event {
+                    EventPayload::CBarCreated(cbar) => {
+                        cbars.insert(cbar.id.expect("cbar id"), ());
+                    }
+                    EventPayload::CBarMerged { cbar, merged } => {
+                        for x in merged {
+                            cbars.remove(&x.id.expect("cbar id"));
+                        }
+                        cbars.insert(cbar.id.expect("cbar id"), ());
+                    }
+                    EventPayload::SwingCreated(x) | EventPayload::SwingUpdated(x) => {
+                        swings.insert(x.id.expect("swing id"), x);
+                    }
+                    EventPayload::SwingRemoved(x) => {
+                        swings.remove(&x.id.expect("swing id"));
+                    }
+                    EventPayload::SwingStateChanged { swing, previous } => {
+                        assert_ne!(swing.state, previous);
+                    }
+                    EventPayload::TrendCreated(x) | EventPayload::TrendUpdated(x) => {
+                        trends.insert(x.id.expect("trend id"), x);
+                    }
+                    EventPayload::TrendRemoved(x) => {
+                        trends.remove(&x.id.expect("trend id"));
+                    }
+                    _ => {}
+                }
+            }
+
+            let tf = Timeframe::M15;
+            let expected_cbars = mtc
+                .get_cbar_window(tf, usize::MAX)
+                .iter()
+                .map(|x| x.id.expect("cbar id"))
+                .collect::<Vec<_>>();
+            assert_eq!(cbars.keys().copied().collect::<Vec<_>>(), expected_cbars);
+            let expected_swings = mtc
+                .get_swing_window(tf, usize::MAX)
+                .iter()
+                .map(|x| (x.id.expect("swing id"), x.state, x.cbar_end_id))
+                .collect::<Vec<_>>();
+            let replayed_swings = swings
+                .values()
+                .map(|x| (x.id.expect("swing id"), x.state, x.cbar_end_id))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                replayed_swings, expected_swings,
+                "{structure_mode:?} swings at #{idx}"
+            );
+            let expected_trends = mtc
+                .get_trend_window(tf, usize::MAX)
+                .iter()
+                .map(|x| (x.id.expect("trend id"), x.is_completed, x.swing_end_id))
+                .collect::<Vec<_>>();
+            let replayed_trends = trends
+                .values()
+                .map(|x| (x.id.expect("trend id"), x.is_completed, x.swing_end_id))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                replayed_trends, expected_trends,
+                "{structure_mode:?} trends at #{idx}"
+            );
+        }
+        assert!(!swings.is_empty());
+    }
+}
+
+#[test]
+fn typed_subscription_receives_only_its_event() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    let recorded = record(&mut mtc, Some(EventType::FractalConfirmed));
+    for sbar in common::wave_bars(120) {
+        mtc.append(Timeframe::M15, sbar);
+    }
+
+    let events = recorded.lock().expect("lock");
+    assert!(!events.is_empty());
+    for event in events.iter() {
+        let EventPayload::FractalConfirmed(fractal) = event else {
+            panic!("unexpected event {:?}", event.event_type());
+        };
+        assert_ne!(fractal.middle.fractal_type, FractalType::None);
+    }
+}
+
+fn record(mtc: &mut MultiTimeframeContext, event_type: Option<EventType>) -> Recorded {
+    let recorded: Recorded = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&recorded);
+    mtc.subscribe(
+        event_type,
+        Arc::new(move |tf, evt, payload| {
+            assert_eq!(tf, Timeframe::M15);
+            assert_eq!(evt, payload.event_type());
+            sink.lock().expect("lock").push(payload.clone());
+        }),
+    );
+    recorded
+}
+
+fn base_dt() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0)
+        .single()
+        .expect("valid dt")
+}
+
+fn bar(idx: usize, high: f64, low: f64) -> SBar {
+    SBar {
+        id: None,
+        symbol: "I8888".to_string(),
+        exchange: "XDCE".to_string(),
+        timeframe: Timeframe::M15,
+        datetime: base_dt() + chrono::Duration::minutes(idx as i64 * 15),
+        open_price: low,
+        high_price: high,
+        low_price: low,
+        close_price: high,
+        volume: 100.0,
+        open_interest: 1000.0,
+        turnover: 100.0 * high,
+    }
+}
@@ -1,30 +1,32 @@
 use chrono::{Timelike, Utc};
 
-use struxis::{Fractal, FractalType, MultiTimeframeContext, SBar, Timeframe};
+use struxis::{Fractal, FractalType, MultiTimeframeContext, SBar, StructureMode, Timeframe};
 
 #[test]
 fn fractal_labels_match_local_three_cbar_rule() {
-    let mut mtc = MultiTimeframeContext::new("I8888.XDCE");
-    mtc.register(Timeframe::M15);
+    for mode in [StructureMode::Batch, StructureMode::Incremental] {
+        let mut mtc = MultiTimeframeContext::with_structure_mode("I8888.XDCE", mode);
+        mtc.register(Timeframe::M15);
 
-    for bar in sample_bars(160) {
-        mtc.append(Timeframe::M15, bar);
-    }
+        for bar in sample_bars(160) {
+            mtc.append(Timeframe::M15, bar);
+        }
 
-    let cbars = mtc.get_cbar_window(Timeframe::M15, usize::MAX);
-    assert!(cbars.len() > 20, "need enough cbar rows for fractal validation");
+        let cbars = mtc.get_cbar_window(Timeframe::M15, usize::MAX);
+        assert!(cbars.len() > 20, "need enough cbar rows for fractal validation");
 
-    assert_eq!(cbars.first().map(|x| x.fractal_type), Some(FractalType::None));
-    assert_eq!(cbars.last().map(|x| x.fractal_type), Some(FractalType::None));
+        assert_eq!(cbars.first().map(|x| x.fractal_type), Some(FractalType::None));
+        assert_eq!(cbars.last().map(|x| x.fractal_type), Some(FractalType::None));
 
-    for i in 1..(cbars.len() - 1) {
-        let expected = Fractal::verify(&cbars[i - 1], &cbars[i], &cbars[i + 1]);
-        assert_eq!(
-            cbars[i].fractal_type,
-            expected,
-            "fractal mismatch at cbar id {:?}",
-            cbars[i].id
-        );
+        for i in 1..(cbars.len() - 1) {
+            let expected = Fractal::verify(&cbars[i - 1], &cbars[i], &cbars[i + 1]);
+            assert_eq!(
+                cbars[i].fractal_type,
+                expected,
+                "fractal mismatch at cbar id {:?}",
+                cbars[i].id
+            );
+        }
     }
 }
 
@@ -0,0 +1,154 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+use struxis::{
+    DataReceiver, IdStrategy, MarketBarInput, MarketHub, MultiTimeframeContext, TickInput,
+    Timeframe,
+};
+
+#[test]
+fn hub_keeps_window_aggregation_separate_per_symbol() {
+    let mut hub = MarketHub::new();
+    hub.register_timeframe(Timeframe::M1);
+    hub.register_timeframe(Timeframe::M5);
+
+    // 两个品种的 tick 交替到达，6 分钟内各自产出 5 根 M1 与 1 根 M5
+    let mut emitted = 0;
+    for minute in 0..6 {
+        emitted += hub.ingest_tick(tick("I2601", "DCE", minute, 100.0 + minute as f64));
+        emitted += hub.ingest_tick(tick("RB2601", "SHFE", minute, 300.0 + minute as f64));
+    }
+    assert_eq!(emitted, 12);
+    assert_eq!(
+        hub.symbols(),
+        vec!["I2601.DCE".to_string(), "RB2601.SHFE".to_string()]
+    );
+
+    let iron = hub.context("I2601.DCE").expect("iron context");
+    let rebar = hub.context("RB2601.SHFE").expect("rebar context");
+    assert_eq!(iron.count(Timeframe::M1), 5);
+    assert_eq!(rebar.count(Timeframe::M1), 5);
+
+    let iron_m5 = iron.get_sbar_window(Timeframe::M5, 10);
+    let rebar_m5 = rebar.get_sbar_window(Timeframe::M5, 10);
+    assert_eq!(iron_m5.len(), 1);
+    assert_eq!(rebar_m5.len(), 1);
+    assert_eq!(
+        (iron_m5[0].low_price, iron_m5[0].high_price),
+        (100.0, 104.0)
+    );
+    assert_eq!(
+        (rebar_m5[0].low_price, rebar_m5[0].high_price),
+        (300.0, 304.0)
+    );
+    assert_eq!(rebar_m5[0].symbol, "RB2601");
+}
+
+#[test]
+fn hub_routes_bars_and_reports_snapshots_across_symbols() {
+    let mut hub = MarketHub::new();
+    hub.register_timeframe(Timeframe::M5);
+    hub.add_symbol("I2601", "DCE");
+
+    let mut inputs = Vec::new();
+    for i in 0..30 {
+        inputs.push(bar("I2601", "DCE", i, 100.0 + (i % 7) as f64));
+        inputs.push(bar("RB2601", "SHFE", i, 300.0 - (i % 5) as f64));
+    }
+    inputs.push(bar("J2601", "DCE", 0, 2000.0));
+    hub.ingest_batch(inputs);
+
+    // 已有品种与后来出现的品种都按当前注册的周期建立 context
+    hub.register_timeframe(Timeframe::M15);
+    assert_eq!(
+        hub.context("I2601.DCE").map(|x| x.count(Timeframe::M5)),
+        Some(30)
+    );
+    assert_eq!(
+        hub.context("RB2601.SHFE").map(|x| x.count(Timeframe::M5)),
+        Some(30)
+    );
+    assert_eq!(
+        hub.context("J2601.DCE").map(|x| x.count(Timeframe::M5)),
+        Some(1)
+    );
+
+    let per_symbol = hub.timeframe_snapshot(Timeframe::M5);
+    assert_eq!(per_symbol.len(), 3);
+    for (key, analysis) in &per_symbol {
+        let cbar = analysis.latest_cbar.as_ref().expect("latest cbar");
+        let sbar = hub
+            .context(key)
+            .and_then(|x| x.get_sbar_window(Timeframe::M5, 1).pop())
+            .expect("latest sbar");
+        assert_eq!(cbar.sbar_end_id, sbar.id.expect("sbar id"));
+        assert!(analysis.sd.is_some());
+    }
+
+    let snapshots = hub.snapshots(Timeframe::M15, Timeframe::M5, Timeframe::M5);
+    let symbols = snapshots
+        .iter()
+        .map(|x| x.symbol.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(symbols, vec!["I2601.DCE", "J2601.DCE", "RB2601.SHFE"]);
+    assert!(snapshots.iter().all(|x| x.higher.latest_cbar.is_none()));
+    assert!(
+        hub.snapshot("UNKNOWN", Timeframe::M15, Timeframe::M5, Timeframe::M5)
+            .is_none()
+    );
+}
+
+#[test]
+fn receiver_does_not_mix_m1_bars_of_different_symbols() {
+    let mut receiver = DataReceiver::new(MultiTimeframeContext::with_id_strategy(
+        "MIXED",
+        IdStrategy::Sequential,
+    ));
+    receiver.register_timeframe(Timeframe::M5);
+
+    for minute in 0..6 {
+        receiver.ingest_tick(tick("I2601", "DCE", minute, 100.0));
+        receiver.ingest_tick(tick("RB2601", "SHFE", minute, 300.0));
+    }
+
+    let m5 = receiver.mtc().get_sbar_window(Timeframe::M5, 10);
+    assert_eq!(m5.len(), 2);
+    for bar in m5 {
+        let expected = if bar.symbol == "I2601" { 100.0 } else { 300.0 };
+        assert_eq!((bar.low_price, bar.high_price), (expected, expected));
+    }
+}
+
+fn base_dt() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0)
+        .single()
+        .expect("valid dt")
+}
+
+fn tick(symbol: &str, exchange: &str, minute: i64, price: f64) -> TickInput {
+    TickInput {
+        symbol: symbol.to_string(),
+        exchange: exchange.to_string(),
+        datetime: base_dt() + chrono::Duration::minutes(minute) + chrono::Duration::seconds(5),
+        last_price: price,
+        volume: 10.0 * (minute + 1) as f64,
+        turnover: 1000.0 * (minute + 1) as f64,
+        open_interest: 200.0,
+        ..TickInput::default()
+    }
+}
+
+fn bar(symbol: &str, exchange: &str, idx: i64, close: f64) -> MarketBarInput {
+    MarketBarInput {
+        symbol: symbol.to_string(),
+        exchange: exchange.to_string(),
+        timeframe: Timeframe::M5,
+        datetime: base_dt() + chrono::Duration::minutes(idx * 5),
+        open_price: close - 0.5,
+        high_price: close + 1.0,
+        low_price: close - 1.0,
+        close_price: close,
+        volume: 100.0,
+        open_interest: 1000.0,
+        turnover: 100.0 * close,
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::{TimeZone, Utc};
+
+use struxis::{IdStrategy, MultiTimeframeContext, SBar, Timeframe};
+
+#[test]
+fn sequential_ids_are_reproducible_across_replays() {
+    let bars = sample_bars(120);
+    let mut first = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    let mut second = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    first.register(Timeframe::M15);
+    second.register(Timeframe::M15);
+
+    // 两个 context 交替推进，验证序列互不干扰
+    for bar in &bars {
+        first.append(Timeframe::M15, bar.clone());
+        second.append(Timeframe::M15, bar.clone());
+    }
+
+    let sbar_ids = structure_ids(&first).0;
+    assert_eq!(sbar_ids, (1..=bars.len() as u64).collect::<Vec<_>>());
+    assert_eq!(structure_ids(&first), structure_ids(&second));
+}
+
+#[test]
+fn content_ids_do_not_depend_on_preceding_history() {
+    let bars = sample_bars(120);
+    let mut full = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Content);
+    let mut replay = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Content);
+    let mut late = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Content);
+    for mtc in [&mut full, &mut replay, &mut late] {
+        mtc.register(Timeframe::M15);
+    }
+
+    for (idx, bar) in bars.iter().enumerate() {
+        full.append(Timeframe::M15, bar.clone());
+        replay.append(Timeframe::M15, bar.clone());
+        if idx >= 40 {
+            late.append(Timeframe::M15, bar.clone());
+        }
+    }
+
+    assert_eq!(structure_ids(&full), structure_ids(&replay));
+
+    let full_sbar_ids = structure_ids(&full).0;
+    assert!(full_sbar_ids.windows(2).all(|x| x[0] < x[1]), "sbar ids must increase");
+    assert_eq!(&full_sbar_ids[40..], &structure_ids(&late).0[..]);
+}
+
+#[test]
+fn snowflake_stays_the_default_strategy() {
+    let mtc = MultiTimeframeContext::new("I8888.XDCE");
+    assert_eq!(mtc.id_strategy(), IdStrategy::Snowflake);
+}
+
+type StructureIds = (Vec<u64>, Vec<u64>, Vec<u64>, Vec<u64>, Vec<u64>);
+
+fn structure_ids(mtc: &MultiTimeframeContext) -> StructureIds {
+    let tf = Timeframe::M15;
+    let ids = |ids: Vec<Option<u64>>| ids.into_iter().map(|x| x.expect("id assigned")).collect();
+    (
+        ids(mtc.get_sbar_window(tf, usize::MAX).iter().map(|x| x.id).collect()),
+        ids(mtc.get_cbar_window(tf, usize::MAX).iter().map(|x| x.id).collect()),
+        ids(mtc.get_swing_window(tf, usize::MAX).iter().map(|x| x.id).collect()),
+        ids(mtc.get_trend_window(tf, usize::MAX).iter().map(|x| x.id).collect()),
+        ids(mtc.get_keyzone_window(tf, usize::MAX).iter().map(|x| x.id).collect()),
+    )
+}
+
+fn sample_bars(count: usize) -> Vec<SBar> {
+    let base_dt = Utc
+        .with_ymd_and_hms(2024, 3, 1, 9, 0, 0)
+        .single()
+        .expect("valid dt");
+
+    let mut price = 100.0_f64;
+    let cycle = [0.0_f64, 2.8, -2.4, 3.7, -3.1, 2.2, -1.6, 3.4, -2.9, 1.8];
+    (0..count)
+        .map(|i| {
+            let open = price;
+            let close = (100.0 + (i as f64) * 0.03 + cycle[i % cycle.len()]).max(1.0);
+            price = close;
+            SBar {
+                id: None,
+                symbol: "I8888".to_string(),
+                exchange: "XDCE".to_string(),
+                timeframe: Timeframe::M15,
+                datetime: base_dt + chrono::Duration::minutes((i as i64) * 15),
+                open_price: open,
+                high_price: open.max(close) + 0.8,
+                low_price: open.min(close) - 0.8,
+                close_price: close,
+                volume: 120.0 + i as f64,
+                open_interest: 1000.0 + i as f64,
+                turnover: 120.0 * close,
+            }
+        })
+        .collect()
+}
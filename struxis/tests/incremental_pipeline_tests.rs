@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use chrono::{Timelike, Utc};
+
+use struxis::constant::Const;
+use struxis::{
+    Direction, FractalType, MultiTimeframeContext, SBar, StructureMode, SwingState, Timeframe,
+};
+
+#[test]
+fn incremental_mode_matches_batch_after_every_sbar() {
+    for seed in [7, 1, 2, 3, 4] {
+        let shape = assert_modes_match(&random_walk_bars(480, seed), &format!("seed {seed}"));
+        assert!(
+            shape.cbars.len() > 100,
+            "need enough cbars to exercise backtracking"
+        );
+        assert!(
+            !shape.swings.is_empty(),
+            "need swings to exercise swing replay"
+        );
+    }
+}
+
+#[test]
+fn incremental_mode_falls_back_to_rebuild_beyond_lookback_limit() {
+    // 单边上涨 400 根，每根各成一根 CBar；随后一根包含全部的大 K 线把它们逐个向前合并，
+    // 变化的 CBar 超出 `Const::LOOKBACK_LIMIT` 个检查点，增量路径须退回全量重建
+    let mut bars = random_walk_bars(480, 11);
+    let run = 400;
+    let base = bars[0].clone();
+    for (i, bar) in bars.iter_mut().enumerate().take(run) {
+        let low = 100.0 + i as f64 * 0.5;
+        *bar = SBar {
+            open_price: low + 0.2,
+            high_price: low + 0.8,
+            low_price: low,
+            close_price: low + 0.6,
+            datetime: base.datetime + chrono::Duration::minutes(i as i64 * 15),
+            ..base.clone()
+        };
+    }
+    let top = bars[run - 1].high_price;
+    bars[run] = SBar {
+        open_price: top,
+        high_price: top + 5.0,
+        low_price: 90.0,
+        close_price: 95.0,
+        datetime: base.datetime + chrono::Duration::minutes(run as i64 * 15),
+        ..base.clone()
+    };
+    for (i, bar) in bars.iter_mut().enumerate().skip(run + 1) {
+        bar.open_price -= 10.0;
+        bar.high_price -= 10.0;
+        bar.low_price -= 10.0;
+        bar.close_price -= 10.0;
+        bar.datetime = base.datetime + chrono::Duration::minutes(i as i64 * 15);
+    }
+
+    let before = assert_modes_match(&bars[..run], "one-way run");
+    assert!(before.cbars.len() > Const::LOOKBACK_LIMIT);
+    let after = assert_modes_match(&bars[..=run], "engulfing bar");
+    assert!(before.cbars.len() - after.cbars.len() > Const::LOOKBACK_LIMIT);
+    assert_modes_match(&bars, "after rebuild");
+}
+
+/// 逐根追加并在每根之后比较两种模式的结构，返回最终结构。
+fn assert_modes_match(bars: &[SBar], label: &str) -> StructureShape {
+    let mut batch = MultiTimeframeContext::with_structure_mode("I8888.XDCE", StructureMode::Batch);
+    let mut incremental =
+        MultiTimeframeContext::with_structure_mode("I8888.XDCE", StructureMode::Incremental);
+    batch.register(Timeframe::M15);
+    incremental.register(Timeframe::M15);
+
+    for (idx, bar) in bars.iter().enumerate() {
+        batch.append(Timeframe::M15, bar.clone());
+        incremental.append(Timeframe::M15, bar.clone());
+
+        let expected = structure_shape(&batch);
+        let actual = structure_shape(&incremental);
+        assert_eq!(
+            expected.cbars, actual.cbars,
+            "{label}: cbar mismatch after sbar #{idx}"
+        );
+        assert_eq!(
+            expected.swings, actual.swings,
+            "{label}: swing mismatch after sbar #{idx}"
+        );
+        assert_eq!(
+            expected.trends, actual.trends,
+            "{label}: trend mismatch after sbar #{idx}"
+        );
+    }
+    structure_shape(&incremental)
+}
+
+type CBarShape = (usize, usize, f64, f64, FractalType);
+type SwingShape = (
+    Direction,
+    usize,
+    usize,
+    usize,
+    usize,
+    f64,
+    f64,
+    usize,
+    SwingState,
+);
+type TrendShape = (Direction, usize, usize, usize, usize, f64, f64, usize, bool);
+
+/// 以下标代替 id 描述结构，两个 context 的 id 生成器互不相同。
+struct StructureShape {
+    cbars: Vec<CBarShape>,
+    swings: Vec<SwingShape>,
+    trends: Vec<TrendShape>,
+}
+
+fn structure_shape(mtc: &MultiTimeframeContext) -> StructureShape {
+    let sbars = mtc.get_sbar_window(Timeframe::M15, usize::MAX);
+    let cbars = mtc.get_cbar_window(Timeframe::M15, usize::MAX);
+    let swings = mtc.get_swing_window(Timeframe::M15, usize::MAX);
+    let trends = mtc.get_trend_window(Timeframe::M15, usize::MAX);
+
+    let sbar_index = index_by_id(sbars.iter().map(|x| x.id));
+    let cbar_index = index_by_id(cbars.iter().map(|x| x.id));
+    let swing_index = index_by_id(swings.iter().map(|x| x.id));
+
+    StructureShape {
+        cbars: cbars
+            .iter()
+            .map(|x| {
+                (
+                    sbar_index[&x.sbar_start_id],
+                    sbar_index[&x.sbar_end_id],
+                    x.high_price,
+                    x.low_price,
+                    x.fractal_type,
+                )
+            })
+            .collect(),
+        swings: swings
+            .iter()
+            .map(|x| {
+                (
+                    x.direction,
+                    cbar_index[&x.cbar_start_id],
+                    cbar_index[&x.cbar_end_id],
+                    sbar_index[&x.sbar_start_id],
+                    sbar_index[&x.sbar_end_id],
+                    x.high_price,
+                    x.low_price,
+                    x.span,
+                    x.state,
+                )
+            })
+            .collect(),
+        trends: trends
+            .iter()
+            .map(|x| {
+                (
+                    x.direction,
+                    swing_index[&x.swing_start_id],
+                    swing_index[&x.swing_end_id],
+                    sbar_index[&x.sbar_start_id],
+                    sbar_index[&x.sbar_end_id],
+                    x.high_price,
+                    x.low_price,
+                    x.span,
+                    x.is_completed,
+                )
+            })
+            .collect(),
+    }
+}
+
+fn index_by_id(ids: impl Iterator<Item = Option<u64>>) -> HashMap<u64, usize> {
+    ids.enumerate()
+        .map(|(idx, id)| (id.expect("structure id assigned"), idx))
+        .collect()
+}
+
+fn random_walk_bars(count: usize, seed: u64) -> Vec<SBar> {
+    let mut bars = Vec::with_capacity(count);
+    let base_dt = Utc::now()
+        .with_second(0)
+        .and_then(|x| x.with_nanosecond(0))
+        .expect("valid dt");
+
+    let mut state = seed;
+    let mut next = move |modulo: u64| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) % modulo
+    };
+
+    let mut price = 100.0_f64;
+    for i in 0..count {
+        // 叠加随机扰动的多级波段，保证分型、包含与回溯都能出现
+        let wave = (i as f64 / 9.0).sin() * 6.0 + (i as f64 / 37.0).sin() * 14.0;
+        let target = (100.0 + wave + (next(9) as f64 - 4.0) * 0.5).max(1.0);
+        let open = price;
+        let close = (target * 2.0).round() / 2.0;
+        let high = open.max(close) + next(4) as f64 * 0.5;
+        let low = open.min(close) - next(4) as f64 * 0.5;
+        let volume = 100.0 + next(50) as f64;
+        price = close;
+
+        bars.push(SBar {
+            id: None,
+            symbol: "I8888".to_string(),
+            exchange: "XDCE".to_string(),
+            timeframe: Timeframe::M15,
+            datetime: base_dt + chrono::Duration::minutes((i as i64) * 15),
+            open_price: open,
+            high_price: high,
+            low_price: low,
+            close_price: close,
+            volume,
+            open_interest: 1000.0 + i as f64,
+            turnover: volume * close,
+        });
+    }
+    bars
+}
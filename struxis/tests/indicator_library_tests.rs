@@ -0,0 +1,276 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+
+use struxis::indicator::{
+    Adx, Atr, Bollinger, Donchian, Ema, Indicator, IndicatorManager, IndicatorOutput, Macd, Obv,
+    Rsi, Sma, Volatility, Vwap, Wma,
+};
+use struxis::{SBar, SessionCalendar, Timeframe, TradingSession};
+
+#[test]
+fn backfill_matches_incremental_updates() {
+    let bars = wave_bars(240);
+    let indicators: Vec<fn() -> Box<dyn Indicator>> = vec![
+        || Box::new(Ema::new(10)),
+        || Box::new(Atr::new(14)),
+        || Box::new(Sma::new(20)),
+        || Box::new(Wma::new(10)),
+        || Box::new(Rsi::new(14)),
+        || Box::new(Macd::default()),
+        || Box::new(Bollinger::new(20, 2.0)),
+        || Box::new(Donchian::new(20)),
+        || Box::new(Vwap::default()),
+        || Box::new(Obv::new()),
+        || Box::new(Adx::new(14)),
+        || Box::new(Volatility::new(20)),
+    ];
+    for make in indicators {
+        let mut incremental = make();
+        let expected = bars
+            .iter()
+            .map(|x| incremental.update(x))
+            .collect::<Vec<_>>();
+        assert!(
+            expected
+                .iter()
+                .all(|x| x.values().len() == incremental.fields().len())
+        );
+
+        let mut bulk = make();
+        assert_eq!(bulk.backfill(&bars, 0), expected, "{}", bulk.name());
+
+        // 从中间续算，与逐根更新一致
+        let mut resumed = make();
+        let mut head = resumed.backfill(&bars[..100], 0);
+        head.extend(resumed.backfill(&bars, 100));
+        assert_eq!(head, expected, "{}", resumed.name());
+    }
+}
+
+#[test]
+fn moving_averages_match_batch_formulas() {
+    let bars = wave_bars(120);
+    let closes = bars.iter().map(|x| x.close_price).collect::<Vec<_>>();
+    let sma = run(&mut Sma::new(20), &bars);
+    let wma = run(&mut Wma::new(10), &bars);
+    for idx in 0..bars.len() {
+        if idx < 19 {
+            assert_eq!(sma[idx].value(), None);
+        } else {
+            let window = &closes[idx - 19..=idx];
+            assert_close(sma[idx].value(), window.iter().sum::<f64>() / 20.0);
+        }
+        if idx >= 9 {
+            let window = &closes[idx - 9..=idx];
+            let weighted = window
+                .iter()
+                .enumerate()
+                .map(|(i, x)| (i + 1) as f64 * x)
+                .sum::<f64>();
+            assert_close(wma[idx].value(), weighted / 55.0);
+        }
+    }
+
+    let macd = run(&mut Macd::new(12, 26, 9), &bars);
+    let fast = ema_series(&closes, 12);
+    let slow = ema_series(&closes, 26);
+    let line = fast
+        .iter()
+        .zip(&slow)
+        .map(|(a, b)| a - b)
+        .collect::<Vec<_>>();
+    let signal = ema_series(&line, 9);
+    let last = macd.last().expect("macd");
+    assert_eq!(last.fields(), &["macd", "signal", "hist"]);
+    assert_close(last.get("macd"), line[119]);
+    assert_close(last.get("signal"), signal[119]);
+    assert_close(last.get("hist"), line[119] - signal[119]);
+}
+
+#[test]
+fn bands_and_channels_match_batch_formulas() {
+    let bars = wave_bars(120);
+    let boll = run(&mut Bollinger::new(20, 2.0), &bars);
+    let donchian = run(&mut Donchian::new(20), &bars);
+    assert_eq!(
+        boll[18],
+        IndicatorOutput::pending(&["middle", "upper", "lower"])
+    );
+    for idx in 19..bars.len() {
+        let window = &bars[idx - 19..=idx];
+        let mean = window.iter().map(|x| x.close_price).sum::<f64>() / 20.0;
+        let std = (window
+            .iter()
+            .map(|x| (x.close_price - mean).powi(2))
+            .sum::<f64>()
+            / 20.0)
+            .sqrt();
+        assert_close(boll[idx].get("middle"), mean);
+        assert_close(boll[idx].get("upper"), mean + 2.0 * std);
+        assert_close(boll[idx].get("lower"), mean - 2.0 * std);
+
+        let upper = window.iter().map(|x| x.high_price).fold(f64::MIN, f64::max);
+        let lower = window.iter().map(|x| x.low_price).fold(f64::MAX, f64::min);
+        assert_close(donchian[idx].get("upper"), upper);
+        assert_close(donchian[idx].get("lower"), lower);
+        assert_close(donchian[idx].value(), (upper + lower) / 2.0);
+    }
+}
+
+#[test]
+fn oscillators_match_batch_formulas() {
+    let bars = wave_bars(150);
+    let closes = bars.iter().map(|x| x.close_price).collect::<Vec<_>>();
+
+    let rsi = run(&mut Rsi::new(14), &bars);
+    assert_eq!(rsi[13].value(), None);
+    let changes = closes.windows(2).map(|x| x[1] - x[0]).collect::<Vec<_>>();
+    let mut gain = changes[..14].iter().map(|x| x.max(0.0)).sum::<f64>() / 14.0;
+    let mut loss = changes[..14].iter().map(|x| (-x).max(0.0)).sum::<f64>() / 14.0;
+    assert_close(rsi[14].value(), 100.0 - 100.0 / (1.0 + gain / loss));
+    for (idx, change) in changes.iter().enumerate().skip(14) {
+        gain = (gain * 13.0 + change.max(0.0)) / 14.0;
+        loss = (loss * 13.0 + (-change).max(0.0)) / 14.0;
+        assert_close(rsi[idx + 1].value(), 100.0 - 100.0 / (1.0 + gain / loss));
+    }
+
+    let obv = run(&mut Obv::new(), &bars);
+    let mut expected = 0.0;
+    for idx in 1..bars.len() {
+        let change = closes[idx] - closes[idx - 1];
+        if change != 0.0 {
+            expected += bars[idx].volume * change.signum();
+        }
+        assert_close(obv[idx].value(), expected);
+    }
+
+    let volatility = run(&mut Volatility::new(20), &bars);
+    assert_eq!(volatility[19].value(), None);
+    let returns = closes
+        .windows(2)
+        .map(|x| (x[1] / x[0]).ln())
+        .collect::<Vec<_>>();
+    let window = &returns[returns.len() - 20..];
+    let mean = window.iter().sum::<f64>() / 20.0;
+    let std = (window.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / 19.0).sqrt();
+    assert_close(volatility[149].value(), std);
+
+    // ±DI 在 period 根变化后就绪，ADX 还需 period 个 DX
+    let adx = run(&mut Adx::new(14), &bars);
+    assert_eq!(adx[13].get("plus_di"), None);
+    assert!(adx[14].get("plus_di").is_some());
+    assert_eq!(adx[26].value(), None);
+    assert!(adx[27..].iter().all(|x| {
+        let value = x.value().expect("adx");
+        (0.0..=100.0).contains(&value) && x.get("minus_di").is_some()
+    }));
+}
+
+#[test]
+fn vwap_restarts_each_trading_day() {
+    let calendar = SessionCalendar::from_session(&TradingSession {
+        sections: vec![
+            ("21:00".to_string(), "23:00".to_string()),
+            ("09:00".to_string(), "15:00".to_string()),
+        ],
+        utc_offset_minutes: 0,
+    })
+    .expect("session");
+    let mut vwap = Vwap::new(calendar);
+    let start = Utc
+        .with_ymd_and_hms(2024, 3, 5, 14, 0, 0)
+        .single()
+        .expect("valid dt");
+    // 14:00、14:30 属于 3-5，21:00 夜盘归属 3-6
+    let bars = [(0, 10.0, 100.0), (30, 12.0, 300.0), (420, 20.0, 50.0)]
+        .into_iter()
+        .map(|(minutes, price, volume)| {
+            let mut bar = flat_bar(start + chrono::Duration::minutes(minutes), price);
+            bar.volume = volume;
+            bar
+        })
+        .collect::<Vec<_>>();
+    let outputs = run(&mut vwap, &bars);
+    assert_close(outputs[0].value(), 10.0);
+    assert_close(outputs[1].value(), (10.0 * 100.0 + 12.0 * 300.0) / 400.0);
+    assert_close(outputs[2].value(), 20.0);
+}
+
+#[test]
+fn manager_splits_multi_valued_outputs_into_columns() {
+    let bars = wave_bars(40);
+    let mut manager = IndicatorManager::default();
+    manager.register(Box::new(Rsi::new(14)));
+    manager.register(Box::new(Bollinger::new(20, 2.0)));
+    for idx in 0..bars.len() {
+        manager.sync(&bars[..=idx]);
+    }
+    let df = manager.dataframe(&bars);
+    assert_eq!(
+        df.get_column_names(),
+        vec![
+            "id",
+            "datetime",
+            "rsi_14",
+            "boll_20_2_middle",
+            "boll_20_2_upper",
+            "boll_20_2_lower",
+        ]
+    );
+    assert_eq!(df.height(), 40);
+    assert!(manager.latest()["boll_20_2"].get("upper").is_some());
+}
+
+fn run(indicator: &mut dyn Indicator, bars: &[SBar]) -> Vec<IndicatorOutput> {
+    bars.iter().map(|x| indicator.update(x)).collect()
+}
+
+fn ema_series(values: &[f64], period: usize) -> Vec<f64> {
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut out = Vec::with_capacity(values.len());
+    for value in values {
+        let next = out
+            .last()
+            .map_or(*value, |prev| prev + alpha * (value - prev));
+        out.push(next);
+    }
+    out
+}
+
+fn assert_close(actual: Option<f64>, expected: f64) {
+    let actual = actual.expect("value ready");
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "actual {actual}, expected {expected}"
+    );
+}
+
+fn flat_bar(datetime: chrono::DateTime<Utc>, price: f64) -> SBar {
+    SBar {
+        id: None,
+        symbol: "I8888".to_string(),
+        exchange: "XDCE".to_string(),
+        timeframe: Timeframe::M15,
+        datetime,
+        open_price: price,
+        high_price: price,
+        low_price: price,
+        close_price: price,
+        volume: 100.0,
+        open_interest: 1000.0,
+        turnover: 100.0 * price,
+    }
+}
+
+/// 成交量逐根变化。
+fn wave_bars(count: usize) -> Vec<SBar> {
+    common::wave_bars(count)
+        .into_iter()
+        .enumerate()
+        .map(|(i, bar)| SBar {
+            volume: 100.0 + (i % 5) as f64 * 10.0,
+            ..bar
+        })
+        .collect()
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use struxis::indicator::{Atr, Ema, Indicator, IndicatorManager};
+use struxis::{AnalysisEngine, IdStrategy, MultiTimeframeContext, Timeframe};
+
+#[test]
+fn registered_indicators_follow_every_sbar() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    mtc.register_indicator(Timeframe::M15, Box::new(Ema::new(20)));
+    mtc.register_indicator(Timeframe::M15, Box::new(Atr::new(14)));
+    let bars = common::wave_bars(120);
+    for bar in bars.clone() {
+        mtc.append(Timeframe::M15, bar);
+    }
+
+    let mut ema = Ema::new(20);
+    let expected = bars.iter().map(|x| ema.update(x)).collect::<Vec<_>>();
+    assert_eq!(
+        mtc.get_indicator_window(Timeframe::M15, "ema_20", usize::MAX),
+        expected
+    );
+    assert_eq!(
+        mtc.get_indicator_window(Timeframe::M15, "ema_20", 3),
+        expected[117..].to_vec()
+    );
+    assert!(
+        mtc.get_indicator_window(Timeframe::M15, "rsi_14", 3)
+            .is_empty()
+    );
+
+    let latest = mtc.get_latest_indicators(Timeframe::M15);
+    assert_eq!(latest.keys().collect::<Vec<_>>(), vec!["atr_14", "ema_20"]);
+    assert_eq!(latest["ema_20"], expected[119]);
+
+    let df = mtc
+        .get_indicator_dataframe(Timeframe::M15)
+        .expect("dataframe");
+    assert_eq!(df.height(), 120);
+    assert_eq!(
+        df.get_column_names(),
+        vec!["id", "datetime", "ema_20", "atr_14"]
+    );
+}
+
+#[test]
+fn late_registration_and_checkpoint_restore_backfill_history() {
+    let bars = common::wave_bars(200);
+    let mut early = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    let mut late = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    early.register(Timeframe::M15);
+    late.register(Timeframe::M15);
+    early.register_indicator(Timeframe::M15, Box::new(Atr::new(14)));
+
+    let (head, tail) = bars.split_at(100);
+    for bar in head {
+        early.append(Timeframe::M15, bar.clone());
+        late.append(Timeframe::M15, bar.clone());
+    }
+    late.register_indicator(Timeframe::M15, Box::new(Atr::new(14)));
+    let window = |mtc: &MultiTimeframeContext| {
+        mtc.get_indicator_window(Timeframe::M15, "atr_14", usize::MAX)
+    };
+    assert_eq!(window(&late), window(&early));
+
+    let path = std::env::temp_dir().join(format!(
+        "struxis_indicator_checkpoint_{}.json",
+        std::process::id()
+    ));
+    late.save_checkpoint(&path).expect("save checkpoint");
+    let mut restored = MultiTimeframeContext::load_checkpoint(&path).expect("load checkpoint");
+    // 内置指标随 checkpoint 恢复并补算，重复注册同名指标不生效
+    assert_eq!(window(&restored), window(&late));
+    restored.register_indicator(Timeframe::M15, Box::new(Atr::new(14)));
+    for bar in tail {
+        early.append(Timeframe::M15, bar.clone());
+        restored.append(Timeframe::M15, bar.clone());
+    }
+    assert_eq!(window(&restored).len(), 200);
+    assert_eq!(window(&restored), window(&early));
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn dirty_range_is_recomputed_through_backfill() {
+    let bars = common::wave_bars(60);
+    let mut manager = IndicatorManager::default();
+    manager.register(Box::new(Ema::new(10)));
+    for idx in 0..bars.len() {
+        manager.sync(&bars[..=idx]);
+    }
+    let expected = manager.values("ema_10").expect("ema").to_vec();
+
+    manager.mark_dirty(25);
+    assert_eq!(manager.dirty_index(), 25);
+    manager.backfill(&bars);
+    assert_eq!(manager.dirty_index(), 60);
+    assert_eq!(manager.values("ema_10").expect("ema"), expected.as_slice());
+
+    // 回填后继续逐根更新，与一次性计算一致
+    let mut more = common::wave_bars(61);
+    more[60].close_price += 3.0;
+    manager.sync(&more);
+    let mut ema = Ema::new(10);
+    let last = more.iter().map(|x| ema.update(x)).last().expect("ema");
+    assert_eq!(manager.latest()["ema_10"], last);
+}
+
+#[test]
+fn snapshot_reports_latest_indicator_values() {
+    let mut engine = AnalysisEngine::new("I8888.XDCE");
+    engine
+        .mtc_mut()
+        .register_indicator(Timeframe::M15, Box::new(Ema::new(5)));
+    for bar in common::wave_bars(30) {
+        engine.append(Timeframe::M15, bar);
+    }
+
+    let snapshot = engine.snapshot();
+    assert!(snapshot.trade.indicators["ema_5"].value().is_some());
+    assert!(snapshot.entry.indicators.is_empty());
+}
@@ -0,0 +1,271 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+use struxis::{
+    ChannelKeyZoneBuilder, Direction, KeyZoneManager, KeyZoneOrientation, KeyZoneOrigin,
+    KeyZoneState, KeyZoneStatus, SBar, Swing, SwingState, Timeframe, Trend,
+};
+
+#[test]
+fn up_trend_builds_trendline_and_parallel_channel() {
+    let zones = build_channel();
+    assert_eq!(zones.len(), 2);
+    let trendline = &zones[0];
+    let channel = &zones[1];
+    assert_eq!(trendline.orientation, KeyZoneOrientation::Trendline);
+    assert_eq!(channel.orientation, KeyZoneOrientation::Channel);
+    for zone in &zones {
+        assert_eq!(zone.origin_type, KeyZoneOrigin::Channel);
+        assert_eq!(zone.direction_hint, Direction::Up);
+        assert_eq!((zone.sbar_start_id, zone.sbar_end_id), (1, 12));
+        assert!(zone.is_sloped());
+        // 两个低点都落在趋势线上
+        assert_eq!(zone.touch_count, 2);
+        assert_eq!(zone.last_touch_id, Some(8));
+    }
+
+    // 趋势线经过 bar1 与 bar8 的低点
+    let (lower, upper) = trendline.bounds_at(idx(8));
+    assert!((lower - 104.0).abs() < 1e-9 && (upper - 104.0).abs() < 1e-9);
+    let line_12 = 100.0 + 4.0 * 11.0 / 7.0;
+    assert!((trendline.bounds_at(idx(12)).0 - line_12).abs() < 1e-9);
+
+    // 上轨平行穿过最远的高点 bar12
+    let (lower, upper) = channel.bounds_at(idx(12));
+    assert!((lower - line_12).abs() < 1e-9);
+    assert!((upper - 116.0).abs() < 1e-9);
+    assert!(channel.contains_at(110.0, idx(12)));
+    assert!(!channel.contains_at(110.0, idx(1)));
+}
+
+#[test]
+fn slope_counts_bars_not_session_breaks() {
+    // bar9 起隔了一个周末，按 bar 计数的趋势线不受影响
+    let mut sbars = up_trend_bars();
+    for sbar in &mut sbars[8..] {
+        sbar.datetime += chrono::Duration::days(3);
+    }
+    let swings = vec![
+        swing(1, Direction::Up, 1, 5, 110.0, 100.0),
+        swing(2, Direction::Down, 5, 8, 110.0, 104.0),
+        swing(3, Direction::Up, 8, 12, 116.0, 104.0),
+    ];
+    let trend = trend(Direction::Up, &swings);
+    let zones = ChannelKeyZoneBuilder::build_from(Timeframe::M15, &trend, &swings, &sbars, 0);
+    assert_eq!(zones.len(), 2);
+    let trendline = &zones[0];
+    assert_eq!(trendline.anchor_index, Some(0));
+    assert!((trendline.slope - 4.0 / 7.0).abs() < 1e-9);
+    let line_12 = 100.0 + 4.0 * 11.0 / 7.0;
+    assert!((trendline.bounds_at(idx(12)).0 - line_12).abs() < 1e-9);
+    assert!((zones[1].bounds_at(idx(12)).1 - 116.0).abs() < 1e-9);
+    assert_eq!(trendline.touch_count, 2);
+}
+
+#[test]
+fn windowed_sbars_anchor_at_absolute_index() {
+    // 窗口前还有 5 根历史 bar，anchor_index 按窗口偏移换算为绝对序号
+    let sbars = up_trend_bars();
+    let swings = vec![
+        swing(1, Direction::Up, 1, 5, 110.0, 100.0),
+        swing(2, Direction::Down, 5, 8, 110.0, 104.0),
+        swing(3, Direction::Up, 8, 12, 116.0, 104.0),
+    ];
+    let trend = trend(Direction::Up, &swings);
+    let zones = ChannelKeyZoneBuilder::build_from(Timeframe::M15, &trend, &swings, &sbars, 5);
+    let full = build_channel();
+    assert_eq!(zones.len(), full.len());
+    for (zone, expected) in zones.iter().zip(&full) {
+        assert_eq!(zone.anchor_index, Some(5));
+        assert!((zone.slope - expected.slope).abs() < 1e-9);
+        assert_eq!(zone.touch_count, expected.touch_count);
+        let (lower, upper) = zone.bounds_at(5 + idx(12));
+        let (exp_lower, exp_upper) = expected.bounds_at(idx(12));
+        assert!((lower - exp_lower).abs() < 1e-9 && (upper - exp_upper).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn down_trend_mirrors_channel_above_prices() {
+    let sbars = pivot_bars(&[(1, 116.0), (5, 106.0), (8, 112.0), (12, 100.0)]);
+    let swings = vec![
+        swing(1, Direction::Down, 1, 5, 116.0, 106.0),
+        swing(2, Direction::Up, 5, 8, 112.0, 106.0),
+        swing(3, Direction::Down, 8, 12, 112.0, 100.0),
+    ];
+    let trend = trend(Direction::Down, &swings);
+    let zones = ChannelKeyZoneBuilder::build_from(Timeframe::M15, &trend, &swings, &sbars, 0);
+
+    assert_eq!(zones.len(), 2);
+    assert!(zones.iter().all(|x| x.slope < 0.0));
+    let (lower, upper) = zones[1].bounds_at(idx(8));
+    assert!((upper - 112.0).abs() < 1e-9);
+    assert!(lower < upper);
+}
+
+#[test]
+fn too_few_pivots_build_nothing() {
+    let sbars = pivot_bars(&[(1, 100.0), (5, 110.0)]);
+    let swings = vec![swing(1, Direction::Up, 1, 5, 110.0, 100.0)];
+    let trend = trend(Direction::Up, &swings);
+    assert!(
+        ChannelKeyZoneBuilder::build_from(Timeframe::M15, &trend, &swings, &sbars, 0).is_empty()
+    );
+}
+
+#[test]
+fn sloped_zone_reacts_along_the_line() {
+    let mut sbars = up_trend_bars();
+    let trendline = build_channel().remove(0);
+    let mut manager = KeyZoneManager::new();
+    manager.update_from(Timeframe::M15, &[], &[], &sbars);
+    let id = manager.track(trendline);
+
+    // 水平的锚定价 100 远低于这些 bar，只有沿斜线求值才会触及
+    let steps = [
+        (bar(13, 112.5, 111.5, 112.0), None),
+        (bar(14, 108.0, 107.0, 107.8), Some(KeyZoneState::Touch)),
+        (bar(15, 109.5, 107.8, 109.0), Some(KeyZoneState::Reject)),
+        (bar(16, 109.0, 104.5, 105.0), Some(KeyZoneState::Touch)),
+    ];
+    for (sbar, expected) in steps {
+        sbars.push(sbar);
+        manager.update_from(Timeframe::M15, &[], &[], &sbars);
+        let zone = manager.rows().iter().find(|x| x.id == Some(id));
+        assert_eq!(zone.expect("tracked zone").state, expected);
+    }
+    let signal = manager.latest_signal();
+    assert!(signal.is_none());
+
+    // 第二根收在斜线下方：穿透视为 Accept，同时确认向下突破
+    sbars.push(bar(17, 105.0, 103.5, 104.0));
+    manager.update_from(Timeframe::M15, &[], &[], &sbars);
+    assert!(manager.rows().iter().all(|x| x.id != Some(id)));
+    let archived = manager.archived();
+    let zone = archived
+        .iter()
+        .find(|x| x.id == Some(id))
+        .expect("archived");
+    assert_eq!(zone.status, KeyZoneStatus::Broken);
+    assert_eq!(zone.state, Some(KeyZoneState::Accept));
+    assert_eq!(
+        manager.latest_signal().map(|x| x.behavior.state()),
+        Some(KeyZoneState::Accept)
+    );
+}
+
+fn build_channel() -> Vec<struxis::KeyZone> {
+    let sbars = up_trend_bars();
+    let swings = vec![
+        swing(1, Direction::Up, 1, 5, 110.0, 100.0),
+        swing(2, Direction::Down, 5, 8, 110.0, 104.0),
+        swing(3, Direction::Up, 8, 12, 116.0, 104.0),
+    ];
+    let trend = trend(Direction::Up, &swings);
+    ChannelKeyZoneBuilder::build_from(Timeframe::M15, &trend, &swings, &sbars, 0)
+}
+
+fn up_trend_bars() -> Vec<SBar> {
+    pivot_bars(&[(1, 100.0), (5, 110.0), (8, 104.0), (12, 116.0)])
+}
+
+/// 拐点之间线性插值；低点 bar 的 low、高点 bar 的 high 恰为拐点价。
+fn pivot_bars(pivots: &[(u64, f64)]) -> Vec<SBar> {
+    let (first_id, _) = pivots[0];
+    let (last_id, _) = pivots[pivots.len() - 1];
+    (first_id..=last_id)
+        .map(|id| {
+            let seg = pivots
+                .windows(2)
+                .find(|x| x[0].0 <= id && id <= x[1].0)
+                .expect("segment");
+            let ((from_id, from), (to_id, to)) = (seg[0], seg[1]);
+            let price = from + (to - from) * (id - from_id) as f64 / (to_id - from_id) as f64;
+            let mut sbar = bar(id, price + 0.5, price - 0.5, price);
+            if let Some(idx) = pivots.iter().position(|x| x.0 == id) {
+                let neighbour = pivots[if idx == 0 { 1 } else { idx - 1 }].1;
+                if price > neighbour {
+                    sbar.high_price = price;
+                } else {
+                    sbar.low_price = price;
+                }
+            }
+            sbar
+        })
+        .collect()
+}
+
+fn swing(
+    id: u64,
+    direction: Direction,
+    sbar_start_id: u64,
+    sbar_end_id: u64,
+    high: f64,
+    low: f64,
+) -> Swing {
+    Swing {
+        id: Some(id),
+        direction,
+        cbar_start_id: sbar_start_id,
+        cbar_end_id: sbar_end_id,
+        sbar_start_id,
+        sbar_end_id,
+        high_price: high,
+        low_price: low,
+        span: (sbar_end_id - sbar_start_id) as usize,
+        volume: 0.0,
+        start_oi: 0.0,
+        end_oi: 0.0,
+        state: SwingState::Confirmed,
+        created_at: Utc::now(),
+    }
+}
+
+fn trend(direction: Direction, swings: &[Swing]) -> Trend {
+    let first = swings.first().expect("swings");
+    let last = swings.last().expect("swings");
+    Trend {
+        id: Some(1),
+        direction,
+        swing_start_id: first.id.expect("id"),
+        swing_end_id: last.id.expect("id"),
+        sbar_start_id: first.sbar_start_id,
+        sbar_end_id: last.sbar_end_id,
+        high_price: swings.iter().map(|x| x.high_price).fold(f64::MIN, f64::max),
+        low_price: swings.iter().map(|x| x.low_price).fold(f64::MAX, f64::min),
+        span: (last.sbar_end_id - first.sbar_start_id) as usize,
+        volume: 0.0,
+        start_oi: 0.0,
+        end_oi: 0.0,
+        is_completed: false,
+        created_at: Utc::now(),
+    }
+}
+
+/// `pivot_bars` 从 id 1 开始，序号为 id - 1。
+fn idx(id: u64) -> usize {
+    id as usize - 1
+}
+
+fn at(id: u64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0)
+        .single()
+        .expect("valid dt")
+        + chrono::Duration::minutes(id as i64 * 15)
+}
+
+fn bar(id: u64, high: f64, low: f64, close: f64) -> SBar {
+    SBar {
+        id: Some(id),
+        symbol: "I8888".to_string(),
+        exchange: "XDCE".to_string(),
+        timeframe: Timeframe::M15,
+        datetime: at(id),
+        open_price: close,
+        high_price: high,
+        low_price: low,
+        close_price: close,
+        volume: 100.0,
+        open_interest: 1000.0,
+        turnover: 100.0 * close,
+    }
+}
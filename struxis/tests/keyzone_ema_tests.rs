@@ -0,0 +1,112 @@
+mod common;
+
+use std::collections::HashSet;
+
+use struxis::indicator::{Atr, Ema, Indicator};
+use struxis::{
+    EmaKeyZoneBuilder, EmaKeyZoneConfig, IdStrategy, KeyZoneBuilder, KeyZoneOrigin,
+    MultiTimeframeContext, Timeframe,
+};
+
+#[test]
+fn builder_wraps_each_registered_ema_with_atr_band() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    let builder = EmaKeyZoneBuilder::new(EmaKeyZoneConfig {
+        periods: vec![10, 30],
+        atr_period: 14,
+        band_atr_multiple: 0.5,
+    });
+    mtc.register_keyzone_builder(Timeframe::M15, Box::new(builder.clone()));
+    let names = mtc.get_latest_indicators(Timeframe::M15);
+    assert!(names.is_empty());
+
+    // 均线与 ATR 按完整历史逐根更新，无需预热窗口：首根有波幅的 bar 起带宽即不为零
+    let bars = common::wave_bars(420);
+    let mut atr = Atr::new(14);
+    let mut emas = [Ema::new(10), Ema::new(30)];
+    for (i, sbar) in bars.into_iter().enumerate() {
+        let width = atr.update(&sbar).value().expect("atr") * 0.5;
+        let centers = emas
+            .iter_mut()
+            .map(|x| x.update(&sbar).value().expect("ema"))
+            .collect::<Vec<_>>();
+        mtc.append(Timeframe::M15, sbar);
+        // 首根 bar 无波幅
+        if i == 0 || (2..400).contains(&i) {
+            continue;
+        }
+
+        let zones = builder.build(&mtc, Timeframe::M15);
+        assert_eq!(zones.len(), 2);
+        for ((zone, period), center) in zones.iter().zip([10, 30]).zip(&centers) {
+            assert_eq!(zone.origin_type, KeyZoneOrigin::Ema);
+            assert_eq!(
+                zone.indicator.as_deref(),
+                Some(format!("ema_{period}").as_str())
+            );
+            assert!(zone.upper > zone.lower);
+            assert!((zone.lower - (center - width)).abs() < 1e-9);
+            assert!((zone.upper - (center + width)).abs() < 1e-9);
+            assert_eq!(zone.sbar_end_id, i as u64 + 1);
+            // 触碰由生命周期累计
+            assert_eq!(zone.touch_count, 0);
+        }
+    }
+    let latest = mtc.get_latest_indicators(Timeframe::M15);
+    for name in ["atr_14", "ema_10", "ema_30"] {
+        assert!(latest.contains_key(name), "{name} registered");
+    }
+}
+
+#[test]
+fn registered_ema_zones_follow_price_and_react() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    mtc.register_keyzone_builder(
+        Timeframe::M15,
+        Box::new(EmaKeyZoneBuilder::new(EmaKeyZoneConfig {
+            periods: vec![20],
+            ..EmaKeyZoneConfig::default()
+        })),
+    );
+
+    let mut ids = HashSet::new();
+    let mut bounds = HashSet::new();
+    let mut ema_signal = false;
+    let mut touch_count = 0;
+    for sbar in common::wave_bars(300) {
+        mtc.append(Timeframe::M15, sbar);
+        let zones = mtc.get_keyzone_window(Timeframe::M15, usize::MAX);
+        let ema = zones
+            .iter()
+            .filter(|x| x.origin_type == KeyZoneOrigin::Ema)
+            .collect::<Vec<_>>();
+        assert_eq!(ema.len(), 1);
+        ids.insert(ema[0].id);
+        bounds.insert(ema[0].upper.to_bits());
+        // 累计计数不会随旧 bar 移出窗口而回落
+        assert!(ema[0].touch_count >= touch_count);
+        touch_count = ema[0].touch_count;
+        ema_signal |= mtc
+            .get_keyzone_signal(Timeframe::M15)
+            .is_some_and(|x| x.zone_id == ema[0].id);
+    }
+
+    // 同一条均线带 id 不变、边界逐根更新，并参与信号评估
+    assert_eq!(ids.len(), 1);
+    assert!(bounds.len() > 200);
+    assert!(ema_signal);
+    assert!(touch_count > 0);
+    let zone = mtc
+        .get_keyzone_window(Timeframe::M15, usize::MAX)
+        .into_iter()
+        .find(|x| x.origin_type == KeyZoneOrigin::Ema)
+        .expect("ema zone");
+    assert!(!zone.reactions.is_empty());
+    assert!(
+        mtc.get_archived_keyzones(Timeframe::M15, usize::MAX)
+            .iter()
+            .all(|x| x.origin_type != KeyZoneOrigin::Ema)
+    );
+}
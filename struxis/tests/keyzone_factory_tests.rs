@@ -0,0 +1,122 @@
+mod common;
+
+use struxis::{
+    Direction, IdStrategy, KeyZone, KeyZoneBuilder, KeyZoneFactory, KeyZoneOrientation,
+    KeyZoneOrigin, KeyZoneStatus, MultiTimeframeContext, Timeframe,
+};
+
+/// 以开盘后前 4 根 SBar 的高低点作为关键区。
+struct OpeningRangeBuilder;
+
+impl KeyZoneBuilder for OpeningRangeBuilder {
+    fn origin_type(&self) -> KeyZoneOrigin {
+        KeyZoneOrigin::Custom(1)
+    }
+
+    fn build(&self, mtc: &MultiTimeframeContext, timeframe: Timeframe) -> Vec<KeyZone> {
+        let sbars = mtc.get_sbar_window(timeframe, usize::MAX);
+        if sbars.len() < 4 {
+            return Vec::new();
+        }
+        let range = &sbars[..4];
+        vec![KeyZone {
+            id: None,
+            timeframe,
+            origin_type: KeyZoneOrigin::Custom(1),
+            orientation: KeyZoneOrientation::Horizontal,
+            upper: range.iter().map(|x| x.high_price).fold(f64::MIN, f64::max),
+            lower: range.iter().map(|x| x.low_price).fold(f64::MAX, f64::min),
+            touch_count: 0,
+            last_touch_id: None,
+            sbar_start_id: range[0].id.unwrap_or_default(),
+            sbar_end_id: range[3].id.unwrap_or_default(),
+            direction_hint: Direction::None,
+            reactions: vec![],
+            status: KeyZoneStatus::Active,
+            invalidated_sbar_id: None,
+            idle_bars: 0,
+            break_bars: 0,
+            state: None,
+            touch_side: None,
+            slope: 0.0,
+            anchor_index: None,
+            indicator: None,
+        }]
+    }
+}
+
+#[test]
+fn registered_timeframes_use_default_factory() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    let factory = mtc.keyzone_factory(Timeframe::M15).expect("factory");
+    assert_eq!(
+        factory.origins(),
+        vec![KeyZoneOrigin::Swing, KeyZoneOrigin::Trend]
+    );
+
+    for sbar in common::wave_bars(300) {
+        mtc.append(Timeframe::M15, sbar);
+    }
+    let zones = mtc.get_keyzone_window(Timeframe::M15, usize::MAX);
+    assert!(zones.iter().any(|x| x.origin_type == KeyZoneOrigin::Swing));
+    assert!(
+        zones
+            .iter()
+            .all(|x| matches!(x.origin_type, KeyZoneOrigin::Swing | KeyZoneOrigin::Trend))
+    );
+}
+
+#[test]
+fn custom_builder_zones_join_the_pipeline() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    mtc.register_keyzone_builder(Timeframe::M15, Box::new(OpeningRangeBuilder));
+
+    let mut ids = Vec::new();
+    for sbar in common::wave_bars(300) {
+        mtc.append(Timeframe::M15, sbar);
+        let zones = mtc.get_keyzone_window(Timeframe::M15, usize::MAX);
+        ids.extend(
+            zones
+                .iter()
+                .filter(|x| x.origin_type == KeyZoneOrigin::Custom(1))
+                .map(|x| x.id),
+        );
+    }
+
+    // 第 4 根起生成，同一来源只建一次
+    assert!(!ids.is_empty());
+    assert!(ids.iter().all(|x| *x == ids[0]));
+    let zones = mtc.get_keyzone_window(Timeframe::M15, usize::MAX);
+    assert!(zones.iter().any(|x| x.origin_type == KeyZoneOrigin::Swing));
+}
+
+#[test]
+fn disabled_origins_stop_generating_zones() {
+    let mut swing_only =
+        MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    swing_only.register(Timeframe::M15);
+    swing_only.set_keyzone_origin_enabled(Timeframe::M15, KeyZoneOrigin::Trend, false);
+    let mut empty = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    empty.register(Timeframe::M15);
+    empty.set_keyzone_factory(Timeframe::M15, KeyZoneFactory::new());
+
+    for sbar in common::wave_bars(300) {
+        swing_only.append(Timeframe::M15, sbar.clone());
+        empty.append(Timeframe::M15, sbar);
+    }
+
+    let factory = swing_only.keyzone_factory(Timeframe::M15).expect("factory");
+    assert!(factory.is_enabled(KeyZoneOrigin::Swing));
+    assert!(!factory.is_enabled(KeyZoneOrigin::Trend));
+    let zones = swing_only.get_keyzone_window(Timeframe::M15, usize::MAX);
+    assert!(!zones.is_empty());
+    assert!(zones.iter().all(|x| x.origin_type == KeyZoneOrigin::Swing));
+    assert!(
+        empty
+            .get_keyzone_window(Timeframe::M15, usize::MAX)
+            .is_empty()
+    );
+    assert!(empty.get_keyzone_signal(Timeframe::M15).is_none());
+}
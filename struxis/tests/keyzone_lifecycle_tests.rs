@@ -0,0 +1,382 @@
+mod common;
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use struxis::{
+    Direction, EventPayload, EventType, IdStrategy, KeyZoneBehavior, KeyZoneConfig, KeyZoneManager,
+    KeyZoneState, KeyZoneStatus, MultiTimeframeContext, SBar, Swing, SwingState, Timeframe,
+};
+
+#[test]
+fn zones_keep_ids_and_count_later_touches() {
+    let swings = vec![up_swing(1, 5, 100.0, 110.0)];
+    let mut sbars = rising_bars();
+    let mut manager = KeyZoneManager::new();
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+    let zone = manager.rows()[0].clone();
+    assert_eq!(zone.status, KeyZoneStatus::Active);
+
+    // 回落后再次上探触及关键区
+    let below = zone.lower - 1.0;
+    sbars.push(bar(6, below, below - 2.0, below - 1.0));
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+    sbars.push(bar(7, zone.lower + 0.5, below - 2.0, below));
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+
+    let updated = manager.rows()[0].clone();
+    assert_eq!(manager.rows().len(), 1);
+    assert_eq!(updated.id, zone.id);
+    assert_eq!(updated.touch_count, zone.touch_count + 1);
+    assert_eq!(updated.last_touch_id, Some(7));
+    assert_eq!(updated.idle_bars, 0);
+
+    // 同一输入重复推进不改变状态
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+    assert_eq!(manager.rows()[0].touch_count, updated.touch_count);
+}
+
+#[test]
+fn bars_sharing_a_timestamp_each_advance_the_lifecycle() {
+    let swings = vec![up_swing(1, 5, 100.0, 110.0)];
+    let mut sbars = rising_bars();
+    let mut manager = KeyZoneManager::new();
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+    let zone = manager.rows()[0].clone();
+
+    // 同一笔 tick 生成的两根 bar：先远离关键区，第二根触及
+    let below = zone.lower - 1.0;
+    sbars.push(bar(6, below, below - 2.0, below - 1.0));
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+    let mut second = bar(7, zone.lower + 0.5, below - 2.0, below);
+    second.datetime = sbars[5].datetime;
+    sbars.push(second);
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+
+    let updated = &manager.rows()[0];
+    assert_eq!(updated.touch_count, zone.touch_count + 1);
+    assert_eq!(updated.last_touch_id, Some(7));
+
+    // 再来一根同时间的 bar，互动状态机同样逐根记录
+    let mut third = bar(8, below - 2.0, zone.lower - 20.0, zone.lower - 19.0);
+    third.datetime = sbars[5].datetime;
+    sbars.push(third);
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+    let reacted = manager.rows()[0]
+        .reactions
+        .iter()
+        .map(|x| x.sbar_id)
+        .collect::<Vec<_>>();
+    assert_eq!(reacted, vec![6, 7, 8]);
+}
+
+#[test]
+fn extending_source_updates_bounds_in_place() {
+    let mut sbars = rising_bars();
+    let mut manager = KeyZoneManager::new();
+    manager.update_from(Timeframe::M15, &[up_swing(1, 5, 100.0, 110.0)], &[], &sbars);
+    let id = manager.rows()[0].id;
+
+    sbars.push(bar(6, 114.0, 109.0, 113.0));
+    manager.update_from(Timeframe::M15, &[up_swing(1, 6, 100.0, 114.0)], &[], &sbars);
+
+    let zone = &manager.rows()[0];
+    assert_eq!(manager.rows().len(), 1);
+    assert_eq!(zone.id, id);
+    assert_eq!(zone.sbar_end_id, 6);
+    assert_eq!(zone.upper, 114.0);
+    assert_eq!(zone.status, KeyZoneStatus::Active);
+}
+
+#[test]
+fn confirmed_break_archives_zone() {
+    let swings = vec![up_swing(1, 5, 100.0, 110.0)];
+    let mut sbars = rising_bars();
+    let mut manager = KeyZoneManager::new();
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+    let zone = manager.rows()[0].clone();
+
+    // 单根收盘越过不构成突破，连续两根才确认
+    sbars.push(bar(6, 113.0, 108.0, 112.5));
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+    assert_eq!(manager.rows().len(), 1);
+    assert_eq!(manager.rows()[0].break_bars, 1);
+
+    sbars.push(bar(7, 115.0, 112.0, 114.5));
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+    assert!(manager.rows().is_empty());
+    let archived = manager.archived();
+    assert_eq!(archived.len(), 1);
+    assert_eq!(archived[0].id, zone.id);
+    assert_eq!(archived[0].status, KeyZoneStatus::Broken);
+    assert_eq!(archived[0].invalidated_sbar_id, Some(7));
+
+    // 来源未变化时失效区不会被重建
+    sbars.push(bar(8, 116.0, 113.0, 115.0));
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+    assert!(manager.rows().is_empty());
+    assert_eq!(manager.archived().len(), 1);
+}
+
+#[test]
+fn idle_zones_expire() {
+    let swings = vec![up_swing(1, 5, 100.0, 110.0)];
+    let mut sbars = rising_bars();
+    let mut manager = KeyZoneManager::new();
+    manager.set_config(KeyZoneConfig {
+        max_idle_bars: 3,
+        ..KeyZoneConfig::default()
+    });
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+
+    for id in 6..=8 {
+        sbars.push(bar(id, 95.0, 90.0, 92.0));
+        manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+    }
+    assert!(manager.rows().is_empty());
+    let archived = manager.archived();
+    assert_eq!(archived[0].status, KeyZoneStatus::Expired);
+    assert_eq!(archived[0].invalidated_sbar_id, Some(8));
+}
+
+#[test]
+fn context_keyzone_ids_do_not_churn() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+
+    let mut previous = Vec::new();
+    let mut seen = HashSet::new();
+    for (idx, sbar) in common::wave_bars(300).into_iter().enumerate() {
+        mtc.append(Timeframe::M15, sbar);
+        let active = mtc.get_keyzone_window(Timeframe::M15, usize::MAX);
+        let archived = mtc
+            .get_archived_keyzones(Timeframe::M15, usize::MAX)
+            .iter()
+            .filter_map(|x| x.id)
+            .collect::<HashSet<_>>();
+        let active_ids = active.iter().filter_map(|x| x.id).collect::<HashSet<_>>();
+        for id in &previous {
+            assert!(
+                active_ids.contains(id) || archived.contains(id),
+                "keyzone {id} vanished at #{idx}"
+            );
+        }
+        assert!(active.iter().all(|x| x.is_active()));
+        seen.extend(active_ids.iter().copied());
+        previous = active_ids.into_iter().collect();
+    }
+    assert!(!seen.is_empty());
+    assert!(seen.len() < 100, "keyzone ids churn: {}", seen.len());
+}
+
+#[test]
+fn reactions_follow_approach_touch_resolve_order() {
+    let swings = vec![up_swing(1, 5, 100.0, 110.0)];
+    let mut sbars = rising_bars();
+    let mut manager = KeyZoneManager::new();
+    manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+    let (lower, upper) = (manager.rows()[0].lower, manager.rows()[0].upper);
+    let h = upper - lower;
+    let at = |x: f64| lower + x * h;
+
+    // 远离 -> 接近 -> 触及 -> 收回下方（Reject）-> 再次触及 -> 收在区内（Accept）-> 停留
+    let steps = [
+        (bar(6, at(-3.0), at(-4.0), at(-3.5)), None),
+        (
+            bar(7, at(-0.5), at(-1.5), at(-0.6)),
+            Some(KeyZoneState::Approach),
+        ),
+        (
+            bar(8, at(0.3), at(-1.0), at(-0.2)),
+            Some(KeyZoneState::Touch),
+        ),
+        (
+            SBar {
+                open_price: at(-0.3),
+                ..bar(9, at(-0.2), at(-1.6), at(-1.5))
+            },
+            Some(KeyZoneState::Reject),
+        ),
+        (
+            bar(10, at(0.5), at(-0.5), at(-0.4)),
+            Some(KeyZoneState::Touch),
+        ),
+        (
+            SBar {
+                open_price: at(0.2),
+                ..bar(11, at(0.95), at(0.1), at(0.9))
+            },
+            Some(KeyZoneState::Accept),
+        ),
+        (
+            bar(12, at(0.8), at(0.3), at(0.5)),
+            Some(KeyZoneState::Accept),
+        ),
+    ];
+    for (sbar, expected) in steps {
+        let sbar_id = sbar.id;
+        sbars.push(sbar);
+        manager.update_from(Timeframe::M15, &swings, &[], &sbars);
+        assert_eq!(
+            manager.rows()[0].state,
+            expected,
+            "state after #{sbar_id:?}"
+        );
+        match sbar_id {
+            Some(9) => {
+                let signal = manager.latest_signal().expect("reject signal");
+                assert_eq!(signal.behavior, KeyZoneBehavior::StrongReject);
+                assert_eq!(signal.sbar_id, 9);
+            }
+            Some(11) => {
+                let signal = manager.latest_signal().expect("accept signal");
+                assert_eq!(signal.behavior.state(), KeyZoneState::Accept);
+            }
+            _ => assert!(manager.latest_signal().is_none()),
+        }
+    }
+
+    let zone = &manager.rows()[0];
+    let reactions = zone
+        .reactions
+        .iter()
+        .map(|x| (x.sbar_id, x.state))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        reactions,
+        vec![
+            (7, KeyZoneState::Approach),
+            (8, KeyZoneState::Touch),
+            (9, KeyZoneState::Reject),
+            (10, KeyZoneState::Touch),
+            (11, KeyZoneState::Accept),
+        ]
+    );
+    assert_eq!(zone.touch_side, Some(Direction::Down));
+}
+
+#[test]
+fn transitions_respect_fsm_constraints() {
+    use KeyZoneState::*;
+    assert!(KeyZoneState::can_transition(None, Some(Approach)));
+    assert!(KeyZoneState::can_transition(Some(Approach), Some(Touch)));
+    assert!(KeyZoneState::can_transition(Some(Touch), Some(Accept)));
+    assert!(KeyZoneState::can_transition(Some(Touch), Some(Reject)));
+    assert!(KeyZoneState::can_transition(Some(Reject), Some(Touch)));
+    assert!(!KeyZoneState::can_transition(Some(Touch), Some(Approach)));
+    assert!(!KeyZoneState::can_transition(Some(Touch), None));
+    assert!(!KeyZoneState::can_transition(Some(Approach), Some(Accept)));
+    assert!(!KeyZoneState::can_transition(None, Some(Reject)));
+    assert!(!KeyZoneState::can_transition(Some(Accept), Some(Touch)));
+}
+
+#[test]
+fn context_emits_reaction_events_in_fsm_order() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    for event_type in [
+        EventType::KeyZoneApproached,
+        EventType::KeyZoneTouched,
+        EventType::KeyZoneAccepted,
+        EventType::KeyZoneRejected,
+    ] {
+        let sink = seen.clone();
+        mtc.subscribe(
+            Some(event_type),
+            Arc::new(move |_, event_type, payload| {
+                let zone = match payload {
+                    EventPayload::KeyZoneApproached(x)
+                    | EventPayload::KeyZoneTouched(x)
+                    | EventPayload::KeyZoneAccepted(x)
+                    | EventPayload::KeyZoneRejected(x) => x,
+                    _ => panic!("unexpected payload for {event_type:?}"),
+                };
+                let reaction = zone.reactions.last().expect("reaction");
+                sink.lock()
+                    .expect("sink")
+                    .push((event_type, zone.id, reaction.state));
+            }),
+        );
+    }
+    for sbar in common::wave_bars(300) {
+        mtc.append(Timeframe::M15, sbar);
+    }
+
+    let seen = seen.lock().expect("sink");
+    assert!(seen.iter().any(|x| x.0 == EventType::KeyZoneTouched));
+    let mut last_state = HashMap::new();
+    for (event_type, zone_id, state) in seen.iter() {
+        let expected = match state {
+            KeyZoneState::Approach => EventType::KeyZoneApproached,
+            KeyZoneState::Touch => EventType::KeyZoneTouched,
+            KeyZoneState::Accept => EventType::KeyZoneAccepted,
+            KeyZoneState::Reject => EventType::KeyZoneRejected,
+        };
+        assert_eq!(*event_type, expected);
+        // 离开（状态回到 None）不派发事件，Touch 之后必须紧跟 Accept/Reject
+        if last_state.get(zone_id) == Some(&KeyZoneState::Touch) {
+            assert!(matches!(state, KeyZoneState::Accept | KeyZoneState::Reject));
+        }
+        if matches!(state, KeyZoneState::Accept | KeyZoneState::Reject) {
+            assert_eq!(last_state.get(zone_id), Some(&KeyZoneState::Touch));
+        }
+        last_state.insert(*zone_id, *state);
+    }
+}
+
+fn up_swing(sbar_start_id: u64, sbar_end_id: u64, low: f64, high: f64) -> Swing {
+    Swing {
+        id: Some(1),
+        direction: Direction::Up,
+        cbar_start_id: sbar_start_id,
+        cbar_end_id: sbar_end_id,
+        sbar_start_id,
+        sbar_end_id,
+        high_price: high,
+        low_price: low,
+        span: (sbar_end_id - sbar_start_id) as usize,
+        volume: 0.0,
+        start_oi: 0.0,
+        end_oi: 0.0,
+        state: SwingState::Confirmed,
+        created_at: Utc::now(),
+    }
+}
+
+fn rising_bars() -> Vec<SBar> {
+    vec![
+        bar(1, 102.0, 100.0, 101.5),
+        bar(2, 104.0, 101.0, 103.5),
+        bar(3, 106.0, 103.0, 105.5),
+        bar(4, 108.0, 105.0, 107.5),
+        bar(5, 110.0, 107.0, 109.0),
+    ]
+}
+
+fn base_dt() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0)
+        .single()
+        .expect("valid dt")
+}
+
+fn bar(id: u64, high: f64, low: f64, close: f64) -> SBar {
+    SBar {
+        id: Some(id),
+        symbol: "I8888".to_string(),
+        exchange: "XDCE".to_string(),
+        timeframe: Timeframe::M15,
+        datetime: base_dt() + chrono::Duration::minutes(id as i64 * 15),
+        open_price: low,
+        high_price: high,
+        low_price: low,
+        close_price: close,
+        volume: 100.0,
+        open_interest: 1000.0,
+        turnover: 100.0 * close,
+    }
+}
@@ -204,7 +204,6 @@ fn controlled_swings() -> Vec<Swing> {
             volume: 10.0,
             start_oi: 1000.0,
             end_oi: 1001.0,
-            is_completed: true,
             state: SwingState::Confirmed,
             created_at: Utc::now(),
         }
@@ -221,6 +220,7 @@ fn controlled_swings() -> Vec<Swing> {
 }
 
 fn controlled_trends() -> Vec<Trend> {
+    #[allow(clippy::too_many_arguments)]
     fn trend(
         id: u64,
         direction: Direction,
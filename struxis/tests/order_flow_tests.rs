@@ -0,0 +1,193 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+use struxis::constant::Const;
+use struxis::{
+    DataReceiver, MultiTimeframeContext, OrderFlow, TickBarAggregator, TickInput, Timeframe,
+    TradeSide,
+};
+
+#[test]
+fn classifies_aggressor_by_side_quote_and_tick_rule() {
+    let mut agg = TickBarAggregator::new();
+    // 首笔只建立累计量基准
+    agg.update(quote(0, 100.0, 0.0, 99.5, 100.5, TradeSide::Unknown));
+    // 显式方向优先于盘口
+    agg.update(quote(1, 99.5, 5.0, 99.5, 100.5, TradeSide::Buy));
+    // 高于中间价为主动买，低于为主动卖
+    agg.update(quote(2, 100.5, 12.0, 99.5, 100.5, TradeSide::Unknown));
+    agg.update(quote(3, 99.5, 15.0, 99.5, 100.5, TradeSide::Unknown));
+    // 无盘口按价格涨跌，价格不变沿用上一笔方向
+    agg.update(quote(4, 100.0, 19.0, 0.0, 0.0, TradeSide::Unknown));
+    agg.update(quote(5, 100.0, 20.0, 0.0, 0.0, TradeSide::Unknown));
+    // 恰在中间价时同样回落到价格涨跌
+    agg.update(quote(6, 99.75, 26.0, 99.5, 100.0, TradeSide::Unknown));
+
+    let flow = agg.current_flow().expect("forming bar");
+    assert_eq!(
+        flow,
+        OrderFlow {
+            buy_volume: 5.0 + 7.0 + 4.0 + 1.0,
+            sell_volume: 3.0 + 6.0,
+        }
+    );
+    assert_eq!(flow.delta(), 8.0);
+    assert!((flow.imbalance() - 8.0 / 26.0).abs() < 1e-12);
+
+    let (bar, finished) = agg.flush_with_flow().pop().expect("bar");
+    assert_eq!(bar.volume, 26.0);
+    assert_eq!(finished, flow);
+    assert!(agg.current_flow().is_none());
+}
+
+#[test]
+fn order_flow_resets_on_each_bar() {
+    let mut agg = TickBarAggregator::new();
+    agg.update(quote(0, 100.0, 0.0, 99.5, 100.5, TradeSide::Unknown));
+    agg.update(quote(10, 100.5, 4.0, 99.5, 100.5, TradeSide::Unknown));
+    agg.update(quote(20, 99.5, 5.0, 99.5, 100.5, TradeSide::Unknown));
+
+    let (bar, flow) = agg
+        .update_with_flow(quote(65, 100.5, 8.0, 99.5, 100.5, TradeSide::Unknown))
+        .pop()
+        .expect("first minute closed");
+    assert_eq!(bar.datetime, at(0));
+    assert_eq!(bar.volume, 5.0);
+    assert_eq!(
+        flow,
+        OrderFlow {
+            buy_volume: 4.0,
+            sell_volume: 1.0,
+        }
+    );
+    // 新 bar 的首笔成交计入新 bar
+    assert_eq!(
+        agg.current_flow(),
+        Some(OrderFlow {
+            buy_volume: 3.0,
+            sell_volume: 0.0,
+        })
+    );
+
+    let bar = agg.update(quote(125, 100.5, 9.0, 99.5, 100.5, TradeSide::Sell));
+    assert_eq!(bar.iter().map(|x| x.volume).collect::<Vec<_>>(), vec![3.0]);
+    assert_eq!(OrderFlow::default().imbalance(), 0.0);
+}
+
+#[test]
+fn receiver_keeps_order_flow_per_sbar_and_timeframe() {
+    let mut receiver = DataReceiver::new(MultiTimeframeContext::new("I2601.DCE"));
+    for tf in [Timeframe::M1, Timeframe::M5, Timeframe::S30] {
+        receiver.register_timeframe(tf);
+    }
+    let ticks = (0..30)
+        .map(|i| {
+            let side = if i % 3 == 0 {
+                TradeSide::Sell
+            } else {
+                TradeSide::Buy
+            };
+            quote(i * 10, 100.0, (i * i) as f64, 99.5, 100.5, side)
+        })
+        .collect::<Vec<_>>();
+    for tick in &ticks {
+        receiver.ingest_tick(tick.clone());
+    }
+    receiver.flush_ticks();
+
+    // M1 与单独聚合的结果一致，并按 SBar id 可查
+    let mut agg = TickBarAggregator::new();
+    let mut expected = ticks
+        .iter()
+        .flat_map(|x| agg.update_with_flow(x.clone()))
+        .collect::<Vec<_>>();
+    expected.extend(agg.flush_with_flow());
+    let m1 = receiver.mtc().get_sbar_window(Timeframe::M1, usize::MAX);
+    assert_eq!(m1.len(), 5);
+    assert_eq!(
+        receiver
+            .order_flows(Timeframe::M1)
+            .into_iter()
+            .map(|(_, flow)| flow)
+            .collect::<Vec<_>>(),
+        expected.iter().map(|(_, flow)| *flow).collect::<Vec<_>>()
+    );
+    for bar in &m1 {
+        let flow = receiver
+            .order_flow(Timeframe::M1, bar.id.expect("id"))
+            .expect("m1 flow");
+        assert_eq!(flow.buy_volume + flow.sell_volume, bar.volume);
+    }
+
+    // M5 为所含 M1 之和，秒级周期同样记录
+    let m5 = receiver.mtc().get_sbar_window(Timeframe::M5, usize::MAX);
+    assert_eq!(m5.len(), 1);
+    let total = expected
+        .iter()
+        .fold(OrderFlow::default(), |acc, (_, x)| OrderFlow {
+            buy_volume: acc.buy_volume + x.buy_volume,
+            sell_volume: acc.sell_volume + x.sell_volume,
+        });
+    assert_eq!(
+        receiver.order_flow(Timeframe::M5, m5[0].id.expect("id")),
+        Some(total)
+    );
+    let s30 = receiver.mtc().get_sbar_window(Timeframe::S30, usize::MAX);
+    assert_eq!(s30.len(), 10);
+    assert_eq!(receiver.order_flows(Timeframe::S30).len(), s30.len());
+    assert!(receiver.order_flows(Timeframe::H1).is_empty());
+}
+
+#[test]
+fn receiver_keeps_only_recent_order_flows() {
+    let mut receiver = DataReceiver::new(MultiTimeframeContext::new("I2601.DCE"));
+    receiver.register_timeframe(Timeframe::M1);
+    let minutes = Const::LOOKBACK_LIMIT as i64 + 50;
+    for i in 0..minutes {
+        let side = if i % 2 == 0 {
+            TradeSide::Buy
+        } else {
+            TradeSide::Sell
+        };
+        receiver.ingest_tick(quote(i * 60, 100.0, i as f64, 99.5, 100.5, side));
+    }
+    receiver.flush_ticks();
+
+    let m1 = receiver.mtc().get_sbar_window(Timeframe::M1, usize::MAX);
+    assert_eq!(m1.len(), minutes as usize);
+    let kept = receiver
+        .order_flows(Timeframe::M1)
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    let recent = m1[m1.len() - Const::LOOKBACK_LIMIT..]
+        .iter()
+        .map(|x| x.id.expect("id"))
+        .collect::<Vec<_>>();
+    assert_eq!(kept, recent);
+    assert!(
+        receiver
+            .order_flow(Timeframe::M1, m1[0].id.expect("id"))
+            .is_none()
+    );
+}
+
+fn quote(second: i64, price: f64, volume: f64, bid: f64, ask: f64, side: TradeSide) -> TickInput {
+    TickInput {
+        symbol: "I2601".to_string(),
+        exchange: "DCE".to_string(),
+        datetime: at(second),
+        last_price: price,
+        volume,
+        turnover: volume * price,
+        open_interest: 100.0,
+        bid_price: bid,
+        bid_volume: if bid > 0.0 { 10.0 } else { 0.0 },
+        ask_price: ask,
+        ask_volume: if ask > 0.0 { 10.0 } else { 0.0 },
+        side,
+    }
+}
+
+fn at(second: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 2, 9, 0, 0).unwrap() + chrono::Duration::seconds(second)
+}
@@ -0,0 +1,206 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+use struxis::{
+    DataReceiver, MarketBarInput, MultiTimeframeContext, QualityAction, QualityCheck, QualityGate,
+    QualityPolicy, SBar, SessionCalendar, Timeframe, TradingSession,
+};
+
+#[test]
+fn default_policy_flags_without_touching_bars() {
+    let mut gate = QualityGate::new("I2601.DCE", Timeframe::M15, QualityPolicy::default());
+    let bad = SBar {
+        high_price: 99.0,
+        low_price: 101.0,
+        volume: -5.0,
+        ..bar(dt(2024, 3, 8, 9, 15), 100.0)
+    };
+    assert!(gate.check(bar(dt(2024, 3, 8, 9, 0), 100.0)).is_some());
+    for _ in 0..2 {
+        let kept = gate.check(bad.clone()).expect("flagged only");
+        assert_eq!(prices(&kept), prices(&bad));
+        assert_eq!(kept.volume, bad.volume);
+    }
+
+    let report = gate.report();
+    assert_eq!((report.bars, report.accepted, report.flagged), (3, 3, 2));
+    assert_eq!(report.count(QualityCheck::Ohlc), 2);
+    assert_eq!(report.count(QualityCheck::NegativeVolume), 2);
+    assert_eq!(report.count(QualityCheck::Duplicate), 1);
+    assert!(
+        report
+            .issues
+            .iter()
+            .all(|x| x.action == QualityAction::Flag)
+    );
+}
+
+#[test]
+fn strict_policy_rejects_and_repairs() {
+    let mut gate = QualityGate::new("I2601.DCE", Timeframe::M15, QualityPolicy::strict());
+    assert!(gate.check(bar(dt(2024, 3, 8, 9, 15), 100.0)).is_some());
+    // 重复、倒退与无效价格无法修复
+    assert!(gate.check(bar(dt(2024, 3, 8, 9, 15), 100.0)).is_none());
+    assert!(gate.check(bar(dt(2024, 3, 8, 9, 0), 100.0)).is_none());
+    assert!(gate.check(bar(dt(2024, 3, 8, 9, 30), f64::NAN)).is_none());
+
+    let repaired = gate
+        .check(SBar {
+            open_price: 101.5,
+            high_price: 101.0,
+            low_price: 99.0,
+            turnover: -1.0,
+            ..bar(dt(2024, 3, 8, 9, 30), 100.0)
+        })
+        .expect("repaired");
+    assert_eq!((repaired.high_price, repaired.low_price), (101.5, 99.0));
+    assert_eq!(repaired.turnover, 0.0);
+
+    // 跳变超过 10% 默认只标记；改为修复后截断到阈值内
+    let spike = bar(dt(2024, 3, 8, 9, 45), 130.0);
+    let kept = gate.check(spike.clone()).expect("flagged only");
+    assert_eq!(prices(&kept), prices(&spike));
+    gate.set_policy(QualityPolicy {
+        outlier: QualityAction::Repair,
+        ..QualityPolicy::strict()
+    });
+    let clamped = gate
+        .check(bar(dt(2024, 3, 8, 10, 0), 100.0))
+        .expect("clamped");
+    assert!((clamped.low_price - 117.0).abs() < 1e-9);
+    assert!((clamped.close_price - 117.0).abs() < 1e-9);
+
+    let report = gate.report();
+    assert_eq!(
+        (
+            report.bars,
+            report.accepted,
+            report.rejected,
+            report.repaired
+        ),
+        (7, 4, 3, 2)
+    );
+    assert_eq!(report.count(QualityCheck::Outlier), 2);
+    assert!(report.to_string().contains("rejected=3"));
+}
+
+#[test]
+fn gaps_follow_the_trading_calendar() {
+    let calendar = SessionCalendar::from_session(&ctp_session()).expect("session");
+    let mut gate = QualityGate::new("I2601.DCE", Timeframe::M15, QualityPolicy::default())
+        .with_calendar(calendar);
+    // 区间终点标记：跨小节休息、午休、收盘到夜盘、周五夜盘到周一均不算缺口
+    let friday = [
+        (10, 0),
+        (10, 15),
+        (10, 45),
+        (11, 0),
+        (11, 15),
+        (11, 30),
+        (13, 45),
+        (14, 0),
+        (14, 15),
+        (14, 30),
+        (14, 45),
+        (15, 0),
+        (21, 15),
+        (21, 30),
+        (21, 45),
+        (22, 0),
+        (22, 15),
+        (22, 30),
+        (22, 45),
+        (23, 0),
+    ];
+    for (h, m) in friday {
+        gate.check(bar(dt(2024, 3, 8, h, m), 100.0));
+    }
+    gate.check(bar(dt(2024, 3, 11, 9, 15), 100.0));
+    assert!(gate.report().is_clean(), "{:?}", gate.report().issues);
+
+    // 缺 09:30、09:45 两根
+    gate.check(bar(dt(2024, 3, 11, 10, 0), 100.0));
+    assert_eq!(gate.report().count(QualityCheck::Gap), 1);
+    assert_eq!(gate.report().missing_bars, 2);
+}
+
+#[test]
+fn receiver_reports_quality_per_symbol_and_timeframe() {
+    let mut receiver = DataReceiver::new(MultiTimeframeContext::new("I2601.DCE"));
+    receiver.register_timeframe(Timeframe::M15);
+    receiver
+        .set_session("I2601", "DCE", &ctp_session())
+        .expect("session");
+    receiver.set_quality_policy(QualityPolicy::strict());
+
+    for datetime in [
+        dt(2024, 3, 8, 9, 15),
+        dt(2024, 3, 8, 9, 30),
+        dt(2024, 3, 8, 9, 30),
+        dt(2024, 3, 8, 10, 15),
+    ] {
+        receiver.ingest_bar(input(datetime, 100.0));
+    }
+    assert_eq!(receiver.mtc().count(Timeframe::M15), 3);
+
+    let report = receiver
+        .quality_report("I2601", "DCE", Timeframe::M15)
+        .expect("report");
+    assert_eq!(report.symbol, "I2601.DCE");
+    assert_eq!(report.rejected, 1);
+    assert_eq!(report.count(QualityCheck::Duplicate), 1);
+    assert_eq!(report.missing_bars, 2);
+    assert!(
+        receiver
+            .quality_report("I2601", "DCE", Timeframe::H1)
+            .is_none()
+    );
+    assert_eq!(receiver.quality_reports().len(), 1);
+}
+
+fn ctp_session() -> TradingSession {
+    TradingSession {
+        sections: [
+            ("21:00", "23:00"),
+            ("09:00", "10:15"),
+            ("10:30", "11:30"),
+            ("13:30", "15:00"),
+        ]
+        .into_iter()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect(),
+        utc_offset_minutes: 0,
+    }
+}
+
+fn prices(bar: &SBar) -> (f64, f64, f64, f64) {
+    (
+        bar.open_price,
+        bar.high_price,
+        bar.low_price,
+        bar.close_price,
+    )
+}
+
+fn bar(datetime: DateTime<Utc>, price: f64) -> SBar {
+    input(datetime, price).into_sbar()
+}
+
+fn input(datetime: DateTime<Utc>, price: f64) -> MarketBarInput {
+    MarketBarInput {
+        symbol: "I2601".to_string(),
+        exchange: "DCE".to_string(),
+        timeframe: Timeframe::M15,
+        datetime,
+        open_price: price,
+        high_price: price + 0.5,
+        low_price: price - 0.5,
+        close_price: price,
+        volume: 10.0,
+        open_interest: 100.0,
+        turnover: 1000.0,
+    }
+}
+
+fn dt(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+}
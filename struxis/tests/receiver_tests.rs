@@ -1,6 +1,8 @@
 use chrono::{Timelike, Utc};
 
-use struxis::{DataReceiver, MarketBarInput, MultiTimeframeContext, TickInput, Timeframe};
+use struxis::{
+    DataReceiver, IdStrategy, MarketBarInput, MultiTimeframeContext, TickInput, Timeframe,
+};
 
 #[test]
 fn ingest_single_bar_works() {
@@ -43,6 +45,7 @@ fn ingest_tick_generates_m1() {
         volume: 10.0,
         turnover: 1000.0,
         open_interest: 200.0,
+        ..TickInput::default()
     });
     let emitted = receiver.ingest_tick(TickInput {
         symbol: "I2601".to_string(),
@@ -52,6 +55,7 @@ fn ingest_tick_generates_m1() {
         volume: 20.0,
         turnover: 2100.0,
         open_interest: 210.0,
+        ..TickInput::default()
     });
 
     assert_eq!(emitted, 1);
@@ -62,11 +66,17 @@ fn ingest_tick_generates_m1() {
 fn e2e_pipeline_is_deterministic_across_independent_runs() {
     let inputs = sample_bars(60);
 
-    let mut receiver_a = DataReceiver::new(MultiTimeframeContext::new("I2601.DCE"));
+    let mut receiver_a = DataReceiver::new(MultiTimeframeContext::with_id_strategy(
+        "I2601.DCE",
+        IdStrategy::Sequential,
+    ));
     receiver_a.register_timeframe(Timeframe::M5);
     receiver_a.ingest_batch(inputs.clone());
 
-    let mut receiver_b = DataReceiver::new(MultiTimeframeContext::new("I2601.DCE"));
+    let mut receiver_b = DataReceiver::new(MultiTimeframeContext::with_id_strategy(
+        "I2601.DCE",
+        IdStrategy::Sequential,
+    ));
     receiver_b.register_timeframe(Timeframe::M5);
     receiver_b.ingest_batch(inputs);
 
@@ -143,11 +153,17 @@ fn e2e_pipeline_is_deterministic_across_independent_runs() {
 fn ingest_batch_matches_incremental_ingest() {
     let inputs = sample_bars(50);
 
-    let mut batch_receiver = DataReceiver::new(MultiTimeframeContext::new("I2601.DCE"));
+    let mut batch_receiver = DataReceiver::new(MultiTimeframeContext::with_id_strategy(
+        "I2601.DCE",
+        IdStrategy::Sequential,
+    ));
     batch_receiver.register_timeframe(Timeframe::M5);
     batch_receiver.ingest_batch(inputs.clone());
 
-    let mut inc_receiver = DataReceiver::new(MultiTimeframeContext::new("I2601.DCE"));
+    let mut inc_receiver = DataReceiver::new(MultiTimeframeContext::with_id_strategy(
+        "I2601.DCE",
+        IdStrategy::Sequential,
+    ));
     inc_receiver.register_timeframe(Timeframe::M5);
     for input in inputs {
         inc_receiver.ingest_bar(input);
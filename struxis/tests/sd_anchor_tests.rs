@@ -0,0 +1,193 @@
+mod common;
+
+use chrono::Utc;
+
+use struxis::{
+    Direction, IdStrategy, MultiTimeframeContext, SBar, SupplyDemand, SupplyDemandAnchorMode,
+    SupplyDemandConfig, Swing, SwingState, Timeframe, Trend,
+};
+
+#[test]
+fn fixed_mode_keeps_trailing_window() {
+    let bars = bars(80);
+    let model = SupplyDemand::default();
+    let result = model.evaluate_anchored(&bars, &[], &[], &[], 0.0);
+    assert_eq!(result.anchor.mode, SupplyDemandAnchorMode::Fixed);
+    assert_eq!(result.anchor.bars, 50);
+    assert_eq!(result.anchor.sbar_id, Some(31));
+    assert_eq!(result.score, model.evaluate_window(&bars[30..]).score);
+}
+
+#[test]
+fn trend_flip_starts_at_current_trend() {
+    let bars = bars(80);
+    let model = with_mode(SupplyDemandAnchorMode::TrendFlip);
+    let trends = [trend(5, 40), trend(41, 80)];
+    let (start, anchor) = model.anchor_window(&bars, &[], &trends);
+    assert_eq!(
+        (start, anchor.mode, anchor.sbar_id),
+        (40, SupplyDemandAnchorMode::TrendFlip, Some(41))
+    );
+    assert_eq!((anchor.bars, anchor.clamped), (40, false));
+
+    // 翻转刚发生时向前补足最少根数
+    let (start, anchor) = model.anchor_window(&bars, &[], &[trend(79, 80)]);
+    assert_eq!((start, anchor.bars, anchor.clamped), (75, 5, true));
+
+    // 没有 trend 时退回固定窗口
+    let (_, anchor) = model.anchor_window(&bars, &[], &[]);
+    assert_eq!(anchor.mode, SupplyDemandAnchorMode::Fixed);
+}
+
+#[test]
+fn anchor_older_than_max_bars_is_reported_as_clamped() {
+    let bars = bars(400);
+    let model = SupplyDemand::with_config(SupplyDemandConfig {
+        anchor_mode: SupplyDemandAnchorMode::TrendFlip,
+        anchor_max_bars: 100,
+        ..SupplyDemandConfig::default()
+    });
+    let (start, anchor) = model.anchor_window(&bars, &[], &[trend(10, 400)]);
+    assert_eq!((start, anchor.sbar_id, anchor.bars), (300, Some(301), 100));
+    assert!(anchor.clamped);
+
+    // 起点在截断范围内时不受影响
+    let (start, anchor) = model.anchor_window(&bars, &[], &[trend(350, 400)]);
+    assert_eq!((start, anchor.bars, anchor.clamped), (349, 51, false));
+
+    // 找不到的起点仍退回固定窗口
+    let (_, anchor) = model.anchor_window(&bars[..200], &[], &[trend(350, 400)]);
+    assert_eq!(anchor.mode, SupplyDemandAnchorMode::Fixed);
+}
+
+#[test]
+fn major_swing_skips_minor_and_forming_swings() {
+    let bars = bars(80);
+    let model = with_mode(SupplyDemandAnchorMode::MajorSwing);
+    let swings = [
+        swing(1, 20, 10.0, SwingState::Confirmed),
+        swing(20, 45, 12.0, SwingState::Confirmed),
+        swing(45, 60, 2.0, SwingState::Confirmed),
+        swing(60, 80, 30.0, SwingState::Forming),
+    ];
+    // 平均幅度 8，最新的主要 swing 从 id 20 开始
+    let (_, anchor) = model.anchor_window(&bars, &swings, &[]);
+    assert_eq!(anchor.mode, SupplyDemandAnchorMode::MajorSwing);
+    assert_eq!(anchor.sbar_id, Some(20));
+}
+
+#[test]
+fn volume_cluster_and_auto_fallback_order() {
+    let mut bars = bars(120);
+    bars[90].volume = 500.0;
+    let model = with_mode(SupplyDemandAnchorMode::VolumeCluster);
+    let (_, anchor) = model.anchor_window(&bars, &[], &[]);
+    assert_eq!(anchor.mode, SupplyDemandAnchorMode::VolumeCluster);
+    assert_eq!(anchor.sbar_id, Some(91));
+
+    // 持仓突变同样视为集聚点
+    for bar in &mut bars[110..] {
+        bar.open_interest += 400.0;
+    }
+    let (_, anchor) = model.anchor_window(&bars, &[], &[]);
+    assert_eq!(anchor.sbar_id, Some(111));
+
+    let auto = SupplyDemand::with_config(SupplyDemandConfig {
+        anchor_mode: SupplyDemandAnchorMode::Auto,
+        anchor_max_bars: 60,
+        ..SupplyDemandConfig::default()
+    });
+    let (_, anchor) = auto.anchor_window(&bars, &[], &[trend(1, 120)]);
+    assert_eq!(anchor.mode, SupplyDemandAnchorMode::TrendFlip);
+    assert_eq!((anchor.sbar_id, anchor.bars), (Some(61), 60));
+    assert!(anchor.clamped);
+    let (_, anchor) = auto.anchor_window(&bars, &[], &[]);
+    assert_eq!(anchor.mode, SupplyDemandAnchorMode::VolumeCluster);
+}
+
+#[test]
+fn anchor_mode_is_configurable_from_yaml_and_reported_by_context() {
+    let config =
+        SupplyDemandConfig::from_yaml_str("anchor_mode: auto\nanchor_min_bars: 8\n").expect("yaml");
+    assert_eq!(config.anchor_mode, SupplyDemandAnchorMode::Auto);
+    assert_eq!(config.anchor_min_bars, 8);
+
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    mtc.set_sd_config(Timeframe::M15, config.clone());
+    let mut wave = common::wave_bars(300);
+    wave[280].volume = 800.0;
+    for bar in wave {
+        mtc.append(Timeframe::M15, bar);
+    }
+
+    // context 用完整的 SBar/Swing/Trend 序列选取起点
+    let sbars = mtc.get_sbar_window(Timeframe::M15, usize::MAX);
+    let (start, expected) = SupplyDemand::with_config(config).anchor_window(
+        &sbars,
+        &mtc.get_swing_window(Timeframe::M15, usize::MAX),
+        &mtc.get_trend_window(Timeframe::M15, usize::MAX),
+    );
+    let sd = mtc.get_sd(Timeframe::M15).expect("sd");
+    assert_eq!(sd.anchor, expected);
+    assert_ne!(sd.anchor.mode, SupplyDemandAnchorMode::Fixed);
+    assert_eq!(sbars[start].id, sd.anchor.sbar_id);
+}
+
+fn with_mode(anchor_mode: SupplyDemandAnchorMode) -> SupplyDemand {
+    SupplyDemand::with_config(SupplyDemandConfig {
+        anchor_mode,
+        ..SupplyDemandConfig::default()
+    })
+}
+
+fn trend(sbar_start_id: u64, sbar_end_id: u64) -> Trend {
+    Trend {
+        id: Some(sbar_start_id),
+        direction: Direction::Up,
+        swing_start_id: 1,
+        swing_end_id: 1,
+        sbar_start_id,
+        sbar_end_id,
+        high_price: 110.0,
+        low_price: 100.0,
+        span: (sbar_end_id - sbar_start_id) as usize,
+        volume: 0.0,
+        start_oi: 0.0,
+        end_oi: 0.0,
+        is_completed: false,
+        created_at: Utc::now(),
+    }
+}
+
+fn swing(sbar_start_id: u64, sbar_end_id: u64, distance: f64, state: SwingState) -> Swing {
+    Swing {
+        id: Some(sbar_start_id),
+        direction: Direction::Up,
+        cbar_start_id: sbar_start_id,
+        cbar_end_id: sbar_end_id,
+        sbar_start_id,
+        sbar_end_id,
+        high_price: 100.0 + distance,
+        low_price: 100.0,
+        span: (sbar_end_id - sbar_start_id) as usize,
+        volume: 0.0,
+        start_oi: 0.0,
+        end_oi: 0.0,
+        state,
+        created_at: Utc::now(),
+    }
+}
+
+/// id 从 1 开始，成交量与持仓平稳。
+fn bars(count: usize) -> Vec<SBar> {
+    common::wave_bars(count)
+        .into_iter()
+        .enumerate()
+        .map(|(idx, mut bar)| {
+            bar.id = Some(idx as u64 + 1);
+            bar.open_interest = 1000.0 + (idx % 2) as f64;
+            bar
+        })
+        .collect()
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use struxis::calibration::{calibrate, collect_samples};
+use struxis::{
+    CalibrationConfig, DataError, Direction, IdStrategy, MultiTimeframeContext, SBar,
+    SupplyDemandConfig, SupplyDemandProfileConfig, Timeframe,
+};
+
+#[test]
+fn samples_are_labeled_with_forward_return_and_swing_direction() {
+    let bars = wave_bars(160);
+    let config = CalibrationConfig {
+        horizon: 5,
+        warmup: 20,
+        ..CalibrationConfig::default()
+    };
+    let samples = collect_samples(
+        "I8888.XDCE",
+        Timeframe::M15,
+        &bars,
+        &SupplyDemandConfig::default(),
+        &config,
+    );
+    // 预热之前与最后 horizon 根没有样本
+    assert_eq!(samples.len(), 160 - 20 - 5);
+    assert_eq!(samples[0].sbar_id, Some(21));
+    let expected = (bars[25].close_price - bars[20].close_price) / bars[20].close_price;
+    assert!((samples[0].forward_return - expected).abs() < 1e-12);
+
+    // swing 标签取全部数据处理完后覆盖该 SBar 的 swing
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    for bar in &bars {
+        mtc.append(Timeframe::M15, bar.clone());
+    }
+    let swings = mtc.get_swing_window(Timeframe::M15, usize::MAX);
+    assert!(!swings.is_empty());
+    for sample in &samples {
+        let id = sample.sbar_id.expect("id");
+        let expected = swings
+            .iter()
+            .rev()
+            .find(|x| x.sbar_start_id <= id && id < x.sbar_end_id)
+            .map_or(Direction::None, |x| x.direction);
+        assert_eq!(sample.swing_direction, expected, "sbar {id}");
+    }
+    assert!(samples.iter().any(|x| x.swing_direction != Direction::None));
+}
+
+#[test]
+fn calibration_beats_baseline_in_sample_and_orders_thresholds() {
+    let bars = wave_bars(240);
+    let config = CalibrationConfig {
+        horizon: 5,
+        warmup: 20,
+        iterations: 200,
+        ..CalibrationConfig::default()
+    };
+    let base = SupplyDemandConfig::default();
+    let report = calibrate("I8888.XDCE", Timeframe::M15, &bars, &base, &config).expect("report");
+    // 分界后 horizon 个样本的前瞻收益与样本内重叠，不计入样本外
+    let samples = 240 - 20 - 5;
+    let train = (samples as f64 * config.train_ratio) as usize;
+    assert_eq!(report.in_sample.samples, train);
+    assert_eq!(report.out_of_sample.samples, samples - train - 5);
+    assert!(report.in_sample.objective >= report.baseline_in_sample.objective);
+    assert!(report.out_of_sample.objective.is_finite());
+
+    let calibrated = &report.config;
+    let layers = calibrated.layer1_weight + calibrated.layer2_weight + calibrated.layer3_weight;
+    assert!((layers - 1.0).abs() < 1e-9);
+    assert!(calibrated.stable_threshold >= calibrated.weakening_threshold);
+    assert!(calibrated.weakening_threshold >= calibrated.critical_threshold);
+    assert_eq!(calibrated.anchor_window, base.anchor_window);
+
+    // 同一 seed 结果可复现
+    let again = calibrate("I8888.XDCE", Timeframe::M15, &bars, &base, &config).expect("report");
+    assert_eq!(again.config.f1_weight, calibrated.f1_weight);
+
+    let short = calibrate("I8888.XDCE", Timeframe::M15, &bars[..40], &base, &config);
+    assert!(matches!(short, Err(DataError::InsufficientSamples(_))));
+}
+
+#[test]
+fn written_profile_resolves_to_calibrated_config() {
+    let bars = wave_bars(200);
+    let config = CalibrationConfig {
+        horizon: 5,
+        warmup: 20,
+        iterations: 50,
+        ..CalibrationConfig::default()
+    };
+    let path = std::env::temp_dir().join(format!(
+        "struxis_sd_calibration_{}.yaml",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    std::fs::write(&path, "default:\n  keyzone_bias_scale: 0.4\n").expect("seed profile");
+
+    let base = SupplyDemandConfig::default();
+    let m15 = calibrate("I8888.XDCE", Timeframe::M15, &bars, &base, &config).expect("m15");
+    m15.write_profile(&path).expect("write m15");
+    let bars_h1 = bars
+        .iter()
+        .map(|x| SBar {
+            timeframe: Timeframe::H1,
+            ..x.clone()
+        })
+        .collect::<Vec<_>>();
+    let h1 = calibrate("I8888.XDCE", Timeframe::H1, &bars_h1, &base, &config).expect("h1");
+    h1.write_profile(&path).expect("write h1");
+    assert_eq!(m15.profile_key(), "I8888.15m");
+
+    let profile = SupplyDemandProfileConfig::from_yaml_file(&path).expect("load profile");
+    for report in [&m15, &h1] {
+        let resolved = profile.resolve_for("I8888", report.timeframe);
+        assert_eq!(resolved.f7_weight, report.config.f7_weight);
+        assert_eq!(resolved.stable_threshold, report.config.stable_threshold);
+        assert_eq!(resolved.keyzone_bias_scale, 0.4);
+    }
+
+    let raw = std::fs::read_to_string(&path).expect("read profile");
+    assert!(raw.contains("calibration:"));
+    assert!(raw.contains("out_of_sample:"));
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn write_profile_leaves_commented_profiles_untouched() {
+    let bars = wave_bars(200);
+    let config = CalibrationConfig {
+        horizon: 5,
+        warmup: 20,
+        iterations: 20,
+        ..CalibrationConfig::default()
+    };
+    let report = calibrate(
+        "I8888.XDCE",
+        Timeframe::M15,
+        &bars,
+        &SupplyDemandConfig::default(),
+        &config,
+    )
+    .expect("report");
+
+    let path =
+        std::env::temp_dir().join(format!("struxis_sd_commented_{}.yaml", std::process::id()));
+    for seed in [
+        "# hand maintained\ndefault:\n  keyzone_bias_scale: 0.4\n",
+        "default:\n  keyzone_bias_scale: 0.4 # tuned\n",
+    ] {
+        std::fs::write(&path, seed).expect("seed profile");
+        let result = report.write_profile(&path);
+        assert!(matches!(result, Err(DataError::ProfileHasComments(_))));
+        assert_eq!(std::fs::read_to_string(&path).expect("read"), seed);
+    }
+    let _ = std::fs::remove_file(path);
+}
+
+/// 成交量与持仓逐根变化。
+fn wave_bars(count: usize) -> Vec<SBar> {
+    common::wave_bars(count)
+        .into_iter()
+        .enumerate()
+        .map(|(i, bar)| SBar {
+            volume: 100.0 + ((i * 13) % 7) as f64 * 20.0,
+            open_interest: 1000.0 + ((i * 11) % 5) as f64 * 10.0,
+            ..bar
+        })
+        .collect()
+}
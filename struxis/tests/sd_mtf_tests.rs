@@ -0,0 +1,157 @@
+mod common;
+
+use chrono::Utc;
+
+use struxis::{
+    Direction, IdStrategy, MultiTimeframeContext, SBar, SupplyDemand, SupplyDemandConfig, Swing,
+    SwingState, Timeframe, TimeframeStructure,
+};
+
+#[test]
+fn swing_relative_strength_compares_same_direction_swings() {
+    // 上涨 swing 幅度 10 -> 15：走强
+    let swings = [
+        swing(Direction::Up, 100.0, 110.0),
+        swing(Direction::Down, 104.0, 110.0),
+        swing(Direction::Up, 104.0, 119.0),
+    ];
+    assert!((SupplyDemand::swing_relative_strength(&swings) - 0.5).abs() < 1e-9);
+
+    // 下跌 swing 幅度 6 -> 3：空头走弱，记为正
+    let swings = [
+        swing(Direction::Down, 104.0, 110.0),
+        swing(Direction::Up, 104.0, 119.0),
+        swing(Direction::Down, 116.0, 119.0),
+    ];
+    assert!((SupplyDemand::swing_relative_strength(&swings) - 0.5).abs() < 1e-9);
+
+    // 下跌 swing 幅度翻倍以上时截断到 -1
+    let swings = [
+        swing(Direction::Down, 104.0, 110.0),
+        swing(Direction::Up, 104.0, 119.0),
+        swing(Direction::Down, 95.0, 119.0),
+    ];
+    assert_eq!(SupplyDemand::swing_relative_strength(&swings), -1.0);
+
+    // 形成中的 swing 不参与比较，仍以最近两个已确认的上涨 swing 计算
+    let mut forming = swing(Direction::Down, 90.0, 119.0);
+    forming.state = SwingState::Forming;
+    let swings = [
+        swing(Direction::Up, 100.0, 110.0),
+        swing(Direction::Down, 104.0, 110.0),
+        swing(Direction::Up, 104.0, 119.0),
+        forming,
+    ];
+    assert!((SupplyDemand::swing_relative_strength(&swings) - 0.5).abs() < 1e-9);
+
+    let single = [swing(Direction::Up, 100.0, 110.0)];
+    assert_eq!(SupplyDemand::swing_relative_strength(&single), 0.0);
+    assert_eq!(SupplyDemand::swing_relative_strength(&[]), 0.0);
+}
+
+#[test]
+fn mtf_alignment_weights_trend_and_swing_of_other_timeframes() {
+    let model = SupplyDemand::default();
+    assert_eq!(model.mtf_alignment(&[]), 0.0);
+
+    let all_up = [
+        structure(Timeframe::M15, Direction::Up, Direction::Up),
+        structure(Timeframe::H1, Direction::Up, Direction::Up),
+    ];
+    assert_eq!(model.mtf_alignment(&all_up), 1.0);
+
+    // 默认 trend 0.6、swing 0.4：(0.6 - 0.4 - 0.6 + 0) / 2
+    let mixed = [
+        structure(Timeframe::M15, Direction::Up, Direction::Down),
+        structure(Timeframe::H1, Direction::Down, Direction::None),
+    ];
+    assert!((model.mtf_alignment(&mixed) + 0.2).abs() < 1e-9);
+
+    let swing_only = SupplyDemand::with_config(
+        SupplyDemandConfig::from_yaml_str("mtf_trend_weight: 0.0\n").expect("yaml"),
+    );
+    assert_eq!(swing_only.mtf_alignment(&mixed), -0.5);
+}
+
+#[test]
+fn context_feeds_other_timeframes_into_f7_and_f9() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    mtc.register(Timeframe::H1);
+    let bars = common::wave_bars(401);
+    for (idx, bar) in bars[..400].iter().enumerate() {
+        mtc.append(Timeframe::M15, bar.clone());
+        if idx % 4 == 3 {
+            let group = &bars[idx - 3..=idx];
+            mtc.append(Timeframe::H1, merge(group));
+        }
+    }
+    // H1 收盘后的下一根 M15 读到的是最新的 H1 结构
+    mtc.append(Timeframe::M15, bars[400].clone());
+
+    let others = mtc.timeframe_structures(Timeframe::M15);
+    assert_eq!(others.len(), 1);
+    let h1 = others[0];
+    assert_eq!(h1.timeframe, Timeframe::H1);
+    assert_eq!(
+        Some(h1.swing_direction),
+        mtc.get_swing_window(Timeframe::H1, 1)
+            .last()
+            .map(|x| x.direction)
+    );
+    assert_ne!(h1.swing_direction, Direction::None);
+
+    let model = SupplyDemand::default();
+    let sd = mtc.get_sd(Timeframe::M15).expect("sd");
+    assert_eq!(sd.factors.f9_mtf_alignment, model.mtf_alignment(&others));
+    assert_ne!(sd.factors.f9_mtf_alignment, 0.0);
+    assert_eq!(
+        sd.factors.f7_swing_relative_strength,
+        SupplyDemand::swing_relative_strength(&mtc.get_swing_window(Timeframe::M15, usize::MAX))
+    );
+}
+
+fn structure(
+    timeframe: Timeframe,
+    trend_direction: Direction,
+    swing_direction: Direction,
+) -> TimeframeStructure {
+    TimeframeStructure {
+        timeframe,
+        trend_direction,
+        swing_direction,
+    }
+}
+
+fn swing(direction: Direction, low_price: f64, high_price: f64) -> Swing {
+    Swing {
+        id: None,
+        direction,
+        cbar_start_id: 0,
+        cbar_end_id: 0,
+        sbar_start_id: 0,
+        sbar_end_id: 0,
+        high_price,
+        low_price,
+        span: 5,
+        volume: 0.0,
+        start_oi: 0.0,
+        end_oi: 0.0,
+        state: SwingState::Confirmed,
+        created_at: Utc::now(),
+    }
+}
+
+fn merge(group: &[SBar]) -> SBar {
+    let first = &group[0];
+    let last = &group[group.len() - 1];
+    SBar {
+        timeframe: Timeframe::H1,
+        open_price: first.open_price,
+        high_price: group.iter().map(|x| x.high_price).fold(f64::MIN, f64::max),
+        low_price: group.iter().map(|x| x.low_price).fold(f64::MAX, f64::min),
+        close_price: last.close_price,
+        volume: group.iter().map(|x| x.volume).sum(),
+        ..first.clone()
+    }
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use struxis::{
+    IdStrategy, MultiTimeframeContext, SBar, SupplyDemandConfig, SupplyDemandProfileConfig,
+    Timeframe,
+};
+
+const PROFILE: &str = r#"
+default:
+  stable_threshold: 0.65
+timeframe:
+  5m:
+    f8_weight: 0.30
+symbol_timeframe:
+  "I2601.15m":
+    stable_threshold: 0.55
+"#;
+
+#[test]
+fn registered_timeframes_resolve_profile_by_symbol_and_timeframe() {
+    let profile = SupplyDemandProfileConfig::from_yaml_str(PROFILE).expect("profile");
+    let mut mtc = MultiTimeframeContext::with_sd_profile("I2601.DCE", profile.clone());
+    mtc.register(Timeframe::M5);
+    mtc.register(Timeframe::M15);
+
+    let m5 = mtc.get_sd_config(Timeframe::M5).expect("m5");
+    assert_eq!(m5.f8_weight, 0.30);
+    assert_eq!(m5.stable_threshold, 0.65);
+    let m15 = mtc.get_sd_config(Timeframe::M15).expect("m15");
+    assert_eq!(m15.stable_threshold, 0.55);
+    assert_eq!(m15.f8_weight, SupplyDemandConfig::default().f8_weight);
+
+    // 没有 profile 时保持默认配置
+    let mut plain = MultiTimeframeContext::new("I2601.DCE");
+    plain.register(Timeframe::M5);
+    assert_eq!(
+        plain.get_sd_config(Timeframe::M5).expect("m5").f8_weight,
+        SupplyDemandConfig::default().f8_weight
+    );
+    assert!(plain.sd_profile().is_none());
+}
+
+#[test]
+fn reloading_profile_keeps_structure_state() {
+    let profile = SupplyDemandProfileConfig::from_yaml_str(PROFILE).expect("profile");
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I2601.DCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    mtc.set_sd_config(
+        Timeframe::M15,
+        SupplyDemandConfig {
+            stable_threshold: 0.9,
+            ..SupplyDemandConfig::default()
+        },
+    );
+    let bars = wave_bars(200);
+    for bar in &bars[..199] {
+        mtc.append(Timeframe::M15, bar.clone());
+    }
+    let ids_before = sbar_ids(&mtc);
+    let swings = mtc.get_swing_window(Timeframe::M15, usize::MAX);
+    let keyzones = mtc.get_keyzone_window(Timeframe::M15, usize::MAX);
+
+    mtc.set_sd_profile(profile);
+    assert_eq!(
+        mtc.get_sd_config(Timeframe::M15)
+            .expect("m15")
+            .stable_threshold,
+        0.55
+    );
+    assert_eq!(sbar_ids(&mtc), ids_before);
+    assert_eq!(
+        mtc.get_swing_window(Timeframe::M15, usize::MAX).len(),
+        swings.len()
+    );
+    assert_eq!(
+        mtc.get_keyzone_window(Timeframe::M15, usize::MAX).len(),
+        keyzones.len()
+    );
+
+    // 新配置从下一根 SBar 起参与评估
+    mtc.append(Timeframe::M15, bars[199].clone());
+    assert_eq!(mtc.count(Timeframe::M15), 200);
+    assert!(mtc.get_sd(Timeframe::M15).is_some());
+}
+
+fn sbar_ids(mtc: &MultiTimeframeContext) -> Vec<Option<u64>> {
+    mtc.get_sbar_window(Timeframe::M15, usize::MAX)
+        .iter()
+        .map(|x| x.id)
+        .collect()
+}
+
+/// I2601.DCE，价格整体上移 700。
+fn wave_bars(count: usize) -> Vec<SBar> {
+    common::wave_bars(count)
+        .into_iter()
+        .map(|bar| SBar {
+            symbol: "I2601".to_string(),
+            exchange: "DCE".to_string(),
+            open_price: bar.open_price + 700.0,
+            high_price: bar.high_price + 700.0,
+            low_price: bar.low_price + 700.0,
+            close_price: bar.close_price + 700.0,
+            turnover: 100.0 * (bar.close_price + 700.0),
+            ..bar
+        })
+        .collect()
+}
@@ -0,0 +1,213 @@
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc, Weekday};
+
+use struxis::{
+    DataReceiver, IdStrategy, MultiTimeframeContext, SBar, SessionBarAggregator, SessionCalendar,
+    TickInput, Timeframe, TradingSession,
+};
+
+#[test]
+fn ctp_session_closes_partial_bars_at_breaks() {
+    let calendar = SessionCalendar::from_session(&ctp_session()).expect("valid session");
+    let mut h1 = SessionBarAggregator::new(Timeframe::H1, calendar).expect("h1 aggregator");
+
+    // 周四夜盘 + 周五日盘，缺失 09:37 一分钟
+    let minutes = trading_minutes(dt(2024, 3, 7, 21, 0), dt(2024, 3, 8, 15, 0))
+        .into_iter()
+        .filter(|x| *x != dt(2024, 3, 8, 9, 37))
+        .collect::<Vec<_>>();
+    let mut bars = Vec::new();
+    for minute in &minutes {
+        bars.extend(h1.update(m1(*minute, 1.0)));
+    }
+    assert!(h1.flush().is_none(), "last bar closes at session end");
+
+    let starts = bars.iter().map(|x| x.datetime).collect::<Vec<_>>();
+    assert_eq!(
+        starts,
+        vec![
+            dt(2024, 3, 7, 21, 0),
+            dt(2024, 3, 7, 22, 0),
+            dt(2024, 3, 8, 9, 0),
+            dt(2024, 3, 8, 10, 0),
+            dt(2024, 3, 8, 11, 0),
+            dt(2024, 3, 8, 13, 30),
+            dt(2024, 3, 8, 14, 0),
+        ]
+    );
+    // 10:00 跨越 10:15-10:30 休息，11:00 在午休前截断，13:30 从开盘起算
+    let volumes = bars.iter().map(|x| x.volume).collect::<Vec<_>>();
+    assert_eq!(volumes, vec![60.0, 60.0, 59.0, 45.0, 30.0, 30.0, 60.0]);
+}
+
+#[test]
+fn missing_minutes_do_not_shift_later_bars() {
+    let mut m15 = SessionBarAggregator::new(Timeframe::M15, SessionCalendar::continuous())
+        .expect("m15 aggregator");
+    let mut bars = Vec::new();
+    for offset in (0..60).filter(|x| !(3..20).contains(x)) {
+        bars.extend(m15.update(m1(
+            dt(2024, 3, 8, 9, 0) + chrono::Duration::minutes(offset),
+            1.0,
+        )));
+    }
+
+    let starts = bars.iter().map(|x| x.datetime).collect::<Vec<_>>();
+    assert_eq!(
+        starts,
+        vec![
+            dt(2024, 3, 8, 9, 0),
+            dt(2024, 3, 8, 9, 15),
+            dt(2024, 3, 8, 9, 30),
+            dt(2024, 3, 8, 9, 45),
+        ]
+    );
+    assert_eq!(bars[0].volume, 3.0);
+    assert_eq!(bars[1].volume, 10.0);
+}
+
+#[test]
+fn night_session_belongs_to_next_trading_day() {
+    let calendar = SessionCalendar::from_session(&ctp_session()).expect("valid session");
+    assert_eq!(
+        calendar.trading_day(dt(2024, 3, 7, 21, 30)),
+        date(2024, 3, 8)
+    );
+    assert_eq!(
+        calendar.trading_day(dt(2024, 3, 8, 10, 0)),
+        date(2024, 3, 8)
+    );
+    // 周五夜盘与其后的周六凌晨都归属下周一
+    assert_eq!(
+        calendar.trading_day(dt(2024, 3, 8, 22, 0)),
+        date(2024, 3, 11)
+    );
+    assert_eq!(
+        calendar.trading_day(dt(2024, 3, 9, 0, 30)),
+        date(2024, 3, 11)
+    );
+    // 集合竞价分钟并入夜盘首根 bar
+    assert_eq!(
+        calendar.trading_day(dt(2024, 3, 7, 20, 59)),
+        date(2024, 3, 8)
+    );
+    assert!(!calendar.is_trading(dt(2024, 3, 8, 12, 0)));
+
+    let mut d1 = SessionBarAggregator::new(Timeframe::D1, calendar).expect("d1 aggregator");
+    let mut bars = Vec::new();
+    for minute in trading_minutes(dt(2024, 3, 7, 21, 0), dt(2024, 3, 11, 15, 0)) {
+        bars.extend(d1.update(m1(minute, 100.0)));
+    }
+    let days = bars.iter().map(|x| x.datetime).collect::<Vec<_>>();
+    assert_eq!(days, vec![dt(2024, 3, 8, 0, 0), dt(2024, 3, 11, 0, 0)]);
+    assert_eq!(bars[0].volume, bars[1].volume);
+}
+
+#[test]
+fn continuous_session_uses_calendar_days() {
+    let mut d1 = SessionBarAggregator::new(Timeframe::D1, SessionCalendar::continuous())
+        .expect("d1 aggregator");
+    let mut bars = Vec::new();
+    let start = dt(2024, 3, 9, 22, 0);
+    for offset in 0..(4 * 60) {
+        bars.extend(d1.update(m1(start + chrono::Duration::minutes(offset), 1.0)));
+    }
+
+    assert_eq!(bars.len(), 1);
+    assert_eq!(bars[0].datetime, dt(2024, 3, 9, 0, 0));
+    assert_eq!(bars[0].volume, 120.0);
+    let open = d1.flush().expect("open day bar");
+    assert_eq!(open.datetime, dt(2024, 3, 10, 0, 0));
+    assert_eq!(open.volume, 120.0);
+}
+
+#[test]
+fn receiver_aggregates_ticks_with_symbol_session() {
+    let mut receiver = DataReceiver::new(MultiTimeframeContext::with_id_strategy(
+        "I2605.DCE",
+        IdStrategy::Sequential,
+    ));
+    receiver.register_timeframe(Timeframe::M15);
+    receiver
+        .set_session("I2605", "DCE", &ctp_session())
+        .expect("valid session");
+
+    // 10:00 - 10:15 后休盘，10:14 的 tick 在 10:30 开盘时才结束该分钟
+    for minute in [
+        dt(2024, 3, 8, 10, 0),
+        dt(2024, 3, 8, 10, 14),
+        dt(2024, 3, 8, 10, 30),
+    ] {
+        receiver.ingest_tick(TickInput {
+            symbol: "I2605".to_string(),
+            exchange: "DCE".to_string(),
+            datetime: minute + chrono::Duration::seconds(3),
+            last_price: 800.0,
+            volume: 0.0,
+            turnover: 0.0,
+            open_interest: 1.0,
+            ..TickInput::default()
+        });
+    }
+
+    let bars = receiver.mtc().get_sbar_window(Timeframe::M15, 10);
+    assert_eq!(bars.len(), 1);
+    assert_eq!(bars[0].datetime, dt(2024, 3, 8, 10, 0));
+}
+
+fn ctp_session() -> TradingSession {
+    let sections = [
+        ("21:00", "23:00"),
+        ("09:00", "10:15"),
+        ("10:30", "11:30"),
+        ("13:30", "15:00"),
+    ];
+    TradingSession {
+        sections: sections
+            .iter()
+            .map(|(start, end)| (start.to_string(), end.to_string()))
+            .collect(),
+        utc_offset_minutes: 0,
+    }
+}
+
+/// `[start, end)` 内处于 CTP 交易时段的分钟（周末无交易）。
+fn trading_minutes(start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    let calendar = SessionCalendar::from_session(&ctp_session()).expect("valid session");
+    let mut out = Vec::new();
+    let mut cursor = start;
+    while cursor < end {
+        let weekend = matches!(cursor.weekday(), Weekday::Sat | Weekday::Sun);
+        if calendar.is_trading(cursor) && !weekend {
+            out.push(cursor);
+        }
+        cursor += chrono::Duration::minutes(1);
+    }
+    out
+}
+
+fn m1(datetime: DateTime<Utc>, volume: f64) -> SBar {
+    SBar {
+        id: None,
+        symbol: "I2605".to_string(),
+        exchange: "DCE".to_string(),
+        timeframe: Timeframe::M1,
+        datetime,
+        open_price: 800.0,
+        high_price: 801.0,
+        low_price: 799.0,
+        close_price: 800.5,
+        volume,
+        open_interest: 1000.0,
+        turnover: 800.0 * volume,
+    }
+}
+
+fn dt(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+        .single()
+        .expect("valid dt")
+}
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).expect("valid date")
+}
@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+
+use struxis::{
+    ContextConfig, FractalType, IdStrategy, MultiTimeframeContext, SBar, StructureMode, Timeframe,
+    load_market_bar_inputs,
+};
+
+#[test]
+fn as_of_snapshot_matches_live_structures_at_every_bar() {
+    let bars = dataset_bars(120);
+    for structure_mode in [StructureMode::Batch, StructureMode::Incremental] {
+        let mut mtc =
+            MultiTimeframeContext::with_config("I8888.XDCE", history_config(structure_mode));
+        mtc.register(Timeframe::M15);
+
+        let mut live = Vec::new();
+        for bar in &bars {
+            mtc.append(Timeframe::M15, bar.clone());
+            let sbar_id = mtc
+                .get_sbar_window(Timeframe::M15, 1)
+                .last()
+                .and_then(|x| x.id)
+                .expect("sbar id");
+            live.push((sbar_id, live_snapshot(&mtc)));
+        }
+
+        for (sbar_id, expected) in &live {
+            let snapshot = mtc
+                .get_structures_as_of(Timeframe::M15, *sbar_id)
+                .expect("history enabled");
+            assert_eq!(snapshot.sbar_id, *sbar_id);
+            let actual = format!(
+                "{:?}|{:?}|{:?}",
+                snapshot.fractals, snapshot.swings, snapshot.trends
+            );
+            assert_eq!(&actual, expected, "{structure_mode:?} sbar {sbar_id}");
+        }
+
+        // 回溯会改写已出现的结构，旧版本保留并标记 revised_at
+        let history = mtc
+            .get_structure_history(Timeframe::M15)
+            .expect("history enabled");
+        assert!(
+            history
+                .swing_versions()
+                .iter()
+                .any(|x| x.revised_at.is_some())
+        );
+        for version in history.swing_versions() {
+            assert!(version.revised_at.is_none_or(|x| x >= version.known_at));
+        }
+    }
+}
+
+#[test]
+fn history_is_opt_in_and_survives_checkpoint() {
+    let bars = dataset_bars(60);
+    let mut plain = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    plain.register(Timeframe::M15);
+    plain.append(Timeframe::M15, bars[0].clone());
+    assert!(plain.get_structure_history(Timeframe::M15).is_none());
+    assert!(plain.get_structures_as_of(Timeframe::M15, 1).is_none());
+
+    let mut mtc =
+        MultiTimeframeContext::with_config("I8888.XDCE", history_config(StructureMode::Batch));
+    mtc.register(Timeframe::M15);
+    for bar in &bars {
+        mtc.append(Timeframe::M15, bar.clone());
+    }
+    let path = std::env::temp_dir().join(format!(
+        "struxis_structure_history_{}.json",
+        std::process::id()
+    ));
+    mtc.save_checkpoint(&path).expect("save checkpoint");
+    let restored = MultiTimeframeContext::load_checkpoint(&path).expect("load checkpoint");
+    let _ = std::fs::remove_file(path);
+
+    for sbar_id in [10, 30, 60] {
+        let before = mtc
+            .get_structures_as_of(Timeframe::M15, sbar_id)
+            .expect("before");
+        let after = restored
+            .get_structures_as_of(Timeframe::M15, sbar_id)
+            .expect("after");
+        assert_eq!(format!("{before:?}"), format!("{after:?}"));
+    }
+}
+
+fn history_config(structure_mode: StructureMode) -> ContextConfig {
+    ContextConfig {
+        structure_mode,
+        id_strategy: IdStrategy::Sequential,
+        record_history: true,
+        ..ContextConfig::default()
+    }
+}
+
+fn live_snapshot(mtc: &MultiTimeframeContext) -> String {
+    let fractals = mtc
+        .get_cbar_window(Timeframe::M15, usize::MAX)
+        .into_iter()
+        .filter(|x| x.fractal_type != FractalType::None)
+        .collect::<Vec<_>>();
+    format!(
+        "{:?}|{:?}|{:?}",
+        fractals,
+        mtc.get_swing_window(Timeframe::M15, usize::MAX),
+        mtc.get_trend_window(Timeframe::M15, usize::MAX)
+    )
+}
+
+fn dataset_bars(limit: usize) -> Vec<SBar> {
+    let dataset = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("dataset")
+        .join("I8888.XDCE_15m.csv");
+    load_market_bar_inputs(dataset, "I8888", "XDCE", Timeframe::M15)
+        .expect("dataset should load")
+        .into_iter()
+        .take(limit)
+        .map(|x| x.into_sbar())
+        .collect()
+}
@@ -72,10 +72,10 @@ fn parse_datetime(value: &str) -> Result<DateTime<Utc>, String> {
         }
     }
 
-    if let Ok(d) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
-        if let Some(dt) = d.and_hms_opt(0, 0, 0) {
-            return Ok(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc));
-        }
+    if let Ok(d) = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        && let Some(dt) = d.and_hms_opt(0, 0, 0)
+    {
+        return Ok(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc));
     }
 
     Err(format!("invalid datetime: {value}"))
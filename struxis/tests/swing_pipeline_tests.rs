@@ -3,70 +3,72 @@ use std::path::PathBuf;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, Timelike, Utc};
 use serde::Deserialize;
 
-use struxis::{Direction, FractalType, MultiTimeframeContext, SBar, Timeframe};
+use struxis::{Direction, FractalType, MultiTimeframeContext, SBar, StructureMode, Timeframe};
 
 #[test]
 fn completed_swings_follow_bottom_top_and_top_bottom_contract() {
-    let mut mtc = MultiTimeframeContext::new("I8888.XDCE");
-    mtc.register(Timeframe::M15);
+    for mode in [StructureMode::Batch, StructureMode::Incremental] {
+        let mut mtc = MultiTimeframeContext::with_structure_mode("I8888.XDCE", mode);
+        mtc.register(Timeframe::M15);
 
-    for bar in sample_dataset_bars(300) {
-        mtc.append(Timeframe::M15, bar);
-    }
+        for bar in sample_dataset_bars(300) {
+            mtc.append(Timeframe::M15, bar);
+        }
 
-    let cbars = mtc.get_cbar_window(Timeframe::M15, usize::MAX);
-    let swings = mtc.get_swing_window(Timeframe::M15, usize::MAX);
-
-    let fractal_by_cbar_id = cbars
-        .iter()
-        .filter_map(|x| x.id.map(|id| (id, x.fractal_type)))
-        .collect::<std::collections::HashMap<_, _>>();
-
-    let completed = swings
-        .iter()
-        .filter(|x| x.state == struxis::SwingState::Confirmed)
-        .collect::<Vec<_>>();
-    assert!(
-        !completed.is_empty(),
-        "need completed swings to validate swing direction contract"
-    );
-
-    for swing in completed {
-        let start_ft = fractal_by_cbar_id
-            .get(&swing.cbar_start_id)
-            .copied()
-            .unwrap_or(FractalType::None);
-        let end_ft = fractal_by_cbar_id
-            .get(&swing.cbar_end_id)
-            .copied()
-            .unwrap_or(FractalType::None);
-
-        match swing.direction {
-            Direction::Up => {
-                assert_eq!(
-                    start_ft,
-                    FractalType::Bottom,
-                    "up swing must start from bottom fractal"
-                );
-                assert_eq!(
-                    end_ft,
-                    FractalType::Top,
-                    "up swing must end at top fractal"
-                );
-            }
-            Direction::Down => {
-                assert_eq!(
-                    start_ft,
-                    FractalType::Top,
-                    "down swing must start from top fractal"
-                );
-                assert_eq!(
-                    end_ft,
-                    FractalType::Bottom,
-                    "down swing must end at bottom fractal"
-                );
+        let cbars = mtc.get_cbar_window(Timeframe::M15, usize::MAX);
+        let swings = mtc.get_swing_window(Timeframe::M15, usize::MAX);
+
+        let fractal_by_cbar_id = cbars
+            .iter()
+            .filter_map(|x| x.id.map(|id| (id, x.fractal_type)))
+            .collect::<std::collections::HashMap<_, _>>();
+
+        let completed = swings
+            .iter()
+            .filter(|x| x.state == struxis::SwingState::Confirmed)
+            .collect::<Vec<_>>();
+        assert!(
+            !completed.is_empty(),
+            "need completed swings to validate swing direction contract"
+        );
+
+        for swing in completed {
+            let start_ft = fractal_by_cbar_id
+                .get(&swing.cbar_start_id)
+                .copied()
+                .unwrap_or(FractalType::None);
+            let end_ft = fractal_by_cbar_id
+                .get(&swing.cbar_end_id)
+                .copied()
+                .unwrap_or(FractalType::None);
+
+            match swing.direction {
+                Direction::Up => {
+                    assert_eq!(
+                        start_ft,
+                        FractalType::Bottom,
+                        "up swing must start from bottom fractal"
+                    );
+                    assert_eq!(
+                        end_ft,
+                        FractalType::Top,
+                        "up swing must end at top fractal"
+                    );
+                }
+                Direction::Down => {
+                    assert_eq!(
+                        start_ft,
+                        FractalType::Top,
+                        "down swing must start from top fractal"
+                    );
+                    assert_eq!(
+                        end_ft,
+                        FractalType::Bottom,
+                        "down swing must end at bottom fractal"
+                    );
+                }
+                _ => panic!("completed swing should not be non-directional"),
             }
-            _ => panic!("completed swing should not be non-directional"),
         }
     }
 }
@@ -136,10 +138,10 @@ fn parse_datetime(value: &str) -> Result<DateTime<Utc>, String> {
         }
     }
 
-    if let Ok(d) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
-        if let Some(dt) = d.and_hms_opt(0, 0, 0) {
-            return Ok(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc));
-        }
+    if let Ok(d) = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        && let Some(dt) = d.and_hms_opt(0, 0, 0)
+    {
+        return Ok(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc));
     }
 
     Err(format!("invalid datetime: {value}"))
@@ -147,44 +149,48 @@ fn parse_datetime(value: &str) -> Result<DateTime<Utc>, String> {
 
 #[test]
 fn incremental_append_keeps_completed_swings_directional() {
-    let mut mtc = MultiTimeframeContext::new("I8888.XDCE");
-    mtc.register(Timeframe::M15);
-
-    for bar in sample_bars(160) {
-        mtc.append(Timeframe::M15, bar);
-        let swings = mtc.get_swing_window(Timeframe::M15, usize::MAX);
-
-        for swing in swings.into_iter().filter(|x| x.state == struxis::SwingState::Confirmed) {
-            assert!(
-                matches!(swing.direction, Direction::Up | Direction::Down),
-                "completed swing must have directional state"
-            );
+    for mode in [StructureMode::Batch, StructureMode::Incremental] {
+        let mut mtc = MultiTimeframeContext::with_structure_mode("I8888.XDCE", mode);
+        mtc.register(Timeframe::M15);
+
+        for bar in sample_bars(160) {
+            mtc.append(Timeframe::M15, bar);
+            let swings = mtc.get_swing_window(Timeframe::M15, usize::MAX);
+
+            for swing in swings.into_iter().filter(|x| x.state == struxis::SwingState::Confirmed) {
+                assert!(
+                    matches!(swing.direction, Direction::Up | Direction::Down),
+                    "completed swing must have directional state"
+                );
+            }
         }
     }
 }
 
 #[test]
 fn confirmed_swings_are_cbar_continuous() {
-    let mut mtc = MultiTimeframeContext::new("I8888.XDCE");
-    mtc.register(Timeframe::M15);
+    for mode in [StructureMode::Batch, StructureMode::Incremental] {
+        let mut mtc = MultiTimeframeContext::with_structure_mode("I8888.XDCE", mode);
+        mtc.register(Timeframe::M15);
 
-    for bar in sample_dataset_bars(300) {
-        mtc.append(Timeframe::M15, bar);
-    }
+        for bar in sample_dataset_bars(300) {
+            mtc.append(Timeframe::M15, bar);
+        }
 
-    let swings = mtc
-        .get_swing_window(Timeframe::M15, usize::MAX)
-        .into_iter()
-        .filter(|x| x.state == struxis::SwingState::Confirmed)
-        .collect::<Vec<_>>();
-
-    assert!(swings.len() >= 2, "need multiple confirmed swings for continuity check");
-    for pair in swings.windows(2) {
-        assert_eq!(
-            pair[0].cbar_end_id,
-            pair[1].cbar_start_id,
-            "adjacent confirmed swings must connect on cbar endpoint"
-        );
+        let swings = mtc
+            .get_swing_window(Timeframe::M15, usize::MAX)
+            .into_iter()
+            .filter(|x| x.state == struxis::SwingState::Confirmed)
+            .collect::<Vec<_>>();
+
+        assert!(swings.len() >= 2, "need multiple confirmed swings for continuity check");
+        for pair in swings.windows(2) {
+            assert_eq!(
+                pair[0].cbar_end_id,
+                pair[1].cbar_start_id,
+                "adjacent confirmed swings must connect on cbar endpoint"
+            );
+        }
     }
 }
 
@@ -16,7 +16,6 @@ fn swing(id: u64, direction: Direction, cbar_start_id: u64, cbar_end_id: u64) ->
         volume: 1.0,
         start_oi: 1.0,
         end_oi: 1.0,
-        is_completed: false,
         state: SwingState::Forming,
         created_at: Utc::now(),
     }
@@ -0,0 +1,200 @@
+use struxis::synthetic::{OutlineCBar, ScriptStep};
+use struxis::{
+    ContextConfig, Direction, FractalType, IdStrategy, MarketScript, MultiTimeframeContext,
+    RandomWalk, RegimeSwitching, SBar, SeriesSpec, StructureMode, StructureOutline, Timeframe,
+};
+
+#[test]
+fn script_produces_expected_cbars_and_fractals() {
+    let spec = SeriesSpec::default();
+    let series = MarketScript::new(100.0)
+        .up(5)
+        .down(3)
+        .inclusive(3)
+        .up(2)
+        .breakout(Direction::Up)
+        .generate(&spec);
+    let expected = series.expected.as_ref().expect("script has ground truth");
+
+    // 种子 + 5 + 3 + 3 根内缩 + 2 + 突破
+    assert_eq!(series.sbars.len(), 15);
+    assert_eq!(expected.cbars.len(), 12);
+    let cluster = expected.cbars[8];
+    assert_eq!((cluster.sbar_start, cluster.sbar_end), (8, 11));
+    assert_eq!(
+        expected.fractals(),
+        vec![(5, FractalType::Top), (8, FractalType::Bottom)]
+    );
+    // 顶分型到底分型为一段已确认的下跌 swing
+    assert_eq!(expected.swings.len(), 1);
+    let swing = expected.swings[0];
+    assert_eq!(swing.direction, Direction::Down);
+    assert_eq!((swing.cbar_start, swing.cbar_end), (5, 8));
+    assert!(swing.confirmed);
+    assert!(expected.trends.is_empty());
+
+    for mode in [StructureMode::Batch, StructureMode::Incremental] {
+        assert_eq!(&run(&series.sbars, mode), expected, "{mode:?}");
+    }
+}
+
+#[test]
+fn multi_leg_script_structures_match_both_modes() {
+    let series = MarketScript::new(100.0)
+        .up(6)
+        .down(3)
+        .up(6)
+        .down(3)
+        .up(6)
+        .down(8)
+        .up(3)
+        .generate(&SeriesSpec::default());
+    let expected = series.expected.as_ref().expect("ground truth");
+    let fractals = expected.fractals();
+    assert_eq!(
+        fractals,
+        vec![
+            (6, FractalType::Top),
+            (9, FractalType::Bottom),
+            (15, FractalType::Top),
+            (18, FractalType::Bottom),
+            (24, FractalType::Top),
+            (32, FractalType::Bottom)
+        ]
+    );
+    // swing 从第一个分型开始，并按规则连到之后的分型
+    let first = expected.swings.first().expect("swing");
+    assert_eq!((first.direction, first.cbar_start), (Direction::Down, 6));
+    for swing in &expected.swings {
+        assert!(fractals.iter().any(|(i, _)| *i == swing.cbar_start));
+    }
+    for trend in &expected.trends {
+        assert!(trend.swing_end < expected.swings.len());
+    }
+
+    for mode in [StructureMode::Batch, StructureMode::Incremental] {
+        assert_eq!(&run(&series.sbars, mode), expected, "{mode:?}");
+    }
+}
+
+#[test]
+fn script_steps_are_recorded_in_order() {
+    let script = MarketScript::new(50.0)
+        .with_step(0.5, 0.8)
+        .up(2)
+        .inclusive(1)
+        .breakout(Direction::Down);
+    assert_eq!(
+        script.steps,
+        vec![
+            ScriptStep::Up(2),
+            ScriptStep::Inclusive(1),
+            ScriptStep::Breakout(Direction::Down)
+        ]
+    );
+    let series = script.generate(&SeriesSpec {
+        timeframe: Timeframe::M5,
+        ..SeriesSpec::default()
+    });
+    let cbars = &series.expected.as_ref().expect("ground truth").cbars;
+    assert_eq!(
+        cbars.last().copied(),
+        Some(OutlineCBar {
+            sbar_start: 4,
+            sbar_end: 4,
+            high: 50.4 + 1.0 - 1.5,
+            low: 49.6 + 1.0 - 1.5,
+            fractal_type: FractalType::None,
+        })
+    );
+    assert_eq!(
+        series.sbars[1].datetime - series.sbars[0].datetime,
+        chrono::Duration::minutes(5)
+    );
+}
+
+#[test]
+fn random_modes_are_reproducible_per_seed() {
+    let spec = SeriesSpec::default();
+    let a = RandomWalk::default().with_seed(7).generate(&spec);
+    let b = RandomWalk::default().with_seed(7).generate(&spec);
+    let c = RandomWalk::default().with_seed(8).generate(&spec);
+    assert_eq!(closes(&a.sbars), closes(&b.sbars));
+    assert_ne!(closes(&a.sbars), closes(&c.sbars));
+    assert!(a.expected.is_none());
+    for bar in &a.sbars {
+        assert!(bar.low_price <= bar.open_price.min(bar.close_price));
+        assert!(bar.high_price > bar.open_price.max(bar.close_price));
+    }
+
+    let regimes = RegimeSwitching::default().with_seed(3).generate(&spec);
+    assert_eq!(regimes.sbars.len(), 300);
+    assert_eq!(regimes.regimes.first().map(|x| x.start), Some(0));
+    assert_eq!(regimes.regimes.last().map(|x| x.end), Some(299));
+    for pair in regimes.regimes.windows(2) {
+        assert_eq!(pair[1].start, pair[0].end + 1);
+        assert_ne!(pair[1].regime, pair[0].regime);
+    }
+    // 单边段的净涨跌与标签同向
+    for span in regimes.regimes.iter().filter(|x| x.end - x.start >= 19) {
+        let change = regimes.sbars[span.end].close_price - regimes.sbars[span.start].open_price;
+        match span.regime {
+            Direction::Up => assert!(change > 0.0, "{span:?}"),
+            Direction::Down => assert!(change < 0.0, "{span:?}"),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn incremental_matches_batch_on_generated_series() {
+    let spec = SeriesSpec::default();
+    for seed in 0..4 {
+        let walk = RandomWalk {
+            bars: 80,
+            ..RandomWalk::default()
+        }
+        .with_seed(seed)
+        .generate(&spec);
+        let regimes = RegimeSwitching {
+            bars: 80,
+            min_len: 10,
+            max_len: 25,
+            ..RegimeSwitching::default()
+        }
+        .with_seed(seed)
+        .generate(&spec);
+        for series in [walk, regimes] {
+            assert_eq!(
+                run(&series.sbars, StructureMode::Incremental),
+                run(&series.sbars, StructureMode::Batch),
+                "seed {seed}"
+            );
+        }
+    }
+}
+
+fn run(bars: &[SBar], structure_mode: StructureMode) -> StructureOutline {
+    let mut mtc = MultiTimeframeContext::with_config(
+        "SYN.SIM",
+        ContextConfig {
+            structure_mode,
+            id_strategy: IdStrategy::Sequential,
+            ..ContextConfig::default()
+        },
+    );
+    mtc.register(Timeframe::M15);
+    for bar in bars {
+        mtc.append(Timeframe::M15, bar.clone());
+    }
+    StructureOutline::from_structures(
+        &mtc.get_sbar_window(Timeframe::M15, usize::MAX),
+        &mtc.get_cbar_window(Timeframe::M15, usize::MAX),
+        &mtc.get_swing_window(Timeframe::M15, usize::MAX),
+        &mtc.get_trend_window(Timeframe::M15, usize::MAX),
+    )
+}
+
+fn closes(bars: &[SBar]) -> Vec<f64> {
+    bars.iter().map(|x| x.close_price).collect()
+}
@@ -0,0 +1,181 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use struxis::{
+    BarKind, DataReceiver, IdStrategy, MultiTimeframeContext, TickAnomalies, TickBarAggregator,
+    TickInput, Timeframe,
+};
+
+#[test]
+fn cumulative_volume_reset_counts_from_new_session() {
+    let mut agg = TickBarAggregator::new();
+    agg.update(tick(0, 100.0, 5000.0));
+    agg.update(tick(10, 101.0, 5020.0));
+    // 换日后累计量清零重新计数，增量即重置后的累计值
+    agg.update(tick(20, 102.0, 7.0));
+    agg.update(tick(30, 101.5, 9.0));
+
+    let bar = agg.flush().pop().expect("bar");
+    assert_eq!(bar.volume, 20.0 + 7.0 + 2.0);
+    assert_eq!(agg.anomalies().volume_resets, 1);
+}
+
+#[test]
+fn out_of_order_ticks_update_extremes_only() {
+    let mut agg = TickBarAggregator::new();
+    agg.update(tick(0, 100.0, 10.0));
+    agg.update(tick(30, 101.0, 20.0));
+    agg.update(tick(15, 99.0, 15.0));
+
+    let bar = agg.flush().pop().expect("bar");
+    assert_eq!((bar.low_price, bar.high_price), (99.0, 101.0));
+    assert_eq!(bar.close_price, 101.0);
+    assert_eq!(bar.volume, 10.0);
+    assert_eq!(agg.anomalies().out_of_order, 1);
+}
+
+#[test]
+fn late_ticks_are_merged_within_tolerance_and_dropped_after() {
+    // 默认无容忍：下一分钟首笔到达即关闭，之后的迟到 tick 被丢弃而非另起一根倒退的 bar
+    let mut strict = TickBarAggregator::new();
+    strict.update(tick(0, 100.0, 10.0));
+    let closed = strict.update(tick(61, 101.0, 20.0));
+    assert_eq!(closed.len(), 1);
+    assert!(strict.update(tick(59, 98.0, 15.0)).is_empty());
+    assert_eq!(strict.anomalies().late_dropped, 1);
+    assert_eq!(strict.flush().len(), 1);
+
+    let mut agg = TickBarAggregator::new().with_late_tolerance(Duration::seconds(5));
+    agg.update(tick(0, 100.0, 10.0));
+    assert!(agg.update(tick(61, 101.0, 20.0)).is_empty());
+    // 09:00 区间仍在容忍期内
+    assert!(agg.update(tick(59, 98.0, 15.0)).is_empty());
+    let closed = agg.update(tick(65, 102.0, 25.0));
+    assert_eq!(closed.len(), 1);
+    assert_eq!(closed[0].datetime, at(0));
+    assert_eq!(closed[0].low_price, 98.0);
+    assert!(agg.update(tick(58, 97.0, 14.0)).is_empty());
+    assert_eq!(
+        agg.anomalies(),
+        TickAnomalies {
+            out_of_order: 1,
+            late_dropped: 1,
+            ..TickAnomalies::default()
+        }
+    );
+}
+
+#[test]
+fn clock_closes_bars_without_later_ticks() {
+    let mut agg = TickBarAggregator::new().with_late_tolerance(Duration::seconds(2));
+    agg.update(tick(0, 100.0, 10.0));
+    agg.update(tick(20, 100.5, 12.0));
+    assert!(agg.on_clock(at(61)).is_empty());
+    let closed = agg.on_clock(at(62));
+    assert_eq!(closed.len(), 1);
+    assert_eq!(closed[0].close_price, 100.5);
+    assert!(agg.on_clock(at(120)).is_empty());
+
+    // 已由时钟关闭的区间，按序到达的迟到 tick 成交量计入下一根
+    assert!(agg.update(tick(50, 100.2, 15.0)).is_empty());
+    agg.update(tick(130, 100.8, 16.0));
+    let bar = agg.flush().pop().expect("bar");
+    assert_eq!(bar.datetime, at(120));
+    assert_eq!(bar.volume, 4.0);
+
+    assert!(agg.update(tick(140, f64::NAN, 17.0)).is_empty());
+    assert_eq!(
+        agg.anomalies(),
+        TickAnomalies {
+            late_dropped: 1,
+            invalid: 1,
+            ..TickAnomalies::default()
+        }
+    );
+}
+
+#[test]
+fn receiver_clock_drives_higher_timeframes() {
+    let mut receiver = DataReceiver::new(MultiTimeframeContext::new("I2601.DCE"));
+    receiver.register_timeframe(Timeframe::M1);
+    receiver.register_timeframe(Timeframe::M5);
+    receiver.set_late_tick_tolerance(Duration::seconds(1));
+
+    for minute in 0..5 {
+        receiver.ingest_tick(tick(minute * 60 + 10, 100.0 + minute as f64, minute as f64));
+    }
+    receiver.ingest_tick(tick(30, 99.0, 0.5));
+    assert_eq!(receiver.mtc().count(Timeframe::M1), 4);
+    assert_eq!(receiver.mtc().count(Timeframe::M5), 0);
+
+    // 最后一分钟由时钟关闭，M5 随之收盘
+    assert_eq!(receiver.on_clock(at(301)), 2);
+    assert_eq!(receiver.mtc().count(Timeframe::M1), 5);
+    assert_eq!(receiver.mtc().count(Timeframe::M5), 1);
+    assert_eq!(
+        receiver.tick_anomalies(),
+        TickAnomalies {
+            out_of_order: 0,
+            late_dropped: 1,
+            ..TickAnomalies::default()
+        }
+    );
+}
+
+#[test]
+fn receiver_assigns_reproducible_ids_across_timeframes() {
+    let timeframes = [
+        Timeframe::M5,
+        Timeframe::S15,
+        Timeframe::Bars(BarKind::TickCount, 7),
+        Timeframe::M1,
+        Timeframe::Bars(BarKind::Volume, 30),
+        Timeframe::Seconds(20),
+    ];
+    let run = || {
+        let mut receiver = DataReceiver::new(MultiTimeframeContext::with_id_strategy(
+            "I2601.DCE",
+            IdStrategy::Sequential,
+        ));
+        for tf in timeframes {
+            receiver.register_timeframe(tf);
+        }
+        for i in 0..600 {
+            let price = 100.0 + ((i * 7) % 13) as f64 * 0.5;
+            receiver.ingest_tick(tick(i * 3, price, i as f64));
+        }
+        receiver.flush_ticks();
+        timeframes
+            .iter()
+            .map(|tf| {
+                receiver
+                    .mtc()
+                    .get_sbar_window(*tf, usize::MAX)
+                    .into_iter()
+                    .map(|x| (x.datetime, x.id))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    let first = run();
+    assert!(first.iter().all(|x| !x.is_empty()));
+    for _ in 0..3 {
+        assert_eq!(run(), first);
+    }
+}
+
+fn tick(second: i64, price: f64, volume: f64) -> TickInput {
+    TickInput {
+        symbol: "I2601".to_string(),
+        exchange: "DCE".to_string(),
+        datetime: at(second),
+        last_price: price,
+        volume,
+        turnover: volume * price,
+        open_interest: 100.0,
+        ..TickInput::default()
+    }
+}
+
+fn at(second: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 2, 9, 0, 0).unwrap() + Duration::seconds(second)
+}
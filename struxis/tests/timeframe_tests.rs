@@ -0,0 +1,271 @@
+use chrono::{DateTime, Datelike, TimeZone, Utc, Weekday};
+
+use struxis::{
+    BarKind, DataReceiver, IdStrategy, MarketBarInput, MultiTimeframeContext, SBar,
+    SessionBarAggregator, SessionCalendar, TickBarAggregator, TickInput, Timeframe, TradingSession,
+};
+
+#[test]
+fn named_timeframes_round_trip_through_labels() {
+    let named = [
+        Timeframe::S1,
+        Timeframe::S5,
+        Timeframe::S15,
+        Timeframe::S30,
+        Timeframe::M1,
+        Timeframe::M3,
+        Timeframe::M5,
+        Timeframe::M15,
+        Timeframe::M30,
+        Timeframe::H1,
+        Timeframe::H2,
+        Timeframe::H4,
+        Timeframe::D1,
+        Timeframe::W1,
+        Timeframe::MN1,
+    ];
+    for tf in named {
+        assert_eq!(Timeframe::parse(&tf.as_str()).expect("parse label"), tf);
+    }
+    assert_eq!(Timeframe::M30.as_str(), "30m");
+    assert_eq!(Timeframe::MN1.as_str(), "1mo");
+    // 按时长排序，伪周期在前，时长相同的自定义值排在具名周期之后
+    assert!(named.windows(2).all(|x| x[0] < x[1]));
+    assert!(Timeframe::Bars(BarKind::Renko, 10) < Timeframe::S1);
+    assert!(Timeframe::M1 < Timeframe::Minutes(1));
+    assert!(Timeframe::Minutes(2) < Timeframe::M3);
+}
+
+#[test]
+fn parse_accepts_aliases_and_custom_lengths() {
+    assert_eq!(Timeframe::parse("60m").expect("60m"), Timeframe::H1);
+    assert_eq!(Timeframe::parse("240min").expect("240min"), Timeframe::H4);
+    assert_eq!(Timeframe::parse(" 4H ").expect("4h"), Timeframe::H4);
+    assert_eq!(Timeframe::parse("mn1").expect("mn1"), Timeframe::MN1);
+    assert_eq!(Timeframe::parse("1W").expect("1w"), Timeframe::W1);
+    assert_eq!(
+        Timeframe::parse("10s").expect("10s"),
+        Timeframe::Seconds(10)
+    );
+    assert_eq!(
+        Timeframe::parse("120s").expect("120s"),
+        Timeframe::Minutes(2)
+    );
+    assert_eq!(
+        Timeframe::parse("90m").expect("90m"),
+        Timeframe::Minutes(90)
+    );
+    assert_eq!(Timeframe::Minutes(90).as_str(), "90m");
+    assert_eq!(Timeframe::Seconds(10).as_str(), "10s");
+
+    for invalid in ["", "2d", "2w", "0m", "abc", "15x"] {
+        assert!(
+            Timeframe::parse(invalid).is_err(),
+            "{invalid} should be rejected"
+        );
+    }
+    assert_eq!(Timeframe::from_seconds(300), Some(Timeframe::M5));
+    assert_eq!(Timeframe::from_seconds(2 * 86400), None);
+}
+
+#[test]
+fn zero_length_timeframes_are_rejected() {
+    for invalid in [
+        Timeframe::Seconds(0),
+        Timeframe::Minutes(0),
+        Timeframe::Minutes(24 * 60),
+        Timeframe::Bars(BarKind::Volume, 0),
+    ] {
+        assert!(invalid.validate().is_err(), "{invalid:?}");
+        let json = serde_json::to_string(&invalid).expect("serialize");
+        assert!(serde_json::from_str::<Timeframe>(&json).is_err(), "{json}");
+    }
+    let json = serde_json::to_string(&Timeframe::Minutes(90)).expect("serialize");
+    assert_eq!(
+        serde_json::from_str::<Timeframe>(&json).expect("deserialize"),
+        Timeframe::Minutes(90)
+    );
+
+    assert!(TickBarAggregator::try_with_timeframe(Timeframe::Seconds(0)).is_err());
+    assert!(TickBarAggregator::try_with_timeframe(Timeframe::D1).is_err());
+    let calendar = SessionCalendar::continuous();
+    let at = dt(2024, 3, 8, 9, 0);
+    assert!(calendar.bucket(at, Timeframe::Minutes(0)).is_err());
+    assert!(calendar.bucket(at, Timeframe::S30).is_err());
+    assert!(calendar.bucket(at, Timeframe::M5).is_ok());
+
+    // 无效周期既不注册也不进入质量闸门
+    let mut receiver = DataReceiver::new(MultiTimeframeContext::new("I2601.DCE"));
+    receiver.register_timeframe(Timeframe::Seconds(0));
+    let bar = m1(at);
+    receiver.ingest_bar(MarketBarInput {
+        symbol: bar.symbol,
+        exchange: bar.exchange,
+        timeframe: Timeframe::Minutes(0),
+        datetime: bar.datetime,
+        open_price: bar.open_price,
+        high_price: bar.high_price,
+        low_price: bar.low_price,
+        close_price: bar.close_price,
+        volume: bar.volume,
+        open_interest: bar.open_interest,
+        turnover: bar.turnover,
+    });
+    receiver.ingest_tick(TickInput {
+        symbol: "I2601".to_string(),
+        exchange: "DCE".to_string(),
+        datetime: at,
+        last_price: 100.0,
+        ..TickInput::default()
+    });
+    assert_eq!(receiver.mtc().count(Timeframe::Seconds(0)), 0);
+    assert!(receiver.quality_reports().is_empty());
+}
+
+#[test]
+fn session_aggregator_supports_extended_timeframes() {
+    let calendar = SessionCalendar::from_session(&day_session()).expect("valid session");
+
+    // 2024-03 的全部交易分钟：M30 对齐整点/半点，W1 以周一为起点，MN1 只有一根
+    let mut m30 = SessionBarAggregator::new(Timeframe::M30, calendar.clone()).expect("m30");
+    let mut h4 = SessionBarAggregator::new(Timeframe::H4, calendar.clone()).expect("h4");
+    let mut w1 = SessionBarAggregator::new(Timeframe::W1, calendar.clone()).expect("w1");
+    let mut mn1 = SessionBarAggregator::new(Timeframe::MN1, calendar.clone()).expect("mn1");
+    let (mut m30_bars, mut h4_bars, mut w1_bars, mut mn1_bars) = (0, 0, Vec::new(), Vec::new());
+
+    let mut cursor = dt(2024, 3, 1, 0, 0);
+    while cursor < dt(2024, 4, 1, 0, 0) {
+        let weekend = matches!(cursor.weekday(), Weekday::Sat | Weekday::Sun);
+        if calendar.is_trading(cursor) && !weekend {
+            let bar = m1(cursor);
+            m30_bars += m30.update(bar.clone()).len();
+            h4_bars += h4.update(bar.clone()).len();
+            w1_bars.extend(w1.update(bar.clone()));
+            mn1_bars.extend(mn1.update(bar));
+        }
+        cursor += chrono::Duration::minutes(1);
+    }
+
+    // 每日 09:30-11:30、13:00-15:00 共 8 根 M30；H4 切成 08:00-12:00 与 12:00-16:00 两根
+    assert_eq!(m30_bars, 21 * 8);
+    assert_eq!(h4_bars, 21 * 2);
+    let weeks = w1_bars.iter().map(|x| x.datetime).collect::<Vec<_>>();
+    assert_eq!(
+        weeks,
+        vec![
+            dt(2024, 2, 26, 0, 0),
+            dt(2024, 3, 4, 0, 0),
+            dt(2024, 3, 11, 0, 0),
+            dt(2024, 3, 18, 0, 0),
+            dt(2024, 3, 25, 0, 0),
+        ]
+    );
+    assert_eq!(w1_bars[0].volume, 240.0);
+    assert_eq!(mn1_bars.len(), 1);
+    assert_eq!(mn1_bars[0].datetime, dt(2024, 3, 1, 0, 0));
+    assert_eq!(mn1_bars[0].volume, 21.0 * 240.0);
+    assert_eq!(mn1_bars[0].timeframe, Timeframe::MN1);
+}
+
+#[test]
+fn receiver_builds_second_bars_from_ticks() {
+    let mut receiver = DataReceiver::new(MultiTimeframeContext::with_id_strategy(
+        "BTCUSDT.BINANCE",
+        IdStrategy::Sequential,
+    ));
+    receiver.register_timeframe(Timeframe::S15);
+    receiver.register_timeframe(Timeframe::Minutes(2));
+
+    let start = dt(2024, 3, 9, 23, 59);
+    for second in (0..180).step_by(5) {
+        receiver.ingest_tick(TickInput {
+            symbol: "BTCUSDT".to_string(),
+            exchange: "BINANCE".to_string(),
+            datetime: start + chrono::Duration::seconds(second),
+            last_price: 60000.0 + second as f64,
+            volume: second as f64,
+            turnover: second as f64 * 60000.0,
+            open_interest: 0.0,
+            ..TickInput::default()
+        });
+    }
+    receiver.flush_ticks();
+
+    let s15 = receiver.mtc().get_sbar_window(Timeframe::S15, usize::MAX);
+    assert_eq!(s15.len(), 12);
+    assert!(s15.iter().all(|x| x.timeframe == Timeframe::S15));
+    assert_eq!(s15[4].datetime, dt(2024, 3, 10, 0, 0));
+    assert_eq!((s15[4].open_price, s15[4].close_price), (60060.0, 60070.0));
+
+    // 23:58 区间只有 23:59 一分钟，到达即收盘；00:00-00:02 一根
+    let m2 = receiver
+        .mtc()
+        .get_sbar_window(Timeframe::Minutes(2), usize::MAX);
+    let starts = m2.iter().map(|x| x.datetime).collect::<Vec<_>>();
+    assert_eq!(starts, vec![dt(2024, 3, 9, 23, 58), dt(2024, 3, 10, 0, 0)]);
+}
+
+#[test]
+fn parquet_snapshot_uses_timeframe_labels() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I2605.DCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M30);
+    mtc.register(Timeframe::Minutes(7));
+    for i in 0..5 {
+        mtc.append(
+            Timeframe::M30,
+            m1(dt(2024, 3, 8, 9, 30) + chrono::Duration::minutes(30 * i)),
+        );
+        mtc.append(
+            Timeframe::Minutes(7),
+            m1(dt(2024, 3, 8, 9, 30) + chrono::Duration::minutes(7 * i)),
+        );
+    }
+
+    let dir = std::env::temp_dir().join(format!("struxis_timeframe_{}", std::process::id()));
+    mtc.write_parquet_snapshot(Timeframe::M30, &dir)
+        .expect("snapshot m30");
+    mtc.write_parquet_snapshot(Timeframe::Minutes(7), &dir)
+        .expect("snapshot 7m");
+    for name in [
+        "sbar_30m.parquet",
+        "trend_30m.parquet",
+        "sbar_7m.parquet",
+        "cbar_7m.parquet",
+    ] {
+        assert!(dir.join(name).exists(), "{name} missing");
+    }
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+fn day_session() -> TradingSession {
+    TradingSession {
+        sections: vec![
+            ("09:30".to_string(), "11:30".to_string()),
+            ("13:00".to_string(), "15:00".to_string()),
+        ],
+        utc_offset_minutes: 0,
+    }
+}
+
+fn m1(datetime: DateTime<Utc>) -> SBar {
+    SBar {
+        id: None,
+        symbol: "I2605".to_string(),
+        exchange: "DCE".to_string(),
+        timeframe: Timeframe::M1,
+        datetime,
+        open_price: 800.0,
+        high_price: 801.0,
+        low_price: 799.0,
+        close_price: 800.5,
+        volume: 1.0,
+        open_interest: 1000.0,
+        turnover: 800.0,
+    }
+}
+
+fn dt(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+        .single()
+        .expect("valid dt")
+}
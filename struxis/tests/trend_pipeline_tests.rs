@@ -85,7 +85,7 @@ fn completed_trend_switch_keeps_opposite_direction_and_contiguous_ids() {
         let left = &pair[0];
         let right = &pair[1];
 
-        if left.state != SwingState::Confirmed {
+        if !left.is_completed {
             continue;
         }
 
@@ -124,7 +124,6 @@ fn controlled_swings_for_switch() -> Vec<Swing> {
             start_oi: 1.0,
             end_oi: 1.0,
             state: SwingState::Confirmed,
-            state: SwingState::Confirmed,
             created_at: Utc::now(),
         }
     }
@@ -100,3 +100,62 @@ fn reports_first_changed_trend_id_on_backtrack_rebuild() {
     assert!(backtrack.is_some());
     assert_eq!(backtrack, Some(2));
 }
+
+#[test]
+fn incremental_update_matches_batch_rebuild() {
+    let mut state = 11_u64;
+    let mut next = move |modulo: u64| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) % modulo
+    };
+
+    let mut swings: Vec<Swing> = Vec::new();
+    let mut batch = TrendManager::new();
+    let mut incremental = TrendManager::new();
+    let mut level = 100.0_f64;
+    for id in 1..=240_u64 {
+        // 偶尔改写最后一段 swing，模拟上游回溯
+        let changed_index = if swings.len() > 1 && next(4) == 0 {
+            let last = swings.last_mut().expect("swing exists");
+            last.high_price += next(3) as f64;
+            last.low_price -= next(3) as f64;
+            swings.len() - 1
+        } else {
+            let direction = if id % 2 == 1 { Direction::Up } else { Direction::Down };
+            let low = level - next(6) as f64;
+            let high = low + 2.0 + next(8) as f64;
+            level = if direction == Direction::Up { high } else { low } - 2.0 + next(5) as f64;
+            swings.push(swing(id, direction, high, low));
+            swings.len() - 1
+        };
+
+        batch.rebuild_from_swings_with_backtrack(&swings, Some(changed_index as u64));
+        incremental.update_from_swings(&swings, Some(changed_index));
+
+        let shape = |rows: &[struxis::Trend]| {
+            rows.iter()
+                .map(|x| {
+                    (
+                        x.direction,
+                        x.swing_start_id,
+                        x.swing_end_id,
+                        x.sbar_start_id,
+                        x.sbar_end_id,
+                        x.high_price,
+                        x.low_price,
+                        x.span,
+                        x.is_completed,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            shape(batch.rows()),
+            shape(incremental.rows()),
+            "trend mismatch after swing #{id}"
+        );
+    }
+    assert!(batch.rows().len() > 3, "need several trends to exercise replay");
+}
@@ -0,0 +1,220 @@
+mod common;
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use struxis::validation::{validate_cbars, validate_structures};
+use struxis::{
+    CBar, ContextConfig, Direction, EventPayload, EventType, FractalType, IdStrategy,
+    InvariantRule, MultiTimeframeContext, SBar, StructureMode, Swing, SwingState, Timeframe, Trend,
+};
+
+#[test]
+fn clean_structures_pass_and_each_rule_is_detected() {
+    let (sbars, cbars, swings, trends) = clean_structures();
+    assert!(validate_structures(&sbars, &cbars, &swings, &trends).is_empty());
+    assert!(validate_cbars(&cbars).is_empty());
+
+    // CBar 缺口：第 3 根 SBar 不属于任何 CBar
+    let mut gap = cbars.clone();
+    gap.remove(2);
+    let rules = rules_of(&validate_structures(&sbars, &gap, &[], &[]));
+    assert!(rules.contains(&InvariantRule::CBarCoverage));
+
+    // 相邻包含与分型标签错误
+    let mut inclusive = cbars.clone();
+    inclusive[4].high_price = 13.0;
+    inclusive[4].low_price = 9.0;
+    let rules = rules_of(&validate_cbars(&inclusive));
+    assert!(rules.contains(&InvariantRule::CBarInclusive));
+    assert!(rules.contains(&InvariantRule::FractalRule));
+
+    let mut range = cbars.clone();
+    range[0].low_price = 11.0;
+    assert!(rules_of(&validate_cbars(&range)).contains(&InvariantRule::CBarRange));
+
+    // 已确认 swing 无方向、起止分型不匹配
+    let mut undirected = swings.clone();
+    undirected[0].direction = Direction::None;
+    let found = validate_structures(&sbars, &cbars, &undirected, &[]);
+    assert_eq!(rules_of(&found), vec![InvariantRule::SwingDirectional]);
+    let mut reversed = swings.clone();
+    reversed[0].direction = Direction::Down;
+    let found = validate_structures(&sbars, &cbars, &reversed, &[]);
+    assert_eq!(rules_of(&found), vec![InvariantRule::SwingFractalEnds]);
+    assert_eq!(found[0].structure_id, Some(21));
+    // 未确认的 swing 不检查方向
+    reversed[0].state = SwingState::Forming;
+    assert!(validate_structures(&sbars, &cbars, &reversed, &[]).is_empty());
+
+    // trend 无同向 swing、边界未包住 swing
+    let mut opposite = trends.clone();
+    opposite[0].direction = Direction::Down;
+    let found = validate_structures(&sbars, &cbars, &swings, &opposite);
+    assert_eq!(rules_of(&found), vec![InvariantRule::TrendDirection]);
+    let mut narrow = trends.clone();
+    narrow[0].high_price = 11.5;
+    let found = validate_structures(&sbars, &cbars, &swings, &narrow);
+    assert_eq!(rules_of(&found), vec![InvariantRule::TrendEnvelope]);
+    assert_eq!(found[0].structure_id, Some(31));
+}
+
+#[test]
+fn report_covers_registered_timeframe_only() {
+    let mut mtc = MultiTimeframeContext::with_id_strategy("I8888.XDCE", IdStrategy::Sequential);
+    mtc.register(Timeframe::M15);
+    for bar in common::wave_bars(60) {
+        mtc.append(Timeframe::M15, bar);
+    }
+    let report = mtc.validate(Timeframe::M15).expect("registered");
+    assert_eq!(report.sbars, 60);
+    assert_eq!(report.sbar_id, Some(60));
+    assert_eq!(
+        report.cbars,
+        mtc.get_cbar_window(Timeframe::M15, usize::MAX).len()
+    );
+    assert_eq!(report.is_valid(), report.violations.is_empty());
+    assert!(mtc.validate(Timeframe::H1).is_none());
+    // 未开启持续校验时不累计违例
+    assert!(mtc.get_violations(Timeframe::M15).is_empty());
+}
+
+#[test]
+fn continuous_validation_reports_each_violation_once() {
+    let mut mtc = MultiTimeframeContext::with_config(
+        "I8888.XDCE",
+        ContextConfig {
+            structure_mode: StructureMode::Incremental,
+            id_strategy: IdStrategy::Sequential,
+            validate_on_append: true,
+            ..ContextConfig::default()
+        },
+    );
+    mtc.register(Timeframe::M15);
+    let recorded = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&recorded);
+    mtc.subscribe(
+        Some(EventType::InvariantViolated),
+        Arc::new(move |_, _, payload| {
+            if let EventPayload::InvariantViolated(violation) = payload {
+                sink.lock().expect("lock").push(violation.clone());
+            }
+        }),
+    );
+    for bar in common::wave_bars(160) {
+        mtc.append(Timeframe::M15, bar);
+    }
+
+    let live = mtc.get_violations(Timeframe::M15);
+    assert_eq!(*recorded.lock().expect("lock"), live);
+    let keys = live
+        .iter()
+        .map(|x| (x.rule, x.structure_id))
+        .collect::<HashSet<_>>();
+    assert_eq!(keys.len(), live.len());
+
+    // 最终结构中的违例在其出现的那次 append 中已被发现
+    let report = mtc.validate(Timeframe::M15).expect("registered");
+    for violation in &report.violations {
+        assert!(
+            keys.contains(&(violation.rule, violation.structure_id)),
+            "{violation}"
+        );
+    }
+}
+
+fn rules_of(violations: &[struxis::Violation]) -> Vec<InvariantRule> {
+    violations.iter().map(|x| x.rule).collect()
+}
+
+/// 5 根 SBar 各成一根 CBar：底分型在第 2 根，顶分型在第 4 根，一个向上 swing 与 trend。
+fn clean_structures() -> (Vec<SBar>, Vec<CBar>, Vec<Swing>, Vec<Trend>) {
+    let prices = [
+        (10.0, 8.0),
+        (9.0, 7.0),
+        (11.0, 9.0),
+        (12.0, 10.0),
+        (11.0, 9.5),
+    ];
+    let fractals = [
+        FractalType::None,
+        FractalType::Bottom,
+        FractalType::None,
+        FractalType::Top,
+        FractalType::None,
+    ];
+    let sbars = prices
+        .iter()
+        .enumerate()
+        .map(|(i, &(high, low))| SBar {
+            id: Some(i as u64 + 1),
+            symbol: "I8888".to_string(),
+            exchange: "XDCE".to_string(),
+            timeframe: Timeframe::M15,
+            datetime: at(i),
+            open_price: low,
+            high_price: high,
+            low_price: low,
+            close_price: high,
+            volume: 100.0,
+            open_interest: 1000.0,
+            turnover: 100.0 * high,
+        })
+        .collect::<Vec<_>>();
+    let cbars = prices
+        .iter()
+        .zip(fractals)
+        .enumerate()
+        .map(|(i, (&(high, low), fractal_type))| CBar {
+            id: Some(i as u64 + 11),
+            sbar_start_id: i as u64 + 1,
+            sbar_end_id: i as u64 + 1,
+            high_price: high,
+            low_price: low,
+            fractal_type,
+            created_at: at(i),
+        })
+        .collect::<Vec<_>>();
+    let swing = Swing {
+        id: Some(21),
+        direction: Direction::Up,
+        cbar_start_id: 12,
+        cbar_end_id: 14,
+        sbar_start_id: 2,
+        sbar_end_id: 4,
+        high_price: 12.0,
+        low_price: 7.0,
+        span: 3,
+        volume: 300.0,
+        start_oi: 1000.0,
+        end_oi: 1000.0,
+        state: SwingState::Confirmed,
+        created_at: at(3),
+    };
+    let trend = Trend {
+        id: Some(31),
+        direction: Direction::Up,
+        swing_start_id: 21,
+        swing_end_id: 21,
+        sbar_start_id: 2,
+        sbar_end_id: 4,
+        high_price: 12.0,
+        low_price: 7.0,
+        span: 3,
+        volume: 300.0,
+        start_oi: 1000.0,
+        end_oi: 1000.0,
+        is_completed: true,
+        created_at: at(3),
+    };
+    (sbars, cbars, vec![swing], vec![trend])
+}
+
+fn at(i: usize) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0)
+        .single()
+        .expect("valid dt")
+        + chrono::Duration::minutes(i as i64 * 15)
+}